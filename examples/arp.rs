@@ -16,7 +16,7 @@ use cortex_m_rt::{entry, exception};
 
 use cortex_m::interrupt::Mutex;
 use stm32_eth::{
-    mac::{phy::BarePhy, Phy},
+    mac::phy::{BarePhy, LinkEvent, LinkMonitor},
     stm32::{interrupt, CorePeripherals, Peripherals, SYST},
     Parts,
 };
@@ -61,23 +61,22 @@ fn main() -> ! {
     .unwrap();
     dma.enable_interrupt();
 
-    let mut last_link_up = false;
+    let mut link_monitor = LinkMonitor::new();
 
     let mut bare_phy = BarePhy::new(mac.with_mii(mdio, mdc), PHY_ADDR, Default::default());
 
     loop {
-        let link_up = bare_phy.phy_link_up();
-
-        if link_up != last_link_up {
-            if link_up {
-                defmt::info!("Ethernet: link detected");
-            } else {
-                defmt::info!("Ethernet: no link detected");
+        while let Some(event) = link_monitor.poll(&mut bare_phy) {
+            match event {
+                LinkEvent::Up => defmt::info!("Ethernet: link detected"),
+                LinkEvent::Down => defmt::info!("Ethernet: no link detected"),
+                LinkEvent::RemoteFault => defmt::warn!("Ethernet: link partner reports a fault"),
+                LinkEvent::RemoteFaultCleared => defmt::info!("Ethernet: remote fault cleared"),
+                LinkEvent::Jabber => defmt::warn!("Ethernet: jabber detected"),
             }
-            last_link_up = link_up;
         }
 
-        if link_up {
+        if link_monitor.state().is_up() {
             const SIZE: usize = 42;
 
             const DST_MAC: [u8; 6] = [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
@@ -114,6 +113,7 @@ fn main() -> ! {
                     defmt::info!("ARP sent");
                 }
                 Err(TxError::WouldBlock) => defmt::info!("ARP failed"),
+                Err(_) => defmt::info!("ARP failed: unexpected error"),
             }
         } else {
             defmt::info!("Down");
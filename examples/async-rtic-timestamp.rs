@@ -44,15 +44,16 @@ mod app {
     use async_rtic as rtic;
     use rtic_sync::channel::{Channel, Receiver, Sender};
 
-    use crate::common::EthernetPhy;
-
     use rtic_sync::arbiter::Arbiter;
 
-    use ieee802_3_miim::{phy::PhySpeed, Phy};
+    use ieee802_3_miim::Phy;
 
     use stm32_eth::{
         dma::{EthernetDMA, PacketId, RxRing, RxRingEntry, TxRing, TxRingEntry},
-        mac::Speed,
+        mac::{
+            phy::{self, PhyAdvertisement},
+            Speed,
+        },
         ptp::{EthernetPTP, Subseconds, Timestamp},
         Parts,
     };
@@ -104,12 +105,9 @@ mod app {
         let (rx, tx) = dma.split();
         let (do_tx_send, do_tx_recv) = cx.local.tx_channel.split();
 
-        match EthernetPhy::from_miim(mac, 0) {
+        match phy::detect(mac) {
             Ok(mut phy) => {
-                defmt::info!(
-                    "Resetting PHY as an extra step. Type: {}",
-                    phy.ident_string()
-                );
+                defmt::info!("Resetting PHY as an extra step. Type: {}", phy.name());
 
                 phy.phy_init();
 
@@ -119,12 +117,7 @@ mod app {
 
                 defmt::info!("Link up.");
 
-                if let Some(speed) = phy.speed().map(|s| match s {
-                    PhySpeed::HalfDuplexBase10T => Speed::HalfDuplexBase10T,
-                    PhySpeed::FullDuplexBase10T => Speed::FullDuplexBase10T,
-                    PhySpeed::HalfDuplexBase100Tx => Speed::HalfDuplexBase100Tx,
-                    PhySpeed::FullDuplexBase100Tx => Speed::FullDuplexBase100Tx,
-                }) {
+                if let Some(speed) = phy.link_configuration().map(|link| link.speed) {
                     phy.get_miim().set_speed(speed);
                     defmt::info!("Detected link speed: {}", speed);
                 } else {
@@ -132,7 +125,7 @@ mod app {
                 }
             }
             Err(_) => {
-                defmt::info!("Not resetting unsupported PHY. Cannot detect link speed.");
+                defmt::info!("No PHY found. Cannot detect link speed.");
             }
         };
 
@@ -1,6 +1,7 @@
 //! For build and run instructions, see README.md
 //!
-//! An example that generates some empty ethernet packets.
+//! An example that generates ethernet packets, and verifies
+//! packets generated by another device running this example.
 #![no_std]
 #![no_main]
 
@@ -15,16 +16,18 @@ use cortex_m::interrupt::Mutex;
 use stm32_eth::{
     mac::{phy::BarePhy, Phy},
     stm32::{interrupt, CorePeripherals, Peripherals, SYST},
+    testing::{PacketGenerator, PacketVerifier, Payload, SizeDistribution, MAX_FRAME_LEN},
     Parts,
 };
 
-use stm32_eth::dma::{RxRingEntry, TxError, TxRingEntry};
+use stm32_eth::dma::{AbnormalInterrupts, RxRingEntry, TxRingEntry};
 
 pub mod common;
 
 const SRC_MAC: [u8; 6] = [0x00, 0x00, 0xDE, 0xAD, 0xBE, 0xEF];
 const DST_MAC: [u8; 6] = [0x00, 0x00, 0xBE, 0xEF, 0xDE, 0xAD];
-const ETH_TYPE: [u8; 2] = [0x80, 0x00];
+const ETH_TYPE: u16 = 0x88B5;
+const PAYLOAD: Payload = Payload::Prbs15;
 const PHY_ADDR: u8 = 0;
 
 static TIME: Mutex<RefCell<usize>> = Mutex::new(RefCell::new(0));
@@ -58,6 +61,7 @@ fn main() -> ! {
     )
     .unwrap();
     dma.enable_interrupt();
+    dma.enable_abnormal_interrupts(AbnormalInterrupts::FATAL);
 
     // Main loop
     let mut last_stats_time = 0usize;
@@ -69,6 +73,11 @@ fn main() -> ! {
 
     let mut phy = BarePhy::new(mac.with_mii(mdio, mdc), PHY_ADDR, Default::default());
 
+    let mut generator = PacketGenerator::new(SRC_MAC, DST_MAC, ETH_TYPE)
+        .size_distribution(SizeDistribution::Fixed(MAX_FRAME_LEN))
+        .payload(PAYLOAD);
+    let mut verifier = PacketVerifier::new(ETH_TYPE, PAYLOAD);
+
     loop {
         let time: usize = cortex_m::interrupt::free(|cs| *TIME.borrow(cs).borrow());
 
@@ -84,6 +93,15 @@ fn main() -> ! {
                 tx_pkts / t
             );
 
+            let stats = verifier.stats();
+            defmt::info!(
+                "Verified: {} received, {} lost, {} reordered, {} corrupted",
+                stats.received,
+                stats.lost,
+                stats.reordered,
+                stats.corrupted
+            );
+
             // Reset
             rx_bytes = 0;
             rx_pkts = 0;
@@ -114,6 +132,7 @@ fn main() -> ! {
             while let Ok(pkt) = dma.recv_next(None) {
                 rx_bytes += pkt.len();
                 rx_pkts += 1;
+                verifier.check(&pkt);
                 pkt.free();
 
                 recvd += 1;
@@ -128,21 +147,18 @@ fn main() -> ! {
         }
 
         // fill tx queue
-        const SIZE: usize = 1500;
         if phy.phy_link_up() {
-            'egress: loop {
-                let r = dma.send(SIZE, None, |buf| {
-                    buf[0..6].copy_from_slice(&DST_MAC);
-                    buf[6..12].copy_from_slice(&SRC_MAC);
-                    buf[12..14].copy_from_slice(&ETH_TYPE);
-                });
+            let now_ms = time as u64 * 1000;
+            'egress: while generator.ready(now_ms) {
+                let size = generator.next_len();
+                let r = dma.send(size, None, |buf| generator.fill(buf));
 
                 match r {
                     Ok(()) => {
-                        tx_bytes += SIZE;
+                        tx_bytes += size;
                         tx_pkts += 1;
                     }
-                    Err(TxError::WouldBlock) => break 'egress,
+                    Err(_) => break 'egress,
                 }
             }
         }
@@ -182,5 +198,9 @@ fn ETH() {
         *eth_pending = true;
     });
 
-    stm32_eth::eth_interrupt_handler();
+    let reason = stm32_eth::eth_interrupt_handler();
+
+    if reason.dma_abnormal.any() {
+        defmt::error!("Abnormal DMA condition: {}", reason.dma_abnormal);
+    }
 }
@@ -21,14 +21,12 @@ const MAC: [u8; 6] = [0x00, 0x01, 0x02, 0x03, 0x04, 0x05];
 #[rtic::app(device = stm32_eth::stm32, dispatchers = [SPI1])]
 mod app {
 
-    use crate::common::EthernetPhy;
-
-    use ieee802_3_miim::{phy::PhySpeed, Phy};
+    use ieee802_3_miim::Phy;
     use systick_monotonic::Systick;
 
     use stm32_eth::{
         dma::{EthernetDMA, RxRingEntry, TxRingEntry},
-        mac::Speed,
+        mac::phy::{self, PhyAdvertisement},
         Parts,
     };
 
@@ -125,11 +123,8 @@ mod app {
 
         interface.poll(now_fn(), &mut &mut dma, &mut sockets);
 
-        if let Ok(mut phy) = EthernetPhy::from_miim(mac, 0) {
-            defmt::info!(
-                "Resetting PHY as an extra step. Type: {}",
-                phy.ident_string()
-            );
+        if let Ok(mut phy) = phy::detect(mac) {
+            defmt::info!("Resetting PHY as an extra step. Type: {}", phy.name());
 
             phy.phy_init();
 
@@ -139,19 +134,14 @@ mod app {
 
             defmt::info!("Link up.");
 
-            if let Some(speed) = phy.speed().map(|s| match s {
-                PhySpeed::HalfDuplexBase10T => Speed::HalfDuplexBase10T,
-                PhySpeed::FullDuplexBase10T => Speed::FullDuplexBase10T,
-                PhySpeed::HalfDuplexBase100Tx => Speed::HalfDuplexBase100Tx,
-                PhySpeed::FullDuplexBase100Tx => Speed::FullDuplexBase100Tx,
-            }) {
+            if let Some(speed) = phy.link_configuration().map(|link| link.speed) {
                 phy.get_miim().set_speed(speed);
                 defmt::info!("Detected link speed: {}", speed);
             } else {
                 defmt::warn!("Failed to detect link speed.");
             }
         } else {
-            defmt::info!("Not resetting unsupported PHY. Cannot detect link speed.");
+            defmt::info!("No PHY found. Cannot detect link speed.");
         }
 
         defmt::info!("Setup done. Listening at {}", crate::SOCKET_ADDRESS);
@@ -53,14 +53,12 @@ mod app {
         wire::{EthernetAddress, IpAddress, IpCidr, IpEndpoint, IpListenEndpoint, Ipv4Address},
     };
 
-    use crate::common::EthernetPhy;
-
-    use ieee802_3_miim::{phy::PhySpeed, Phy};
+    use ieee802_3_miim::Phy;
     use systick_monotonic::Systick;
 
     use stm32_eth::{
         dma::{EthernetDMA, RxRingEntry, TxRingEntry},
-        mac::Speed,
+        mac::phy::{self, PhyAdvertisement},
         ptp::{EthernetPTP, Timestamp},
         Parts,
     };
@@ -149,12 +147,9 @@ mod app {
         defmt::info!("Enabling interrupts");
         dma.enable_interrupt();
 
-        match EthernetPhy::from_miim(mac, 0) {
+        match phy::detect(mac) {
             Ok(mut phy) => {
-                defmt::info!(
-                    "Resetting PHY as an extra step. Type: {}",
-                    phy.ident_string()
-                );
+                defmt::info!("Resetting PHY as an extra step. Type: {}", phy.name());
 
                 phy.phy_init();
 
@@ -164,12 +159,7 @@ mod app {
 
                 defmt::info!("Link up.");
 
-                if let Some(speed) = phy.speed().map(|s| match s {
-                    PhySpeed::HalfDuplexBase10T => Speed::HalfDuplexBase10T,
-                    PhySpeed::FullDuplexBase10T => Speed::FullDuplexBase10T,
-                    PhySpeed::HalfDuplexBase100Tx => Speed::HalfDuplexBase100Tx,
-                    PhySpeed::FullDuplexBase100Tx => Speed::FullDuplexBase100Tx,
-                }) {
+                if let Some(speed) = phy.link_configuration().map(|link| link.speed) {
                     phy.get_miim().set_speed(speed);
                     defmt::info!("Detected link speed: {}", speed);
                 } else {
@@ -177,7 +167,7 @@ mod app {
                 }
             }
             Err(_) => {
-                defmt::info!("Not resetting unsupported PHY. Cannot detect link speed.");
+                defmt::info!("No PHY found. Cannot detect link speed.");
             }
         };
 
@@ -0,0 +1,58 @@
+//! For build and run instructions, see README.md
+//!
+//! A responder for the raw Ethernet throughput test. Run
+//! `tools/throughput.py <interface> 00:00:de:ad:be:ef` on a host that is
+//! connected to the device to measure the throughput of the driver.
+#![no_std]
+#![no_main]
+
+use defmt_rtt as _;
+use panic_probe as _;
+
+use core::default::Default;
+use cortex_m_rt::entry;
+
+use stm32_eth::{
+    dma::{RxRingEntry, TxRingEntry},
+    stm32::Peripherals,
+    testing::ThroughputResponder,
+    Parts,
+};
+
+pub mod common;
+
+const MAC: [u8; 6] = [0x00, 0x00, 0xDE, 0xAD, 0xBE, 0xEF];
+
+#[entry]
+fn main() -> ! {
+    let p = Peripherals::take().unwrap();
+
+    let (clocks, gpio, ethernet) = common::setup_peripherals(p);
+
+    defmt::info!("Enabling ethernet...");
+    let (eth_pins, _mdio, _mdc, _) = common::setup_pins(gpio);
+
+    let mut rx_ring: [RxRingEntry; 8] = Default::default();
+    let mut tx_ring: [TxRingEntry; 8] = Default::default();
+    let Parts {
+        mut dma,
+        mac: _,
+        #[cfg(feature = "ptp")]
+            ptp: _,
+    } = stm32_eth::new(
+        ethernet,
+        &mut rx_ring[..],
+        &mut tx_ring[..],
+        clocks,
+        eth_pins,
+    )
+    .unwrap();
+
+    let mut responder = ThroughputResponder::new(MAC);
+
+    defmt::info!("Waiting for throughput test requests...");
+
+    loop {
+        responder.poll(&mut dma);
+    }
+}
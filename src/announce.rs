@@ -0,0 +1,191 @@
+//! Transmission of announcement frames when the link comes up.
+//!
+//! After a cable is moved to another switch port, switches and neighbours
+//! keep sending traffic to the old port until they learn the new location
+//! of this station. Devices with a static IP address speed this up by
+//! announcing themselves, for instance with a gratuitous ARP (see
+//! [`gratuitous_arp`]) or an unsolicited IPv6 neighbor advertisement.
+//!
+//! An [`Announcer`] holds such frames, and transmits all of them whenever
+//! it is passed a [`LinkEvent::Up`].
+//!
+//! ```rust,ignore
+//! let arp = gratuitous_arp(mac, [192, 168, 1, 10]);
+//! let mut announcer: Announcer<2> = Announcer::new();
+//! announcer.register(&arp).unwrap();
+//!
+//! while let Some(event) = link.poll(&mut phy) {
+//!     announcer.on_link_event(event);
+//! }
+//! announcer.poll(&mut dma).ok();
+//! ```
+
+use crate::{
+    dma::{EthernetDMA, TxError, MTU},
+    mac::{phy::LinkEvent, Mac},
+};
+
+/// The length of a gratuitous ARP frame, excluding padding.
+pub const GRATUITOUS_ARP_LEN: usize = 42;
+
+/// An error that occurs when registering an announcement.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnounceError {
+    /// The maximum amount of announcements has been registered already.
+    Full,
+    /// The frame is longer than [`MTU`].
+    TooLong,
+}
+
+/// Transmits a set of registered frames whenever the link comes up.
+///
+/// At most `N` frames can be registered.
+#[derive(Debug, Clone)]
+pub struct Announcer<'a, const N: usize> {
+    frames: [Option<&'a [u8]>; N],
+    next: Option<usize>,
+}
+
+impl<const N: usize> Default for Announcer<'_, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, const N: usize> Announcer<'a, N> {
+    /// Create an announcer without any frames.
+    pub const fn new() -> Self {
+        Self {
+            frames: [None; N],
+            next: None,
+        }
+    }
+
+    /// Register `frame` to be transmitted whenever the link comes up.
+    ///
+    /// Frames shorter than [`MIN_FRAME_LEN`](crate::dma::MIN_FRAME_LEN) are zero-padded.
+    pub fn register(&mut self, frame: &'a [u8]) -> Result<(), AnnounceError> {
+        if frame.len() > MTU {
+            return Err(AnnounceError::TooLong);
+        }
+
+        let slot = self
+            .frames
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .ok_or(AnnounceError::Full)?;
+        *slot = Some(frame);
+        Ok(())
+    }
+
+    /// Remove all registered frames, and cancel pending announcements.
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Process a `event` reported by [`LinkMonitor::poll`](crate::mac::phy::LinkMonitor::poll).
+    ///
+    /// If the link went up, all registered frames are transmitted by the
+    /// following calls to [`Announcer::poll`]. If the link went down,
+    /// pending announcements are cancelled.
+    pub fn on_link_event(&mut self, event: LinkEvent) {
+        match event {
+            LinkEvent::Up => self.trigger(),
+            LinkEvent::Down => self.next = None,
+            _ => {}
+        }
+    }
+
+    /// Transmit all registered frames on the following calls to
+    /// [`Announcer::poll`], regardless of the state of the link.
+    pub fn trigger(&mut self) {
+        self.next = Some(0);
+    }
+
+    /// Whether there are announcements that have not been transmitted yet.
+    pub fn is_pending(&self) -> bool {
+        self.next.is_some()
+    }
+
+    /// Transmit pending announcements, and return how many were sent.
+    ///
+    /// If the TX ring fills up, [`Err(TxError::WouldBlock)`](TxError::WouldBlock)
+    /// is returned, and the remaining announcements are sent on a later call.
+    pub fn poll<const RX_BUF: usize>(
+        &mut self,
+        dma: &mut EthernetDMA<'_, '_, RX_BUF>,
+    ) -> Result<usize, TxError> {
+        let mut sent = 0;
+
+        while let Some(index) = self.next {
+            match self.frames.get(index).copied().flatten() {
+                Some(frame) => {
+                    dma.send_slice(frame, None, true)?;
+                    sent += 1;
+                    self.next = Some(index + 1);
+                }
+                None => self.next = None,
+            }
+        }
+
+        Ok(sent)
+    }
+}
+
+/// Build a gratuitous ARP request, in which `address` announces that it
+/// owns the IPv4 address `ip`.
+///
+/// The frame is not padded to [`MIN_FRAME_LEN`](crate::dma::MIN_FRAME_LEN),
+/// which is done when it is transmitted by an [`Announcer`].
+#[rustfmt::skip]
+pub const fn gratuitous_arp(address: Mac, ip: [u8; 4]) -> [u8; GRATUITOUS_ARP_LEN] {
+    let m = address.raw();
+    [
+        // Ethernet header: broadcast destination, ethertype ARP.
+        0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+        m[0], m[1], m[2], m[3], m[4], m[5],
+        0x08, 0x06,
+        // Hardware type Ethernet, protocol type IPv4, lengths, request.
+        0x00, 0x01, 0x08, 0x00, 6, 4, 0x00, 0x01,
+        // Sender hardware and protocol address.
+        m[0], m[1], m[2], m[3], m[4], m[5],
+        ip[0], ip[1], ip[2], ip[3],
+        // Target hardware address (unused), and target protocol address.
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ip[0], ip[1], ip[2], ip[3],
+    ]
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+    use crate::dma::MIN_FRAME_LEN;
+
+    #[test]
+    fn gratuitous_arp_frame() {
+        let address = Mac::new([0x02, 0x00, 0x00, 0x12, 0x34, 0x56]);
+        let frame = gratuitous_arp(address, [192, 168, 1, 10]);
+        assert_eq!(&frame[..6], Mac::BROADCAST.raw());
+        assert_eq!(&frame[6..12], address.raw());
+        assert_eq!(&frame[12..14], &[0x08, 0x06]);
+        assert_eq!(&frame[22..28], address.raw());
+        assert_eq!(&frame[28..32], &[192, 168, 1, 10]);
+        assert_eq!(&frame[38..42], &[192, 168, 1, 10]);
+        assert!(frame.len() < MIN_FRAME_LEN);
+    }
+
+    #[test]
+    fn register_and_trigger() {
+        let frame = [0u8; 60];
+        let mut announcer: Announcer<1> = Announcer::new();
+        assert_eq!(announcer.register(&frame), Ok(()));
+        assert_eq!(announcer.register(&frame), Err(AnnounceError::Full));
+        assert!(!announcer.is_pending());
+
+        announcer.on_link_event(LinkEvent::Up);
+        assert!(announcer.is_pending());
+        announcer.on_link_event(LinkEvent::Down);
+        assert!(!announcer.is_pending());
+    }
+}
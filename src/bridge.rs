@@ -0,0 +1,221 @@
+//! Bridging of frames between the ethernet MAC and another interface.
+//!
+//! The other interface can be anything that sends and receives ethernet
+//! frames, such as a USB CDC-ECM or RNDIS class, which allows a device to
+//! act as a USB-to-Ethernet adapter or as a debug tap. The other interface
+//! only has to implement [`Interface`], which [`EthernetDMA`] implements as
+//! well.
+//!
+//! Frames are copied exactly once: directly from the receive buffer of one
+//! interface into the transmit buffer of the other.
+
+use crate::dma::{EthernetDMA, MTU};
+
+/// An interface that sends and receives ethernet frames.
+pub trait Interface {
+    /// The maximum length of a frame that can be transmitted
+    /// on this interface.
+    fn max_frame_len(&self) -> usize;
+
+    /// Check if a frame can be transmitted on this interface now.
+    ///
+    /// If this function returns `true`, the next call to
+    /// [`Interface::transmit`] must succeed.
+    fn can_transmit(&mut self) -> bool;
+
+    /// Transmit a frame of `length` bytes, which are written
+    /// into the transmit buffer by `f`.
+    ///
+    /// Returns `false` if the frame could not be transmitted.
+    fn transmit<F>(&mut self, length: usize, f: F) -> bool
+    where
+        F: FnOnce(&mut [u8]);
+
+    /// Receive a frame, and pass it to `f`.
+    ///
+    /// Returns `None` if no frame was available.
+    fn receive<R, F>(&mut self, f: F) -> Option<R>
+    where
+        F: FnOnce(&[u8]) -> R;
+}
+
+impl<const RX_BUF: usize> Interface for EthernetDMA<'_, '_, RX_BUF> {
+    fn max_frame_len(&self) -> usize {
+        MTU
+    }
+
+    fn can_transmit(&mut self) -> bool {
+        self.tx_available()
+    }
+
+    fn transmit<F>(&mut self, length: usize, f: F) -> bool
+    where
+        F: FnOnce(&mut [u8]),
+    {
+        self.send(length, None, f).is_ok()
+    }
+
+    fn receive<R, F>(&mut self, f: F) -> Option<R>
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        self.recv_next(None).ok().map(|packet| f(&packet))
+    }
+}
+
+/// Counters of a [`Bridge`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BridgeStats {
+    /// The amount of frames forwarded from the ethernet MAC
+    /// to the other interface.
+    pub to_other: u32,
+    /// The amount of frames forwarded from the other interface
+    /// to the ethernet MAC.
+    pub to_ethernet: u32,
+    /// The amount of frames that were dropped because they were
+    /// too long for the destination interface, or because transmitting
+    /// them failed.
+    pub dropped: u32,
+}
+
+/// A bridge that forwards all frames between the ethernet MAC and
+/// another [`Interface`].
+#[derive(Debug, Default, Clone)]
+pub struct Bridge {
+    stats: BridgeStats,
+}
+
+impl Bridge {
+    /// Create a new bridge.
+    pub const fn new() -> Self {
+        Self {
+            stats: BridgeStats {
+                to_other: 0,
+                to_ethernet: 0,
+                dropped: 0,
+            },
+        }
+    }
+
+    /// The counters of this bridge.
+    pub fn stats(&self) -> BridgeStats {
+        self.stats
+    }
+
+    /// Forward all frames that can be forwarded without blocking,
+    /// in both directions.
+    ///
+    /// This function should be called whenever a frame is received or a
+    /// TX slot frees up on either interface.
+    pub fn poll<E, O>(&mut self, ethernet: &mut E, other: &mut O)
+    where
+        E: Interface,
+        O: Interface,
+    {
+        let (forwarded, dropped) = forward(ethernet, other);
+        self.stats.to_other = self.stats.to_other.wrapping_add(forwarded);
+        self.stats.dropped = self.stats.dropped.wrapping_add(dropped);
+
+        let (forwarded, dropped) = forward(other, ethernet);
+        self.stats.to_ethernet = self.stats.to_ethernet.wrapping_add(forwarded);
+        self.stats.dropped = self.stats.dropped.wrapping_add(dropped);
+    }
+}
+
+/// Forward frames from `from` to `to` until either no more frames are
+/// available, or `to` can not transmit any more frames.
+///
+/// Returns the amount of forwarded and dropped frames.
+pub fn forward<F, T>(from: &mut F, to: &mut T) -> (u32, u32)
+where
+    F: Interface,
+    T: Interface,
+{
+    let mut forwarded = 0;
+    let mut dropped = 0;
+
+    while to.can_transmit() {
+        let max_len = to.max_frame_len();
+        let sent = from.receive(|frame| {
+            frame.len() <= max_len && to.transmit(frame.len(), |buf| buf.copy_from_slice(frame))
+        });
+
+        match sent {
+            Some(true) => forwarded += 1,
+            Some(false) => dropped += 1,
+            None => break,
+        }
+    }
+
+    (forwarded, dropped)
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    /// An interface with room for a single frame in each direction.
+    struct Loopback {
+        rx: Option<([u8; 64], usize)>,
+        tx: Option<([u8; 64], usize)>,
+    }
+
+    impl Interface for Loopback {
+        fn max_frame_len(&self) -> usize {
+            64
+        }
+
+        fn can_transmit(&mut self) -> bool {
+            self.tx.is_none()
+        }
+
+        fn transmit<F>(&mut self, length: usize, f: F) -> bool
+        where
+            F: FnOnce(&mut [u8]),
+        {
+            let mut buf = [0u8; 64];
+            f(&mut buf[..length]);
+            self.tx = Some((buf, length));
+            true
+        }
+
+        fn receive<R, F>(&mut self, f: F) -> Option<R>
+        where
+            F: FnOnce(&[u8]) -> R,
+        {
+            self.rx.take().map(|(buf, len)| f(&buf[..len]))
+        }
+    }
+
+    #[test]
+    fn forwards_in_both_directions() {
+        let mut a = Loopback {
+            rx: Some(([0xAA; 64], 60)),
+            tx: None,
+        };
+        let mut b = Loopback {
+            rx: Some(([0xBB; 64], 42)),
+            tx: None,
+        };
+
+        let mut bridge = Bridge::new();
+        bridge.poll(&mut a, &mut b);
+
+        assert_eq!(b.tx.map(|(buf, len)| (buf[0], len)), Some((0xAA, 60)));
+        assert_eq!(a.tx.map(|(buf, len)| (buf[0], len)), Some((0xBB, 42)));
+        assert_eq!(
+            bridge.stats(),
+            BridgeStats {
+                to_other: 1,
+                to_ethernet: 1,
+                dropped: 0,
+            }
+        );
+
+        // `b` can not transmit until its pending frame is taken
+        a.rx = Some(([0xCC; 64], 60));
+        bridge.poll(&mut a, &mut b);
+        assert!(a.rx.is_some());
+    }
+}
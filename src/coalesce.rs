@@ -0,0 +1,396 @@
+//! Coalescing of received TCP segments.
+//!
+//! During bulk downloads, most received frames are in-order TCP segments of
+//! a single flow. A [`Coalescer`] merges consecutive segments of the same
+//! flow into one larger IPv4 packet before it is handed to the IP stack,
+//! which reduces the per-packet overhead of the stack.
+//!
+//! Only IPv4 TCP segments without IP options, which carry data, and which
+//! only have the `ACK` and `PSH` flags set, are merged. A segment is
+//! only merged with the previous one if all of their headers match, apart
+//! from the IP identification, the checksums, and the sequence number,
+//! which must directly follow the data of the previous segment. All other
+//! frames are passed through unmodified, in the order they were received.
+//!
+//! The IP and TCP checksums of merged packets are recomputed, so the IP
+//! stack does not have to be configured any differently.
+//!
+//! ```rust,no_run
+//! # fn handle(frame: &[u8]) {}
+//! # fn example(dma: &mut stm32_eth::dma::EthernetDMA) {
+//! use stm32_eth::coalesce::Coalescer;
+//!
+//! let mut coalescer = Coalescer::<8192>::new();
+//!
+//! while let Ok(packet) = dma.recv_next(None) {
+//!     coalescer.push(&packet, handle);
+//! }
+//! // Deliver the last merged packet once no more frames are available.
+//! coalescer.flush(handle);
+//! # }
+//! ```
+
+use crate::dma::ETH_HEADER_LEN;
+
+/// The length of an IPv4 header without options.
+const IP_HEADER_LEN: usize = 20;
+/// The offset of the TCP header.
+const TCP_OFFSET: usize = ETH_HEADER_LEN + IP_HEADER_LEN;
+
+const ETHERTYPE_IPV4: [u8; 2] = [0x08, 0x00];
+const IP_PROTOCOL_TCP: u8 = 6;
+
+const TCP_FLAG_PSH: u8 = 1 << 3;
+const TCP_FLAG_ACK: u8 = 1 << 4;
+
+/// Counters of a [`Coalescer`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CoalesceStats {
+    /// The amount of segments that were merged into a previous segment.
+    pub merged: u32,
+    /// The amount of frames that were delivered to the IP stack.
+    pub delivered: u32,
+}
+
+/// A TCP segment that is a candidate for merging.
+struct Segment<'a> {
+    /// The headers, from the start of the ethernet header up to
+    /// the end of the TCP header.
+    headers: &'a [u8],
+    payload: &'a [u8],
+    seq: u32,
+    flags: u8,
+}
+
+impl<'a> Segment<'a> {
+    fn parse(frame: &'a [u8]) -> Option<Self> {
+        if frame.len() < TCP_OFFSET + 20 || frame[12..14] != ETHERTYPE_IPV4 {
+            return None;
+        }
+
+        let ip = &frame[ETH_HEADER_LEN..];
+        let fragmented = u16::from_be_bytes([ip[6], ip[7]]) & 0x3FFF != 0;
+        if ip[0] != 0x45 || ip[9] != IP_PROTOCOL_TCP || fragmented {
+            return None;
+        }
+
+        let total_len = u16::from_be_bytes([ip[2], ip[3]]) as usize;
+        let tcp_header_len = ((frame[TCP_OFFSET + 12] >> 4) as usize) * 4;
+        if tcp_header_len < 20 || ETH_HEADER_LEN + total_len > frame.len() {
+            return None;
+        }
+
+        let headers_len = TCP_OFFSET + tcp_header_len;
+        let end = ETH_HEADER_LEN + total_len;
+        if headers_len >= end {
+            return None;
+        }
+
+        let tcp = &frame[TCP_OFFSET..];
+        let flags = tcp[13];
+        if tcp[12] & 0x0F != 0 || flags & !TCP_FLAG_PSH != TCP_FLAG_ACK {
+            return None;
+        }
+
+        Some(Self {
+            headers: &frame[..headers_len],
+            payload: &frame[headers_len..end],
+            seq: u32::from_be_bytes([tcp[4], tcp[5], tcp[6], tcp[7]]),
+            flags,
+        })
+    }
+
+    /// Check whether the headers of this segment match `headers`,
+    /// ignoring the fields that differ between consecutive segments.
+    fn matches(&self, headers: &[u8]) -> bool {
+        const IP_ID: usize = ETH_HEADER_LEN + 4;
+        const IP_CHECKSUM: usize = ETH_HEADER_LEN + 10;
+
+        let ignored = |i: usize| {
+            (ETH_HEADER_LEN + 2..ETH_HEADER_LEN + 2 + 2).contains(&i)
+                || (IP_ID..IP_ID + 2).contains(&i)
+                || (IP_CHECKSUM..IP_CHECKSUM + 2).contains(&i)
+                || (TCP_OFFSET + 4..TCP_OFFSET + 8).contains(&i)
+                || i == TCP_OFFSET + 13
+                || (TCP_OFFSET + 16..TCP_OFFSET + 18).contains(&i)
+        };
+
+        self.headers.len() == headers.len()
+            && self
+                .headers
+                .iter()
+                .zip(headers)
+                .enumerate()
+                .all(|(i, (a, b))| a == b || ignored(i))
+    }
+}
+
+/// Merges consecutive in-order TCP segments of the same flow into
+/// packets of at most `N` bytes, including the ethernet header.
+///
+/// `N` should be a multiple of the maximum segment size, plus
+/// the length of the headers.
+pub struct Coalescer<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+    headers_len: usize,
+    next_seq: u32,
+    segments: u32,
+    stats: CoalesceStats,
+}
+
+impl<const N: usize> Default for Coalescer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Coalescer<N> {
+    /// Create a new coalescer.
+    pub const fn new() -> Self {
+        Self {
+            buf: [0; N],
+            len: 0,
+            headers_len: 0,
+            next_seq: 0,
+            segments: 0,
+            stats: CoalesceStats {
+                merged: 0,
+                delivered: 0,
+            },
+        }
+    }
+
+    /// The counters of this coalescer.
+    pub fn stats(&self) -> CoalesceStats {
+        self.stats
+    }
+
+    /// Check whether no packet is pending delivery.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Process a received `frame`.
+    ///
+    /// `deliver` is called with every frame that is ready to be handed to
+    /// the IP stack. If `frame` can be merged with later segments, it is
+    /// kept until a segment arrives that can not be merged with it, or until
+    /// [`Coalescer::flush`] is called.
+    pub fn push<F>(&mut self, frame: &[u8], mut deliver: F)
+    where
+        F: FnMut(&[u8]),
+    {
+        let segment = match Segment::parse(frame) {
+            Some(segment) => segment,
+            None => {
+                self.flush(&mut deliver);
+                self.deliver(frame, deliver);
+                return;
+            }
+        };
+
+        if self.len != 0
+            && segment.seq == self.next_seq
+            && self.len + segment.payload.len() <= N
+            && segment.matches(&self.buf[..self.headers_len])
+        {
+            self.buf[self.len..self.len + segment.payload.len()].copy_from_slice(segment.payload);
+            self.len += segment.payload.len();
+            self.next_seq = self.next_seq.wrapping_add(segment.payload.len() as u32);
+            self.segments += 1;
+            self.stats.merged = self.stats.merged.wrapping_add(1);
+
+            if segment.flags & TCP_FLAG_PSH != 0 {
+                self.buf[TCP_OFFSET + 13] |= TCP_FLAG_PSH;
+                self.flush(deliver);
+            }
+            return;
+        }
+
+        self.flush(&mut deliver);
+
+        let len = segment.headers.len() + segment.payload.len();
+        if segment.flags & TCP_FLAG_PSH != 0 || len > N {
+            self.deliver(frame, deliver);
+            return;
+        }
+
+        self.buf[..len].copy_from_slice(&frame[..len]);
+        self.len = len;
+        self.headers_len = segment.headers.len();
+        self.next_seq = segment.seq.wrapping_add(segment.payload.len() as u32);
+        self.segments = 1;
+    }
+
+    /// Deliver the pending packet, if there is one.
+    ///
+    /// This function should be called once no more frames are available,
+    /// so that the last segments are not held back.
+    pub fn flush<F>(&mut self, deliver: F)
+    where
+        F: FnOnce(&[u8]),
+    {
+        if self.len == 0 {
+            return;
+        }
+
+        if self.segments > 1 {
+            self.update_headers();
+        }
+
+        let len = core::mem::replace(&mut self.len, 0);
+        self.stats.delivered = self.stats.delivered.wrapping_add(1);
+        deliver(&self.buf[..len]);
+    }
+
+    fn deliver<F>(&mut self, frame: &[u8], deliver: F)
+    where
+        F: FnOnce(&[u8]),
+    {
+        self.stats.delivered = self.stats.delivered.wrapping_add(1);
+        deliver(frame);
+    }
+
+    /// Update the IP total length, and recompute the
+    /// checksums of the pending packet.
+    fn update_headers(&mut self) {
+        let ip_len = (self.len - ETH_HEADER_LEN) as u16;
+        let tcp_len = (self.len - TCP_OFFSET) as u16;
+
+        let ip = &mut self.buf[ETH_HEADER_LEN..TCP_OFFSET];
+        ip[2..4].copy_from_slice(&ip_len.to_be_bytes());
+        ip[10..12].copy_from_slice(&[0, 0]);
+        let checksum = !fold(sum(ip));
+        ip[10..12].copy_from_slice(&checksum.to_be_bytes());
+
+        // The pseudo header: source and destination address,
+        // protocol, and TCP length.
+        let pseudo = sum(&self.buf[ETH_HEADER_LEN + 12..TCP_OFFSET])
+            + IP_PROTOCOL_TCP as u32
+            + tcp_len as u32;
+
+        let tcp = &mut self.buf[TCP_OFFSET..self.len];
+        tcp[16..18].copy_from_slice(&[0, 0]);
+        let checksum = !fold(pseudo + sum(tcp));
+        tcp[16..18].copy_from_slice(&checksum.to_be_bytes());
+    }
+}
+
+/// The ones' complement sum of `data`, as 16 bit big endian
+/// words, before folding.
+fn sum(data: &[u8]) -> u32 {
+    let mut chunks = data.chunks_exact(2);
+    let mut sum = chunks
+        .by_ref()
+        .map(|w| u16::from_be_bytes([w[0], w[1]]) as u32)
+        .fold(0u32, |acc, w| acc.wrapping_add(w));
+
+    if let [last] = chunks.remainder() {
+        sum = sum.wrapping_add((*last as u32) << 8);
+    }
+    sum
+}
+
+/// Fold a ones' complement sum into 16 bits.
+fn fold(mut sum: u32) -> u16 {
+    while sum > 0xFFFF {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    sum as u16
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    /// Build a TCP segment with valid checksums.
+    fn segment(seq: u32, flags: u8, payload: &[u8]) -> ([u8; 128], usize) {
+        let mut frame = [0u8; 128];
+        let len = TCP_OFFSET + 20 + payload.len();
+
+        frame[..6].copy_from_slice(&[0x02, 0, 0, 0, 0, 1]);
+        frame[6..12].copy_from_slice(&[0x02, 0, 0, 0, 0, 2]);
+        frame[12..14].copy_from_slice(&ETHERTYPE_IPV4);
+
+        let ip = &mut frame[ETH_HEADER_LEN..];
+        ip[0] = 0x45;
+        ip[2..4].copy_from_slice(&((len - ETH_HEADER_LEN) as u16).to_be_bytes());
+        ip[4..6].copy_from_slice(&(seq as u16).to_be_bytes());
+        ip[8] = 64;
+        ip[9] = IP_PROTOCOL_TCP;
+        ip[12..16].copy_from_slice(&[10, 0, 0, 1]);
+        ip[16..20].copy_from_slice(&[10, 0, 0, 2]);
+
+        let tcp = &mut frame[TCP_OFFSET..];
+        tcp[0..2].copy_from_slice(&80u16.to_be_bytes());
+        tcp[2..4].copy_from_slice(&50000u16.to_be_bytes());
+        tcp[4..8].copy_from_slice(&seq.to_be_bytes());
+        tcp[8..12].copy_from_slice(&1u32.to_be_bytes());
+        tcp[12] = 5 << 4;
+        tcp[13] = flags;
+        tcp[14..16].copy_from_slice(&1024u16.to_be_bytes());
+        tcp[20..20 + payload.len()].copy_from_slice(payload);
+
+        let mut coalescer = Coalescer::<128>::new();
+        coalescer.buf.copy_from_slice(&frame);
+        coalescer.len = len;
+        coalescer.update_headers();
+        (coalescer.buf, len)
+    }
+
+    fn is_valid(frame: &[u8]) -> bool {
+        let pseudo = sum(&frame[ETH_HEADER_LEN + 12..TCP_OFFSET])
+            + IP_PROTOCOL_TCP as u32
+            + (frame.len() - TCP_OFFSET) as u32;
+
+        fold(sum(&frame[ETH_HEADER_LEN..TCP_OFFSET])) == 0xFFFF
+            && fold(pseudo + sum(&frame[TCP_OFFSET..])) == 0xFFFF
+    }
+
+    #[test]
+    fn merges_in_order_segments() {
+        let mut coalescer = Coalescer::<256>::new();
+        let mut delivered = [([0u8; 256], 0usize); 4];
+        let mut count = 0;
+        let mut deliver = |frame: &[u8]| {
+            delivered[count].0[..frame.len()].copy_from_slice(frame);
+            delivered[count].1 = frame.len();
+            count += 1;
+        };
+
+        let (a, a_len) = segment(1000, TCP_FLAG_ACK, &[1; 10]);
+        let (b, b_len) = segment(1010, TCP_FLAG_ACK, &[2; 10]);
+        let (c, c_len) = segment(1020, TCP_FLAG_ACK | TCP_FLAG_PSH, &[3; 5]);
+        // Out of order
+        let (d, d_len) = segment(2000, TCP_FLAG_ACK, &[4; 10]);
+
+        coalescer.push(&a[..a_len], &mut deliver);
+        coalescer.push(&b[..b_len], &mut deliver);
+        coalescer.push(&c[..c_len], &mut deliver);
+        coalescer.push(&d[..d_len], &mut deliver);
+        coalescer.push(&[0xFF; 60], &mut deliver);
+        assert!(coalescer.is_empty());
+
+        assert_eq!(count, 3);
+        let (merged, len) = &delivered[0];
+        let merged = &merged[..*len];
+        assert_eq!(merged.len(), TCP_OFFSET + 20 + 25);
+        assert!(is_valid(merged));
+        assert_eq!(merged[TCP_OFFSET + 13], TCP_FLAG_ACK | TCP_FLAG_PSH);
+        assert_eq!(&merged[TCP_OFFSET + 20..TCP_OFFSET + 30], &[1; 10]);
+        assert_eq!(&merged[TCP_OFFSET + 40..], &[3; 5]);
+
+        assert_eq!(&delivered[1].0[..delivered[1].1], &d[..d_len]);
+        assert_eq!(delivered[2].1, 60);
+
+        assert_eq!(
+            coalescer.stats(),
+            CoalesceStats {
+                merged: 2,
+                delivered: 3,
+            }
+        );
+    }
+}
@@ -0,0 +1,103 @@
+//! The CRC-32 that is used as the frame check sequence (FCS) of Ethernet frames.
+//!
+//! This implementation is table-free, so it is small, but processes one
+//! bit at a time. It is used to compute the index of an address in the
+//! [`HashTable`](crate::mac::frame_filtering::HashTable), to verify received
+//! frames (see [`RxRing::start_crc_check`](crate::dma::RxRing::start_crc_check)),
+//! and to append an FCS to transmitted frames in software (see
+//! [`TxPacket::append_fcs`](crate::dma::TxPacket::append_fcs)).
+
+/// The length of the FCS of an Ethernet frame, in bytes.
+pub const FCS_LEN: usize = 4;
+
+/// The (reflected) Ethernet CRC-32 polynomial.
+const POLYNOMIAL: u32 = 0xEDB8_8320;
+
+/// The Ethernet CRC-32 of `data`.
+pub const fn crc32(data: &[u8]) -> u32 {
+    !update(0xFFFF_FFFF, data)
+}
+
+/// The FCS of `data`, in the order in which it is transmitted
+/// after the frame.
+pub const fn fcs(data: &[u8]) -> [u8; FCS_LEN] {
+    crc32(data).to_le_bytes()
+}
+
+/// Feed `data` into the (non-inverted) CRC `state`.
+const fn update(mut state: u32, data: &[u8]) -> u32 {
+    let mut i = 0;
+    while i < data.len() {
+        state ^= data[i] as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            let mask = (state & 1).wrapping_neg();
+            state = (state >> 1) ^ (POLYNOMIAL & mask);
+            bit += 1;
+        }
+        i += 1;
+    }
+    state
+}
+
+/// A CRC-32 that is calculated over several consecutive parts of
+/// a frame, such as the segments of an
+/// [`RxSegmentedPacket`](crate::dma::RxSegmentedPacket).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Crc32 {
+    state: u32,
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Crc32 {
+    /// A CRC-32 over no data.
+    pub const fn new() -> Self {
+        Self { state: 0xFFFF_FFFF }
+    }
+
+    /// Feed the next part of the data into the CRC.
+    pub fn update(&mut self, data: &[u8]) {
+        self.state = update(self.state, data);
+    }
+
+    /// The CRC-32 of all data that was fed into this CRC.
+    pub const fn finish(&self) -> u32 {
+        !self.state
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn incremental() {
+        let mut crc = Crc32::new();
+        crc.update(b"1234");
+        crc.update(b"");
+        crc.update(b"56789");
+        assert_eq!(crc.finish(), crc32(b"123456789"));
+    }
+
+    #[test]
+    fn fcs_of_frame_has_known_residue() {
+        // Appending the FCS to a frame results in the well-known residue.
+        let mut frame = [0u8; 64];
+        for (i, byte) in frame[..60].iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        let fcs = fcs(&frame[..60]);
+        frame[60..].copy_from_slice(&fcs);
+        assert_eq!(crc32(&frame), 0x2144_DF1C);
+    }
+}
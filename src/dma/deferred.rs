@@ -0,0 +1,102 @@
+//! Deferred clearing of the DMA status, for integrations in which the
+//! `ETH` interrupt is shared with other code that inspects `DMASR`.
+
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use super::{EthernetDMA, InterruptReasonSummary, DMASR_HANDLED};
+use crate::peripherals::ETHERNET_DMA;
+
+/// Normal interrupt summary enable
+const DMAIER_NISE: u32 = 1 << 16;
+/// Abnormal interrupt summary enable
+const DMAIER_AISE: u32 = 1 << 15;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static PENDING: AtomicU32 = AtomicU32::new(0);
+/// The summary interrupt enable bits that the interrupt handler cleared.
+static MASKED: AtomicU32 = AtomicU32::new(0);
+
+pub(super) fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Record `status` without clearing it, mask the DMA interrupts until it
+/// is cleared, and wake the wakers of the rings that it concerns.
+pub(super) fn record(status: u32) -> InterruptReasonSummary {
+    PENDING.fetch_or(status, Ordering::Relaxed);
+    mask();
+
+    let summary = InterruptReasonSummary::from_status(status);
+    EthernetDMA::wake(&summary);
+    summary
+}
+
+/// Disable the summary interrupts of the DMA, so that the `ETH` interrupt
+/// does not fire again while the status bits remain set.
+fn mask() {
+    // SAFETY: we only clear interrupt enable bits, which are restored by `unmask`.
+    let eth_dma = unsafe { &*ETHERNET_DMA::ptr() };
+
+    let enabled = eth_dma.dmaier.read().bits() & (DMAIER_NISE | DMAIER_AISE);
+    MASKED.fetch_or(enabled, Ordering::Relaxed);
+    eth_dma
+        .dmaier
+        .modify(|r, w| unsafe { w.bits(r.bits() & !enabled) });
+}
+
+/// Enable the summary interrupts that were disabled by `mask` again.
+fn unmask() {
+    // SAFETY: we only set interrupt enable bits that were set before `mask`.
+    let eth_dma = unsafe { &*ETHERNET_DMA::ptr() };
+
+    cortex_m::interrupt::free(|_| {
+        let masked = MASKED.swap(0, Ordering::Relaxed);
+        eth_dma
+            .dmaier
+            .modify(|r, w| unsafe { w.bits(r.bits() | masked) });
+    });
+}
+
+impl EthernetDMA<'_, '_> {
+    /// Enable or disable deferred clearing of the DMA status.
+    ///
+    /// By default, [`EthernetDMA::interrupt_handler`] (and therefore
+    /// [`eth_interrupt_handler`](crate::eth_interrupt_handler)) counts and
+    /// clears the DMA status bits in `DMASR`. If the `ETH` interrupt is
+    /// shared with other code that also inspects `DMASR`, such as a vendor
+    /// TCP offload library, this clearing hides the status from that code.
+    ///
+    /// With deferred clearing enabled, the interrupt handler only records the
+    /// status atomically and wakes the wakers of the rings. The recorded
+    /// status must then be counted and cleared from thread context using
+    /// [`EthernetDMA::complete_deferred_interrupt`]. As the status bits
+    /// remain set until then, the interrupt handler also disables the normal
+    /// and abnormal summary interrupts of the DMA, so that the `ETH` interrupt
+    /// does not fire again before thread context gets to run.
+    /// [`EthernetDMA::complete_deferred_interrupt`] enables them again.
+    ///
+    /// Only the status of the DMA is deferred: PTP and MAC interrupts
+    /// are handled as usual.
+    pub fn set_deferred_status_clearing(enabled: bool) {
+        ENABLED.store(enabled, Ordering::Relaxed);
+
+        if !enabled {
+            unmask();
+        }
+    }
+
+    /// Count and clear the DMA status that was recorded by the interrupt
+    /// handler since the previous call, together with the current status,
+    /// and enable the DMA interrupts again.
+    ///
+    /// See [`EthernetDMA::set_deferred_status_clearing`].
+    pub fn complete_deferred_interrupt() -> InterruptReasonSummary {
+        // SAFETY: we only perform an atomic read of `dmasr`.
+        let eth_dma = unsafe { &*ETHERNET_DMA::ptr() };
+
+        let status = PENDING.swap(0, Ordering::Relaxed) | eth_dma.dmasr.read().bits();
+        let summary = Self::process_status(status & DMASR_HANDLED);
+        unmask();
+        summary
+    }
+}
@@ -9,6 +9,10 @@ const DESC_SIZE: usize = 8;
 #[cfg(feature = "stm32f1xx-hal")]
 const DESC_SIZE: usize = 4;
 
+/// The OWN bit, which is bit 31 of the first word of both RX and TX descriptors.
+#[cfg(not(any(feature = "rx-only", feature = "tx-only")))]
+const DESC_0_OWN: u32 = 1 << 31;
+
 #[repr(C)]
 pub struct Descriptor {
     pub(crate) desc: Aligned<A8, [u32; DESC_SIZE]>,
@@ -35,6 +39,7 @@ impl Descriptor {
         }
     }
 
+    #[cfg(not(feature = "rx-only"))]
     pub unsafe fn clear(&mut self) {
         (0..DESC_SIZE).for_each(|i| self.write(i, 0));
     }
@@ -64,3 +69,39 @@ impl Descriptor {
         self.rw(n).modify(f)
     }
 }
+
+/// The descriptor that the DMA uses to transmit a frame from a buffer
+/// that belongs to another ring.
+///
+/// The owner of the buffer keeps this, so that it can check whether the
+/// DMA is done with the buffer without access to the ring of the descriptor.
+#[cfg(not(any(feature = "rx-only", feature = "tx-only")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Borrower {
+    // The address of the descriptor is not stored as a pointer,
+    // so that the owning ring remains `Send`.
+    desc: usize,
+    buffer: u32,
+}
+
+#[cfg(not(any(feature = "rx-only", feature = "tx-only")))]
+impl Borrower {
+    /// The borrower of `buffer` that was handed to the DMA with `desc`.
+    pub(crate) fn new(desc: &Descriptor, buffer: u32) -> Self {
+        Self {
+            desc: desc as *const Descriptor as usize,
+            buffer,
+        }
+    }
+
+    /// Whether the DMA is done with the borrowed buffer.
+    pub(crate) fn is_done(&self) -> bool {
+        // SAFETY: both rings belong to the same `EthernetDMA`, so the
+        // descriptor outlives the borrowed buffer. It is only read.
+        let desc = unsafe { &*(self.desc as *const Descriptor) };
+
+        // Once the DMA is done, the descriptor may already have
+        // been reused for a frame in another buffer.
+        desc.read(0) & DESC_0_OWN == 0 || desc.read(2) != self.buffer
+    }
+}
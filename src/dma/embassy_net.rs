@@ -0,0 +1,134 @@
+use core::task::Context;
+
+use embassy_net_driver::{
+    Capabilities, Checksum, Driver, HardwareAddress, LinkState, RxToken, TxToken,
+};
+
+use super::{
+    rx::RxRing,
+    tx::{ChecksumInsertion, TxRing},
+    EthernetDMA, MTU,
+};
+use crate::mac::Mac;
+
+/// Use this Ethernet driver with [embassy-net](https://github.com/embassy-rs/embassy).
+///
+/// The DMA wakers are woken by [`eth_interrupt_handler`](crate::eth_interrupt_handler),
+/// so it must be called from the `ETH` interrupt. The link state is taken from
+/// [`ready::is_ready`](crate::ready::is_ready), so a [`ReadyMonitor`](crate::ready::ReadyMonitor)
+/// must be updated periodically for the link to come up.
+pub struct EmbassyDriver<'dma, 'rx, 'tx, const RX_BUF: usize = MTU> {
+    dma: &'dma mut EthernetDMA<'rx, 'tx, RX_BUF>,
+    address: Mac,
+}
+
+impl<'dma, 'rx, 'tx, const RX_BUF: usize> EmbassyDriver<'dma, 'rx, 'tx, RX_BUF> {
+    /// Create a new driver for `dma`, which reports `address` as its
+    /// hardware address.
+    pub fn new(dma: &'dma mut EthernetDMA<'rx, 'tx, RX_BUF>, address: Mac) -> Self {
+        Self { dma, address }
+    }
+}
+
+impl<'dma, 'rx, 'tx, const RX_BUF: usize> Driver for EmbassyDriver<'dma, 'rx, 'tx, RX_BUF> {
+    type RxToken<'token> = EmbassyRxToken<'token, 'rx, RX_BUF> where Self: 'token;
+    type TxToken<'token> = EmbassyTxToken<'token, 'tx> where Self: 'token;
+
+    fn receive(&mut self, cx: &mut Context) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        EthernetDMA::rx_waker().register(cx.waker());
+        EthernetDMA::tx_waker().register(cx.waker());
+
+        if self.dma.rx_available() && self.dma.tx_available() {
+            let EthernetDMA {
+                rx_ring, tx_ring, ..
+            } = &mut *self.dma;
+            Some((EmbassyRxToken { rx_ring }, EmbassyTxToken { tx_ring }))
+        } else {
+            None
+        }
+    }
+
+    fn transmit(&mut self, cx: &mut Context) -> Option<Self::TxToken<'_>> {
+        EthernetDMA::tx_waker().register(cx.waker());
+
+        if self.dma.tx_available() {
+            let EthernetDMA { tx_ring, .. } = &mut *self.dma;
+            Some(EmbassyTxToken { tx_ring })
+        } else {
+            None
+        }
+    }
+
+    fn link_state(&mut self, cx: &mut Context) -> LinkState {
+        crate::ready::register_waker(cx.waker());
+
+        if crate::ready::is_ready() {
+            LinkState::Up
+        } else {
+            LinkState::Down
+        }
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        let mut caps = Capabilities::default();
+        // Received frames must fit in a single RX buffer.
+        caps.max_transmission_unit = MTU.min(RX_BUF);
+        caps.max_burst_size = Some(1);
+        // Received checksums are verified by the MAC, but transmitted
+        // checksums that the MAC does not insert must be calculated.
+        let (header, payload) = match self.dma.tx_ring.checksum_insertion() {
+            ChecksumInsertion::Full => (Checksum::None, Checksum::None),
+            ChecksumInsertion::IpHeader => (Checksum::None, Checksum::Tx),
+            ChecksumInsertion::Disabled => (Checksum::Tx, Checksum::Tx),
+        };
+        caps.checksum.ipv4 = header;
+        caps.checksum.udp = payload;
+        caps.checksum.tcp = payload;
+        caps.checksum.icmpv4 = payload;
+        caps.checksum.icmpv6 = payload;
+        caps
+    }
+
+    fn hardware_address(&self) -> HardwareAddress {
+        HardwareAddress::Ethernet(*self.address.raw())
+    }
+}
+
+/// An embassy-net RX token, which processes the received frame in
+/// place in the buffer of the RX descriptor.
+pub struct EmbassyRxToken<'a, 'rx, const N: usize = MTU> {
+    rx_ring: &'a mut RxRing<'rx, N>,
+}
+
+impl<const N: usize> RxToken for EmbassyRxToken<'_, '_, N> {
+    fn consume<R, F>(self, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        // NOTE(unwrap): an `EmbassyRxToken` is only created when `rx_available()`
+        let mut packet = self.rx_ring.recv_next(None).ok().unwrap();
+        let result = f(&mut packet);
+        packet.free();
+        result
+    }
+}
+
+/// An embassy-net TX token, which writes the frame directly into the
+/// buffer of the TX descriptor.
+pub struct EmbassyTxToken<'a, 'tx> {
+    tx_ring: &'a mut TxRing<'tx>,
+}
+
+impl TxToken for EmbassyTxToken<'_, '_> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        // NOTE(unwrap): an `EmbassyTxToken` is only created if
+        // there is a descriptor available for sending.
+        let mut tx_packet = self.tx_ring.send_next(len, None).ok().unwrap();
+        let result = f(&mut tx_packet);
+        tx_packet.send();
+        result
+    }
+}
@@ -0,0 +1,280 @@
+//! Weighted, interleaved processing of received frames and TX completions.
+
+use cortex_m::peripheral::NVIC;
+
+use super::{EthernetDMA, RxError, RxPacket, TxCompletion};
+use crate::stm32::Interrupt;
+
+/// The weights with which [`EthernetDMA::process_fair`] interleaves
+/// received frames and TX completions.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProcessingWeights {
+    /// The maximum amount of received frames that are processed
+    /// before TX completions are reclaimed.
+    pub rx: usize,
+    /// The maximum amount of TX completions that are reclaimed
+    /// before received frames are processed.
+    pub tx: usize,
+    /// The maximum amount of frames and completions that are processed
+    /// in total, or `None` to process until both rings are idle.
+    pub budget: Option<usize>,
+}
+
+impl ProcessingWeights {
+    /// Alternate between single received frames and single
+    /// TX completions, until both rings are idle.
+    pub const EQUAL: Self = Self {
+        rx: 1,
+        tx: 1,
+        budget: None,
+    };
+}
+
+impl Default for ProcessingWeights {
+    fn default() -> Self {
+        Self::EQUAL
+    }
+}
+
+/// The work that was done by [`EthernetDMA::process_fair`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProcessingSummary {
+    /// The amount of received frames that were passed on.
+    pub received: usize,
+    /// The amount of received frames that were dropped because
+    /// they were truncated or had a DMA error.
+    pub rx_errors: usize,
+    /// The amount of TX completions that were reclaimed.
+    pub reclaimed: usize,
+    /// Whether processing stopped because the budget was used up,
+    /// rather than because both rings were idle.
+    pub budget_exhausted: bool,
+}
+
+/// The result of a single step of one of the rings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Done,
+    Failed,
+    Idle,
+}
+
+/// The smaller of two budgets, where `None` is unlimited.
+fn min_budget(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+/// Alternate between `rx` and `tx` according to `weights`, until both
+/// are idle or the budget is used up.
+fn interleave<R, T>(weights: &ProcessingWeights, mut rx: R, mut tx: T) -> ProcessingSummary
+where
+    R: FnMut() -> Step,
+    T: FnMut() -> bool,
+{
+    let mut summary = ProcessingSummary::default();
+    let mut remaining = weights.budget.unwrap_or(usize::MAX);
+
+    loop {
+        let mut progress = false;
+
+        let mut rx_round = 0;
+        while rx_round < weights.rx && remaining > 0 {
+            match rx() {
+                Step::Done => summary.received += 1,
+                Step::Failed => summary.rx_errors += 1,
+                Step::Idle => break,
+            }
+            rx_round += 1;
+            remaining -= 1;
+            progress = true;
+        }
+
+        let mut tx_round = 0;
+        while tx_round < weights.tx && remaining > 0 {
+            if !tx() {
+                break;
+            }
+            summary.reclaimed += 1;
+            tx_round += 1;
+            remaining -= 1;
+            progress = true;
+        }
+
+        if !progress {
+            return summary;
+        }
+
+        if remaining == 0 {
+            summary.budget_exhausted = true;
+            return summary;
+        }
+    }
+}
+
+impl<'rx, 'tx, const RX_BUF: usize> EthernetDMA<'rx, 'tx, RX_BUF> {
+    /// Process received frames and reclaim TX completions in an
+    /// interleaved fashion.
+    ///
+    /// Up to `weights.rx` received frames are passed to `on_rx`, followed
+    /// by up to `weights.tx` TX completions that are passed to `on_tx`,
+    /// and so on, until both rings are idle or `weights.budget` is used up.
+    /// Unlike draining the RX ring first, this guarantees that a flood of
+    /// received frames cannot delay the reclamation of TX descriptors
+    /// indefinitely.
+    ///
+    /// Received frames that are truncated or have a DMA error are dropped,
+    /// and counted in [`ProcessingSummary::rx_errors`].
+    pub fn process_fair<R, T>(
+        &mut self,
+        weights: &ProcessingWeights,
+        mut on_rx: R,
+        mut on_tx: T,
+    ) -> ProcessingSummary
+    where
+        R: FnMut(RxPacket<'_, RX_BUF>),
+        T: FnMut(TxCompletion),
+    {
+        let rx_ring = &mut self.rx_ring;
+        let tx_ring = &mut self.tx_ring;
+
+        interleave(
+            weights,
+            || match rx_ring.recv_next(None) {
+                Ok(packet) => {
+                    on_rx(packet);
+                    Step::Done
+                }
+                Err(RxError::WouldBlock) => Step::Idle,
+                Err(_) => Step::Failed,
+            },
+            || match tx_ring.poll_completion() {
+                Some(completion) => {
+                    on_tx(completion);
+                    true
+                }
+                None => false,
+            },
+        )
+    }
+
+    /// Limit the amount of received frames and TX completions that
+    /// [`EthernetDMA::process_interrupt`] processes per call, or `None`
+    /// to process until both rings are idle.
+    ///
+    /// This bounds the time that is spent in the `ETH` interrupt during an
+    /// RX flood, so that other interrupts of the same priority, such as
+    /// motor control timers, still meet their deadlines.
+    pub fn set_interrupt_budget(&mut self, budget: Option<usize>) {
+        self.interrupt_budget = budget;
+    }
+
+    /// The limit that was set with [`EthernetDMA::set_interrupt_budget`].
+    pub fn interrupt_budget(&self) -> Option<usize> {
+        self.interrupt_budget
+    }
+
+    /// Process received frames and reclaim TX completions from the `ETH`
+    /// interrupt, like [`EthernetDMA::process_fair`], but at most as many
+    /// as the [interrupt budget](EthernetDMA::set_interrupt_budget) allows.
+    ///
+    /// If the budget is used up, the `ETH` interrupt is pended again, so
+    /// that the remaining frames and completions are processed by the next
+    /// invocation, or by a poll in the meantime. Pending interrupts of the
+    /// same priority with a lower interrupt number are served first.
+    pub fn process_interrupt<R, T>(
+        &mut self,
+        weights: &ProcessingWeights,
+        on_rx: R,
+        on_tx: T,
+    ) -> ProcessingSummary
+    where
+        R: FnMut(RxPacket<'_, RX_BUF>),
+        T: FnMut(TxCompletion),
+    {
+        let weights = ProcessingWeights {
+            budget: min_budget(weights.budget, self.interrupt_budget),
+            ..*weights
+        };
+
+        let summary = self.process_fair(&weights, on_rx, on_tx);
+
+        if summary.budget_exhausted {
+            NVIC::pend(Interrupt::ETH);
+        }
+
+        summary
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn rx_flood_does_not_starve_tx() {
+        let weights = ProcessingWeights {
+            rx: 4,
+            tx: 1,
+            budget: Some(20),
+        };
+
+        let mut completions = 3;
+        let summary = interleave(
+            &weights,
+            || Step::Done,
+            || {
+                if completions > 0 {
+                    completions -= 1;
+                    true
+                } else {
+                    false
+                }
+            },
+        );
+
+        assert_eq!(completions, 0);
+        assert_eq!(summary.reclaimed, 3);
+        assert_eq!(summary.received, 17);
+        assert!(summary.budget_exhausted);
+    }
+
+    #[test]
+    fn stops_when_idle() {
+        let mut frames = 5;
+        let summary = interleave(
+            &ProcessingWeights::EQUAL,
+            || {
+                if frames > 0 {
+                    frames -= 1;
+                    if frames == 2 {
+                        Step::Failed
+                    } else {
+                        Step::Done
+                    }
+                } else {
+                    Step::Idle
+                }
+            },
+            || false,
+        );
+
+        assert_eq!(summary.received, 4);
+        assert_eq!(summary.rx_errors, 1);
+        assert_eq!(summary.reclaimed, 0);
+        assert!(!summary.budget_exhausted);
+    }
+
+    #[test]
+    fn smallest_budget_applies() {
+        assert_eq!(min_budget(None, None), None);
+        assert_eq!(min_budget(Some(8), None), Some(8));
+        assert_eq!(min_budget(None, Some(4)), Some(4));
+        assert_eq!(min_budget(Some(8), Some(4)), Some(4));
+    }
+}
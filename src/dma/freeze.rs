@@ -0,0 +1,105 @@
+//! Suspending reception during short stalls, such as flash writes.
+//!
+//! While the CPU is stalled (for instance because code executes from the
+//! flash bank that is being written), the received frames are not processed,
+//! so the RX ring fills up and subsequent frames are dropped. With
+//! [`EthernetDMA::freeze_rx`], the link partner is asked to stop transmitting
+//! instead:
+//!
+//! 1. A pause frame is transmitted, which throttles the link partner for the
+//!    configured [`FlowControlConfig::pause_time`].
+//! 2. The RX DMA is stopped, so that frames that are still in flight are kept
+//!    in the RX FIFO of the MAC instead of being written to the RX ring.
+//!
+//! When the returned [`RxFreeze`] is dropped, the RX DMA continues at the
+//! descriptor at which it stopped, and a pause frame with a pause time of zero
+//! tells the link partner to resume transmission immediately. If that pause
+//! frame can not be transmitted within a few milliseconds, for instance
+//! because the link went down, it is skipped, and the link partner resumes
+//! once the pause time expires.
+//!
+//! ```rust,ignore
+//! mac.configure_flow_control(&FlowControlConfig::symmetric(0xFFFF));
+//!
+//! let mut freeze = dma.freeze_rx();
+//! for page in pages {
+//!     write_flash_page(page);
+//!     freeze.refresh();
+//! }
+//! drop(freeze);
+//! ```
+//!
+//! No frames are lost as long as the link partner honours pause frames, the
+//! pause is refreshed before it expires, and the frames that the link partner
+//! transmitted before receiving the pause frame fit into the RX FIFO. A pause
+//! time of `0xFFFF` slot times lasts about 335 ms at 100 Mbit/s, and about
+//! 3.4 s at 10 Mbit/s.
+//!
+//! [`FlowControlConfig::pause_time`]: crate::mac::flow_control::FlowControlConfig::pause_time
+
+use super::{EthernetDMA, RingState};
+use crate::mac::{flow_control, EthernetMAC};
+
+/// A guard that keeps reception suspended until it is dropped.
+///
+/// See [`EthernetDMA::freeze_rx`].
+pub struct RxFreeze<'dma, 'rx, 'tx, const RX_BUF: usize> {
+    dma: &'dma mut EthernetDMA<'rx, 'tx, RX_BUF>,
+    paused: bool,
+    suspended: bool,
+}
+
+impl<'rx, 'tx, const RX_BUF: usize> EthernetDMA<'rx, 'tx, RX_BUF> {
+    /// Ask the link partner to pause transmission, and stop the RX DMA,
+    /// until the returned [`RxFreeze`] is dropped.
+    ///
+    /// The link partner is only paused if the transmission of pause
+    /// frames is enabled (see [`EthernetMAC::configure_flow_control`]).
+    /// Otherwise, reception is only suspended, and frames are dropped once
+    /// the RX FIFO overflows.
+    ///
+    /// The RX DMA is only suspended if it is [`RingState::Running`].
+    pub fn freeze_rx(&mut self) -> RxFreeze<'_, 'rx, 'tx, RX_BUF> {
+        // Pause the link partner first, so that as few frames as
+        // possible arrive after the RX DMA has stopped.
+        let paused = flow_control::transmit_pause_enabled();
+        if paused {
+            EthernetMAC::request_pause();
+        }
+        let suspended = self.rx_ring.state() == RingState::Running;
+        if suspended {
+            self.rx_ring.suspend(&self.eth_dma);
+        }
+
+        RxFreeze {
+            dma: self,
+            paused,
+            suspended,
+        }
+    }
+}
+
+impl<const RX_BUF: usize> RxFreeze<'_, '_, '_, RX_BUF> {
+    /// Transmit another pause frame, which restarts the pause time
+    /// of the link partner.
+    ///
+    /// This should be called more often than the pause time expires.
+    /// Returns `false` if a pause frame is still being transmitted, in
+    /// which case the pause is not restarted, or if the transmission of
+    /// pause frames is disabled.
+    pub fn refresh(&mut self) -> bool {
+        self.paused && EthernetMAC::request_pause()
+    }
+}
+
+impl<const RX_BUF: usize> Drop for RxFreeze<'_, '_, '_, RX_BUF> {
+    fn drop(&mut self) {
+        if self.suspended {
+            self.dma.rx_ring.resume(&self.dma.eth_dma);
+        }
+
+        if self.paused {
+            flow_control::send_zero_quanta_pause();
+        }
+    }
+}
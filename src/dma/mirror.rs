@@ -0,0 +1,22 @@
+/// The direction of a mirrored frame.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// The frame was received.
+    Rx,
+    /// The frame is being transmitted.
+    Tx,
+}
+
+/// A hook that is called with a borrow of every received and
+/// transmitted frame.
+///
+/// The hook is called from the context that receives or sends the frame,
+/// and should return quickly: it delays the data path. To keep a frame,
+/// it must be copied.
+///
+/// Frames that are received using [`RxRing::recv_segmented`](super::RxRing::recv_segmented)
+/// are passed to the hook one segment at a time, in order.
+///
+/// See [`EthernetDMA::set_mirror`](super::EthernetDMA::set_mirror).
+pub type Mirror = fn(Direction, &[u8]);
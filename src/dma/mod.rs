@@ -2,28 +2,74 @@
 
 use cortex_m::peripheral::NVIC;
 
-use crate::{peripherals::ETHERNET_DMA, stm32::Interrupt};
+use crate::{peripherals::ETHERNET_DMA, stm32::Interrupt, InitError};
 
 #[cfg(feature = "smoltcp-phy")]
 mod smoltcp_phy;
 #[cfg(feature = "smoltcp-phy")]
 pub use smoltcp_phy::*;
 
+#[cfg(feature = "embassy-net")]
+mod embassy_net;
+#[cfg(feature = "embassy-net")]
+pub use embassy_net::{EmbassyDriver, EmbassyRxToken, EmbassyTxToken};
+
 #[cfg(feature = "async-await")]
 use futures::task::AtomicWaker;
 
-#[cfg(any(feature = "ptp", feature = "async-await"))]
+#[cfg(any(
+    all(feature = "ptp", not(feature = "rx-only")),
+    all(
+        feature = "async-await",
+        not(any(feature = "rx-only", feature = "tx-only"))
+    )
+))]
 use core::task::Poll;
 
 pub(crate) mod desc;
 
 pub(crate) mod ring;
+pub use ring::{MAX_RING_LEN, MAX_RX_BUFFER_LEN, MIN_RING_LEN};
 
+#[cfg(not(feature = "tx-only"))]
 mod rx;
-pub use rx::{RunningState as RxRunningState, RxError, RxPacket, RxRing, RxRingEntry};
+#[cfg(all(not(feature = "tx-only"), not(feature = "stm32f1xx-hal")))]
+pub use rx::{IpPayloadType, IpVersion, RxFrameType};
+#[cfg(not(feature = "tx-only"))]
+pub use rx::{
+    RunningState as RxRunningState, RxError, RxPacket, RxPacketMeta, RxRing, RxRingEntry,
+    RxSegmentedPacket, VlanTag, VLAN_TAG_LEN, VLAN_TPID,
+};
 
+#[cfg(not(feature = "rx-only"))]
 mod tx;
-pub use tx::{RunningState as TxRunningState, TxError, TxPacket, TxRing, TxRingEntry};
+#[cfg(all(not(feature = "rx-only"), feature = "tx-classification"))]
+pub use tx::{
+    reset_tx_class_statistics, tx_class_statistics, ClassCounters, TrafficClass, TxClassStatistics,
+};
+#[cfg(not(feature = "rx-only"))]
+pub use tx::{
+    ChecksumInsertion, RunningState as TxRunningState, TxCompletion, TxError, TxPacket, TxRing,
+    TxRingEntry,
+};
+
+#[cfg(not(feature = "rx-only"))]
+mod shared_tx;
+#[cfg(not(feature = "rx-only"))]
+pub use shared_tx::{SharedTx, TxHandle, MAX_TX_HANDLES};
+
+#[cfg(not(feature = "tx-only"))]
+mod freeze;
+#[cfg(not(feature = "tx-only"))]
+pub use freeze::RxFreeze;
+
+#[cfg(all(not(feature = "rx-only"), not(feature = "tx-only")))]
+mod fair;
+#[cfg(all(not(feature = "rx-only"), not(feature = "tx-only")))]
+pub use fair::{ProcessingSummary, ProcessingWeights};
+
+#[cfg(all(not(feature = "rx-only"), not(feature = "tx-only")))]
+mod reflect;
 
 #[cfg(feature = "ptp")]
 use crate::ptp::Timestamp;
@@ -31,8 +77,60 @@ use crate::ptp::Timestamp;
 mod packet_id;
 pub use packet_id::PacketId;
 
+mod snapshot;
+pub use snapshot::{DebugSnapshot, RingPosition};
+
+mod ring_state;
+pub use ring_state::{InvalidTransition, RingFault, RingState};
+
+mod deferred;
+
+#[cfg(feature = "mirror")]
+mod mirror;
+#[cfg(feature = "mirror")]
+pub use mirror::{Direction, Mirror};
+
+/// The default size of an RX or TX buffer, in bytes.
+///
 /// From the datasheet: *VLAN Frame maxsize = 1522*
-pub(crate) const MTU: usize = 1522;
+pub const MTU: usize = 1522;
+
+/// The minimum length of an Ethernet frame, excluding the FCS.
+pub const MIN_FRAME_LEN: usize = 60;
+
+/// The length of an Ethernet header (destination address, source address
+/// and EtherType), in bytes.
+pub const ETH_HEADER_LEN: usize = 14;
+
+// The (write-1-to-clear) status bits of `DMASR`.
+const DMASR_TS: u32 = 1 << 0;
+const DMASR_TPSS: u32 = 1 << 1;
+const DMASR_TBUS: u32 = 1 << 2;
+const DMASR_TJTS: u32 = 1 << 3;
+const DMASR_ROS: u32 = 1 << 4;
+const DMASR_TUS: u32 = 1 << 5;
+const DMASR_RS: u32 = 1 << 6;
+const DMASR_RBUS: u32 = 1 << 7;
+const DMASR_RPSS: u32 = 1 << 8;
+const DMASR_PWTS: u32 = 1 << 9;
+const DMASR_FBES: u32 = 1 << 13;
+const DMASR_AIS: u32 = 1 << 15;
+const DMASR_NIS: u32 = 1 << 16;
+
+/// The status bits of `DMASR` that are handled by the driver.
+const DMASR_HANDLED: u32 = DMASR_TS
+    | DMASR_TPSS
+    | DMASR_TBUS
+    | DMASR_TJTS
+    | DMASR_ROS
+    | DMASR_TUS
+    | DMASR_RS
+    | DMASR_RBUS
+    | DMASR_RPSS
+    | DMASR_PWTS
+    | DMASR_FBES
+    | DMASR_AIS
+    | DMASR_NIS;
 
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -41,32 +139,55 @@ pub(crate) const MTU: usize = 1522;
 pub struct PacketIdNotFound;
 
 /// Ethernet DMA.
-pub struct EthernetDMA<'rx, 'tx> {
+///
+/// `RX_BUF` is the size of the buffer of every RX descriptor, see [`RxRingEntry`].
+pub struct EthernetDMA<'rx, 'tx, const RX_BUF: usize = MTU> {
     pub(crate) eth_dma: ETHERNET_DMA,
-    pub(crate) rx_ring: RxRing<'rx>,
+    #[cfg(not(feature = "tx-only"))]
+    pub(crate) rx_ring: RxRing<'rx, RX_BUF>,
+    #[cfg(not(feature = "rx-only"))]
     pub(crate) tx_ring: TxRing<'tx>,
+    #[cfg(any(feature = "rx-only", feature = "tx-only"))]
+    _rings: core::marker::PhantomData<(&'rx mut (), &'tx mut ())>,
+    #[cfg(all(not(feature = "rx-only"), not(feature = "tx-only")))]
+    interrupt_budget: Option<usize>,
 
     #[cfg(feature = "ptp")]
     packet_id_counter: u32,
 }
 
-impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
+impl<'rx, 'tx, const RX_BUF: usize> EthernetDMA<'rx, 'tx, RX_BUF> {
     /// Create and initialise the ethernet DMA
     ///
     /// # Note
     /// - Make sure that the buffers reside in a memory region that is
     /// accessible by the peripheral. Core-Coupled Memory (CCM) is
     /// usually not accessible.
+    /// - The reference clock of the PHY must be running, or
+    /// [`InitError::NoRefClk`] is returned.
     pub(crate) fn new(
         eth_dma: ETHERNET_DMA,
-        rx_buffer: &'rx mut [RxRingEntry],
-        tx_buffer: &'tx mut [TxRingEntry],
-    ) -> Self {
+        #[cfg(not(feature = "tx-only"))] rx_buffer: &'rx mut [RxRingEntry<RX_BUF>],
+        #[cfg(not(feature = "rx-only"))] tx_buffer: &'tx mut [TxRingEntry],
+    ) -> Result<Self, InitError> {
+        /// The amount of times that the reset bit is polled before
+        /// giving up. The reset takes a few cycles of the reference
+        /// clock, so this leaves a margin of several milliseconds
+        /// even at the highest HCLK.
+        const RESET_POLLS: u32 = 1_000_000;
+
         // reset DMA bus mode register
         eth_dma.dmabmr.modify(|_, w| w.sr().set_bit());
 
-        // Wait until done
-        while eth_dma.dmabmr.read().sr().bit_is_set() {}
+        // Wait until done. The reset never completes if the
+        // reference clock is missing.
+        let mut polls = 0;
+        while eth_dma.dmabmr.read().sr().bit_is_set() {
+            polls += 1;
+            if polls == RESET_POLLS {
+                return Err(InitError::NoRefClk);
+            }
+        }
 
         // operation mode register
         eth_dma.dmaomr.modify(|_, w| {
@@ -121,22 +242,33 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
 
         let mut dma = EthernetDMA {
             eth_dma,
+            #[cfg(not(feature = "tx-only"))]
             rx_ring: RxRing::new(rx_buffer),
+            #[cfg(not(feature = "rx-only"))]
             tx_ring: TxRing::new(tx_buffer),
+            #[cfg(any(feature = "rx-only", feature = "tx-only"))]
+            _rings: core::marker::PhantomData,
+            #[cfg(all(not(feature = "rx-only"), not(feature = "tx-only")))]
+            interrupt_budget: None,
 
             #[cfg(feature = "ptp")]
             packet_id_counter: 0,
         };
 
+        #[cfg(not(feature = "tx-only"))]
         dma.rx_ring.start(&dma.eth_dma);
+        #[cfg(not(feature = "rx-only"))]
         dma.tx_ring.start(&dma.eth_dma);
 
-        dma
+        crate::setup::set_driver_in_use(true);
+
+        Ok(dma)
     }
 
     /// Split the [`EthernetDMA`] into concurrently operating send and
     /// receive parts.
-    pub fn split(&mut self) -> (&mut RxRing<'rx>, &mut TxRing<'tx>) {
+    #[cfg(not(any(feature = "rx-only", feature = "tx-only")))]
+    pub fn split(&mut self) -> (&mut RxRing<'rx, RX_BUF>, &mut TxRing<'tx>) {
         (&mut self.rx_ring, &mut self.tx_ring)
     }
 
@@ -159,10 +291,10 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
                 .set_bit()
                 // Receive Interrupt Enable
                 .rie()
-                .set_bit()
+                .bit(cfg!(not(feature = "tx-only")))
                 // Transmit Interrupt Enable
                 .tie()
-                .set_bit()
+                .bit(cfg!(not(feature = "rx-only")))
         });
 
         // Enable ethernet interrupts
@@ -171,43 +303,185 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
         }
     }
 
+    /// Enable the `ETH` interrupt for the given abnormal conditions, and
+    /// disable it for all other abnormal conditions.
+    ///
+    /// The conditions that occured are reported in [`InterruptReasonSummary::abnormal`],
+    /// exactly once per occurence, so the application can react to them (for instance
+    /// by logging and resetting the interface) as soon as they occur.
+    ///
+    /// [`AbnormalInterrupts::FATAL`] is a good default.
+    pub fn enable_abnormal_interrupts(&self, interrupts: AbnormalInterrupts) {
+        self.eth_dma.dmaier.modify(|_, w| {
+            w
+                // Abnormal interrupt summary enable
+                .aise()
+                .bit(interrupts.any())
+                // Fatal bus error interrupt enable
+                .fbeie()
+                .bit(interrupts.fatal_bus_error)
+                // Receive process stopped interrupt enable
+                .rpsie()
+                .bit(interrupts.rx_process_stopped)
+                // Transmit process stopped interrupt enable
+                .tpsie()
+                .bit(interrupts.tx_process_stopped)
+                // Receive buffer unavailable interrupt enable
+                .rbuie()
+                .bit(interrupts.rx_buffer_unavailable)
+                // Overflow interrupt enable
+                .roie()
+                .bit(interrupts.rx_overflow)
+                // Underflow interrupt enable
+                .tuie()
+                .bit(interrupts.tx_underflow)
+                // Receive watchdog timeout interrupt enable
+                .rwtie()
+                .bit(interrupts.rx_watchdog_timeout)
+                // Transmit jabber timeout interrupt enable
+                .tjtie()
+                .bit(interrupts.tx_jabber_timeout)
+        });
+    }
+}
+
+impl EthernetDMA<'_, '_> {
+    /// Stop all DMA transfers and disable all DMA interrupts, without
+    /// taking any locks.
+    ///
+    /// See [`emergency_stop`](crate::emergency_stop).
+    pub(crate) fn emergency_stop() {
+        // SAFETY: we only perform register accesses that stop the DMA,
+        // which is always safe to do.
+        let eth_dma = unsafe { &*ETHERNET_DMA::ptr() };
+
+        eth_dma.dmaier.write(|w| unsafe { w.bits(0) });
+        eth_dma
+            .dmaomr
+            .modify(|_, w| w.st().clear_bit().sr().clear_bit());
+
+        NVIC::mask(Interrupt::ETH);
+    }
+
     /// Handle the DMA parts of the `ETH` interrupt.
+    ///
+    /// If deferred status clearing is enabled (see
+    /// [`EthernetDMA::set_deferred_status_clearing`]), the status is only
+    /// recorded, and must be cleared using [`EthernetDMA::complete_deferred_interrupt`].
     pub fn interrupt_handler() -> InterruptReasonSummary {
         // SAFETY: we only perform atomic reads/writes through `eth_dma`.
         let eth_dma = unsafe { &*ETHERNET_DMA::ptr() };
 
-        let status = eth_dma.dmasr.read();
+        let status = eth_dma.dmasr.read().bits() & DMASR_HANDLED;
+
+        if deferred::is_enabled() {
+            return deferred::record(status);
+        }
+
+        Self::process_status(status)
+    }
+
+    /// Count, report, and clear the DMA `status`, and wake the wakers
+    /// of the rings that it concerns.
+    fn process_status(status: u32) -> InterruptReasonSummary {
+        // SAFETY: we only perform atomic reads/writes through `eth_dma`.
+        let eth_dma = unsafe { &*ETHERNET_DMA::ptr() };
+
+        let stats = crate::stats::counters();
+        let count = |bit: u32, counter| {
+            if status & bit == bit {
+                crate::stats::add(counter, 1);
+            }
+        };
+        count(DMASR_RS, &stats.dma_rx_interrupts);
+        count(DMASR_TS, &stats.dma_tx_interrupts);
+        count(DMASR_RBUS, &stats.dma_rx_buffer_unavailable);
+        count(DMASR_TBUS, &stats.dma_tx_buffer_unavailable);
+        count(DMASR_RPSS, &stats.dma_rx_process_stopped);
+        count(DMASR_TPSS, &stats.dma_tx_process_stopped);
+        count(DMASR_ROS, &stats.dma_rx_overflow);
+        count(DMASR_TUS, &stats.dma_tx_underflow);
+        count(DMASR_PWTS, &stats.dma_rx_watchdog_timeout);
+        count(DMASR_TJTS, &stats.dma_tx_jabber_timeout);
+        count(DMASR_FBES, &stats.dma_fatal_bus_error);
 
-        let status = InterruptReasonSummary {
-            is_rx: status.rs().bit_is_set(),
-            is_tx: status.ts().bit_is_set(),
-            is_error: status.ais().bit_is_set(),
+        let summary = InterruptReasonSummary::from_status(status);
+
+        // Report injected faults as if the DMA had reported them.
+        #[cfg(feature = "test-hooks")]
+        let summary = {
+            let injected = crate::test_hooks::take_dma_faults();
+            let count = |is_set: bool, counter| {
+                if is_set {
+                    crate::stats::add(counter, 1);
+                }
+            };
+            count(
+                injected.rx_buffer_unavailable,
+                &stats.dma_rx_buffer_unavailable,
+            );
+            count(injected.fatal_bus_error, &stats.dma_fatal_bus_error);
+            count(injected.tx_underflow, &stats.dma_tx_underflow);
+
+            let mut summary = summary;
+            summary.is_error |= injected.any();
+            summary.abnormal.rx_buffer_unavailable |= injected.rx_buffer_unavailable;
+            summary.abnormal.fatal_bus_error |= injected.fatal_bus_error;
+            summary.abnormal.tx_underflow |= injected.tx_underflow;
+            summary
         };
 
-        eth_dma
-            .dmasr
-            .write(|w| w.nis().set_bit().ts().set_bit().rs().set_bit());
+        #[cfg(not(feature = "tx-only"))]
+        if summary.abnormal.rx_buffer_unavailable || summary.abnormal.rx_overflow {
+            crate::mac::flow_control::on_rx_exhaustion();
+        }
+
+        #[cfg(feature = "event-log")]
+        if summary.abnormal.any() {
+            crate::event_log::record(crate::event_log::DriverEvent::DmaAbnormal(summary.abnormal));
+        }
+
+        // Clear exactly the (latching) status bits that were counted, so
+        // that every occurence is counted exactly once.
+        // SAFETY: all bits of `status` are write-1-to-clear bits.
+        eth_dma.dmasr.write(|w| unsafe { w.bits(status) });
+
+        Self::wake(&summary);
+
+        summary
+    }
 
+    /// Wake the wakers of the rings that `summary` concerns.
+    fn wake(summary: &InterruptReasonSummary) {
         #[cfg(feature = "async-await")]
         {
-            if status.is_tx {
+            #[cfg(not(feature = "rx-only"))]
+            if summary.is_tx {
                 EthernetDMA::tx_waker().wake();
             }
 
-            if status.is_rx {
+            #[cfg(not(feature = "tx-only"))]
+            if summary.is_rx {
                 EthernetDMA::rx_waker().wake();
             }
         }
 
-        status
+        #[cfg(not(feature = "async-await"))]
+        let _ = summary;
     }
+}
 
+impl<'rx, 'tx, const RX_BUF: usize> EthernetDMA<'rx, 'tx, RX_BUF> {
     /// Try to receive a packet.
     ///
     /// If no packet is available, this function returns [`Err(RxError::WouldBlock)`](RxError::WouldBlock).
     ///
     /// It may also return another kind of [`RxError`].
-    pub fn recv_next(&mut self, packet_id: Option<PacketId>) -> Result<RxPacket, RxError> {
+    #[cfg(not(feature = "tx-only"))]
+    pub fn recv_next(
+        &mut self,
+        packet_id: Option<PacketId>,
+    ) -> Result<RxPacket<'_, RX_BUF>, RxError> {
         self.rx_ring.recv_next(packet_id.map(Into::into))
     }
 
@@ -215,19 +489,53 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
     ///
     /// It stops if the ring is full. Call [`EthernetDMA::recv_next()`] to free an
     /// entry and to demand poll from the hardware.
+    #[cfg(not(feature = "tx-only"))]
     pub fn rx_is_running(&self) -> bool {
         self.rx_ring.running_state().is_running()
     }
 
+    /// Read and clear the counters of received frames that the DMA
+    /// dropped.
+    ///
+    /// The counts are also added to the [`statistics`](crate::stats::statistics).
+    #[cfg(not(feature = "tx-only"))]
+    pub fn rx_drop_counters(&self) -> RxDropCounters {
+        // NOTE: reading `DMAMFBOCR` clears it.
+        let counters = RxDropCounters::from_bits(self.eth_dma.dmamfbocr.read().bits());
+
+        let stats = crate::stats::counters();
+        crate::stats::add(&stats.rx_missed_frames, counters.missed_frames as u32);
+        crate::stats::add(&stats.rx_fifo_overflows, counters.fifo_overflows as u32);
+
+        counters
+    }
+
     /// Is Tx DMA currently running?
+    #[cfg(not(feature = "rx-only"))]
     pub fn tx_is_running(&self) -> bool {
         self.tx_ring.is_running()
     }
 
+    /// Check whether all DMA directions that are in use have been started,
+    /// and have not been stopped since.
+    ///
+    /// Unlike [`EthernetDMA::rx_is_running`], this does not change when the
+    /// DMA is suspended because it ran out of descriptors.
+    pub fn is_started(&self) -> bool {
+        let dmaomr = self.eth_dma.dmaomr.read();
+        let started = true;
+        #[cfg(not(feature = "tx-only"))]
+        let started = started && dmaomr.sr().bit_is_set();
+        #[cfg(not(feature = "rx-only"))]
+        let started = started && dmaomr.st().bit_is_set();
+        started
+    }
+
     /// Try to send a packet with data.
     ///
     /// If there are no free TX slots, this function will
     /// return [`Err(TxError::WouldBlock)`](TxError::WouldBlock).
+    #[cfg(not(feature = "rx-only"))]
     pub fn send<F>(
         &mut self,
         length: usize,
@@ -243,10 +551,284 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
         Ok(())
     }
 
+    /// Reserve a TX slot for a packet of `length` bytes, without
+    /// sending it yet.
+    ///
+    /// The returned [`TxPacket`] points directly into the buffer of the
+    /// TX descriptor, so its contents can be written by anything that can
+    /// write to memory, such as the DMA of another peripheral (using the
+    /// pointer returned by [`as_mut_ptr`](slice::as_mut_ptr)). Once the
+    /// contents are complete, call [`TxPacket::send`] to pass the packet to
+    /// the DMA. The packet is also sent when it is dropped, so it must be
+    /// kept alive until the other peripheral is done writing.
+    ///
+    /// If there are no free TX slots, this function will
+    /// return [`Err(TxError::WouldBlock)`](TxError::WouldBlock).
+    #[cfg(not(feature = "rx-only"))]
+    pub fn prepare_send(
+        &mut self,
+        length: usize,
+        packet_id: Option<PacketId>,
+    ) -> Result<TxPacket<'_, 'tx>, TxError> {
+        self.tx_ring.send_next(length, packet_id)
+    }
+
+    /// Try to send `frame`.
+    ///
+    /// If `pad` is `true`, frames shorter than [`MIN_FRAME_LEN`] are
+    /// zero-filled up to that length. Otherwise, they are sent as-is and
+    /// padded by the MAC.
+    ///
+    /// If there are no free TX slots, this function will
+    /// return [`Err(TxError::WouldBlock)`](TxError::WouldBlock).
+    #[cfg(not(feature = "rx-only"))]
+    pub fn send_slice(
+        &mut self,
+        frame: &[u8],
+        packet_id: Option<PacketId>,
+        pad: bool,
+    ) -> Result<(), TxError> {
+        let length = if pad {
+            frame.len().max(MIN_FRAME_LEN)
+        } else {
+            frame.len()
+        };
+
+        self.send(length, packet_id, |buffer| {
+            let (data, padding) = buffer.split_at_mut(frame.len());
+            data.copy_from_slice(frame);
+            padding.fill(0);
+        })
+    }
+
+    /// Try to send a packet with data, and attach a user `tag` to it.
+    ///
+    /// The tag is returned in the [`TxCompletion`] of the packet,
+    /// see [`EthernetDMA::poll_tx_completion`].
+    ///
+    /// If there are no free TX slots, this function will
+    /// return [`Err(TxError::WouldBlock)`](TxError::WouldBlock).
+    #[cfg(not(feature = "rx-only"))]
+    pub fn send_tagged<F>(
+        &mut self,
+        length: usize,
+        packet_id: Option<PacketId>,
+        tag: u32,
+        f: F,
+    ) -> Result<(), TxError>
+    where
+        F: FnOnce(&mut [u8]),
+    {
+        let mut tx_packet = self.tx_ring.send_next(length, packet_id)?;
+        tx_packet.set_tag(tag);
+        f(&mut tx_packet);
+        tx_packet.send();
+        Ok(())
+    }
+
+    /// Try to send a packet with data, but only if it can plausibly
+    /// be transmitted within `deadline_ns` nanoseconds from now.
+    ///
+    /// The time until transmission is estimated from the frames that are
+    /// still queued in the TX ring and the speed that is programmed into the
+    /// MAC, see [`TxRing::estimate_tx_latency_ns`]. If the deadline cannot be
+    /// met, nothing is queued, and this function returns
+    /// [`Err(TxError::DeadlineMissed)`](TxError::DeadlineMissed), so that
+    /// cyclic protocols can skip the cycle instead of sending stale data.
+    ///
+    /// If there are no free TX slots, this function will
+    /// return [`Err(TxError::WouldBlock)`](TxError::WouldBlock).
+    #[cfg(not(feature = "rx-only"))]
+    pub fn send_before<F>(
+        &mut self,
+        deadline_ns: u64,
+        length: usize,
+        packet_id: Option<PacketId>,
+        f: F,
+    ) -> Result<(), TxError>
+    where
+        F: FnOnce(&mut [u8]),
+    {
+        let mbps = crate::mac::EthernetMAC::configured_speed().mbps();
+        if self.tx_ring.estimate_tx_latency_ns(length, mbps) > deadline_ns {
+            return Err(TxError::DeadlineMissed);
+        }
+
+        self.send(length, packet_id, f)
+    }
+
+    /// Get the completion of the oldest sent frame whose completion
+    /// has not been returned yet.
+    ///
+    /// See [`TxRing::poll_completion`].
+    #[cfg(not(feature = "rx-only"))]
+    pub fn poll_tx_completion(&mut self) -> Option<TxCompletion> {
+        self.tx_ring.poll_completion()
+    }
+
+    /// Retire all sent frames whose completion has not been returned
+    /// yet, and pass their completions to `f`.
+    ///
+    /// See [`TxRing::reclaim`].
+    #[cfg(not(feature = "rx-only"))]
+    pub fn reclaim_tx<F>(&mut self, f: F) -> usize
+    where
+        F: FnMut(TxCompletion),
+    {
+        self.tx_ring.reclaim(f)
+    }
+
+    /// Close the RX gate, discarding all received frames until
+    /// [`EthernetDMA::open_rx_gate`] is called.
+    ///
+    /// Call this directly after creating the driver to make sure that the
+    /// ring does not fill up with frames while the application finishes
+    /// its own initialization. Frames received before this call are
+    /// discarded as well.
+    ///
+    /// See [`RxRing::close_gate`].
+    #[cfg(not(feature = "tx-only"))]
+    pub fn close_rx_gate(&mut self) {
+        self.rx_ring.close_gate();
+    }
+
+    /// Open the RX gate, discarding all frames that were received
+    /// while it was closed.
+    ///
+    /// See [`RxRing::open_gate`].
+    #[cfg(not(feature = "tx-only"))]
+    pub fn open_rx_gate(&mut self) {
+        self.rx_ring.open_gate();
+    }
+
+    /// Check whether a frame can be injected using [`EthernetDMA::inject_rx`].
+    #[cfg(feature = "rx-inject")]
+    pub fn can_inject_rx(&self) -> bool {
+        self.rx_ring.can_inject()
+    }
+
+    /// Inject `frame` into the receive path, as if it was received
+    /// from the wire.
+    ///
+    /// This can be used to test the handling of application protocols on
+    /// target, without a traffic generator.
+    ///
+    /// See [`RxRing::inject`].
+    #[cfg(feature = "rx-inject")]
+    pub fn inject_rx(&mut self, frame: &[u8]) -> Result<(), RxError> {
+        self.rx_ring.inject(frame)
+    }
+
+    /// Set the hook that is called with a borrow of every received
+    /// and transmitted frame, or remove it if `mirror` is `None`.
+    ///
+    /// This can be used for on-device protocol logging, or for streaming
+    /// captures to a host. Received frames are mirrored when they are
+    /// returned by [`EthernetDMA::recv_next`] (or `recv`), transmitted
+    /// frames are mirrored when they are handed to the DMA.
+    #[cfg(feature = "mirror")]
+    pub fn set_mirror(&mut self, mirror: Option<Mirror>) {
+        #[cfg(not(feature = "tx-only"))]
+        self.rx_ring.set_mirror(mirror);
+        #[cfg(not(feature = "rx-only"))]
+        self.tx_ring.set_mirror(mirror);
+    }
+
+    /// Set the checksums that the MAC inserts into transmitted frames.
+    ///
+    /// See [`TxRing::set_checksum_insertion`].
+    #[cfg(not(feature = "rx-only"))]
+    pub fn set_checksum_insertion(&mut self, checksum: ChecksumInsertion) {
+        self.tx_ring.set_checksum_insertion(checksum);
+    }
+
+    /// Receive the next frame (if any is ready), which may span
+    /// several descriptors.
+    ///
+    /// See [`RxRing::recv_segmented`].
+    #[cfg(not(feature = "tx-only"))]
+    pub fn recv_segmented(&mut self) -> Result<RxSegmentedPacket<'_, 'rx, RX_BUF>, RxError> {
+        self.rx_ring.recv_segmented()
+    }
+
+    /// Cross-check the CRC status reported by the MAC against a CRC
+    /// calculated in software, for the next `frames` received frames.
+    ///
+    /// See [`RxRing::start_crc_check`].
+    #[cfg(not(feature = "tx-only"))]
+    pub fn start_crc_check(&mut self, frames: u32) {
+        self.rx_ring.start_crc_check(frames);
+    }
+
+    /// Set the time source that is used to measure how long queued
+    /// frames wait for transmission.
+    ///
+    /// See [`EthernetDMA::oldest_tx_age`].
+    #[cfg(not(feature = "rx-only"))]
+    pub fn set_tx_clock(&mut self, clock: Option<fn() -> u32>) {
+        self.tx_ring.set_clock(clock);
+    }
+
+    /// Get how long the oldest frame that is still owned by the DMA
+    /// has been queued.
+    ///
+    /// See [`TxRing::oldest_tx_age`].
+    #[cfg(not(feature = "rx-only"))]
+    pub fn oldest_tx_age(&self) -> Option<u32> {
+        self.tx_ring.oldest_tx_age()
+    }
+
+    /// Get the amount of bytes in frames that are queued in the TX ring,
+    /// but have not been transmitted yet.
+    ///
+    /// Together with the link speed, this can be used to pace transmissions.
+    /// See [`TxRing::backlog_bytes`].
+    #[cfg(not(feature = "rx-only"))]
+    pub fn tx_backlog_bytes(&self) -> usize {
+        self.tx_ring.backlog_bytes()
+    }
+
+    /// Get the state that the driver put the RX DMA into.
+    #[cfg(not(feature = "tx-only"))]
+    pub fn rx_state(&self) -> RingState {
+        self.rx_ring.state()
+    }
+
+    /// Get the state that the driver put the TX DMA into.
+    #[cfg(not(feature = "rx-only"))]
+    pub fn tx_state(&self) -> RingState {
+        self.tx_ring.state()
+    }
+
+    /// Record the faults that are reported in `summary` in the states
+    /// of the rings (see [`EthernetDMA::rx_state`] and
+    /// [`EthernetDMA::tx_state`]).
+    ///
+    /// [`EthernetDMA::interrupt_handler`] does not have access to the rings,
+    /// so the summary that it returns should be passed to this function.
+    pub fn record_faults(&mut self, summary: &InterruptReasonSummary) {
+        let abnormal = &summary.abnormal;
+
+        #[cfg(not(feature = "tx-only"))]
+        if abnormal.fatal_bus_error {
+            self.rx_ring.fault(RingFault::FatalBusError);
+        } else if abnormal.rx_process_stopped {
+            self.rx_ring.fault(RingFault::ProcessStopped);
+        }
+
+        #[cfg(not(feature = "rx-only"))]
+        if abnormal.fatal_bus_error {
+            self.tx_ring.fault(RingFault::FatalBusError);
+        } else if abnormal.tx_process_stopped {
+            self.tx_ring.fault(RingFault::ProcessStopped);
+        }
+    }
+
     /// Check if there is a packet available for reading.
     ///
     /// If this function returns true, it is guaranteed that the
     /// next call to [`EthernetDMA::recv_next`] will return [`Ok`].
+    #[cfg(not(feature = "tx-only"))]
     pub fn rx_available(&mut self) -> bool {
         self.rx_ring.next_entry_available()
     }
@@ -255,42 +837,54 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
     ///
     /// If this function returns true, it is guaranteed that
     /// the next call to [`EthernetDMA::send`] will return [`Ok`]
+    #[cfg(not(feature = "rx-only"))]
     pub fn tx_available(&mut self) -> bool {
         self.tx_ring.next_entry_available()
     }
 }
 
-impl Drop for EthernetDMA<'_, '_> {
+impl<const RX_BUF: usize> Drop for EthernetDMA<'_, '_, RX_BUF> {
     // On drop, stop all DMA actions.
     fn drop(&mut self) {
+        #[cfg(not(feature = "rx-only"))]
         self.tx_ring.stop(&self.eth_dma);
 
+        #[cfg(not(feature = "tx-only"))]
         self.rx_ring.stop(&self.eth_dma);
+
+        crate::setup::set_driver_in_use(false);
     }
 }
 
 #[cfg(feature = "async-await")]
-impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
+impl EthernetDMA<'_, '_> {
+    #[cfg(not(feature = "tx-only"))]
     pub(crate) fn rx_waker() -> &'static AtomicWaker {
         static WAKER: AtomicWaker = AtomicWaker::new();
         &WAKER
     }
 
+    #[cfg(not(feature = "rx-only"))]
     pub(crate) fn tx_waker() -> &'static AtomicWaker {
         static WAKER: AtomicWaker = AtomicWaker::new();
         &WAKER
     }
+}
 
+#[cfg(feature = "async-await")]
+impl<'rx, 'tx, const RX_BUF: usize> EthernetDMA<'rx, 'tx, RX_BUF> {
     /// Receive a packet.
     ///
     /// See [`RxRing::recv`].
-    pub async fn recv(&mut self, packet_id: Option<PacketId>) -> RxPacket {
+    #[cfg(not(feature = "tx-only"))]
+    pub async fn recv(&mut self, packet_id: Option<PacketId>) -> RxPacket<'_, RX_BUF> {
         self.rx_ring.recv(packet_id).await
     }
 
     /// Prepare a packet for sending.
     ///
     /// See [`TxRing::prepare_packet`].
+    #[cfg(not(feature = "rx-only"))]
     pub async fn prepare_packet<'borrow>(
         &'borrow mut self,
         length: usize,
@@ -299,8 +893,22 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
         self.tx_ring.prepare_packet(length, packet_id).await
     }
 
+    /// Wait until a TX slot is free, and send a packet with data.
+    ///
+    /// This is the asynchronous counterpart of [`EthernetDMA::send`].
+    #[cfg(not(feature = "rx-only"))]
+    pub async fn transmit<F>(&mut self, length: usize, packet_id: Option<PacketId>, f: F)
+    where
+        F: FnOnce(&mut [u8]),
+    {
+        let mut tx_packet = self.tx_ring.prepare_packet(length, packet_id).await;
+        f(&mut tx_packet);
+        tx_packet.send();
+    }
+
     /// Wait for an RX or TX interrupt to have
     /// occured.
+    #[cfg(not(any(feature = "rx-only", feature = "tx-only")))]
     pub async fn rx_or_tx(&mut self) {
         let mut polled_once = false;
         core::future::poll_fn(|ctx| {
@@ -318,11 +926,12 @@ impl<'rx, 'tx> EthernetDMA<'rx, 'tx> {
 }
 
 #[cfg(feature = "ptp")]
-impl EthernetDMA<'_, '_> {
+impl<const RX_BUF: usize> EthernetDMA<'_, '_, RX_BUF> {
     /// Try to get the timestamp for the given packet ID.
     ///
     /// This function will attempt to find both RX and TX timestamps,
     /// so make sure that the provided packet ID is unique between the two.
+    #[cfg(not(any(feature = "rx-only", feature = "tx-only")))]
     pub fn poll_timestamp(
         &self,
         packet_id: &PacketId,
@@ -338,7 +947,28 @@ impl EthernetDMA<'_, '_> {
         Poll::Ready(self.rx_timestamp(packet_id))
     }
 
+    /// Try to send a packet with data, and request that its TX
+    /// timestamp is captured.
+    ///
+    /// Returns the [`PacketId`] that the timestamp can be retrieved with,
+    /// see [`EthernetDMA::poll_tx_timestamp`].
+    ///
+    /// If there are no free TX slots, this function will
+    /// return [`Err(TxError::WouldBlock)`](TxError::WouldBlock).
+    #[cfg(not(feature = "rx-only"))]
+    pub fn send_timestamped<F>(&mut self, length: usize, f: F) -> Result<PacketId, TxError>
+    where
+        F: FnOnce(&mut [u8]),
+    {
+        let packet_id = self.next_packet_id();
+        let mut tx_packet = self.tx_ring.send_next(length, Some(packet_id.clone()))?;
+        f(&mut tx_packet);
+        tx_packet.send();
+        Ok(packet_id)
+    }
+
     /// Get the RX timestamp for the given packet ID.
+    #[cfg(not(feature = "tx-only"))]
     pub fn rx_timestamp(
         &self,
         packet_id: &PacketId,
@@ -348,6 +978,7 @@ impl EthernetDMA<'_, '_> {
 
     /// Blockingly wait until the TX timestamp for
     /// the given ID is available.
+    #[cfg(not(feature = "rx-only"))]
     pub fn wait_for_tx_timestamp(
         &self,
         packet_id: &PacketId,
@@ -357,6 +988,7 @@ impl EthernetDMA<'_, '_> {
 
     /// Poll to check if the TX timestamp for the given
     /// ID is available.
+    #[cfg(not(feature = "rx-only"))]
     pub fn poll_tx_timestamp(
         &self,
         packet_id: &PacketId,
@@ -366,6 +998,7 @@ impl EthernetDMA<'_, '_> {
 
     /// Get the TX timestamp for the given ID.
     #[cfg(feature = "async-await")]
+    #[cfg(not(feature = "rx-only"))]
     pub async fn tx_timestamp(
         &mut self,
         packet_id: &PacketId,
@@ -381,6 +1014,46 @@ impl EthernetDMA<'_, '_> {
     }
 }
 
+/// The amount of received frames that the DMA dropped since the
+/// counters were last read, see [`EthernetDMA::rx_drop_counters`].
+#[cfg(not(feature = "tx-only"))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RxDropCounters {
+    /// The amount of frames that were dropped because no RX descriptor
+    /// was available, which means that received frames are not processed
+    /// quickly enough.
+    pub missed_frames: u16,
+    /// `missed_frames` overflowed, so more frames were dropped.
+    pub missed_frames_overflow: bool,
+    /// The amount of frames that were dropped because the RX FIFO
+    /// overflowed.
+    pub fifo_overflows: u16,
+    /// `fifo_overflows` overflowed, so more frames were dropped.
+    pub fifo_overflows_overflow: bool,
+}
+
+#[cfg(not(feature = "tx-only"))]
+impl RxDropCounters {
+    /// Decode the value of the `DMAMFBOCR` register.
+    const fn from_bits(bits: u32) -> Self {
+        Self {
+            missed_frames: (bits & 0xFFFF) as u16,
+            missed_frames_overflow: bits & (1 << 16) != 0,
+            fifo_overflows: ((bits >> 17) & 0x7FF) as u16,
+            fifo_overflows_overflow: bits & (1 << 28) != 0,
+        }
+    }
+
+    /// Whether any frames were dropped.
+    pub const fn any(&self) -> bool {
+        self.missed_frames != 0
+            || self.missed_frames_overflow
+            || self.fifo_overflows != 0
+            || self.fifo_overflows_overflow
+    }
+}
+
 /// A summary of the reasons for the interrupt
 /// that occured
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -392,4 +1065,99 @@ pub struct InterruptReasonSummary {
     pub is_tx: bool,
     /// The interrupt was caused by an error event.
     pub is_error: bool,
+    /// The abnormal conditions that occured since the previous interrupt.
+    ///
+    /// Every condition is only reported once.
+    pub abnormal: AbnormalInterrupts,
+}
+
+impl InterruptReasonSummary {
+    /// Decode the (masked) value of the `DMASR` register.
+    fn from_status(status: u32) -> Self {
+        let is_set = |bit: u32| status & bit == bit;
+        Self {
+            is_rx: is_set(DMASR_RS),
+            is_tx: is_set(DMASR_TS),
+            is_error: is_set(DMASR_AIS),
+            abnormal: AbnormalInterrupts {
+                fatal_bus_error: is_set(DMASR_FBES),
+                rx_process_stopped: is_set(DMASR_RPSS),
+                tx_process_stopped: is_set(DMASR_TPSS),
+                rx_buffer_unavailable: is_set(DMASR_RBUS),
+                rx_overflow: is_set(DMASR_ROS),
+                tx_underflow: is_set(DMASR_TUS),
+                rx_watchdog_timeout: is_set(DMASR_PWTS),
+                tx_jabber_timeout: is_set(DMASR_TJTS),
+            },
+        }
+    }
+}
+
+/// A set of abnormal DMA conditions.
+///
+/// This is used both to select the conditions that trigger an interrupt
+/// (see [`EthernetDMA::enable_abnormal_interrupts`]), and to report the
+/// conditions that occured (see [`InterruptReasonSummary::abnormal`]).
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AbnormalInterrupts {
+    /// A bus error occured, and the DMA has stopped all operations.
+    ///
+    /// The DMA must be reset to recover from this condition.
+    pub fatal_bus_error: bool,
+    /// The RX process was stopped.
+    pub rx_process_stopped: bool,
+    /// The TX process was stopped.
+    pub tx_process_stopped: bool,
+    /// The next RX descriptor was owned by the CPU, so the RX process
+    /// was suspended.
+    pub rx_buffer_unavailable: bool,
+    /// The receive FIFO overflowed.
+    pub rx_overflow: bool,
+    /// The transmit FIFO underflowed.
+    pub tx_underflow: bool,
+    /// A frame longer than 2048 bytes was received.
+    pub rx_watchdog_timeout: bool,
+    /// The transmitter was active for longer than 2048 bytes.
+    pub tx_jabber_timeout: bool,
+}
+
+impl AbnormalInterrupts {
+    /// No abnormal conditions.
+    pub const NONE: Self = Self {
+        fatal_bus_error: false,
+        rx_process_stopped: false,
+        tx_process_stopped: false,
+        rx_buffer_unavailable: false,
+        rx_overflow: false,
+        tx_underflow: false,
+        rx_watchdog_timeout: false,
+        tx_jabber_timeout: false,
+    };
+
+    /// The conditions after which the DMA no longer transfers
+    /// any frames without intervention.
+    pub const FATAL: Self = Self {
+        fatal_bus_error: true,
+        rx_process_stopped: true,
+        tx_process_stopped: true,
+        ..Self::NONE
+    };
+
+    /// All abnormal conditions.
+    pub const ALL: Self = Self {
+        fatal_bus_error: true,
+        rx_process_stopped: true,
+        tx_process_stopped: true,
+        rx_buffer_unavailable: true,
+        rx_overflow: true,
+        tx_underflow: true,
+        rx_watchdog_timeout: true,
+        tx_jabber_timeout: true,
+    };
+
+    /// Check whether any condition is set.
+    pub fn any(&self) -> bool {
+        *self != Self::NONE
+    }
 }
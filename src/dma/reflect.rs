@@ -0,0 +1,66 @@
+//! Transmitting received frames without copying them.
+//!
+//! Responders such as an ICMP echo responder, or forwarding applications,
+//! often transmit a received frame again after modifying some of its
+//! headers. With [`EthernetDMA::reflect_next`], the frame is modified in
+//! place, and the TX DMA reads it directly from the RX buffer. The RX
+//! descriptor is only returned to the RX DMA once the frame has been
+//! transmitted.
+//!
+//! ```rust,ignore
+//! // Echo every frame back to its sender.
+//! while let Ok(_) = dma.reflect_next(|frame| {
+//!     let (dst, src) = frame.split_at_mut(6);
+//!     dst.swap_with_slice(&mut src[..6]);
+//!     Some(frame.len())
+//! }) {}
+//! ```
+//!
+//! While a frame is being transmitted, its RX descriptor can not receive
+//! another frame, so the RX ring fills up faster. The RX descriptors of
+//! transmitted frames are returned to the RX DMA whenever a frame is
+//! received, through any of the receive functions. Reflected frames are
+//! passed to the [`Mirror`](super::Mirror) hook, and counted by the TX
+//! traffic classification, like any other received and transmitted frame.
+
+use super::{EthernetDMA, RxError};
+
+impl<const RX_BUF: usize> EthernetDMA<'_, '_, RX_BUF> {
+    /// Receive the next frame, and transmit it again from the RX buffer.
+    ///
+    /// `f` may modify the received frame in place, and returns the length
+    /// of the frame that is transmitted, or `None` to drop the frame instead.
+    /// Returns whether the frame was transmitted.
+    ///
+    /// The frame is transmitted with the checksum insertion mode of the TX
+    /// ring. If no frame has been received, or if no TX descriptor is
+    /// available, this function returns [`Err(RxError::WouldBlock)`](RxError::WouldBlock).
+    ///
+    /// # Panics
+    /// This function panics if `f` returns a length that is larger than
+    /// the length of the received frame.
+    pub fn reflect_next<F>(&mut self, f: F) -> Result<bool, RxError>
+    where
+        F: FnOnce(&mut [u8]) -> Option<usize>,
+    {
+        if !self.tx_ring.next_entry_available() {
+            return Err(RxError::WouldBlock);
+        }
+
+        let tx_ring = &mut self.tx_ring;
+        self.rx_ring.reflect_next(f, |frame| {
+            tx_ring
+                .send_borrowed(frame)
+                .expect("The next TX entry is available")
+        })
+    }
+
+    /// Return the RX descriptors of reflected frames that have been
+    /// transmitted to the RX DMA.
+    ///
+    /// This is done whenever a frame is received, so it only has to be
+    /// called to return the descriptors without receiving a frame.
+    pub fn release_reflected(&mut self) {
+        self.rx_ring.release_lent();
+    }
+}
@@ -1,42 +1,98 @@
-use super::{rx::RxDescriptor, tx::TxDescriptor, MTU};
-
-pub trait RingDescriptor {
-    fn setup(&mut self, buffer: *const u8, len: usize, next: Option<&Self>);
+use core::mem::MaybeUninit;
+
+#[cfg(not(feature = "tx-only"))]
+use super::rx::RxDescriptor;
+#[cfg(not(feature = "rx-only"))]
+use super::tx::TxDescriptor;
+use super::MTU;
+
+/// The maximum size of the buffer of an RX ring entry, in bytes.
+pub const MAX_RX_BUFFER_LEN: usize = 0x0fff;
+
+/// The minimum amount of entries in a ring.
+pub const MIN_RING_LEN: usize = 1;
+
+/// The maximum amount of entries in a ring.
+///
+/// Ring positions are tracked as `usize`, so the index arithmetic
+/// works for rings of any length up to this bound, which is far larger
+/// than any ring that fits in the RAM of a supported part.
+pub const MAX_RING_LEN: usize = u16::MAX as usize;
+
+/// Check that a ring with `len` entries is not empty, and not longer
+/// than [`MAX_RING_LEN`].
+pub(crate) fn assert_ring_len(len: usize) {
+    assert!(
+        (MIN_RING_LEN..=MAX_RING_LEN).contains(&len),
+        "A ring must contain between {} and {} entries",
+        MIN_RING_LEN,
+        MAX_RING_LEN
+    );
 }
 
-#[repr(C, align(8))]
-pub struct Buffer {
-    buffer: [u8; MTU],
+/// Get the index of the entry following the entry at `index` in a ring
+/// with `len` entries.
+#[inline(always)]
+pub(crate) fn next_index(index: usize, len: usize) -> usize {
+    let next = index + 1;
+    if next >= len {
+        0
+    } else {
+        next
+    }
 }
 
-impl Buffer {
-    pub const fn new() -> Self {
-        Self { buffer: [0; MTU] }
+/// Get the index of the entry `count` entries after the entry at `index`
+/// in a ring with `len` entries.
+///
+/// `count` may not be larger than `len`.
+#[cfg(not(feature = "tx-only"))]
+#[inline(always)]
+pub(crate) fn advance_index(index: usize, count: usize, len: usize) -> usize {
+    let next = index + count;
+    if next >= len {
+        next - len
+    } else {
+        next
     }
 }
 
-impl core::ops::Deref for Buffer {
-    type Target = [u8; MTU];
+pub trait RingDescriptor {
+    fn setup(&mut self, buffer: *const u8, len: usize, next: Option<&Self>);
+}
 
-    fn deref(&self) -> &Self::Target {
-        &self.buffer
-    }
+/// The data buffer of a ring entry.
+///
+/// The buffer is backed by [`MaybeUninit`] memory, so the driver never
+/// relies on its initial contents: RX buffers are only read after the DMA
+/// has written to them, and TX buffers are zeroed when the TX ring is
+/// started.
+///
+/// Note that a ring placed in a `static` is still zeroed by the startup
+/// code like any other `static`. To skip that initialization, place the
+/// ring in a section that is not zeroed at startup, such as
+/// `#[link_section = ".uninit.ETH_RINGS"]` with `cortex-m-rt`.
+#[repr(C, align(8))]
+pub struct Buffer<const N: usize> {
+    buffer: [MaybeUninit<u8>; N],
 }
 
-impl core::ops::DerefMut for Buffer {
-    fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.buffer
+impl<const N: usize> Buffer<N> {
+    pub const fn new() -> Self {
+        Self {
+            buffer: [MaybeUninit::uninit(); N],
+        }
     }
 }
 
-/// An entry in a DMA Descriptor ring
+/// An entry in a DMA Descriptor ring, with a buffer of `N` bytes.
 #[repr(C, align(8))]
-pub struct RingEntry<T: RingDescriptor> {
+pub struct RingEntry<T: RingDescriptor, const N: usize = MTU> {
     desc: T,
-    buffer: Buffer,
+    buffer: Buffer<N>,
 }
 
-impl<T: RingDescriptor + Default> Default for RingEntry<T> {
+impl<T: RingDescriptor + Default, const N: usize> Default for RingEntry<T, N> {
     fn default() -> Self {
         RingEntry {
             desc: T::default(),
@@ -45,6 +101,7 @@ impl<T: RingDescriptor + Default> Default for RingEntry<T> {
     }
 }
 
+#[cfg(not(feature = "rx-only"))]
 impl RingEntry<TxDescriptor> {
     /// The initial value of a TxRingDescriptor
     pub const INIT: Self = Self::new();
@@ -58,7 +115,8 @@ impl RingEntry<TxDescriptor> {
     }
 }
 
-impl RingEntry<RxDescriptor> {
+#[cfg(not(feature = "tx-only"))]
+impl<const N: usize> RingEntry<RxDescriptor, N> {
     /// The initial value of an RxRingDescriptor
     pub const INIT: Self = Self::new();
 
@@ -71,10 +129,10 @@ impl RingEntry<RxDescriptor> {
     }
 }
 
-impl<T: RingDescriptor> RingEntry<T> {
+impl<T: RingDescriptor, const N: usize> RingEntry<T, N> {
     pub(crate) fn setup(&mut self, next: Option<&Self>) {
-        let buffer = self.buffer.as_ptr();
-        let len = self.buffer.len();
+        let buffer = self.buffer.buffer.as_ptr() as *const u8;
+        let len = self.buffer.buffer.len();
         self.desc_mut()
             .setup(buffer, len, next.map(|next| next.desc()));
     }
@@ -89,13 +147,89 @@ impl<T: RingDescriptor> RingEntry<T> {
         &mut self.desc
     }
 
+    /// Set all bytes of the buffer of this entry to zero.
+    #[cfg(not(feature = "rx-only"))]
+    pub(crate) fn zero_buffer(&mut self) {
+        self.buffer
+            .buffer
+            .iter_mut()
+            .for_each(|b| *b = MaybeUninit::new(0));
+    }
+
+    /// Get the first `len` bytes of the buffer of this entry.
+    ///
+    /// # Safety
+    /// The first `len` bytes of the buffer must have been initialized, either
+    /// by the DMA or by [`RingEntry::zero_buffer`].
     #[inline]
-    pub(crate) fn as_slice(&self) -> &[u8] {
-        &(*self.buffer)[..]
+    pub(crate) unsafe fn as_slice(&self, len: usize) -> &[u8] {
+        let buffer = &self.buffer.buffer[..len];
+        core::slice::from_raw_parts(buffer.as_ptr() as *const u8, buffer.len())
     }
 
+    /// Get the first `len` bytes of the buffer of this entry.
+    ///
+    /// # Safety
+    /// The first `len` bytes of the buffer must have been initialized, either
+    /// by the DMA or by [`RingEntry::zero_buffer`].
     #[inline]
-    pub(crate) fn as_mut_slice(&mut self) -> &mut [u8] {
-        &mut (*self.buffer)[..]
+    pub(crate) unsafe fn as_mut_slice(&mut self, len: usize) -> &mut [u8] {
+        let buffer = &mut self.buffer.buffer[..len];
+        core::slice::from_raw_parts_mut(buffer.as_mut_ptr() as *mut u8, buffer.len())
+    }
+
+    /// Initialize the first `data.len()` bytes of the buffer with `data`.
+    #[cfg(feature = "rx-inject")]
+    pub(crate) fn write_buffer(&mut self, data: &[u8]) {
+        self.buffer
+            .buffer
+            .iter_mut()
+            .zip(data)
+            .for_each(|(b, d)| *b = MaybeUninit::new(*d));
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn next_index_wraps_large_rings() {
+        let len = 512;
+
+        let mut index = 0;
+        for expected in (1..len).chain(0..1) {
+            index = next_index(index, len);
+            assert_eq!(index, expected);
+        }
+
+        assert_eq!(next_index(0, 1), 0);
+    }
+
+    #[test]
+    #[cfg(not(feature = "tx-only"))]
+    fn advance_index_wraps() {
+        let len = 512;
+
+        assert_eq!(advance_index(510, 3, len), 1);
+        assert_eq!(advance_index(3, len, len), 3);
+    }
+
+    #[test]
+    fn ring_len_bounds() {
+        assert_ring_len(MIN_RING_LEN);
+        assert_ring_len(MAX_RING_LEN);
+    }
+
+    #[test]
+    #[should_panic]
+    fn empty_ring_is_rejected() {
+        assert_ring_len(0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn overlong_ring_is_rejected() {
+        assert_ring_len(MAX_RING_LEN + 1);
     }
 }
@@ -0,0 +1,126 @@
+//! The state of the RX and TX descriptor rings, as tracked by the driver.
+//!
+//! Every ring records the [`RingState`] that the driver put it into, and
+//! only changes it through the transitions that
+//! [`RingState::can_transition_to`] allows, so that a fault reported by
+//! the DMA is not overwritten by a later state change.
+
+/// The state of a descriptor ring, as tracked by the driver.
+///
+/// Unlike the running state that is read from `DMASR`, this is the state
+/// that the driver put the ring into. It is stored in the ring as a small
+/// enum with a `u8` discriminant (two bytes in total, including the
+/// [`RingFault`] of [`RingState::Error`]), so it can be inspected
+/// symbolically by a debugger.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum RingState {
+    /// The ring is not processed by the DMA.
+    Stopped,
+    /// The ring is processed by the DMA.
+    Running,
+    /// Processing of the ring was suspended by the driver, and
+    /// continues where it left off once it is resumed.
+    Suspended,
+    /// The DMA stopped processing the ring because of a fault.
+    Error(RingFault),
+}
+
+/// The reason why a ring is in [`RingState::Error`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum RingFault {
+    /// A bus error occured, and the DMA stopped all operations.
+    FatalBusError,
+    /// The DMA stopped processing the ring while it was running.
+    ProcessStopped,
+}
+
+/// A [`RingState`] transition that is not valid.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidTransition {
+    /// The state of the ring.
+    pub from: RingState,
+    /// The state that the ring was supposed to transition to.
+    pub to: RingState,
+}
+
+impl RingState {
+    /// Whether a ring in this state may transition to `to`.
+    pub fn can_transition_to(&self, to: RingState) -> bool {
+        use RingState::*;
+
+        match (self, to) {
+            // Stopping is always possible. A suspended ring was stopped
+            // by the driver, so it can only fault because of a bus error.
+            (_, Stopped) => true,
+            (Running, Error(_)) => true,
+            (Suspended, Error(RingFault::FatalBusError)) => true,
+            (Stopped, Running) => true,
+            (Running, Suspended) => true,
+            (Suspended, Running) => true,
+            _ => false,
+        }
+    }
+
+    /// Transition to `to`, if that is valid.
+    pub(super) fn transition(&mut self, to: RingState) -> Result<(), InvalidTransition> {
+        if self.can_transition_to(to) {
+            *self = to;
+            Ok(())
+        } else {
+            Err(InvalidTransition { from: *self, to })
+        }
+    }
+
+    /// Transition to `to`, which the driver expects to be valid.
+    ///
+    /// Invalid transitions indicate a bug in the driver, so they
+    /// panic in debug builds.
+    pub(super) fn set(&mut self, to: RingState) {
+        let result = self.transition(to);
+        debug_assert!(
+            result.is_ok(),
+            "Invalid ring state transition: {:?}",
+            result
+        );
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn transitions() {
+        let mut state = RingState::Stopped;
+        assert!(state.transition(RingState::Running).is_ok());
+        assert!(state.transition(RingState::Suspended).is_ok());
+        assert!(state.transition(RingState::Running).is_ok());
+        assert!(state
+            .transition(RingState::Error(RingFault::FatalBusError))
+            .is_ok());
+
+        assert_eq!(
+            state.transition(RingState::Running),
+            Err(InvalidTransition {
+                from: RingState::Error(RingFault::FatalBusError),
+                to: RingState::Running,
+            })
+        );
+        assert!(state.transition(RingState::Stopped).is_ok());
+        assert!(!state.can_transition_to(RingState::Suspended));
+
+        state = RingState::Suspended;
+        assert!(!state.can_transition_to(RingState::Error(RingFault::ProcessStopped)));
+        assert!(state.can_transition_to(RingState::Error(RingFault::FatalBusError)));
+    }
+
+    #[test]
+    fn size() {
+        assert_eq!(core::mem::size_of::<RingState>(), 2);
+    }
+}
@@ -1,10 +1,14 @@
 use crate::dma::{
     desc::Descriptor,
     ring::{RingDescriptor, RingEntry},
+    MTU,
 };
 
 use crate::dma::PacketId;
 
+#[cfg(not(feature = "rx-only"))]
+use crate::dma::desc::Borrower;
+
 #[cfg(feature = "ptp")]
 use crate::ptp::Timestamp;
 
@@ -30,9 +34,25 @@ const RXDESC_0_FS: u32 = 1 << 9;
 const RXDESC_0_LS: u32 = 1 << 8;
 /// Error summary
 const RXDESC_0_ES: u32 = 1 << 15;
+/// Destination address filter fail
+const RXDESC_0_AFM: u32 = 1 << 30;
+/// VLAN tag
+const RXDESC_0_VLAN: u32 = 1 << 10;
 /// Frame length
 const RXDESC_0_FL_MASK: u32 = 0x3FFF;
 const RXDESC_0_FL_SHIFT: usize = 16;
+/// Extended status available in RDES4
+#[cfg(not(feature = "stm32f1xx-hal"))]
+const RXDESC_0_ESA: u32 = 1 << 0;
+/// Frame type (Ethernet type frame)
+#[cfg(feature = "stm32f1xx-hal")]
+const RXDESC_0_FT: u32 = 1 << 5;
+/// IP header checksum error
+#[cfg(feature = "stm32f1xx-hal")]
+const RXDESC_0_IPHCE: u32 = 1 << 7;
+/// Payload checksum error
+#[cfg(feature = "stm32f1xx-hal")]
+const RXDESC_0_PCE: u32 = 1 << 0;
 
 const RXDESC_1_RBS_SHIFT: usize = 0;
 const RXDESC_1_RBS_MASK: u32 = 0x0fff << RXDESC_1_RBS_SHIFT;
@@ -41,6 +61,69 @@ const RXDESC_1_RCH: u32 = 1 << 14;
 /// End Of Ring
 const RXDESC_1_RER: u32 = 1 << 15;
 
+/// IP header error
+#[cfg(not(feature = "stm32f1xx-hal"))]
+const RXDESC_4_IPHE: u32 = 1 << 3;
+/// IP payload error
+#[cfg(not(feature = "stm32f1xx-hal"))]
+const RXDESC_4_IPPE: u32 = 1 << 4;
+/// IP payload type
+#[cfg(not(feature = "stm32f1xx-hal"))]
+const RXDESC_4_IPPT_MASK: u32 = 0b111;
+/// IP checksum bypassed
+#[cfg(not(feature = "stm32f1xx-hal"))]
+const RXDESC_4_IPCB: u32 = 1 << 5;
+/// IPv4 packet received
+#[cfg(not(feature = "stm32f1xx-hal"))]
+const RXDESC_4_IPV4PR: u32 = 1 << 6;
+/// IPv6 packet received
+#[cfg(not(feature = "stm32f1xx-hal"))]
+const RXDESC_4_IPV6PR: u32 = 1 << 7;
+
+/// The IP version of a received frame.
+#[cfg(not(feature = "stm32f1xx-hal"))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpVersion {
+    /// IPv4
+    V4,
+    /// IPv6
+    V6,
+}
+
+/// The type of the payload of a received IP packet.
+#[cfg(not(feature = "stm32f1xx-hal"))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpPayloadType {
+    /// UDP
+    Udp,
+    /// TCP
+    Tcp,
+    /// ICMP
+    Icmp,
+    /// Another or an unknown payload type, or a payload that the MAC
+    /// did not process, such as that of an IP fragment.
+    Other,
+}
+
+/// The type of a received frame, as decoded by the MAC.
+#[cfg(not(feature = "stm32f1xx-hal"))]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RxFrameType {
+    /// The frame contains an IP packet.
+    Ip {
+        /// The IP version of the packet.
+        version: IpVersion,
+        /// The type of the payload of the packet.
+        payload: IpPayloadType,
+    },
+    /// The frame does not contain an IP packet, or it
+    /// could not be classified.
+    Other,
+}
+
 #[repr(C)]
 /// An RX DMA Descriptor
 pub struct RxDescriptor {
@@ -48,6 +131,8 @@ pub struct RxDescriptor {
     buffer1: Option<u32>,
     next_descriptor: Option<u32>,
     packet_id: Option<PacketId>,
+    #[cfg(not(feature = "rx-only"))]
+    lent_to: Option<Borrower>,
     #[cfg(feature = "ptp")]
     cached_timestamp: Option<Timestamp>,
 }
@@ -66,6 +151,8 @@ impl RxDescriptor {
             buffer1: None,
             next_descriptor: None,
             packet_id: None,
+            #[cfg(not(feature = "rx-only"))]
+            lent_to: None,
             #[cfg(feature = "ptp")]
             cached_timestamp: None,
         }
@@ -80,6 +167,10 @@ impl RxDescriptor {
     ///
     /// Overrides old timestamp data
     pub fn set_owned(&mut self) {
+        #[cfg(not(feature = "rx-only"))]
+        {
+            self.lent_to = None;
+        }
         self.write_buffer1();
         self.write_buffer2();
 
@@ -98,17 +189,17 @@ impl RxDescriptor {
         core::sync::atomic::fence(core::sync::atomic::Ordering::SeqCst);
     }
 
-    fn has_error(&self) -> bool {
+    pub(super) fn has_error(&self) -> bool {
         (self.desc.read(0) & RXDESC_0_ES) == RXDESC_0_ES
     }
 
     /// Descriptor contains first buffer of frame
-    fn is_first(&self) -> bool {
+    pub(super) fn is_first(&self) -> bool {
         (self.desc.read(0) & RXDESC_0_FS) == RXDESC_0_FS
     }
 
     /// Descriptor contains last buffers of frame
-    fn is_last(&self) -> bool {
+    pub(super) fn is_last(&self) -> bool {
         (self.desc.read(0) & RXDESC_0_LS) == RXDESC_0_LS
     }
 
@@ -186,13 +277,118 @@ impl RxDescriptor {
         }
     }
 
-    fn get_frame_len(&self) -> usize {
+    /// Write the status of a received frame of length `len`, as the
+    /// DMA would, and pass ownership to the CPU.
+    #[cfg(feature = "rx-inject")]
+    fn set_injected(&mut self, len: usize) {
+        // There is no "timestamp valid" indicator bit on STM32F1XX,
+        // so mark the timestamp as unsuccesful instead.
+        #[cfg(feature = "stm32f1xx-hal")]
+        unsafe {
+            self.desc.write(2, 0xFFFF_FFFF);
+            self.desc.write(3, 0xFFFF_FFFF);
+        }
+
+        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::Release);
+
+        unsafe {
+            self.desc.write(
+                0,
+                RXDESC_0_FS | RXDESC_0_LS | ((len as u32 & RXDESC_0_FL_MASK) << RXDESC_0_FL_SHIFT),
+            );
+        }
+    }
+
+    /// The length of the frame, which is only valid in the
+    /// descriptor of the last segment.
+    pub(super) fn frame_len(&self) -> usize {
         ((self.desc.read(0) >> RXDESC_0_FL_SHIFT) & RXDESC_0_FL_MASK) as usize
     }
+
+    /// The extended status word, if the DMA wrote it.
+    #[cfg(not(feature = "stm32f1xx-hal"))]
+    fn extended_status(&self) -> u32 {
+        if self.desc.read(0) & RXDESC_0_ESA == RXDESC_0_ESA {
+            self.desc.read(4)
+        } else {
+            0
+        }
+    }
+
+    #[cfg(not(feature = "stm32f1xx-hal"))]
+    pub(super) fn frame_type(&self) -> RxFrameType {
+        let rdes4 = self.extended_status();
+
+        let version = if rdes4 & RXDESC_4_IPV4PR == RXDESC_4_IPV4PR {
+            IpVersion::V4
+        } else if rdes4 & RXDESC_4_IPV6PR == RXDESC_4_IPV6PR {
+            IpVersion::V6
+        } else {
+            return RxFrameType::Other;
+        };
+
+        let payload = match rdes4 & RXDESC_4_IPPT_MASK {
+            0b001 => IpPayloadType::Udp,
+            0b010 => IpPayloadType::Tcp,
+            0b011 => IpPayloadType::Icmp,
+            _ => IpPayloadType::Other,
+        };
+
+        RxFrameType::Ip { version, payload }
+    }
+
+    /// The frame failed the destination address filter, but was passed
+    /// because the MAC is promiscuous.
+    pub(super) fn failed_address_filter(&self) -> bool {
+        self.desc.read(0) & RXDESC_0_AFM == RXDESC_0_AFM
+    }
+
+    /// The MAC detected the frame as a VLAN frame.
+    pub(super) fn is_vlan_frame(&self) -> bool {
+        self.desc.read(0) & RXDESC_0_VLAN == RXDESC_0_VLAN
+    }
+
+    /// The checksum offload engine detected an error in the IP header.
+    pub(super) fn has_ip_header_error(&self) -> bool {
+        #[cfg(not(feature = "stm32f1xx-hal"))]
+        {
+            self.extended_status() & RXDESC_4_IPHE == RXDESC_4_IPHE
+        }
+
+        #[cfg(feature = "stm32f1xx-hal")]
+        {
+            let rdes0 = self.desc.read(0);
+            rdes0 & (RXDESC_0_FT | RXDESC_0_IPHCE) == (RXDESC_0_FT | RXDESC_0_IPHCE)
+        }
+    }
+
+    /// The checksum offload engine detected an error in the IP payload.
+    pub(super) fn has_ip_payload_error(&self) -> bool {
+        #[cfg(not(feature = "stm32f1xx-hal"))]
+        {
+            self.extended_status() & RXDESC_4_IPPE == RXDESC_4_IPPE
+        }
+
+        #[cfg(feature = "stm32f1xx-hal")]
+        {
+            let rdes0 = self.desc.read(0);
+            rdes0 & (RXDESC_0_FT | RXDESC_0_PCE) == (RXDESC_0_FT | RXDESC_0_PCE)
+        }
+    }
+
+    /// The checksum offload engine was bypassed.
+    #[cfg(not(feature = "stm32f1xx-hal"))]
+    pub(super) fn is_checksum_bypassed(&self) -> bool {
+        self.extended_status() & RXDESC_4_IPCB == RXDESC_4_IPCB
+    }
 }
 
-/// An RX DMA Ring Descriptor entry
-pub type RxRingEntry = RingEntry<RxDescriptor>;
+/// An RX DMA Ring Descriptor entry, with a buffer of `N` bytes.
+///
+/// `N` must be at most [`MAX_RX_BUFFER_LEN`](crate::dma::MAX_RX_BUFFER_LEN).
+/// Frames that are longer than `N` bytes span several entries, and can
+/// only be received using [`RxRing::recv_segmented`](super::RxRing::recv_segmented).
+pub type RxRingEntry<const N: usize = MTU> = RingEntry<RxDescriptor, N>;
 
 impl RingDescriptor for RxDescriptor {
     fn setup(&mut self, buffer: *const u8, len: usize, next: Option<&Self>) {
@@ -213,12 +409,39 @@ impl RingDescriptor for RxDescriptor {
     }
 }
 
-impl RxRingEntry {
+impl<const N: usize> RxRingEntry<N> {
     /// The initial value for an Rx Ring Entry
     pub const RX_INIT: Self = Self::new();
 
     pub(super) fn is_available(&self) -> bool {
-        !self.desc().is_owned()
+        !self.desc().is_owned() && !self.is_lent()
+    }
+
+    /// Keep the frame in this entry from the DMA until `borrower`
+    /// is done transmitting it.
+    ///
+    /// The entry is returned to the DMA with `set_owned`.
+    #[cfg(not(feature = "rx-only"))]
+    pub(super) fn lend(&mut self, borrower: Borrower) {
+        self.desc_mut().lent_to = Some(borrower);
+    }
+
+    /// The borrower that transmits the frame in this entry, if it is lent.
+    #[cfg(not(feature = "rx-only"))]
+    pub(super) fn lent_to(&self) -> Option<Borrower> {
+        self.desc().lent_to
+    }
+
+    /// Whether the frame in this entry is lent to a borrower.
+    #[cfg(not(feature = "rx-only"))]
+    pub(super) fn is_lent(&self) -> bool {
+        self.desc().lent_to.is_some()
+    }
+
+    /// Whether the frame in this entry is lent to a borrower.
+    #[cfg(feature = "rx-only")]
+    pub(super) fn is_lent(&self) -> bool {
+        false
     }
 
     /// Only call this if [`RxRingEntry::is_available`]
@@ -227,7 +450,7 @@ impl RxRingEntry {
             self.desc_mut().set_owned();
             Err(RxDescriptorError::DmaError)
         } else if self.desc().is_first() && self.desc().is_last() {
-            let frame_len = self.desc().get_frame_len();
+            let frame_len = self.desc().frame_len();
 
             // "Subsequent reads and writes cannot be moved ahead of preceding reads."
             core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::Acquire);
@@ -249,8 +472,57 @@ impl RxRingEntry {
     }
 }
 
+#[cfg(feature = "rx-inject")]
+impl<const N: usize> RxRingEntry<N> {
+    /// Fill this entry with `frame`, as if it was received from the wire.
+    ///
+    /// Only call this on entries that are not part of the DMA ring, and
+    /// if [`RxRingEntry::is_available`] returns `false`.
+    pub(super) fn inject(&mut self, frame: &[u8]) {
+        self.write_buffer(frame);
+        self.desc_mut().set_injected(frame.len());
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+impl<const N: usize> RxRingEntry<N> {
+    /// Pass ownership to the DMA, without setting up a buffer.
+    pub(super) fn set_dma_owned(&mut self) {
+        unsafe {
+            self.desc_mut().desc.write(0, RXDESC_0_OWN);
+        }
+    }
+
+    /// Write the status of a received segment, as the DMA would,
+    /// and pass ownership to the CPU.
+    pub(super) fn set_segment(&mut self, first: bool, last: bool, error: bool, len: usize) {
+        let flag = |set: bool, bit: u32| if set { bit } else { 0 };
+        let status = flag(first, RXDESC_0_FS)
+            | flag(last, RXDESC_0_LS)
+            | flag(error, RXDESC_0_ES)
+            | ((len as u32 & RXDESC_0_FL_MASK) << RXDESC_0_FL_SHIFT);
+
+        unsafe {
+            self.desc_mut().desc.write(0, status);
+        }
+    }
+}
+
+#[cfg(feature = "test-hooks")]
+impl<const N: usize> RxRingEntry<N> {
+    /// Set the error summary bit of this entry, as if the DMA reported
+    /// an error for the received frame.
+    ///
+    /// Only call this if [`RxRingEntry::is_available`] returns `true`.
+    pub(super) fn corrupt(&mut self) {
+        unsafe {
+            self.desc_mut().desc.modify(0, |w| w | RXDESC_0_ES);
+        }
+    }
+}
+
 #[cfg(feature = "ptp")]
-impl RxRingEntry {
+impl<const N: usize> RxRingEntry<N> {
     pub fn has_packet_id(&self, id: &PacketId) -> bool {
         Some(id) == self.desc().packet_id.as_ref()
     }
@@ -0,0 +1,84 @@
+use core::fmt::{Result, Write};
+
+/// Write a one-line summary of `frame` to `w`, in the form
+/// `dst 01:00:5e:00:00:01 src 02:00:00:00:00:01 type 0x0800 len 60`.
+pub(super) fn write_summary<W: Write>(frame: &[u8], w: &mut W) -> Result {
+    if frame.len() < 14 {
+        return write!(w, "truncated len {}", frame.len());
+    }
+
+    w.write_str("dst ")?;
+    write_address(&frame[..6], w)?;
+    w.write_str(" src ")?;
+    write_address(&frame[6..12], w)?;
+    write!(
+        w,
+        " type 0x{:02x}{:02x} len {}",
+        frame[12],
+        frame[13],
+        frame.len()
+    )
+}
+
+/// Write a hexdump of `frame` to `w`, with 16 bytes per line
+/// that are prefixed by their offset.
+pub(super) fn write_hexdump<W: Write>(frame: &[u8], w: &mut W) -> Result {
+    for (line, chunk) in frame.chunks(16).enumerate() {
+        write!(w, "{:04x}:", line * 16)?;
+        for byte in chunk {
+            write!(w, " {:02x}", byte)?;
+        }
+        w.write_char('\n')?;
+    }
+    Ok(())
+}
+
+fn write_address<W: Write>(address: &[u8], w: &mut W) -> Result {
+    for (i, byte) in address.iter().enumerate() {
+        if i != 0 {
+            w.write_char(':')?;
+        }
+        write!(w, "{:02x}", byte)?;
+    }
+    Ok(())
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    extern crate std;
+    use std::string::String;
+
+    use super::*;
+
+    #[test]
+    fn summary() {
+        let mut frame = [0u8; 60];
+        frame[..6].copy_from_slice(&[0x01, 0x00, 0x5E, 0x00, 0x00, 0x01]);
+        frame[6..12].copy_from_slice(&[0x02, 0x00, 0x00, 0x00, 0x00, 0x01]);
+        frame[12..14].copy_from_slice(&[0x08, 0x00]);
+
+        let mut s = String::new();
+        write_summary(&frame, &mut s).unwrap();
+        assert_eq!(
+            s,
+            "dst 01:00:5e:00:00:01 src 02:00:00:00:00:01 type 0x0800 len 60"
+        );
+
+        s.clear();
+        write_summary(&frame[..10], &mut s).unwrap();
+        assert_eq!(s, "truncated len 10");
+    }
+
+    #[test]
+    fn hexdump() {
+        let mut frame = [0u8; 18];
+        frame.iter_mut().enumerate().for_each(|(i, b)| *b = i as u8);
+
+        let mut s = String::new();
+        write_hexdump(&frame, &mut s).unwrap();
+        assert_eq!(
+            s,
+            "0000: 00 01 02 03 04 05 06 07 08 09 0a 0b 0c 0d 0e 0f\n0010: 10 11\n"
+        );
+    }
+}
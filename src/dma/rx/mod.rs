@@ -3,10 +3,28 @@ pub(crate) use self::descriptor::RxDescriptor;
 use self::descriptor::RxDescriptorError;
 pub use self::descriptor::RxRingEntry;
 
-use super::PacketId;
-use crate::peripherals::ETHERNET_DMA;
+#[cfg(not(feature = "stm32f1xx-hal"))]
+pub use self::descriptor::{IpPayloadType, IpVersion, RxFrameType};
+
+use super::{
+    ring::{assert_ring_len, next_index, MAX_RX_BUFFER_LEN},
+    PacketId, RingFault, RingPosition, RingState, MTU,
+};
+
+#[cfg(not(feature = "rx-only"))]
+use super::desc::Borrower;
+#[cfg(feature = "mirror")]
+use super::{Direction, Mirror};
+use crate::{crc, peripherals::ETHERNET_DMA};
 
 mod descriptor;
+mod format;
+
+mod segmented;
+pub use segmented::RxSegmentedPacket;
+
+mod vlan;
+pub use vlan::{VlanTag, VLAN_TAG_LEN, VLAN_TPID};
 
 #[cfg(feature = "ptp")]
 use crate::{dma::PacketIdNotFound, ptp::Timestamp};
@@ -16,7 +34,7 @@ use core::task::Poll;
 
 /// Errors that can occur during RX
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum RxError {
     /// The received packet was truncated
     Truncated,
@@ -35,18 +53,47 @@ impl From<RxDescriptorError> for RxError {
     }
 }
 
-/// Rx DMA state
-pub struct RxRing<'a> {
-    entries: &'a mut [RxRingEntry],
+/// Rx DMA state, for a ring of entries with buffers of `N` bytes.
+pub struct RxRing<'a, const N: usize = MTU> {
+    entries: &'a mut [RxRingEntry<N>],
     next_entry: usize,
+    gate_open: bool,
+    crc_check_remaining: u32,
+    state: RingState,
+    #[cfg(not(feature = "rx-only"))]
+    lent: usize,
+    #[cfg(feature = "rx-inject")]
+    injected: RxRingEntry<N>,
+    #[cfg(feature = "mirror")]
+    mirror: Option<Mirror>,
 }
 
-impl<'a> RxRing<'a> {
+/// The entry index used for the injected frame.
+#[cfg(feature = "rx-inject")]
+const INJECTED: usize = usize::MAX;
+
+impl<'a, const N: usize> RxRing<'a, N> {
     /// Allocate
-    pub(crate) fn new(entries: &'a mut [RxRingEntry]) -> Self {
+    pub(crate) fn new(entries: &'a mut [RxRingEntry<N>]) -> Self {
+        assert!(
+            N > 0 && N <= MAX_RX_BUFFER_LEN,
+            "The size of an RX buffer must be between 1 and {} bytes",
+            MAX_RX_BUFFER_LEN
+        );
+        assert_ring_len(entries.len());
+
         RxRing {
             entries,
             next_entry: 0,
+            gate_open: true,
+            crc_check_remaining: 0,
+            state: RingState::Stopped,
+            #[cfg(not(feature = "rx-only"))]
+            lent: 0,
+            #[cfg(feature = "rx-inject")]
+            injected: RxRingEntry::new(),
+            #[cfg(feature = "mirror")]
+            mirror: None,
         }
     }
 
@@ -54,7 +101,7 @@ impl<'a> RxRing<'a> {
     pub(crate) fn start(&mut self, eth_dma: &ETHERNET_DMA) {
         // Setup ring
         {
-            let mut previous: Option<&mut RxRingEntry> = None;
+            let mut previous: Option<&mut RxRingEntry<N>> = None;
             for entry in self.entries.iter_mut() {
                 if let Some(prev_entry) = &mut previous {
                     prev_entry.setup(Some(entry));
@@ -66,23 +113,47 @@ impl<'a> RxRing<'a> {
             }
         }
         self.next_entry = 0;
+
+        // The entry for injected frames is not part of the ring, and
+        // is never accessed by the DMA.
+        #[cfg(feature = "rx-inject")]
+        self.injected.setup(None);
+
         let ring_ptr = self.entries[0].desc() as *const RxDescriptor;
 
         // Register RxDescriptor
-        eth_dma
-            .dmardlar
-            .write(|w| unsafe { w.srl().bits(ring_ptr as u32) });
+        crate::quirks::write_rx_descriptor_list(eth_dma, ring_ptr as u32);
 
         // We already have fences in `set_owned`, which is called in `setup`
 
         // Start receive
         eth_dma.dmaomr.modify(|_, w| w.sr().set_bit());
+        self.state.set(RingState::Running);
 
         self.demand_poll();
     }
 
     /// Stop the RX DMA
-    pub(crate) fn stop(&self, eth_dma: &ETHERNET_DMA) {
+    pub(crate) fn stop(&mut self, eth_dma: &ETHERNET_DMA) {
+        self.halt(eth_dma);
+        self.state.set(RingState::Stopped);
+    }
+
+    /// Stop the RX DMA, so that it can be resumed with [`RxRing::resume`].
+    pub(crate) fn suspend(&mut self, eth_dma: &ETHERNET_DMA) {
+        self.halt(eth_dma);
+        self.state.set(RingState::Suspended);
+    }
+
+    /// Restart the RX DMA after [`RxRing::suspend`], continuing at
+    /// the descriptor at which it stopped.
+    pub(crate) fn resume(&mut self, eth_dma: &ETHERNET_DMA) {
+        eth_dma.dmaomr.modify(|_, w| w.sr().set_bit());
+        self.state.set(RingState::Running);
+        self.demand_poll();
+    }
+
+    fn halt(&self, eth_dma: &ETHERNET_DMA) {
         eth_dma.dmaomr.modify(|_, w| w.sr().clear_bit());
 
         // DMA accesses do not stop before the running state
@@ -91,12 +162,25 @@ impl<'a> RxRing<'a> {
         while self.running_state().is_running() {}
     }
 
+    /// The state that the driver put the RX DMA into.
+    pub fn state(&self) -> RingState {
+        self.state
+    }
+
+    /// Record that the RX DMA stopped because of `fault`.
+    ///
+    /// Faults that are not valid in the current state, such as the
+    /// process stopping after the driver stopped it, are ignored.
+    pub(crate) fn fault(&mut self, fault: RingFault) {
+        let _ = self.state.transition(RingState::Error(fault));
+    }
+
     /// Demand that the DMA engine polls the current `RxDescriptor`
     /// (when in [`RunningState::Stopped`].)
     fn demand_poll(&self) {
         // SAFETY: we only perform an atomic write to `dmarpdr`.
         let eth_dma = unsafe { &*ETHERNET_DMA::ptr() };
-        eth_dma.dmarpdr.write(|w| unsafe { w.rpd().bits(1) });
+        crate::quirks::rx_demand_poll(eth_dma);
     }
 
     /// Get current `RunningState`
@@ -122,13 +206,149 @@ impl<'a> RxRing<'a> {
 
     /// Check if we can receive a new packet
     pub fn next_entry_available(&self) -> bool {
+        if !self.gate_open {
+            return false;
+        }
+
         if !self.running_state().is_running() {
             self.demand_poll();
         }
 
+        #[cfg(feature = "rx-inject")]
+        if self.injected.is_available() {
+            return true;
+        }
+
         self.entries[self.next_entry].is_available()
     }
 
+    /// Close the RX gate.
+    ///
+    /// While the gate is closed, all received frames are discarded (and
+    /// counted in [`Statistics::rx_frames_suppressed`](crate::stats::Statistics::rx_frames_suppressed))
+    /// instead of being returned, so that the ring does not fill up with
+    /// (broadcast) frames while the application is still initializing.
+    ///
+    /// Frames are discarded whenever [`RxRing::recv_next`] (or `recv`) is
+    /// called, and when the gate is opened again.
+    pub fn close_gate(&mut self) {
+        self.gate_open = false;
+    }
+
+    /// Open the RX gate, discarding all frames that were received
+    /// while it was closed.
+    ///
+    /// See [`RxRing::close_gate`].
+    pub fn open_gate(&mut self) {
+        self.suppress();
+        self.gate_open = true;
+    }
+
+    /// Check whether the RX gate is open.
+    pub fn gate_is_open(&self) -> bool {
+        self.gate_open
+    }
+
+    /// Cross-check the CRC status reported by the MAC against a CRC that is
+    /// calculated in software, for the next `frames` received frames.
+    ///
+    /// This is intended for the bring-up of new boards, to gain confidence in
+    /// their signal integrity. While the check is active, the MAC does not
+    /// strip the FCS from received frames. The FCS of every frame that the MAC
+    /// passed as valid is verified, and removed before the frame is returned.
+    /// Mismatches are counted in [`Statistics::rx_crc_mismatches`](crate::stats::Statistics::rx_crc_mismatches),
+    /// and recorded in the event log if the `event-log` feature is enabled.
+    ///
+    /// Frames that were already received when the check is started or
+    /// finishes may be reported as mismatches, so the check should be
+    /// started before traffic is expected.
+    pub fn start_crc_check(&mut self, frames: u32) {
+        self.crc_check_remaining = frames;
+        crate::mac::EthernetMAC::set_crc_stripping(frames == 0);
+    }
+
+    /// The amount of frames that remain to be checked, see [`RxRing::start_crc_check`].
+    pub fn crc_check_remaining(&self) -> u32 {
+        self.crc_check_remaining
+    }
+
+    /// Verify the FCS of the frame of `length` bytes in entry `entry`,
+    /// and return the length of the frame without FCS.
+    fn check_crc(&mut self, entry: usize, length: usize) -> usize {
+        // SAFETY: the DMA has written `length` bytes to the buffer.
+        let frame = unsafe { self.entries[entry].as_slice(length) };
+
+        let length = length.saturating_sub(crc::FCS_LEN);
+        let (data, fcs) = frame.split_at(length);
+
+        self.record_crc_check(fcs == crc::fcs(data));
+
+        length
+    }
+
+    /// Count a frame whose FCS was verified in software, and stop the
+    /// check once enough frames have been checked.
+    fn record_crc_check(&mut self, matches: bool) {
+        let counters = crate::stats::counters();
+        crate::stats::add(&counters.rx_crc_checked, 1);
+
+        if !matches {
+            crate::stats::add(&counters.rx_crc_mismatches, 1);
+
+            #[cfg(feature = "event-log")]
+            crate::event_log::record(crate::event_log::DriverEvent::CrcMismatch);
+        }
+
+        self.crc_check_remaining -= 1;
+        if self.crc_check_remaining == 0 {
+            crate::mac::EthernetMAC::set_crc_stripping(true);
+        }
+    }
+
+    /// The position of software and of the DMA, which is currently
+    /// processing the descriptor at `address`.
+    pub(crate) fn position(&self, address: u32) -> RingPosition {
+        RingPosition::new(
+            self.next_entry,
+            address,
+            self.entries
+                .iter()
+                .map(|e| e.desc() as *const RxDescriptor as u32),
+        )
+    }
+
+    /// Discard all frames that are currently in the ring.
+    fn suppress(&mut self) {
+        let mut suppressed = 0;
+
+        #[cfg(feature = "rx-inject")]
+        if self.injected.is_available() {
+            self.injected.desc_mut().set_owned();
+            suppressed += 1;
+        }
+
+        // Visit every entry at most once, so that this terminates
+        // even if frames keep arriving.
+        for _ in 0..self.entries.len() {
+            let entry = &mut self.entries[self.next_entry];
+            if !entry.is_available() {
+                break;
+            }
+
+            entry.desc_mut().set_owned();
+            self.next_entry = next_index(self.next_entry, self.entries.len());
+            suppressed += 1;
+        }
+
+        if suppressed != 0 {
+            crate::stats::add(&crate::stats::counters().rx_frames_suppressed, suppressed);
+        }
+
+        if !self.running_state().is_running() {
+            self.demand_poll();
+        }
+    }
+
     /// Receive the next packet (if any is ready).
     ///
     /// This function returns a tuple of `Ok((entry_index, length))` on
@@ -139,18 +359,47 @@ impl<'a> RxRing<'a> {
         // NOTE(allow): packet_id is unused if ptp is disabled.
         #[allow(unused_variables)] packet_id: Option<PacketId>,
     ) -> Result<(usize, usize), RxError> {
+        #[cfg(not(feature = "rx-only"))]
+        self.release_lent();
+
+        if !self.gate_open {
+            self.suppress();
+            return Err(RxError::WouldBlock);
+        }
+
+        #[cfg(feature = "rx-inject")]
+        if self.injected.is_available() {
+            let length = self.injected.recv(packet_id)?;
+            return Ok((INJECTED, length));
+        }
+
         if !self.running_state().is_running() {
             self.demand_poll();
         }
 
-        let entries_len = self.entries.len();
         let entry_num = self.next_entry;
         let entry = &mut self.entries[entry_num];
 
         if entry.is_available() {
-            let length = entry.recv(packet_id)?;
+            #[cfg(feature = "test-hooks")]
+            if crate::test_hooks::take_rx_corruption() {
+                entry.corrupt();
+            }
+
+            let length = entry.recv(packet_id).map_err(RxError::from);
+
+            #[cfg(feature = "event-log")]
+            if let Err(e) = length {
+                crate::event_log::record(crate::event_log::DriverEvent::Rx(e));
+            }
 
-            self.next_entry = (self.next_entry + 1) % entries_len;
+            let mut length = length?;
+
+            if self.crc_check_remaining != 0 {
+                length = self.check_crc(entry_num, length);
+            }
+
+            self.next_entry = next_index(self.next_entry, self.entries.len());
 
             Ok((entry_num, length))
         } else {
@@ -160,12 +409,9 @@ impl<'a> RxRing<'a> {
 
     /// Receive the next packet (if any is ready), or return [`Err`]
     /// immediately.
-    pub fn recv_next(&mut self, packet_id: Option<PacketId>) -> Result<RxPacket, RxError> {
+    pub fn recv_next(&mut self, packet_id: Option<PacketId>) -> Result<RxPacket<'_, N>, RxError> {
         let (entry, length) = self.recv_next_impl(packet_id.map(|p| p.into()))?;
-        Ok(RxPacket {
-            entry: &mut self.entries[entry],
-            length,
-        })
+        Ok(self.packet(entry, length))
     }
 
     /// Receive the next packet.
@@ -173,7 +419,7 @@ impl<'a> RxRing<'a> {
     /// The returned [`RxPacket`] can be used as a slice, and
     /// will contain the ethernet data.
     #[cfg(feature = "async-await")]
-    pub async fn recv(&mut self, packet_id: Option<PacketId>) -> RxPacket {
+    pub async fn recv(&mut self, packet_id: Option<PacketId>) -> RxPacket<'_, N> {
         let (entry, length) = core::future::poll_fn(|ctx| {
             let res = self.recv_next_impl(packet_id.clone());
 
@@ -187,19 +433,191 @@ impl<'a> RxRing<'a> {
         })
         .await;
 
-        RxPacket {
-            entry: &mut self.entries[entry],
+        self.packet(entry, length)
+    }
+
+    /// Receive the next packet, and pass the part of it that `f` selects
+    /// to `send`, which transmits it directly from the RX buffer and
+    /// returns its [`Borrower`].
+    ///
+    /// The RX entry is lent to the borrower, and is only returned to the
+    /// DMA by [`RxRing::release_lent`] once the borrower is done with it.
+    /// Returns `Ok(false)` if `f` drops the packet.
+    #[cfg(not(feature = "rx-only"))]
+    pub(crate) fn reflect_next<F, S>(&mut self, f: F, send: S) -> Result<bool, RxError>
+    where
+        F: FnOnce(&mut [u8]) -> Option<usize>,
+        S: FnOnce(&[u8]) -> Borrower,
+    {
+        let (index, length) = self.recv_next_impl(None)?;
+
+        #[cfg(feature = "mirror")]
+        let mirror = self.mirror;
+
+        let entry = self.entry_mut(index);
+
+        // SAFETY: the DMA has written `length` bytes to the buffer.
+        let frame = unsafe { entry.as_mut_slice(length) };
+
+        #[cfg(feature = "mirror")]
+        if let Some(mirror) = mirror {
+            mirror(Direction::Rx, frame);
+        }
+
+        match f(frame) {
+            Some(reflected) => {
+                assert!(
+                    reflected <= length,
+                    "A reflected frame can not be longer than the received frame"
+                );
+                let borrower = send(&frame[..reflected]);
+                entry.lend(borrower);
+                self.lent += 1;
+                Ok(true)
+            }
+            None => {
+                entry.desc_mut().set_owned();
+                Ok(false)
+            }
+        }
+    }
+
+    /// Return the entries whose borrowers are done with them to the DMA.
+    ///
+    /// This is done whenever a packet is received.
+    #[cfg(not(feature = "rx-only"))]
+    pub(crate) fn release_lent(&mut self) {
+        if self.lent == 0 {
+            return;
+        }
+
+        let release = |entry: &mut RxRingEntry<N>| match entry.lent_to() {
+            Some(borrower) if borrower.is_done() => {
+                entry.desc_mut().set_owned();
+                1
+            }
+            _ => 0,
+        };
+
+        let mut released = 0;
+        for entry in self.entries.iter_mut() {
+            released += release(entry);
+        }
+
+        #[cfg(feature = "rx-inject")]
+        {
+            released += release(&mut self.injected);
+        }
+
+        self.lent -= released;
+
+        if released != 0 && !self.running_state().is_running() {
+            self.demand_poll();
+        }
+    }
+
+    /// Create the packet for a received entry, and mirror it.
+    fn packet(&mut self, index: usize, length: usize) -> RxPacket<'_, N> {
+        #[cfg(feature = "mirror")]
+        let mirror = self.mirror;
+
+        let packet = RxPacket {
+            entry: self.entry_mut(index),
             length,
+            offset: 0,
+        };
+
+        #[cfg(feature = "mirror")]
+        if let Some(mirror) = mirror {
+            mirror(Direction::Rx, &packet);
         }
+
+        packet
+    }
+
+    /// Check that the entries that are not owned by the DMA form a single
+    /// run that starts at the next entry that software will process.
+    ///
+    /// The DMA releases entries in order, so this only holds reliably
+    /// while no frames are being received.
+    #[cfg(not(any(feature = "rx-only", feature = "tx-only")))]
+    pub(crate) fn ownership_consistent(&self) -> bool {
+        let len = self.entries.len();
+        let mut entries = (0..len).map(|offset| &self.entries[(self.next_entry + offset) % len]);
+
+        while matches!(entries.next(), Some(entry) if entry.is_available()) {}
+        entries.all(|entry| !entry.is_available())
+    }
+
+    /// Set the hook that is called with every received frame.
+    ///
+    /// See [`EthernetDMA::set_mirror`](crate::dma::EthernetDMA::set_mirror).
+    #[cfg(feature = "mirror")]
+    pub fn set_mirror(&mut self, mirror: Option<Mirror>) {
+        self.mirror = mirror;
+    }
+
+    fn entry(&self, index: usize) -> &RxRingEntry<N> {
+        #[cfg(feature = "rx-inject")]
+        if index == INJECTED {
+            return &self.injected;
+        }
+
+        &self.entries[index]
+    }
+
+    fn entry_mut(&mut self, index: usize) -> &mut RxRingEntry<N> {
+        #[cfg(feature = "rx-inject")]
+        if index == INJECTED {
+            return &mut self.injected;
+        }
+
+        &mut self.entries[index]
+    }
+
+    /// Check whether a frame can be injected using [`RxRing::inject`].
+    #[cfg(feature = "rx-inject")]
+    pub fn can_inject(&self) -> bool {
+        !self.injected.is_available() && !self.injected.is_lent()
+    }
+
+    /// Inject `frame` into the receive path, as if it was received
+    /// from the wire.
+    ///
+    /// The injected frame is returned by the next call to
+    /// [`RxRing::recv_next`] (or `recv`), before any frames that are
+    /// waiting in the ring. Only a single frame can be injected at a time:
+    /// if the previously injected frame has not been received yet, this
+    /// function returns [`Err(RxError::WouldBlock)`](RxError::WouldBlock).
+    ///
+    /// # Panics
+    /// This function panics if `frame` is longer than the RX buffers.
+    #[cfg(feature = "rx-inject")]
+    pub fn inject(&mut self, frame: &[u8]) -> Result<(), RxError> {
+        assert!(frame.len() <= N, "Injected frame is too long");
+
+        if !self.can_inject() {
+            return Err(RxError::WouldBlock);
+        }
+
+        self.injected.inject(frame);
+
+        #[cfg(feature = "async-await")]
+        crate::dma::EthernetDMA::rx_waker().wake();
+
+        Ok(())
     }
 }
 
 #[cfg(feature = "ptp")]
-impl<'a> RxRing<'a> {
+impl<const N: usize> RxRing<'_, N> {
     /// Get the timestamp for a specific ID
     pub fn timestamp(&self, id: &PacketId) -> Result<Option<Timestamp>, PacketIdNotFound> {
         let entry = self.entries.iter().find(|e| e.has_packet_id(id));
 
+        #[cfg(feature = "rx-inject")]
+        let entry = entry.or_else(|| Some(&self.injected).filter(|e| e.has_packet_id(id)));
+
         let entry = entry.ok_or(PacketIdNotFound)?;
 
         Ok(entry.read_timestamp())
@@ -224,44 +642,251 @@ impl RunningState {
     }
 }
 
+/// The receive status of an [`RxPacket`], as reported by the MAC.
+///
+/// See [`RxPacket::meta`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RxPacketMeta {
+    /// See [`RxPacket::has_ip_header_error`].
+    pub ip_header_error: bool,
+    /// See [`RxPacket::has_ip_payload_error`].
+    pub ip_payload_error: bool,
+    /// See [`RxPacket::failed_address_filter`].
+    pub failed_address_filter: bool,
+    /// See [`RxPacket::frame_type`].
+    #[cfg(not(feature = "stm32f1xx-hal"))]
+    pub frame_type: RxFrameType,
+    /// See [`RxPacket::is_checksum_bypassed`].
+    #[cfg(not(feature = "stm32f1xx-hal"))]
+    pub checksum_bypassed: bool,
+    /// See [`RxPacket::vlan_tag`].
+    pub vlan_tag: Option<VlanTag>,
+}
+
+impl RxPacketMeta {
+    /// Whether the checksum offload engine checked the IP header and the
+    /// TCP, UDP or ICMP payload of the packet, and found no errors, so that
+    /// the checksums of the packet do not have to be verified in software.
+    ///
+    /// This is `false` for packets that the engine did not check: frames
+    /// that do not contain an IP packet, IP fragments and other payloads
+    /// whose type is [`IpPayloadType::Other`], and packets for which the
+    /// engine was bypassed. The STM32F1 does not report which packets it
+    /// checked, so this is always `false` there.
+    pub fn checksums_ok(&self) -> bool {
+        #[cfg(not(feature = "stm32f1xx-hal"))]
+        {
+            let checked = !self.checksum_bypassed
+                && matches!(
+                    self.frame_type,
+                    RxFrameType::Ip {
+                        payload: IpPayloadType::Udp | IpPayloadType::Tcp | IpPayloadType::Icmp,
+                        ..
+                    }
+                );
+
+            checked && !self.ip_header_error && !self.ip_payload_error
+        }
+
+        #[cfg(feature = "stm32f1xx-hal")]
+        {
+            false
+        }
+    }
+}
+
 /// A received packet.
 ///
 /// This packet implements [Deref<\[u8\]>](core::ops::Deref) and should be used
 /// as a slice.
-pub struct RxPacket<'a> {
-    entry: &'a mut RxRingEntry,
+pub struct RxPacket<'a, const N: usize = MTU> {
+    entry: &'a mut RxRingEntry<N>,
     length: usize,
+    /// The offset of the start of the packet in the buffer, which
+    /// is non-zero once the VLAN tag has been stripped.
+    offset: usize,
 }
 
-impl<'a> core::ops::Deref for RxPacket<'a> {
+impl<const N: usize> core::ops::Deref for RxPacket<'_, N> {
     type Target = [u8];
 
     fn deref(&self) -> &Self::Target {
-        &self.entry.as_slice()[0..self.length]
+        // SAFETY: the DMA has written `length` bytes to the buffer.
+        unsafe { &self.entry.as_slice(self.length)[self.offset..] }
     }
 }
 
-impl<'a> core::ops::DerefMut for RxPacket<'a> {
+impl<const N: usize> core::ops::DerefMut for RxPacket<'_, N> {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.entry.as_mut_slice()[0..self.length]
+        // SAFETY: the DMA has written `length` bytes to the buffer.
+        unsafe { &mut self.entry.as_mut_slice(self.length)[self.offset..] }
     }
 }
 
-impl<'a> Drop for RxPacket<'a> {
+impl<const N: usize> Drop for RxPacket<'_, N> {
     fn drop(&mut self) {
         self.entry.desc_mut().set_owned();
     }
 }
 
-impl<'a> RxPacket<'a> {
+impl<const N: usize> RxPacket<'_, N> {
     /// Pass the received packet back to the DMA engine.
     pub fn free(self) {
         drop(self)
     }
 
+    /// Whether this packet did not match the destination address filters,
+    /// and was only passed because the MAC is promiscuous.
+    pub fn failed_address_filter(&self) -> bool {
+        self.entry.desc().failed_address_filter()
+    }
+
+    /// Whether the checksum offload engine detected an error in the IP
+    /// header of this packet (IPv4 header checksum error, or a mismatch
+    /// between the EtherType and the IP version).
+    ///
+    /// If this returns `true`, the checksums of this packet must be
+    /// verified in software.
+    pub fn has_ip_header_error(&self) -> bool {
+        self.entry.desc().has_ip_header_error()
+    }
+
+    /// Whether the checksum offload engine detected an error in the
+    /// TCP, UDP or ICMP payload of this packet.
+    ///
+    /// If this returns `true`, the checksums of this packet must be
+    /// verified in software.
+    pub fn has_ip_payload_error(&self) -> bool {
+        self.entry.desc().has_ip_payload_error()
+    }
+
+    /// The type of this packet, as decoded by the MAC.
+    ///
+    /// This can be used to dispatch received packets without parsing
+    /// their headers.
+    #[cfg(not(feature = "stm32f1xx-hal"))]
+    pub fn frame_type(&self) -> RxFrameType {
+        self.entry.desc().frame_type()
+    }
+
+    /// Whether the checksum offload engine was bypassed for this packet,
+    /// so that its checksums were not checked.
+    #[cfg(not(feature = "stm32f1xx-hal"))]
+    pub fn is_checksum_bypassed(&self) -> bool {
+        self.entry.desc().is_checksum_bypassed()
+    }
+
+    /// Get the receive status of this packet.
+    ///
+    /// This can be used to skip software checksum verification, or to
+    /// drop packets with bad checksums early.
+    pub fn meta(&self) -> RxPacketMeta {
+        RxPacketMeta {
+            ip_header_error: self.has_ip_header_error(),
+            ip_payload_error: self.has_ip_payload_error(),
+            failed_address_filter: self.failed_address_filter(),
+            #[cfg(not(feature = "stm32f1xx-hal"))]
+            frame_type: self.frame_type(),
+            #[cfg(not(feature = "stm32f1xx-hal"))]
+            checksum_bypassed: self.is_checksum_bypassed(),
+            vlan_tag: self.vlan_tag(),
+        }
+    }
+
+    /// The VLAN tag of this packet, if the MAC detected it as a VLAN
+    /// frame (see [`EthernetMAC::set_vlan_tag_comparison`]).
+    ///
+    /// Returns `None` once the tag has been stripped.
+    ///
+    /// [`EthernetMAC::set_vlan_tag_comparison`]: crate::mac::EthernetMAC::set_vlan_tag_comparison
+    pub fn vlan_tag(&self) -> Option<VlanTag> {
+        if self.offset == 0 && self.entry.desc().is_vlan_frame() {
+            VlanTag::parse(self)
+        } else {
+            None
+        }
+    }
+
+    /// Remove the VLAN tag from this packet, if the MAC detected it as a
+    /// VLAN frame, and return it.
+    ///
+    /// The tag is removed in place, by moving the destination and source
+    /// addresses over it, so the packet becomes [`VLAN_TAG_LEN`] bytes
+    /// shorter.
+    pub fn strip_vlan_tag(&mut self) -> Option<VlanTag> {
+        if self.offset != 0 || !self.entry.desc().is_vlan_frame() {
+            return None;
+        }
+
+        let (tag, offset) = vlan::strip(self)?;
+        self.offset = offset;
+        Some(tag)
+    }
+
     /// Get the timestamp associated with this packet
+    ///
+    /// This is the hardware timestamp that the MAC captured in the
+    /// RX descriptor when the frame was received. It is `None` if the
+    /// MAC did not timestamp the frame.
     #[cfg(feature = "ptp")]
     pub fn timestamp(&self) -> Option<Timestamp> {
         self.entry.read_timestamp()
     }
+
+    /// Write a one-line summary of this packet (destination, source,
+    /// EtherType and length) to `w`.
+    pub fn summary<W: core::fmt::Write>(&self, w: &mut W) -> core::fmt::Result {
+        format::write_summary(self, w)
+    }
+
+    /// Write a hexdump of this packet to `w`.
+    pub fn hexdump<W: core::fmt::Write>(&self, w: &mut W) -> core::fmt::Result {
+        format::write_hexdump(self, w)
+    }
+}
+
+#[cfg(all(test, not(target_os = "none"), not(feature = "stm32f1xx-hal")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn unchecked_packets_are_not_ok() {
+        let tcp = RxPacketMeta {
+            ip_header_error: false,
+            ip_payload_error: false,
+            failed_address_filter: false,
+            frame_type: RxFrameType::Ip {
+                version: IpVersion::V4,
+                payload: IpPayloadType::Tcp,
+            },
+            checksum_bypassed: false,
+            vlan_tag: None,
+        };
+        assert!(tcp.checksums_ok());
+
+        assert!(!RxPacketMeta {
+            ip_payload_error: true,
+            ..tcp
+        }
+        .checksums_ok());
+        assert!(!RxPacketMeta {
+            checksum_bypassed: true,
+            ..tcp
+        }
+        .checksums_ok());
+        assert!(!RxPacketMeta {
+            frame_type: RxFrameType::Other,
+            ..tcp
+        }
+        .checksums_ok());
+        assert!(!RxPacketMeta {
+            frame_type: RxFrameType::Ip {
+                version: IpVersion::V4,
+                payload: IpPayloadType::Other,
+            },
+            ..tcp
+        }
+        .checksums_ok());
+    }
 }
@@ -0,0 +1,359 @@
+use super::{RxError, RxRing, RxRingEntry};
+use crate::{
+    crc::{Crc32, FCS_LEN},
+    dma::{
+        ring::{advance_index, next_index},
+        MTU,
+    },
+};
+
+#[cfg(feature = "mirror")]
+use crate::dma::Direction;
+
+/// A received frame that may span several RX descriptors.
+///
+/// The segments of the frame can be accessed using [`RxSegmentedPacket::segments`],
+/// or copied into a contiguous buffer using [`RxSegmentedPacket::copy_to`].
+/// The descriptors are passed back to the DMA when this packet is dropped.
+///
+/// See [`RxRing::recv_segmented`].
+pub struct RxSegmentedPacket<'ring, 'rx, const N: usize = MTU> {
+    ring: &'ring mut RxRing<'rx, N>,
+    first: usize,
+    count: usize,
+    length: usize,
+}
+
+impl<const N: usize> RxSegmentedPacket<'_, '_, N> {
+    /// The total length of the frame.
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    /// Whether the frame is empty.
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// The amount of descriptors that the frame spans.
+    pub fn segment_count(&self) -> usize {
+        self.count
+    }
+
+    /// Iterate over the segments of the frame, in order.
+    pub fn segments<'a>(&'a self) -> impl Iterator<Item = &'a [u8]> + 'a {
+        // Shorten the ring's lifetime, so that the iterator only captures `'a`.
+        let ring: &'a RxRing<'a, N> = self.ring;
+        let (first, length) = (self.first, self.length);
+        (0..self.count).map(move |i| ring.segment(first, length, i))
+    }
+
+    /// Copy the frame into `buffer`, and return the amount of bytes
+    /// that were copied.
+    ///
+    /// If `buffer` is shorter than the frame, the frame is truncated.
+    pub fn copy_to(&self, buffer: &mut [u8]) -> usize {
+        let mut copied = 0;
+        for segment in self.segments() {
+            let remaining = &mut buffer[copied..];
+            let len = segment.len().min(remaining.len());
+            remaining[..len].copy_from_slice(&segment[..len]);
+            copied += len;
+        }
+        copied
+    }
+
+    /// Pass the received packet back to the DMA engine.
+    pub fn free(self) {
+        drop(self)
+    }
+}
+
+impl<const N: usize> Drop for RxSegmentedPacket<'_, '_, N> {
+    fn drop(&mut self) {
+        self.ring.release(self.first, self.count);
+    }
+}
+
+impl<'rx, const N: usize> RxRing<'rx, N> {
+    /// Receive the next frame (if any is ready), which may span several
+    /// descriptors, or return [`Err`] immediately.
+    ///
+    /// Unlike [`RxRing::recv_next`], which reports frames that do not fit
+    /// into a single descriptor as [`RxError::Truncated`], this collects all
+    /// descriptors of a frame, from the one marked as first segment up to the
+    /// one marked as last segment. This is required for frames that are
+    /// longer than the buffer of a descriptor, such as jumbo frames.
+    ///
+    /// If only a part of the frame has been received yet, this returns
+    /// [`Err(RxError::WouldBlock)`](RxError::WouldBlock). Frames that are
+    /// longer than the whole ring are discarded and reported as
+    /// [`RxError::Truncated`].
+    ///
+    /// Frames go through the same steps as in [`RxRing::recv_next`]: an
+    /// injected frame is returned first, the FCS is verified while a
+    /// [CRC check](RxRing::start_crc_check) is active, and the frame is
+    /// passed to the mirror hook, one segment at a time.
+    pub fn recv_segmented(&mut self) -> Result<RxSegmentedPacket<'_, 'rx, N>, RxError> {
+        let result = self.find_segments();
+
+        #[cfg(feature = "event-log")]
+        if let Err(e) = result {
+            if e != RxError::WouldBlock {
+                crate::event_log::record(crate::event_log::DriverEvent::Rx(e));
+            }
+        }
+
+        let (first, count, length) = result?;
+
+        let packet = RxSegmentedPacket {
+            ring: self,
+            first,
+            count,
+            length,
+        };
+
+        #[cfg(feature = "mirror")]
+        if let Some(mirror) = packet.ring.mirror {
+            packet
+                .segments()
+                .for_each(|segment| mirror(Direction::Rx, segment));
+        }
+
+        Ok(packet)
+    }
+
+    /// Find the descriptors of the next frame, and return the index of
+    /// the first descriptor, the amount of descriptors, and the length
+    /// of the frame.
+    fn find_segments(&mut self) -> Result<(usize, usize, usize), RxError> {
+        #[cfg(not(feature = "rx-only"))]
+        self.release_lent();
+
+        if !self.gate_open {
+            self.suppress();
+            return Err(RxError::WouldBlock);
+        }
+
+        #[cfg(feature = "rx-inject")]
+        if self.injected.is_available() {
+            let length = self.injected.recv(None)?;
+            return Ok((super::INJECTED, 1, length));
+        }
+
+        if !self.running_state().is_running() {
+            self.demand_poll();
+        }
+
+        let first = self.next_entry;
+        let scanned = scan(self.entries, first);
+
+        #[cfg(feature = "test-hooks")]
+        let scanned = match scanned {
+            Scan::Frame { count, .. } if crate::test_hooks::take_rx_corruption() => {
+                Scan::Error { count }
+            }
+            scanned => scanned,
+        };
+
+        let (count, result) = match scanned {
+            Scan::Incomplete => return Err(RxError::WouldBlock),
+            Scan::Frame { count, length } => (count, Ok(length)),
+            Scan::Discard { count } => {
+                self.release(first, count);
+                (count, Err(RxError::Truncated))
+            }
+            Scan::Error { count } => {
+                self.release(first, count);
+                (count, Err(RxError::DmaError))
+            }
+        };
+
+        self.next_entry = advance_index(first, count, self.entries.len());
+
+        let mut length = result?;
+        if self.crc_check_remaining != 0 {
+            length = self.check_segments_crc(first, count, length);
+        }
+
+        Ok((first, count, length))
+    }
+
+    /// Get segment `index` of the frame of `length` bytes whose first
+    /// descriptor is at `first`.
+    fn segment(&self, first: usize, length: usize, index: usize) -> &[u8] {
+        let entry = if index == 0 {
+            self.entry(first)
+        } else {
+            &self.entries[advance_index(first, index, self.entries.len())]
+        };
+
+        let length = length.saturating_sub(index * N).min(N);
+        // SAFETY: the DMA has written `length` bytes to the buffer.
+        unsafe { entry.as_slice(length) }
+    }
+
+    /// Verify the FCS of the frame of `length` bytes that spans `count`
+    /// descriptors starting at `first`, and return the length of the
+    /// frame without FCS.
+    ///
+    /// The FCS may be split over the last two segments.
+    fn check_segments_crc(&mut self, first: usize, count: usize, length: usize) -> usize {
+        let data_len = length.saturating_sub(FCS_LEN);
+
+        let mut crc = Crc32::new();
+        let mut fcs = [0; FCS_LEN];
+        for index in 0..count {
+            let start = index * N;
+            let segment = self.segment(first, length, index);
+            let (data, tail) = segment.split_at(data_len.saturating_sub(start).min(segment.len()));
+
+            crc.update(data);
+            if !tail.is_empty() {
+                let offset = start + data.len() - data_len;
+                fcs[offset..offset + tail.len()].copy_from_slice(tail);
+            }
+        }
+
+        self.record_crc_check(crc.finish().to_le_bytes() == fcs);
+
+        data_len
+    }
+
+    /// Pass `count` entries starting at `first` back to the DMA.
+    fn release(&mut self, first: usize, count: usize) {
+        let mut index = first;
+        self.entry_mut(index).desc_mut().set_owned();
+        for _ in 1..count {
+            index = next_index(index, self.entries.len());
+            self.entries[index].desc_mut().set_owned();
+        }
+    }
+}
+
+/// The result of scanning the ring for the descriptors of the next frame.
+#[derive(Debug, PartialEq)]
+enum Scan {
+    /// The last segment of the next frame has not been received yet.
+    Incomplete,
+    /// The next frame spans `count` descriptors, and is `length` bytes long.
+    Frame { count: usize, length: usize },
+    /// The first `count` descriptors do not belong to a frame that can be
+    /// received, and must be discarded.
+    Discard { count: usize },
+    /// The next frame spans `count` descriptors, and the DMA reported an
+    /// error for it.
+    Error { count: usize },
+}
+
+/// Scan `entries` for the descriptors of the frame that starts at `first`.
+fn scan<const N: usize>(entries: &[RxRingEntry<N>], first: usize) -> Scan {
+    let mut index = first;
+
+    for count in 1..=entries.len() {
+        let entry = &entries[index];
+        if !entry.is_available() {
+            return Scan::Incomplete;
+        }
+
+        let desc = entry.desc();
+        if desc.is_first() != (count == 1) {
+            // Either this is the continuation of a frame whose first
+            // segment was discarded, or the previous frame ended
+            // without a last segment.
+            let count = if count == 1 { 1 } else { count - 1 };
+            return Scan::Discard { count };
+        }
+
+        if desc.is_last() {
+            if desc.has_error() {
+                return Scan::Error { count };
+            }
+
+            let length = desc.frame_len();
+
+            // "Subsequent reads and writes cannot be moved ahead of preceding reads."
+            core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::Acquire);
+
+            return Scan::Frame { count, length };
+        }
+
+        index = next_index(index, entries.len());
+    }
+
+    // The frame is longer than the whole ring.
+    Scan::Discard {
+        count: entries.len(),
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    const LEN: usize = 4;
+
+    fn ring() -> [RxRingEntry<16>; LEN] {
+        let mut entries = [RxRingEntry::<16>::INIT; LEN];
+        entries.iter_mut().for_each(|e| e.set_dma_owned());
+        entries
+    }
+
+    #[test]
+    fn frames_are_collected_across_the_end_of_the_ring() {
+        let mut entries = ring();
+        assert_eq!(scan(&entries, 0), Scan::Incomplete);
+
+        entries[0].set_segment(true, false, false, 0);
+        entries[1].set_segment(false, false, false, 0);
+        assert_eq!(scan(&entries, 0), Scan::Incomplete);
+
+        entries[2].set_segment(false, true, false, 40);
+        assert_eq!(
+            scan(&entries, 0),
+            Scan::Frame {
+                count: 3,
+                length: 40
+            }
+        );
+
+        let mut entries = ring();
+        entries[3].set_segment(true, false, false, 0);
+        entries[0].set_segment(false, true, false, 20);
+        assert_eq!(
+            scan(&entries, 3),
+            Scan::Frame {
+                count: 2,
+                length: 20
+            }
+        );
+    }
+
+    #[test]
+    fn broken_frames_are_discarded() {
+        // A continuation without first segment.
+        let mut entries = ring();
+        entries[0].set_segment(false, true, false, 20);
+        assert_eq!(scan(&entries, 0), Scan::Discard { count: 1 });
+
+        // A frame without last segment, followed by a new frame.
+        let mut entries = ring();
+        entries[0].set_segment(true, false, false, 0);
+        entries[1].set_segment(false, false, false, 0);
+        entries[2].set_segment(true, true, false, 20);
+        assert_eq!(scan(&entries, 0), Scan::Discard { count: 2 });
+
+        // A frame that is longer than the whole ring.
+        let mut entries = ring();
+        entries[0].set_segment(true, false, false, 0);
+        for entry in &mut entries[1..] {
+            entry.set_segment(false, false, false, 0);
+        }
+        assert_eq!(scan(&entries, 0), Scan::Discard { count: LEN });
+
+        let mut entries = ring();
+        entries[0].set_segment(true, false, false, 0);
+        entries[1].set_segment(false, true, true, 20);
+        assert_eq!(scan(&entries, 0), Scan::Error { count: 2 });
+    }
+}
@@ -0,0 +1,87 @@
+//! IEEE 802.1Q VLAN tags of received frames.
+
+/// The EtherType (TPID) that identifies an 802.1Q VLAN tag.
+pub const VLAN_TPID: u16 = 0x8100;
+
+/// The length of an 802.1Q VLAN tag, in bytes.
+pub const VLAN_TAG_LEN: usize = 4;
+
+/// The offset of the VLAN tag in an ethernet frame.
+const VLAN_TAG_OFFSET: usize = 12;
+
+/// An IEEE 802.1Q VLAN tag.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VlanTag {
+    /// The tag control information (TCI): the priority, the drop
+    /// eligible indicator, and the VLAN ID.
+    pub tci: u16,
+}
+
+impl VlanTag {
+    /// The VLAN ID (VID).
+    pub const fn id(&self) -> u16 {
+        self.tci & 0x0FFF
+    }
+
+    /// The priority code point (PCP).
+    pub const fn priority(&self) -> u8 {
+        (self.tci >> 13) as u8
+    }
+
+    /// The drop eligible indicator (DEI).
+    pub const fn drop_eligible(&self) -> bool {
+        self.tci & (1 << 12) != 0
+    }
+
+    /// Parse the VLAN tag of `frame`, if it has one.
+    pub fn parse(frame: &[u8]) -> Option<Self> {
+        let tag = frame.get(VLAN_TAG_OFFSET..VLAN_TAG_OFFSET + VLAN_TAG_LEN)?;
+
+        if u16::from_be_bytes([tag[0], tag[1]]) != VLAN_TPID {
+            return None;
+        }
+
+        Some(Self {
+            tci: u16::from_be_bytes([tag[2], tag[3]]),
+        })
+    }
+}
+
+/// Remove the VLAN tag from `frame`, by moving the destination and
+/// source addresses over it.
+///
+/// Returns the tag, and the offset at which the untagged frame starts.
+pub(super) fn strip(frame: &mut [u8]) -> Option<(VlanTag, usize)> {
+    let tag = VlanTag::parse(frame)?;
+    frame.copy_within(..VLAN_TAG_OFFSET, VLAN_TAG_LEN);
+    Some((tag, VLAN_TAG_LEN))
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_and_strip() {
+        let mut frame = [0u8; 20];
+        frame[..12].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]);
+        frame[12..16].copy_from_slice(&[0x81, 0x00, 0xB0, 0x2A]);
+        frame[16..18].copy_from_slice(&[0x08, 0x00]);
+
+        let tag = VlanTag::parse(&frame).unwrap();
+        assert_eq!(tag.id(), 0x02A);
+        assert_eq!(tag.priority(), 5);
+        assert!(tag.drop_eligible());
+
+        let (stripped, offset) = strip(&mut frame).unwrap();
+        assert_eq!(stripped, tag);
+        assert_eq!(
+            &frame[offset..offset + 14],
+            &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 0x08, 0x00]
+        );
+
+        assert_eq!(VlanTag::parse(&frame[offset..]), None);
+        assert_eq!(VlanTag::parse(&frame[..14]), None);
+    }
+}
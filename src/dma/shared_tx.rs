@@ -0,0 +1,204 @@
+//! Sharing the TX side of the DMA between several producers.
+//!
+//! Applications with several independent sources of frames, such as an
+//! IP stack and a fieldbus protocol running at different interrupt
+//! priorities, can each send through their own [`TxHandle`], without
+//! coordinating their access to the [`EthernetDMA`].
+//!
+//! ```rust,ignore
+//! let shared = SharedTx::new(&mut dma);
+//! let ip_stack = shared.handle();
+//! let fieldbus = shared.handle();
+//! ```
+
+use core::cell::{Cell, RefCell};
+
+use cortex_m::interrupt::Mutex;
+
+use super::{EthernetDMA, PacketId, TxError, MTU};
+
+/// The maximum amount of [`TxHandle`]s of a [`SharedTx`] that can
+/// exist at the same time.
+pub const MAX_TX_HANDLES: usize = 32;
+
+#[derive(Debug, Clone, Copy)]
+struct Turns {
+    /// A bit for every handle whose last send attempt failed
+    /// because the TX ring was full.
+    waiting: u32,
+    /// The handle that sent a frame most recently.
+    last: u8,
+    /// A bit for every handle that currently exists.
+    allocated: u32,
+}
+
+/// Shares the TX side of an [`EthernetDMA`] between several producers.
+///
+/// Every producer sends frames through its own [`TxHandle`]. All access
+/// to the DMA happens inside of a critical section, so handles may be
+/// used from different tasks and interrupt priorities.
+///
+/// When the TX ring fills up, the producers that were refused a slot are
+/// served in round-robin order as soon as slots free up, so that a single
+/// busy producer can not starve the others. A producer that was refused a
+/// slot must retry until its frame is sent, or drop its handle, as the other
+/// producers otherwise wait for it indefinitely.
+pub struct SharedTx<'dma, 'rx, 'tx, const RX_BUF: usize = MTU> {
+    // Only `None` once the DMA has been released, which consumes the `SharedTx`.
+    dma: Mutex<RefCell<Option<&'dma mut EthernetDMA<'rx, 'tx, RX_BUF>>>>,
+    turns: Mutex<Cell<Turns>>,
+}
+
+impl<'dma, 'rx, 'tx, const RX_BUF: usize> SharedTx<'dma, 'rx, 'tx, RX_BUF> {
+    /// Share the TX side of `dma`.
+    pub fn new(dma: &'dma mut EthernetDMA<'rx, 'tx, RX_BUF>) -> Self {
+        Self {
+            dma: Mutex::new(RefCell::new(Some(dma))),
+            turns: Mutex::new(Cell::new(Turns {
+                waiting: 0,
+                last: 0,
+                allocated: 0,
+            })),
+        }
+    }
+
+    /// Create a new handle for a producer.
+    ///
+    /// # Panics
+    /// This function panics if [`MAX_TX_HANDLES`] handles already exist.
+    pub fn handle(&self) -> TxHandle<'_, 'dma, 'rx, 'tx, RX_BUF> {
+        let id = cortex_m::interrupt::free(|cs| {
+            let turns = self.turns.borrow(cs);
+            let mut state = turns.get();
+            let id = free_id(state.allocated).expect("Too many TX handles");
+            state.allocated |= 1 << id;
+            turns.set(state);
+            id
+        });
+
+        TxHandle { shared: self, id }
+    }
+
+    /// Release the shared DMA.
+    pub fn release(self) -> &'dma mut EthernetDMA<'rx, 'tx, RX_BUF> {
+        cortex_m::interrupt::free(|cs| self.dma.borrow(cs).borrow_mut().take())
+            .expect("The DMA is only released once")
+    }
+}
+
+/// A producer's handle to a [`SharedTx`].
+///
+/// Cloning a handle creates a new producer with its own turn.
+pub struct TxHandle<'shared, 'dma, 'rx, 'tx, const RX_BUF: usize = MTU> {
+    shared: &'shared SharedTx<'dma, 'rx, 'tx, RX_BUF>,
+    id: u8,
+}
+
+impl<const RX_BUF: usize> Clone for TxHandle<'_, '_, '_, '_, RX_BUF> {
+    fn clone(&self) -> Self {
+        self.shared.handle()
+    }
+}
+
+impl<const RX_BUF: usize> Drop for TxHandle<'_, '_, '_, '_, RX_BUF> {
+    fn drop(&mut self) {
+        cortex_m::interrupt::free(|cs| {
+            let turns = self.shared.turns.borrow(cs);
+            let mut state = turns.get();
+            state.waiting &= !(1 << self.id);
+            state.allocated &= !(1 << self.id);
+            turns.set(state);
+        });
+    }
+}
+
+impl<const RX_BUF: usize> TxHandle<'_, '_, '_, '_, RX_BUF> {
+    /// Try to send a packet with data.
+    ///
+    /// `f` is called inside of a critical section, so it should only copy
+    /// the frame into the TX buffer.
+    ///
+    /// If there are no free TX slots, or if another producer that was
+    /// refused a slot earlier has its turn first, this function will
+    /// return [`Err(TxError::WouldBlock)`](TxError::WouldBlock).
+    pub fn send<F>(&self, length: usize, packet_id: Option<PacketId>, f: F) -> Result<(), TxError>
+    where
+        F: FnOnce(&mut [u8]),
+    {
+        cortex_m::interrupt::free(|cs| {
+            let turns = self.shared.turns.borrow(cs);
+            let mut state = turns.get();
+            let bit = 1 << self.id;
+
+            let result = if is_turn(state.waiting, state.last, self.id) {
+                let mut dma = self.shared.dma.borrow(cs).borrow_mut();
+                let dma = dma.as_mut().expect("The DMA is not released yet");
+                dma.send(length, packet_id, f)
+            } else {
+                Err(TxError::WouldBlock)
+            };
+
+            if result.is_ok() {
+                state.waiting &= !bit;
+                state.last = self.id;
+            } else {
+                state.waiting |= bit;
+            }
+            turns.set(state);
+
+            result
+        })
+    }
+}
+
+/// The lowest handle ID that is not `allocated`.
+fn free_id(allocated: u32) -> Option<u8> {
+    let id = allocated.trailing_ones();
+    (id < MAX_TX_HANDLES as u32).then_some(id as u8)
+}
+
+/// Check whether `id` may send, given the set of `waiting` handles
+/// and the handle that sent `last`.
+///
+/// The first handle after `last` in round-robin order, out of the waiting
+/// handles and `id` itself, has the turn.
+fn is_turn(waiting: u32, last: u8, id: u8) -> bool {
+    let candidates = waiting | (1 << id);
+    let start = (last as u32 + 1) % MAX_TX_HANDLES as u32;
+    let next = (candidates.rotate_right(start).trailing_zeros() + start) % MAX_TX_HANDLES as u32;
+    next == id as u32
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn uncontended_handle_has_turn() {
+        assert!(is_turn(0, 0, 0));
+        assert!(is_turn(0, 3, 3));
+        assert!(is_turn(0b100, 5, 2));
+    }
+
+    #[test]
+    fn waiting_handles_are_served_round_robin() {
+        let waiting = 0b1011;
+
+        // Handle 1 sent last, so handle 3 is next, then 0.
+        assert!(is_turn(waiting, 1, 3));
+        assert!(!is_turn(waiting, 1, 0));
+        assert!(is_turn(waiting & !0b1000, 3, 0));
+
+        // A handle that was not waiting does not skip the queue.
+        assert!(!is_turn(waiting, 3, 2));
+    }
+
+    #[test]
+    fn ids_of_dropped_handles_are_reused() {
+        assert_eq!(free_id(0), Some(0));
+        assert_eq!(free_id(0b0111), Some(3));
+        assert_eq!(free_id(0b1101), Some(1));
+        assert_eq!(free_id(u32::MAX & !(1 << 31)), Some(31));
+        assert_eq!(free_id(u32::MAX), None);
+    }
+}
@@ -1,23 +1,44 @@
 use super::rx::RxRing;
-use super::tx::TxRing;
-use super::EthernetDMA;
+use super::tx::{ChecksumInsertion, TxRing};
+use super::{EthernetDMA, MTU};
 
 #[cfg(feature = "ptp")]
 use super::PacketId;
 
-use smoltcp::phy::{ChecksumCapabilities, Device, DeviceCapabilities, RxToken, TxToken};
+use smoltcp::phy::{Checksum, ChecksumCapabilities, Device, DeviceCapabilities, RxToken, TxToken};
 use smoltcp::time::Instant;
 
 /// Use this Ethernet driver with [smoltcp](https://github.com/smoltcp-rs/smoltcp)
-impl<'a, 'rx, 'tx> Device for &'a mut EthernetDMA<'rx, 'tx> {
-    type RxToken<'token> = EthRxToken<'token, 'rx> where Self: 'token;
+impl<'a, 'rx, 'tx, const RX_BUF: usize> Device for &'a mut EthernetDMA<'rx, 'tx, RX_BUF> {
+    type RxToken<'token> = EthRxToken<'token, 'rx, RX_BUF> where Self: 'token;
     type TxToken<'token> = EthTxToken<'token, 'tx> where Self: 'token;
 
     fn capabilities(&self) -> DeviceCapabilities {
         let mut caps = DeviceCapabilities::default();
-        caps.max_transmission_unit = crate::dma::MTU;
+        // Received frames must fit in a single RX buffer.
+        caps.max_transmission_unit = MTU.min(RX_BUF);
         caps.max_burst_size = Some(1);
         caps.checksum = ChecksumCapabilities::ignored();
+
+        // Received checksums are verified by the MAC, but transmitted
+        // checksums that the MAC does not insert must be calculated.
+        match self.tx_ring.checksum_insertion() {
+            ChecksumInsertion::Full => {}
+            ChecksumInsertion::IpHeader => {
+                caps.checksum.udp = Checksum::Tx;
+                caps.checksum.tcp = Checksum::Tx;
+                caps.checksum.icmpv4 = Checksum::Tx;
+                caps.checksum.icmpv6 = Checksum::Tx;
+            }
+            ChecksumInsertion::Disabled => {
+                caps.checksum.ipv4 = Checksum::Tx;
+                caps.checksum.udp = Checksum::Tx;
+                caps.checksum.tcp = Checksum::Tx;
+                caps.checksum.icmpv4 = Checksum::Tx;
+                caps.checksum.icmpv6 = Checksum::Tx;
+            }
+        }
+
         caps
     }
 
@@ -63,13 +84,17 @@ impl<'a, 'rx, 'tx> Device for &'a mut EthernetDMA<'rx, 'tx> {
 
 /// An Ethernet RX token that can be consumed in order to receive
 /// an ethernet packet.
-pub struct EthRxToken<'a, 'rx> {
-    rx_ring: &'a mut RxRing<'rx>,
+///
+/// No data is copied: [`RxToken::consume`] passes the buffer of the
+/// RX descriptor to smoltcp, which processes the packet in place. The
+/// descriptor is returned to the DMA once `consume` returns.
+pub struct EthRxToken<'a, 'rx, const N: usize = MTU> {
+    rx_ring: &'a mut RxRing<'rx, N>,
     #[cfg(feature = "ptp")]
     meta: PacketId,
 }
 
-impl<'dma, 'rx> RxToken for EthRxToken<'dma, 'rx> {
+impl<'dma, 'rx, const N: usize> RxToken for EthRxToken<'dma, 'rx, N> {
     fn consume<R, F>(self, f: F) -> R
     where
         F: FnOnce(&mut [u8]) -> R,
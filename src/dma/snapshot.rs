@@ -0,0 +1,86 @@
+use super::{EthernetDMA, RingState};
+
+/// The position of the software and the DMA in a descriptor ring.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RingPosition {
+    /// The index of the next descriptor that software will use.
+    pub software: usize,
+    /// The index of the descriptor that the DMA is currently processing,
+    /// as read from its current host descriptor register.
+    ///
+    /// This is `None` if the DMA has not fetched a descriptor yet, or if
+    /// the register points outside of the ring.
+    pub hardware: Option<usize>,
+}
+
+impl RingPosition {
+    /// Find the index of the descriptor at `address` in a ring whose
+    /// descriptors are located at `descriptors`.
+    pub(super) fn new<I>(software: usize, address: u32, mut descriptors: I) -> Self
+    where
+        I: Iterator<Item = u32>,
+    {
+        let hardware = descriptors.position(|d| d == address);
+        Self { software, hardware }
+    }
+}
+
+/// A snapshot of the positions of software and the DMA in the
+/// descriptor rings, and of the states of the rings.
+///
+/// See [`EthernetDMA::debug_snapshot`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DebugSnapshot {
+    /// The positions in the RX ring.
+    #[cfg(not(feature = "tx-only"))]
+    pub rx: RingPosition,
+    /// The positions in the TX ring.
+    #[cfg(not(feature = "rx-only"))]
+    pub tx: RingPosition,
+    /// The state of the RX ring.
+    #[cfg(not(feature = "tx-only"))]
+    pub rx_state: RingState,
+    /// The state of the TX ring.
+    #[cfg(not(feature = "rx-only"))]
+    pub tx_state: RingState,
+}
+
+impl<const RX_BUF: usize> EthernetDMA<'_, '_, RX_BUF> {
+    /// Take a snapshot of the positions of software and the DMA in
+    /// the descriptor rings.
+    ///
+    /// The position of the DMA is translated from its current host
+    /// descriptor registers (`DMACHRDR` and `DMACHTDR`) into an index
+    /// into the rings that were passed to the driver.
+    pub fn debug_snapshot(&self) -> DebugSnapshot {
+        DebugSnapshot {
+            #[cfg(not(feature = "tx-only"))]
+            rx: self.rx_ring.position(self.eth_dma.dmachrdr.read().bits()),
+            #[cfg(not(feature = "rx-only"))]
+            tx: self.tx_ring.position(self.eth_dma.dmachtdr.read().bits()),
+            #[cfg(not(feature = "tx-only"))]
+            rx_state: self.rx_ring.state(),
+            #[cfg(not(feature = "rx-only"))]
+            tx_state: self.tx_ring.state(),
+        }
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn translates_descriptor_address() {
+        let descriptors = [0x2000_0000, 0x2000_0040, 0x2000_0080];
+
+        let position = RingPosition::new(2, 0x2000_0040, descriptors.iter().copied());
+        assert_eq!(position.hardware, Some(1));
+        assert_eq!(position.software, 2);
+
+        let position = RingPosition::new(0, 0, descriptors.iter().copied());
+        assert_eq!(position.hardware, None);
+    }
+}
@@ -0,0 +1,181 @@
+//! Classification of transmitted frames by EtherType.
+//!
+//! Every frame that is passed to the DMA is counted in one of the
+//! [`TrafficClass`]es, which gives a cheap insight into the composition
+//! of the transmitted traffic. Like the [`statistics`](crate::stats), the
+//! counters are atomics that can be read from any context.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+/// The offset of the EtherType in an untagged frame.
+const ETHERTYPE_OFFSET: usize = 12;
+
+/// The class of a transmitted frame, by EtherType.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrafficClass {
+    /// IPv4 (`0x0800`).
+    Ipv4,
+    /// IPv6 (`0x86DD`).
+    Ipv6,
+    /// ARP (`0x0806`).
+    Arp,
+    /// VLAN tagged frames (`0x8100` or `0x88A8`), regardless of the
+    /// EtherType of the payload.
+    Vlan,
+    /// PTP over ethernet (`0x88F7`).
+    Ptp,
+    /// Any other EtherType, or a frame that is too short to contain one.
+    Other,
+}
+
+impl TrafficClass {
+    const COUNT: usize = 6;
+
+    /// Classify `frame`.
+    pub fn of(frame: &[u8]) -> Self {
+        let ethertype = match frame.get(ETHERTYPE_OFFSET..ETHERTYPE_OFFSET + 2) {
+            Some(ethertype) => u16::from_be_bytes([ethertype[0], ethertype[1]]),
+            None => return TrafficClass::Other,
+        };
+
+        match ethertype {
+            0x0800 => TrafficClass::Ipv4,
+            0x86DD => TrafficClass::Ipv6,
+            0x0806 => TrafficClass::Arp,
+            0x8100 | 0x88A8 => TrafficClass::Vlan,
+            0x88F7 => TrafficClass::Ptp,
+            _ => TrafficClass::Other,
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// The amount of frames and bytes that were transmitted in a
+/// [`TrafficClass`].
+///
+/// Both counters wrap around on overflow.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClassCounters {
+    /// The amount of frames.
+    pub frames: u32,
+    /// The amount of bytes, excluding the FCS.
+    pub bytes: u32,
+}
+
+/// A snapshot of the amount of transmitted traffic per [`TrafficClass`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TxClassStatistics {
+    /// See [`TrafficClass::Ipv4`].
+    pub ipv4: ClassCounters,
+    /// See [`TrafficClass::Ipv6`].
+    pub ipv6: ClassCounters,
+    /// See [`TrafficClass::Arp`].
+    pub arp: ClassCounters,
+    /// See [`TrafficClass::Vlan`].
+    pub vlan: ClassCounters,
+    /// See [`TrafficClass::Ptp`].
+    pub ptp: ClassCounters,
+    /// See [`TrafficClass::Other`].
+    pub other: ClassCounters,
+}
+
+impl TxClassStatistics {
+    /// The counters of `class`.
+    pub fn get(&self, class: TrafficClass) -> ClassCounters {
+        match class {
+            TrafficClass::Ipv4 => self.ipv4,
+            TrafficClass::Ipv6 => self.ipv6,
+            TrafficClass::Arp => self.arp,
+            TrafficClass::Vlan => self.vlan,
+            TrafficClass::Ptp => self.ptp,
+            TrafficClass::Other => self.other,
+        }
+    }
+}
+
+struct Counters {
+    frames: [AtomicU32; TrafficClass::COUNT],
+    bytes: [AtomicU32; TrafficClass::COUNT],
+}
+
+#[allow(clippy::declare_interior_mutable_const)]
+const ZERO: AtomicU32 = AtomicU32::new(0);
+
+static COUNTERS: Counters = Counters {
+    frames: [ZERO; TrafficClass::COUNT],
+    bytes: [ZERO; TrafficClass::COUNT],
+};
+
+/// Classify `frame`, and count it.
+pub(super) fn record(frame: &[u8]) {
+    let index = TrafficClass::of(frame).index();
+    COUNTERS.frames[index].fetch_add(1, Ordering::Relaxed);
+    COUNTERS.bytes[index].fetch_add(frame.len() as u32, Ordering::Relaxed);
+}
+
+/// Get a snapshot of the amount of transmitted traffic per [`TrafficClass`].
+///
+/// This function may be called from any context.
+pub fn tx_class_statistics() -> TxClassStatistics {
+    let get = |class: TrafficClass| ClassCounters {
+        frames: COUNTERS.frames[class.index()].load(Ordering::Relaxed),
+        bytes: COUNTERS.bytes[class.index()].load(Ordering::Relaxed),
+    };
+
+    TxClassStatistics {
+        ipv4: get(TrafficClass::Ipv4),
+        ipv6: get(TrafficClass::Ipv6),
+        arp: get(TrafficClass::Arp),
+        vlan: get(TrafficClass::Vlan),
+        ptp: get(TrafficClass::Ptp),
+        other: get(TrafficClass::Other),
+    }
+}
+
+/// Reset the amount of transmitted traffic per [`TrafficClass`] to zero.
+pub fn reset_tx_class_statistics() {
+    for counter in COUNTERS.frames.iter().chain(COUNTERS.bytes.iter()) {
+        counter.store(0, Ordering::Relaxed);
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    fn frame(ethertype: u16) -> [u8; 60] {
+        let mut frame = [0; 60];
+        frame[ETHERTYPE_OFFSET..ETHERTYPE_OFFSET + 2].copy_from_slice(&ethertype.to_be_bytes());
+        frame
+    }
+
+    #[test]
+    fn classify_and_count() {
+        assert_eq!(TrafficClass::of(&frame(0x86DD)), TrafficClass::Ipv6);
+        assert_eq!(TrafficClass::of(&frame(0x88A8)), TrafficClass::Vlan);
+        assert_eq!(TrafficClass::of(&frame(0x88CC)), TrafficClass::Other);
+        assert_eq!(TrafficClass::of(&[0x08; 13]), TrafficClass::Other);
+
+        reset_tx_class_statistics();
+        record(&frame(0x0800));
+        record(&frame(0x0800)[..42]);
+        record(&frame(0x88F7));
+
+        let statistics = tx_class_statistics();
+        assert_eq!(
+            statistics.get(TrafficClass::Ipv4),
+            ClassCounters {
+                frames: 2,
+                bytes: 102
+            }
+        );
+        assert_eq!(statistics.ptp.frames, 1);
+        assert_eq!(statistics.arp, ClassCounters::default());
+    }
+}
@@ -1,12 +1,15 @@
 use crate::dma::{
     desc::Descriptor,
     ring::{RingDescriptor, RingEntry},
-    PacketId,
+    PacketId, MTU,
 };
 
 #[cfg(feature = "ptp")]
 use crate::ptp::Timestamp;
 
+#[cfg(not(feature = "tx-only"))]
+use crate::dma::desc::Borrower;
+
 /// Owned by DMA engine
 const TXDESC_0_OWN: u32 = 1 << 31;
 /// Interrupt on completion
@@ -18,6 +21,10 @@ const TXDESC_0_LS: u32 = 1 << 29;
 /// Checksum insertion control
 const TXDESC_0_CIC0: u32 = 1 << 23;
 const TXDESC_0_CIC1: u32 = 1 << 22;
+/// Disable CRC
+const TXDESC_0_DC: u32 = 1 << 27;
+/// Disable pad
+const TXDESC_0_DP: u32 = 1 << 26;
 /// Timestamp this packet
 const TXDESC_0_TIMESTAMP_ENABLE: u32 = 1 << 25;
 /// This descriptor contains a timestamp
@@ -30,15 +37,51 @@ const TXDESC_0_TER: u32 = 1 << 21;
 const TXDESC_0_TCH: u32 = 1 << 20;
 /// Error status
 const TXDESC_0_ES: u32 = 1 << 15;
+/// Collision count
+const TXDESC_0_CC_SHIFT: usize = 3;
+const TXDESC_0_CC_MASK: u32 = 0x0f << TXDESC_0_CC_SHIFT;
+/// Excessive deferral
+const TXDESC_0_ED: u32 = 1 << 2;
+/// Deferred bit
+const TXDESC_0_DB: u32 = 1 << 0;
 /// TX done bit
 const TXDESC_1_TBS_SHIFT: usize = 0;
 const TXDESC_1_TBS_MASK: u32 = 0x0fff << TXDESC_1_TBS_SHIFT;
 
+/// The checksums that the MAC inserts into a transmitted frame.
+///
+/// Checksums are only inserted into IPv4 and IPv6 frames. The checksum
+/// fields of the frame may contain any value.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChecksumInsertion {
+    /// No checksums are inserted, so they must be calculated in software.
+    Disabled,
+    /// Only the IPv4 header checksum is inserted.
+    IpHeader,
+    /// The IPv4 header checksum and the TCP, UDP or ICMP payload
+    /// checksum are inserted.
+    #[default]
+    Full,
+}
+
+impl ChecksumInsertion {
+    const fn bits(&self) -> u32 {
+        match self {
+            ChecksumInsertion::Disabled => 0,
+            ChecksumInsertion::IpHeader => TXDESC_0_CIC1,
+            ChecksumInsertion::Full => TXDESC_0_CIC0 | TXDESC_0_CIC1,
+        }
+    }
+}
+
 /// A TX DMA Ring Descriptor
 #[repr(C)]
 pub struct TxDescriptor {
     desc: Descriptor,
     packet_id: Option<PacketId>,
+    tag: Option<u32>,
+    queued_at: u32,
     buffer1: u32,
     next_descriptor: u32,
     is_last: bool,
@@ -56,13 +99,14 @@ impl TxDescriptor {
         Self {
             desc: Descriptor::new(),
             packet_id: None,
+            tag: None,
+            queued_at: 0,
             buffer1: 0,
             next_descriptor: 0,
             is_last: false,
         }
     }
 
-    #[allow(unused)]
     fn has_error(&self) -> bool {
         (self.desc.read(0) & TXDESC_0_ES) == TXDESC_0_ES
     }
@@ -78,16 +122,25 @@ impl TxDescriptor {
         self.desc.read(0) & TXDESC_0_LS == TXDESC_0_LS
     }
 
-    /// Pass ownership to the DMA engine
-    fn set_owned(&mut self, length: usize, packet_id: Option<PacketId>) {
-        // Reconfigure packet ID
+    /// Pass ownership to the DMA engine, to transmit `length`
+    /// bytes from `buffer1`.
+    fn set_owned(
+        &mut self,
+        buffer1: u32,
+        length: usize,
+        packet_id: Option<PacketId>,
+        tag: Option<u32>,
+        checksum: ChecksumInsertion,
+        custom_fcs: bool,
+    ) {
+        // Reconfigure packet ID and tag
         self.packet_id = packet_id;
+        self.tag = tag;
 
         self.set_buffer1_len(length);
 
         // These descriptor values are sometimes overwritten by
         // timestamp data, so we rewrite this data.
-        let buffer1 = self.buffer1;
         unsafe {
             self.desc.write(2, buffer1);
         }
@@ -102,7 +155,14 @@ impl TxDescriptor {
         core::sync::atomic::fence(core::sync::atomic::Ordering::Release);
         core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::Release);
 
-        let mut extra_flags = 0;
+        let mut extra_flags = checksum.bits();
+
+        if custom_fcs {
+            // The frame already ends with an FCS, and is at least
+            // `MIN_FRAME_LEN` bytes long, so the MAC must neither pad
+            // it nor append another FCS.
+            extra_flags |= TXDESC_0_DC | TXDESC_0_DP;
+        }
 
         if self.packet_id.is_some() {
             extra_flags |= TXDESC_0_TIMESTAMP_ENABLE;
@@ -115,14 +175,7 @@ impl TxDescriptor {
         unsafe {
             self.desc.write(
                 0,
-                TXDESC_0_OWN
-                    | TXDESC_0_TCH
-                    | TXDESC_0_FS
-                    | TXDESC_0_LS
-                    | TXDESC_0_CIC0
-                    | TXDESC_0_CIC1
-                    | TXDESC_0_IC
-                    | extra_flags,
+                TXDESC_0_OWN | TXDESC_0_TCH | TXDESC_0_FS | TXDESC_0_LS | TXDESC_0_IC | extra_flags,
             )
         }
 
@@ -156,6 +209,38 @@ impl TxDescriptor {
     }
 }
 
+/// The completion of the transmission of a frame.
+///
+/// See [`TxRing::poll_completion`](super::TxRing::poll_completion).
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TxCompletion {
+    tag: Option<u32>,
+    error: bool,
+    #[cfg(feature = "ptp")]
+    timestamp: Option<Timestamp>,
+}
+
+impl TxCompletion {
+    /// The user tag that was attached to the frame using
+    /// [`TxPacket::set_tag`](super::TxPacket::set_tag), if any.
+    pub fn tag(&self) -> Option<u32> {
+        self.tag
+    }
+
+    /// Whether an error occured while transmitting the frame.
+    pub fn has_error(&self) -> bool {
+        self.error
+    }
+
+    /// The time at which the frame was transmitted, if it was
+    /// timestamped.
+    #[cfg(feature = "ptp")]
+    pub fn timestamp(&self) -> Option<Timestamp> {
+        self.timestamp
+    }
+}
+
 /// A TX DMA Ring Descriptor entry
 pub type TxRingEntry = RingEntry<TxDescriptor>;
 
@@ -183,19 +268,90 @@ impl TxRingEntry {
         !self.desc().is_owned()
     }
 
+    /// The time at which the frame in this entry was queued.
+    pub(super) fn queued_at(&self) -> u32 {
+        self.desc().queued_at
+    }
+
+    /// The length of the frame in this entry, excluding the FCS.
+    pub(super) fn frame_len(&self) -> usize {
+        ((self.desc().desc.read(1) & TXDESC_1_TBS_MASK) >> TXDESC_1_TBS_SHIFT) as usize
+    }
+
+    /// Record the time at which the frame in this entry was queued.
+    pub(super) fn set_queued_at(&mut self, time: u32) {
+        self.desc_mut().queued_at = time;
+    }
+
+    /// Only call this if [`TxRingEntry::is_available`]
+    pub(super) fn send(
+        &mut self,
+        length: usize,
+        packet_id: Option<PacketId>,
+        tag: Option<u32>,
+        checksum: ChecksumInsertion,
+        custom_fcs: bool,
+    ) {
+        let buffer1 = self.desc().buffer1;
+        self.desc_mut()
+            .set_owned(buffer1, length, packet_id, tag, checksum, custom_fcs);
+    }
+
+    /// Transmit `frame`, which is located outside of the buffer
+    /// of this entry, instead of the contents of the buffer.
+    ///
+    /// `frame` must not be modified or reused until the returned
+    /// [`Borrower`] is done with it. Only call this if
+    /// [`TxRingEntry::is_available`].
+    #[cfg(not(feature = "tx-only"))]
+    pub(super) fn send_borrowed(&mut self, frame: &[u8], checksum: ChecksumInsertion) -> Borrower {
+        let buffer = frame.as_ptr() as u32;
+        self.desc_mut()
+            .set_owned(buffer, frame.len(), None, None, checksum, false);
+        Borrower::new(&self.desc().desc, buffer)
+    }
+
+    /// Aggregate the collision and deferral status of the
+    /// transmitted frame into the statistics.
+    ///
+    /// Only call this once per transmitted frame, and only if
+    /// [`TxRingEntry::is_available`]
+    pub(super) fn count_statistics(&self) {
+        let tdes0 = self.desc().desc.read(0);
+        let counters = crate::stats::counters();
+
+        let collisions = (tdes0 & TXDESC_0_CC_MASK) >> TXDESC_0_CC_SHIFT;
+        crate::stats::add(&counters.tx_collisions, collisions);
+
+        if tdes0 & TXDESC_0_DB == TXDESC_0_DB {
+            crate::stats::add(&counters.tx_deferred, 1);
+        }
+
+        if tdes0 & TXDESC_0_ED == TXDESC_0_ED {
+            crate::stats::add(&counters.tx_excessive_deferral, 1);
+        }
+    }
+
     /// Only call this if [`TxRingEntry::is_available`]
-    pub(super) fn send(&mut self, length: usize, packet_id: Option<PacketId>) {
-        self.desc_mut().set_owned(length, packet_id);
+    pub(super) fn completion(&self) -> TxCompletion {
+        TxCompletion {
+            tag: self.desc().tag,
+            error: self.desc().has_error(),
+            #[cfg(feature = "ptp")]
+            timestamp: self.desc().timestamp(),
+        }
     }
 
     /// Only call this if [`TxRingEntry::is_available`]
-    pub fn buffer(&self) -> &[u8] {
-        self.as_slice()
+    pub(super) fn buffer(&self) -> &[u8] {
+        // SAFETY: all TX buffers are zeroed when the TX ring is started.
+        unsafe { self.as_slice(MTU) }
     }
 
     /// Only call this if [`TxRingEntry::is_available`]
-    pub fn buffer_mut(&mut self) -> &mut [u8] {
-        self.as_mut_slice()
+    pub(super) fn buffer_mut(&mut self) -> &mut [u8] {
+        // SAFETY: all TX buffers are zeroed when the TX ring is started.
+        unsafe { self.as_mut_slice(MTU) }
     }
 }
 
@@ -1,26 +1,65 @@
-use super::PacketId;
-use crate::peripherals::ETHERNET_DMA;
+use super::{
+    ring::{assert_ring_len, next_index},
+    PacketId, RingFault, RingPosition, RingState, MIN_FRAME_LEN,
+};
+use crate::{crc, peripherals::ETHERNET_DMA};
 
 #[cfg(feature = "ptp")]
 use super::{PacketIdNotFound, Timestamp};
 
+#[cfg(not(feature = "tx-only"))]
+use super::desc::Borrower;
+
+#[cfg(feature = "mirror")]
+use super::{Direction, Mirror};
+
 mod descriptor;
-pub use descriptor::{TxDescriptor, TxRingEntry};
+pub use descriptor::{ChecksumInsertion, TxCompletion, TxDescriptor, TxRingEntry};
+
+#[cfg(feature = "tx-classification")]
+mod classify;
+#[cfg(feature = "tx-classification")]
+pub use classify::{
+    reset_tx_class_statistics, tx_class_statistics, ClassCounters, TrafficClass, TxClassStatistics,
+};
 
 #[cfg(any(feature = "ptp", feature = "async-await"))]
 use core::task::Poll;
 
 /// Errors that can occur during Ethernet TX
 #[derive(Debug, PartialEq)]
+#[non_exhaustive]
 pub enum TxError {
     /// Ring buffer is full
     WouldBlock,
+    /// The frames that are already queued make it unlikely that the
+    /// frame is transmitted before its deadline.
+    DeadlineMissed,
+}
+
+/// The bytes that the MAC transmits in addition to the contents of
+/// the buffer of each frame: preamble and start frame delimiter (8),
+/// FCS (4) and the minimum interframe gap (12).
+const FRAME_OVERHEAD: usize = 8 + 4 + 12;
+
+/// Estimate how long it takes to transmit `frames` frames that
+/// contain `bytes` bytes in total at `mbps` Mbit/s, in nanoseconds.
+fn wire_time_ns(frames: usize, bytes: usize, mbps: u32) -> u64 {
+    let bits = (bytes + frames * FRAME_OVERHEAD) as u64 * 8;
+    bits * 1000 / mbps.max(1) as u64
 }
 
 /// Tx DMA state
 pub struct TxRing<'a> {
     entries: &'a mut [TxRingEntry],
     next_entry: usize,
+    completed_entry: usize,
+    in_flight: usize,
+    checksum: ChecksumInsertion,
+    clock: Option<fn() -> u32>,
+    state: RingState,
+    #[cfg(feature = "mirror")]
+    mirror: Option<Mirror>,
 }
 
 impl<'ring> TxRing<'ring> {
@@ -28,9 +67,18 @@ impl<'ring> TxRing<'ring> {
     ///
     /// `start()` will be needed before `send()`
     pub(crate) fn new(entries: &'ring mut [TxRingEntry]) -> Self {
+        assert_ring_len(entries.len());
+
         TxRing {
             entries,
             next_entry: 0,
+            completed_entry: 0,
+            in_flight: 0,
+            checksum: ChecksumInsertion::Full,
+            clock: None,
+            state: RingState::Stopped,
+            #[cfg(feature = "mirror")]
+            mirror: None,
         }
     }
 
@@ -40,6 +88,7 @@ impl<'ring> TxRing<'ring> {
         {
             let mut previous: Option<&mut TxRingEntry> = None;
             for entry in self.entries.iter_mut() {
+                entry.zero_buffer();
                 if let Some(prev_entry) = &mut previous {
                     prev_entry.setup(Some(entry));
                 }
@@ -52,10 +101,7 @@ impl<'ring> TxRing<'ring> {
 
         let ring_ptr = self.entries[0].desc() as *const TxDescriptor;
         // Register TxDescriptor
-        eth_dma
-            .dmatdlar
-            // Note: unsafe block required for `stm32f107`.
-            .write(|w| unsafe { w.stl().bits(ring_ptr as u32) });
+        crate::quirks::write_tx_descriptor_list(eth_dma, ring_ptr as u32);
 
         // "Preceding reads and writes cannot be moved past subsequent writes."
         #[cfg(feature = "fence")]
@@ -66,16 +112,32 @@ impl<'ring> TxRing<'ring> {
 
         // Start transmission
         eth_dma.dmaomr.modify(|_, w| w.st().set_bit());
+        self.state.set(RingState::Running);
     }
 
     /// Stop the TX DMA
-    pub(crate) fn stop(&self, eth_dma: &ETHERNET_DMA) {
+    pub(crate) fn stop(&mut self, eth_dma: &ETHERNET_DMA) {
         eth_dma.dmaomr.modify(|_, w| w.st().clear_bit());
 
         // DMA accesses do not stop before the running state
         // of the DMA has changed to something other than
         // running.
         while self.is_running() {}
+
+        self.state.set(RingState::Stopped);
+    }
+
+    /// The state that the driver put the TX DMA into.
+    pub fn state(&self) -> RingState {
+        self.state
+    }
+
+    /// Record that the TX DMA stopped because of `fault`.
+    ///
+    /// Faults that are not valid in the current state, such as the
+    /// process stopping after the driver stopped it, are ignored.
+    pub(crate) fn fault(&mut self, fault: RingFault) {
+        let _ = self.state.transition(RingState::Error(fault));
     }
 
     /// If this returns `true`, the next `send` will succeed.
@@ -83,24 +145,222 @@ impl<'ring> TxRing<'ring> {
         self.entries[self.next_entry].is_available()
     }
 
+    /// Get the completion of the oldest frame that has been sent, but
+    /// whose completion has not been returned yet.
+    ///
+    /// Completions are returned in the order in which the frames were
+    /// queued. If completions are not retrieved before the ring wraps
+    /// around, the completions of the oldest frames are discarded.
+    pub fn poll_completion(&mut self) -> Option<TxCompletion> {
+        if self.in_flight == 0 {
+            return None;
+        }
+
+        let entry = &self.entries[self.completed_entry];
+        if entry.is_available() {
+            Some(self.retire_oldest())
+        } else {
+            None
+        }
+    }
+
+    /// Retire all frames that have been sent, but whose completion
+    /// has not been returned yet, and pass their completions to `f`.
+    ///
+    /// Returns the amount of frames that were retired.
+    ///
+    /// Unlike [`TxRing::poll_completion`], this is intended to be called
+    /// after a TX interrupt, regardless of whether new frames are queued.
+    pub fn reclaim<F>(&mut self, mut f: F) -> usize
+    where
+        F: FnMut(TxCompletion),
+    {
+        let mut reclaimed = 0;
+        while let Some(completion) = self.poll_completion() {
+            f(completion);
+            reclaimed += 1;
+        }
+        reclaimed
+    }
+
+    /// Retire the oldest entry that is in flight, which must
+    /// be available.
+    fn retire_oldest(&mut self) -> TxCompletion {
+        let entry = &self.entries[self.completed_entry];
+        entry.count_statistics();
+        let completion = entry.completion();
+
+        self.completed_entry = next_index(self.completed_entry, self.entries.len());
+        self.in_flight -= 1;
+        completion
+    }
+
+    /// The checksums that are inserted into transmitted frames, unless
+    /// overridden with [`TxPacket::set_checksum_insertion`].
+    pub fn checksum_insertion(&self) -> ChecksumInsertion {
+        self.checksum
+    }
+
+    /// Set the checksums that are inserted into transmitted frames.
+    ///
+    /// The default is [`ChecksumInsertion::Full`].
+    pub fn set_checksum_insertion(&mut self, checksum: ChecksumInsertion) {
+        self.checksum = checksum;
+    }
+
+    /// Set the time source that is used to measure how long queued
+    /// frames wait for transmission, or remove it if `clock` is `None`.
+    ///
+    /// See [`TxRing::oldest_tx_age`].
+    pub fn set_clock(&mut self, clock: Option<fn() -> u32>) {
+        self.clock = clock;
+    }
+
+    /// Get how long the oldest frame that is still owned by the DMA has
+    /// been queued, in ticks of the clock set with [`TxRing::set_clock`].
+    ///
+    /// A steadily increasing age indicates head-of-line blocking, for
+    /// instance because the link is down or the link partner sent a
+    /// pause frame. Returns `None` if no clock is set, or if no frames
+    /// are waiting for transmission.
+    pub fn oldest_tx_age(&self) -> Option<u32> {
+        let clock = self.clock?;
+        let len = self.entries.len();
+
+        let queued_at = (0..self.in_flight)
+            .map(|offset| &self.entries[(self.completed_entry + offset) % len])
+            .find(|entry| !entry.is_available())?
+            .queued_at();
+
+        Some(clock().wrapping_sub(queued_at))
+    }
+
+    /// Estimate how long it takes until a frame of `length` bytes that is
+    /// queued now has been transmitted at `mbps` Mbit/s, in nanoseconds.
+    ///
+    /// The estimate assumes that the link is idle, apart from the frames
+    /// that are still owned by the DMA, and that no pause frames are received.
+    pub fn estimate_tx_latency_ns(&self, length: usize, mbps: u32) -> u64 {
+        let (frames, bytes) = self.backlog();
+        wire_time_ns(frames + 1, bytes + length.max(MIN_FRAME_LEN), mbps)
+    }
+
+    /// The amount of bytes in frames that are queued, but have not
+    /// been transmitted yet.
+    ///
+    /// Frames shorter than [`MIN_FRAME_LEN`] are counted as padded by
+    /// the MAC, and the FCS is not included.
+    pub fn backlog_bytes(&self) -> usize {
+        self.backlog().1
+    }
+
+    /// The amount of frames that are still owned by the DMA, and the
+    /// amount of bytes in those frames.
+    fn backlog(&self) -> (usize, usize) {
+        let len = self.entries.len();
+
+        (0..self.in_flight)
+            .map(|offset| &self.entries[(self.completed_entry + offset) % len])
+            .filter(|entry| !entry.is_available())
+            .fold((0, 0), |(frames, bytes), entry| {
+                (frames + 1, bytes + entry.frame_len().max(MIN_FRAME_LEN))
+            })
+    }
+
+    /// The position of software and of the DMA, which is currently
+    /// processing the descriptor at `address`.
+    pub(crate) fn position(&self, address: u32) -> RingPosition {
+        RingPosition::new(
+            self.next_entry,
+            address,
+            self.entries
+                .iter()
+                .map(|e| e.desc() as *const TxDescriptor as u32),
+        )
+    }
+
+    /// Check that no entry outside of the frames in flight is owned by
+    /// the DMA.
+    ///
+    /// The DMA only ever releases entries, so this holds regardless of
+    /// the progress of the DMA.
+    #[cfg(not(any(feature = "rx-only", feature = "tx-only")))]
+    pub(crate) fn ownership_consistent(&self) -> bool {
+        let len = self.entries.len();
+
+        (self.in_flight..len)
+            .map(|offset| &self.entries[(self.completed_entry + offset) % len])
+            .all(|entry| entry.is_available())
+    }
+
+    /// Set the hook that is called with every transmitted frame.
+    ///
+    /// See [`EthernetDMA::set_mirror`](crate::dma::EthernetDMA::set_mirror).
+    #[cfg(feature = "mirror")]
+    pub fn set_mirror(&mut self, mirror: Option<Mirror>) {
+        self.mirror = mirror;
+    }
+
+    /// Record that the entry at `self.next_entry` was handed to the DMA.
+    fn track_sent(&mut self) {
+        // The oldest completion is about to be overwritten.
+        if self.in_flight == self.entries.len() {
+            self.retire_oldest();
+        }
+        self.in_flight += 1;
+    }
+
     /// Check if we can send the next TX entry.
     ///
     /// If [`Ok(res)`] is returned, the caller of must ensure
     /// that [`self.entries[res].send()`](TxRingEntry::send) is called
     /// before a new invocation of `send_next_impl`.
     fn send_next_impl(&mut self) -> Result<usize, TxError> {
-        let entries_len = self.entries.len();
         let entry_num = self.next_entry;
         let entry = &mut self.entries[entry_num];
 
         if entry.is_available() {
-            self.next_entry = (self.next_entry + 1) % entries_len;
+            self.next_entry = next_index(self.next_entry, self.entries.len());
             Ok(entry_num)
         } else {
             Err(TxError::WouldBlock)
         }
     }
 
+    /// Transmit `frame` directly from where it is located, instead of
+    /// copying it into the buffer of the next entry.
+    ///
+    /// Returns the [`Borrower`] of `frame`, which must not be modified or
+    /// reused until the borrower is done with it.
+    #[cfg(not(feature = "tx-only"))]
+    pub(crate) fn send_borrowed(&mut self, frame: &[u8]) -> Result<Borrower, TxError> {
+        let entry = self.send_next_impl()?;
+
+        self.observe(frame);
+        self.track_sent();
+        if let Some(clock) = self.clock {
+            self.entries[entry].set_queued_at(clock());
+        }
+        let borrower = self.entries[entry].send_borrowed(frame, self.checksum);
+        self.demand_poll();
+
+        Ok(borrower)
+    }
+
+    /// Pass a frame that is about to be transmitted to the mirror
+    /// hook and the traffic classification.
+    // NOTE(allow): `frame` is unused if neither feature is enabled.
+    #[allow(unused_variables)]
+    fn observe(&self, frame: &[u8]) {
+        #[cfg(feature = "mirror")]
+        if let Some(mirror) = self.mirror {
+            mirror(Direction::Tx, frame);
+        }
+
+        #[cfg(feature = "tx-classification")]
+        classify::record(frame);
+    }
+
     /// Prepare a packet for sending.
     ///
     /// Write the data that you wish to send to the buffer
@@ -119,11 +379,15 @@ impl<'ring> TxRing<'ring> {
 
         assert!(length <= tx_buffer.len(), "Not enough space in TX buffer");
 
+        let checksum = self.checksum;
         Ok(TxPacket {
             ring: self,
             idx: entry,
             length,
             packet_id,
+            tag: None,
+            checksum,
+            custom_fcs: false,
         })
     }
 
@@ -153,11 +417,15 @@ impl<'ring> TxRing<'ring> {
         let tx_buffer = self.entries[entry].buffer_mut();
         assert!(length <= tx_buffer.len(), "Not enough space in TX buffer");
 
+        let checksum = self.checksum;
         TxPacket {
             ring: self,
             idx: entry,
             length,
             packet_id,
+            tag: None,
+            checksum,
+            custom_fcs: false,
         }
     }
 
@@ -166,17 +434,7 @@ impl<'ring> TxRing<'ring> {
     pub(crate) fn demand_poll(&self) {
         // SAFETY: we only perform an atomic write to `dmatpdr`
         let eth_dma = unsafe { &*ETHERNET_DMA::ptr() };
-        eth_dma.dmatpdr.write(|w| {
-            #[cfg(any(feature = "stm32f4xx-hal", feature = "stm32f7xx-hal"))]
-            {
-                w.tpd().poll()
-            }
-            #[cfg(feature = "stm32f1xx-hal")]
-            unsafe {
-                // TODO: There is no nice `poll` method for `stm32f107`?
-                w.tpd().bits(0)
-            }
-        });
+        crate::quirks::tx_demand_poll(eth_dma);
     }
 
     /// Is the Tx DMA engine running?
@@ -313,6 +571,9 @@ pub struct TxPacket<'borrow, 'ring> {
     idx: usize,
     length: usize,
     packet_id: Option<PacketId>,
+    tag: Option<u32>,
+    checksum: ChecksumInsertion,
+    custom_fcs: bool,
 }
 
 impl core::ops::Deref for TxPacket<'_, '_> {
@@ -330,7 +591,75 @@ impl core::ops::DerefMut for TxPacket<'_, '_> {
 }
 
 impl TxPacket<'_, '_> {
+    /// Attach a user `tag` to this packet.
+    ///
+    /// The tag is returned in the [`TxCompletion`] of this packet, which
+    /// can be used to correlate completions with the bookkeeping of upper
+    /// layers.
+    pub fn set_tag(&mut self, tag: u32) {
+        self.tag = Some(tag);
+    }
+
+    /// Set the checksums that are inserted into this packet, overriding
+    /// [`TxRing::checksum_insertion`].
+    ///
+    /// This has no effect once an FCS has been appended.
+    pub fn set_checksum_insertion(&mut self, checksum: ChecksumInsertion) {
+        if !self.custom_fcs {
+            self.checksum = checksum;
+        }
+    }
+
+    /// Calculate the FCS of this packet in software, and append it,
+    /// instead of letting the MAC append it.
+    ///
+    /// See [`TxPacket::append_custom_fcs`].
+    pub fn append_fcs(&mut self) {
+        self.pad();
+        let fcs = crc::crc32(self);
+        self.append_custom_fcs(fcs);
+    }
+
+    /// Append `fcs` to this packet, instead of letting the MAC append
+    /// the FCS. This can be used to transmit frames with a deliberately
+    /// invalid FCS.
+    ///
+    /// The packet is zero-padded to [`MIN_FRAME_LEN`] first, as the MAC
+    /// does not pad frames that already contain an FCS. The packet grows
+    /// by [`FCS_LEN`](crc::FCS_LEN) bytes.
+    ///
+    /// Checksum insertion is disabled for this packet, regardless of
+    /// [`TxPacket::set_checksum_insertion`], as checksums that the MAC
+    /// inserts would invalidate the FCS. The IP, TCP, and UDP checksums of
+    /// the packet must therefore already be filled in.
+    ///
+    /// # Panics
+    /// This function panics if the FCS does not fit into the TX buffer,
+    /// or if an FCS was already appended.
+    pub fn append_custom_fcs(&mut self, fcs: u32) {
+        assert!(!self.custom_fcs, "An FCS was already appended");
+        self.pad();
+
+        let start = self.length;
+        let end = start + crc::FCS_LEN;
+        let buffer = self.ring.entries[self.idx].buffer_mut();
+        assert!(end <= buffer.len(), "Not enough space in TX buffer");
+        buffer[start..end].copy_from_slice(&fcs.to_le_bytes());
+
+        self.length = end;
+        self.custom_fcs = true;
+        self.checksum = ChecksumInsertion::Disabled;
+    }
+
+    /// Zero-pad this packet to [`MIN_FRAME_LEN`].
+    fn pad(&mut self) {
+        let length = self.length.max(MIN_FRAME_LEN);
+        self.ring.entries[self.idx].buffer_mut()[self.length..length].fill(0);
+        self.length = length;
+    }
+
     /// Send this packet!
+    #[doc(alias = "commit")]
     pub fn send(self) {
         drop(self);
     }
@@ -338,7 +667,33 @@ impl TxPacket<'_, '_> {
 
 impl Drop for TxPacket<'_, '_> {
     fn drop(&mut self) {
-        self.ring.entries[self.idx].send(self.length, self.packet_id.clone());
+        let frame = &self[..];
+        self.ring.observe(frame);
+
+        self.ring.track_sent();
+        if let Some(clock) = self.ring.clock {
+            self.ring.entries[self.idx].set_queued_at(clock());
+        }
+        self.ring.entries[self.idx].send(
+            self.length,
+            self.packet_id.clone(),
+            self.tag,
+            self.checksum,
+            self.custom_fcs,
+        );
         self.ring.demand_poll();
     }
 }
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wire_time() {
+        // A minimum size frame takes 6.72 us at 100 Mbit/s.
+        assert_eq!(wire_time_ns(1, 60, 100), 6_720);
+        assert_eq!(wire_time_ns(1, 60, 10), 67_200);
+        assert_eq!(wire_time_ns(3, 1514 + 2 * 60, 100), 136_480);
+    }
+}
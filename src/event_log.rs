@@ -0,0 +1,205 @@
+//! A log of recent driver events.
+//!
+//! When the `event-log` feature is enabled, the driver keeps a small ring
+//! of the last [`EVENT_LOG_LEN`] events (link changes, errors, and abnormal
+//! DMA conditions) in RAM. The log can be retrieved at any time, for instance
+//! post-mortem, using [`EthernetDMA::event_log`], which is useful for field
+//! debugging where no console is attached.
+//!
+//! Every event is stamped with the PTP time if the `ptp` feature is enabled,
+//! and with the time of the application's monotonic clock if one was
+//! registered using [`set_monotonic_clock`]. This allows correlating events
+//! such as link flaps with timeouts observed by the application.
+
+use core::cell::{Cell, RefCell};
+
+use cortex_m::interrupt::Mutex;
+
+use crate::{
+    dma::{AbnormalInterrupts, EthernetDMA},
+    mac::phy::LinkEvent,
+};
+
+#[cfg(not(feature = "tx-only"))]
+use crate::dma::RxError;
+
+#[cfg(feature = "ptp")]
+use crate::ptp::{EthernetPTP, Timestamp};
+
+/// The amount of events that are kept in the event log.
+pub const EVENT_LOG_LEN: usize = 16;
+
+/// An event that occured in the driver.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub enum DriverEvent {
+    /// An event occured on the link of the PHY, as reported
+    /// by a [`LinkMonitor`](crate::mac::phy::LinkMonitor).
+    Link(LinkEvent),
+    /// The DMA reported abnormal conditions.
+    DmaAbnormal(AbnormalInterrupts),
+    /// Receiving a frame failed.
+    #[cfg(not(feature = "tx-only"))]
+    Rx(RxError),
+    /// An interrupt storm was detected, and the unhandled interrupt
+    /// sources were masked.
+    InterruptStorm,
+    /// The FCS of a received frame did not match the CRC calculated in
+    /// software, see [`RxRing::start_crc_check`](crate::dma::RxRing::start_crc_check).
+    #[cfg(not(feature = "tx-only"))]
+    CrcMismatch,
+}
+
+/// An event in the [`EventLog`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoggedEvent {
+    /// The sequence number of this event.
+    ///
+    /// The sequence number increases by one for every event, so gaps
+    /// between the sequence numbers of consecutive entries indicate that
+    /// events were overwritten.
+    pub sequence: u32,
+    /// The PTP time at which the event occured.
+    #[cfg(feature = "ptp")]
+    pub time: Timestamp,
+    /// The time of the monotonic clock at which the event occured,
+    /// or `0` if no clock was registered.
+    ///
+    /// See [`set_monotonic_clock`].
+    pub ticks: u32,
+    /// The event that occured.
+    pub event: DriverEvent,
+}
+
+/// A ring of the last [`EVENT_LOG_LEN`] driver events.
+#[derive(Debug, Clone, Copy)]
+pub struct EventLog {
+    entries: [Option<LoggedEvent>; EVENT_LOG_LEN],
+    sequence: u32,
+}
+
+impl EventLog {
+    const fn new() -> Self {
+        Self {
+            entries: [None; EVENT_LOG_LEN],
+            sequence: 0,
+        }
+    }
+
+    fn push(&mut self, event: LoggedEvent) {
+        self.entries[event.sequence as usize % EVENT_LOG_LEN] = Some(event);
+        self.sequence = self.sequence.wrapping_add(1);
+    }
+
+    /// The total amount of events that occured, including the
+    /// ones that have been overwritten.
+    pub fn total(&self) -> u32 {
+        self.sequence
+    }
+
+    /// The amount of events in this log.
+    pub fn len(&self) -> usize {
+        self.entries.iter().filter(|e| e.is_some()).count()
+    }
+
+    /// Check whether this log is empty.
+    pub fn is_empty(&self) -> bool {
+        self.sequence == 0
+    }
+
+    /// Iterate over the events in this log, from oldest to newest.
+    pub fn iter(&self) -> impl Iterator<Item = &LoggedEvent> {
+        let start = self.sequence as usize % EVENT_LOG_LEN;
+        let (newest, oldest) = self.entries.split_at(start);
+        oldest.iter().chain(newest.iter()).flatten()
+    }
+}
+
+fn log() -> &'static Mutex<RefCell<EventLog>> {
+    static LOG: Mutex<RefCell<EventLog>> = Mutex::new(RefCell::new(EventLog::new()));
+    &LOG
+}
+
+fn monotonic_clock() -> &'static Mutex<Cell<Option<fn() -> u32>>> {
+    static CLOCK: Mutex<Cell<Option<fn() -> u32>>> = Mutex::new(Cell::new(None));
+    &CLOCK
+}
+
+/// Register the monotonic clock that events are stamped with, for
+/// instance the tick counter of an RTOS or a free-running timer.
+///
+/// `clock` is called from the context in which the event occurs,
+/// which may be the `ETH` interrupt.
+pub fn set_monotonic_clock(clock: Option<fn() -> u32>) {
+    cortex_m::interrupt::free(|cs| monotonic_clock().borrow(cs).set(clock));
+}
+
+/// Record `event` in the event log.
+pub(crate) fn record(event: DriverEvent) {
+    #[cfg(feature = "ptp")]
+    let time = EthernetPTP::get_time();
+
+    cortex_m::interrupt::free(|cs| {
+        let ticks = monotonic_clock()
+            .borrow(cs)
+            .get()
+            .map_or(0, |clock| clock());
+        let mut log = log().borrow(cs).borrow_mut();
+        let sequence = log.sequence;
+        log.push(LoggedEvent {
+            sequence,
+            #[cfg(feature = "ptp")]
+            time,
+            ticks,
+            event,
+        });
+    });
+}
+
+impl EthernetDMA<'_, '_> {
+    /// Get a copy of the log of recent driver events.
+    ///
+    /// This function may be called from any context.
+    pub fn event_log() -> EventLog {
+        cortex_m::interrupt::free(|cs| *log().borrow(cs).borrow())
+    }
+
+    /// Clear the log of recent driver events.
+    pub fn clear_event_log() {
+        cortex_m::interrupt::free(|cs| *log().borrow(cs).borrow_mut() = EventLog::new());
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    fn event(sequence: u32) -> LoggedEvent {
+        LoggedEvent {
+            sequence,
+            #[cfg(feature = "ptp")]
+            time: Timestamp::new_raw(0),
+            ticks: 0,
+            event: DriverEvent::Link(LinkEvent::Up),
+        }
+    }
+
+    #[test]
+    fn keeps_last_events_in_order() {
+        let mut log = EventLog::new();
+        assert!(log.is_empty());
+
+        for sequence in 0..EVENT_LOG_LEN as u32 + 3 {
+            log.push(event(sequence));
+        }
+
+        assert_eq!(log.len(), EVENT_LOG_LEN);
+        assert_eq!(log.total(), EVENT_LOG_LEN as u32 + 3);
+        assert!(log
+            .iter()
+            .map(|e| e.sequence)
+            .eq(3..EVENT_LOG_LEN as u32 + 3));
+    }
+}
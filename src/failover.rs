@@ -0,0 +1,213 @@
+//! Failover between the ethernet MAC and a secondary interface.
+//!
+//! Devices that need a maintenance path when the ethernet cable is
+//! unplugged can use [`Failover`] to route their traffic over a secondary
+//! interface, such as a serial SLIP or PPP link, while the ethernet link is
+//! down. The secondary interface only has to implement [`Interface`].
+
+use crate::{bridge::Interface, mac::phy::LinkState};
+
+/// The maximum amount of frames that [`Failover::receive`] discards from
+/// the inactive path per call.
+///
+/// This bounds the time spent in a single call when the inactive path
+/// is flooded. Frames that are left over are discarded by later calls.
+pub const MAX_DISCARDED_FRAMES: usize = 16;
+
+/// The path that traffic is routed over.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Path {
+    /// The ethernet MAC.
+    Ethernet,
+    /// The secondary interface.
+    Secondary,
+}
+
+/// A failover policy that switches traffic between the ethernet MAC and
+/// a secondary interface, based on the state of the ethernet link.
+///
+/// Traffic is switched to the secondary interface as soon as the ethernet
+/// link goes down. It is switched back once the ethernet link has been up
+/// for the configured switch-back delay, so that a flapping link does not
+/// cause traffic to switch back and forth continuously.
+#[derive(Debug, Clone)]
+pub struct Failover {
+    active: Path,
+    switch_back_delay_ms: u64,
+    up_since_ms: Option<u64>,
+    switches: u32,
+}
+
+impl Failover {
+    /// Create a new failover policy that starts out routing traffic over
+    /// the ethernet MAC, and switches back to it once the link has been up
+    /// for `switch_back_delay_ms`.
+    pub const fn new(switch_back_delay_ms: u64) -> Self {
+        Self {
+            active: Path::Ethernet,
+            switch_back_delay_ms,
+            up_since_ms: None,
+            switches: 0,
+        }
+    }
+
+    /// The path that traffic is currently routed over.
+    pub fn active(&self) -> Path {
+        self.active
+    }
+
+    /// The amount of times that traffic has been switched to another path.
+    pub fn switches(&self) -> u32 {
+        self.switches
+    }
+
+    /// Update the policy with the current state of the ethernet `link`
+    /// at time `now_ms`.
+    ///
+    /// Returns the new path if traffic was switched to another path.
+    pub fn update(&mut self, link: LinkState, now_ms: u64) -> Option<Path> {
+        let next = if link.is_up() {
+            let up_since = *self.up_since_ms.get_or_insert(now_ms);
+            if now_ms.saturating_sub(up_since) >= self.switch_back_delay_ms {
+                Path::Ethernet
+            } else {
+                self.active
+            }
+        } else {
+            self.up_since_ms = None;
+            Path::Secondary
+        };
+
+        if next != self.active {
+            self.active = next;
+            self.switches = self.switches.wrapping_add(1);
+            Some(next)
+        } else {
+            None
+        }
+    }
+
+    /// Transmit a frame over the active path.
+    ///
+    /// See [`Interface::transmit`].
+    pub fn transmit<E, S, F>(
+        &self,
+        ethernet: &mut E,
+        secondary: &mut S,
+        length: usize,
+        f: F,
+    ) -> bool
+    where
+        E: Interface,
+        S: Interface,
+        F: FnOnce(&mut [u8]),
+    {
+        match self.active {
+            Path::Ethernet => ethernet.transmit(length, f),
+            Path::Secondary => secondary.transmit(length, f),
+        }
+    }
+
+    /// Receive a frame from the active path.
+    ///
+    /// Up to [`MAX_DISCARDED_FRAMES`] frames received on the inactive path
+    /// are discarded.
+    ///
+    /// See [`Interface::receive`].
+    pub fn receive<E, S, R, F>(&self, ethernet: &mut E, secondary: &mut S, f: F) -> Option<R>
+    where
+        E: Interface,
+        S: Interface,
+        F: FnOnce(&[u8]) -> R,
+    {
+        match self.active {
+            Path::Ethernet => {
+                discard(secondary);
+                ethernet.receive(f)
+            }
+            Path::Secondary => {
+                discard(ethernet);
+                secondary.receive(f)
+            }
+        }
+    }
+}
+
+/// Discard at most [`MAX_DISCARDED_FRAMES`] frames received on `interface`.
+fn discard<I: Interface>(interface: &mut I) {
+    for _ in 0..MAX_DISCARDED_FRAMES {
+        if interface.receive(|_| ()).is_none() {
+            break;
+        }
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn switches_over_and_back() {
+        let mut failover = Failover::new(1000);
+        assert_eq!(failover.update(LinkState::Up, 0), None);
+        assert_eq!(failover.active(), Path::Ethernet);
+
+        assert_eq!(failover.update(LinkState::Down, 10), Some(Path::Secondary));
+
+        // Flapping link: not up for long enough
+        assert_eq!(failover.update(LinkState::Up, 20), None);
+        assert_eq!(failover.update(LinkState::Down, 500), None);
+        assert_eq!(failover.update(LinkState::Up, 600), None);
+        assert_eq!(failover.update(LinkState::Up, 1599), None);
+        assert_eq!(failover.active(), Path::Secondary);
+
+        assert_eq!(failover.update(LinkState::Up, 1600), Some(Path::Ethernet));
+        assert_eq!(failover.switches(), 2);
+    }
+
+    /// An interface that always has a frame available, and counts the
+    /// frames that were received from it.
+    struct Flood {
+        received: usize,
+    }
+
+    impl Interface for Flood {
+        fn max_frame_len(&self) -> usize {
+            64
+        }
+
+        fn can_transmit(&mut self) -> bool {
+            false
+        }
+
+        fn transmit<F>(&mut self, _: usize, _: F) -> bool
+        where
+            F: FnOnce(&mut [u8]),
+        {
+            false
+        }
+
+        fn receive<R, F>(&mut self, f: F) -> Option<R>
+        where
+            F: FnOnce(&[u8]) -> R,
+        {
+            self.received += 1;
+            Some(f(&[0; 64]))
+        }
+    }
+
+    #[test]
+    fn discarding_is_bounded() {
+        let failover = Failover::new(1000);
+        let mut ethernet = Flood { received: 0 };
+        let mut secondary = Flood { received: 0 };
+
+        assert_eq!(
+            failover.receive(&mut ethernet, &mut secondary, |frame| frame.len()),
+            Some(64)
+        );
+        assert_eq!(ethernet.received, 1);
+        assert_eq!(secondary.received, MAX_DISCARDED_FRAMES);
+    }
+}
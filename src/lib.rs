@@ -7,6 +7,18 @@
 #[cfg(not(feature = "device-selected"))]
 compile_error!("No device was selected! Exactly one stm32fxxx feature must be selected.");
 
+#[cfg(all(feature = "rx-only", feature = "tx-only"))]
+compile_error!("The `rx-only` and `tx-only` features are mutually exclusive.");
+
+#[cfg(all(feature = "smoltcp-phy", any(feature = "rx-only", feature = "tx-only")))]
+compile_error!("The `smoltcp-phy` feature requires both RX and TX support.");
+
+#[cfg(all(feature = "embassy-net", any(feature = "rx-only", feature = "tx-only")))]
+compile_error!("The `embassy-net` feature requires both RX and TX support.");
+
+#[cfg(all(feature = "rx-inject", feature = "tx-only"))]
+compile_error!("The `rx-inject` feature requires RX support.");
+
 /// Re-export
 #[cfg(feature = "stm32f7xx-hal")]
 pub use stm32f7xx_hal as hal;
@@ -21,8 +33,6 @@ pub use stm32f1xx_hal as hal;
 
 #[cfg(feature = "device-selected")]
 pub use hal::pac as stm32;
-#[cfg(feature = "device-selected")]
-use hal::rcc::Clocks;
 
 #[cfg(feature = "device-selected")]
 pub mod dma;
@@ -32,13 +42,71 @@ pub mod mac;
 
 #[cfg(feature = "device-selected")]
 pub mod setup;
+
+#[cfg(feature = "device-selected")]
+pub mod prelude;
+
+#[cfg(feature = "device-selected")]
+pub mod ready;
+
+#[cfg(feature = "device-selected")]
+pub mod crc;
+
+#[cfg(feature = "device-selected")]
+pub mod stats;
+
+#[cfg(feature = "device-selected")]
+pub mod storm;
+
+#[cfg(feature = "device-selected")]
+pub mod testing;
+
+#[cfg(all(feature = "device-selected", feature = "event-log"))]
+pub mod event_log;
+
+#[cfg(all(feature = "device-selected", feature = "test-hooks"))]
+pub mod test_hooks;
+
+#[cfg(all(
+    feature = "device-selected",
+    not(any(feature = "rx-only", feature = "tx-only"))
+))]
+pub mod bridge;
+
+#[cfg(all(feature = "device-selected", not(feature = "tx-only")))]
+pub mod coalesce;
+
+#[cfg(all(feature = "device-selected", not(feature = "rx-only")))]
+pub mod segmentation;
+
+#[cfg(all(feature = "device-selected", not(feature = "rx-only")))]
+pub mod lldp;
+
+#[cfg(all(feature = "device-selected", not(feature = "rx-only")))]
+pub mod announce;
+
+#[cfg(all(
+    feature = "device-selected",
+    not(any(feature = "rx-only", feature = "tx-only"))
+))]
+pub mod failover;
+
+#[cfg(all(
+    feature = "device-selected",
+    feature = "tunnel",
+    not(any(feature = "rx-only", feature = "tx-only"))
+))]
+pub mod tunnel;
 #[doc(inline)]
 #[cfg(feature = "device-selected")]
-pub use setup::{EthPins, Parts, PartsIn};
+pub use setup::{EthPins, Hclk, HclkHz, MediaInterface, Parts, PartsIn};
 
 #[cfg(feature = "device-selected")]
 pub(crate) mod peripherals;
 
+#[cfg(feature = "device-selected")]
+pub(crate) mod quirks;
+
 #[cfg(feature = "ptp")]
 pub mod ptp;
 
@@ -47,14 +115,48 @@ pub use smoltcp;
 
 #[cfg(feature = "device-selected")]
 use {
-    dma::{EthernetDMA, RxRingEntry, TxRingEntry},
+    dma::EthernetDMA,
     mac::{EthernetMAC, EthernetMACWithMii, MdcPin, MdioPin, Speed, WrongClock},
     setup::*,
 };
 
+#[cfg(all(feature = "device-selected", not(feature = "tx-only")))]
+use dma::RxRingEntry;
+
+// Without an RX ring, the size of the RX buffers can not be inferred
+// from the arguments of [`new`] and [`new_with_mii`], so it is fixed.
+#[cfg(all(feature = "device-selected", feature = "tx-only"))]
+const RX_BUF: usize = dma::MTU;
+
+#[cfg(all(feature = "device-selected", not(feature = "rx-only")))]
+use dma::TxRingEntry;
+
 #[cfg(all(feature = "device-selected", feature = "ptp"))]
 use ptp::EthernetPTP;
 
+/// An error that can occur while creating the ethernet driver.
+#[cfg(feature = "device-selected")]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InitError {
+    /// HCLK is below 25 MHz.
+    WrongClock,
+    /// The software reset of the DMA did not complete.
+    ///
+    /// The reset only completes if the reference clock of the PHY
+    /// (`REF_CLK` for RMII) is running, so this usually means that the
+    /// PHY is not clocked, is held in reset, or that the `REF_CLK`
+    /// pin is not connected.
+    NoRefClk,
+}
+
+#[cfg(feature = "device-selected")]
+impl From<WrongClock> for InitError {
+    fn from(_: WrongClock) -> Self {
+        Self::WrongClock
+    }
+}
+
 /// A summary of the reasons for the occurence of an
 /// interrupt
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -66,10 +168,20 @@ pub struct InterruptReason {
     pub tx: bool,
     /// A DMA error occured.
     pub dma_error: bool,
+    /// The abnormal DMA conditions that occured. Only conditions
+    /// that were enabled using [`EthernetDMA::enable_abnormal_interrupts`]
+    /// cause an interrupt by themselves.
+    #[cfg(feature = "device-selected")]
+    pub dma_abnormal: dma::AbnormalInterrupts,
     #[cfg(all(feature = "ptp", not(feature = "stm32f1xx-hal")))]
     /// The target time configured for PTP has
     /// passed.
     pub time_passed: bool,
+    /// Too many consecutive interrupts had no known cause, so the
+    /// unhandled interrupt sources were masked.
+    ///
+    /// See [`storm`].
+    pub interrupt_storm: bool,
 }
 
 /// Handle the `ETH` interrupt.
@@ -83,15 +195,45 @@ pub fn eth_interrupt_handler() -> InterruptReason {
     #[cfg(all(feature = "ptp", not(feature = "stm32f1xx-hal")))]
     let is_time_trigger = EthernetPTP::interrupt_handler();
 
+    #[cfg(all(feature = "ptp", not(feature = "stm32f1xx-hal")))]
+    let handled_ptp = is_time_trigger;
+    #[cfg(not(all(feature = "ptp", not(feature = "stm32f1xx-hal"))))]
+    let handled_ptp = false;
+
+    let handled = dma.is_rx || dma.is_tx || dma.is_error || dma.abnormal.any() || handled_ptp;
+
     InterruptReason {
         rx: dma.is_rx,
         tx: dma.is_tx,
         dma_error: dma.is_error,
+        dma_abnormal: dma.abnormal,
         #[cfg(all(feature = "ptp", not(feature = "stm32f1xx-hal")))]
         time_passed: is_time_trigger,
+        interrupt_storm: storm::check(handled),
     }
 }
 
+/// Immediately stop the ethernet DMA.
+///
+/// This function accesses the DMA registers directly, and does not take
+/// any locks, so it may be called from any context, including a panic
+/// handler or `HardFault` handler. This ensures that the DMA can not
+/// write to RAM while a crash dump is being collected. Any bus transfer
+/// that is in progress is completed first.
+///
+/// The DMA is stopped with a read-modify-write of the operation mode
+/// register. If this function preempts code that is modifying that
+/// register, the preempted code may restart the DMA when it resumes.
+/// This can not happen if the calling context never returns, as is the
+/// case for panic and `HardFault` handlers.
+///
+/// After calling this function, the driver no longer sends or receives
+/// any frames.
+#[cfg(feature = "device-selected")]
+pub fn emergency_stop() {
+    EthernetDMA::emergency_stop();
+}
+
 /// Create and initialise the ethernet driver.
 ///
 /// Initialize and start tx and rx DMA engines.
@@ -109,16 +251,36 @@ pub fn eth_interrupt_handler() -> InterruptReason {
 /// - Make sure that the buffers reside in a memory region that is
 /// accessible by the peripheral. Core-Coupled Memory (CCM) is
 /// usually not accessible.
-/// - HCLK must be at least 25 MHz.
+/// - HCLK must be at least 25 MHz. `clocks` may be the `Clocks` of the HAL,
+/// or an [`HclkHz`] if the clocks were configured without the HAL.
+/// - The reference clock of the PHY must be running, or
+/// [`InitError::NoRefClk`] is returned.
+/// - Both rings must contain between [`dma::MIN_RING_LEN`] and
+/// [`dma::MAX_RING_LEN`] entries.
+/// - If the `rx-only` or `tx-only` feature is enabled, the unused ring
+/// is not passed to this function.
 #[cfg(feature = "device-selected")]
-pub fn new<'rx, 'tx, REFCLK, CRS, TXEN, TXD0, TXD1, RXD0, RXD1>(
+pub fn new<
+    'rx,
+    'tx,
+    #[cfg(not(feature = "tx-only"))] const RX_BUF: usize,
+    CLK,
+    REFCLK,
+    CRS,
+    TXEN,
+    TXD0,
+    TXD1,
+    RXD0,
+    RXD1,
+>(
     parts: PartsIn,
-    rx_buffer: &'rx mut [RxRingEntry],
-    tx_buffer: &'tx mut [TxRingEntry],
-    clocks: Clocks,
+    #[cfg(not(feature = "tx-only"))] rx_buffer: &'rx mut [RxRingEntry<RX_BUF>],
+    #[cfg(not(feature = "rx-only"))] tx_buffer: &'tx mut [TxRingEntry],
+    clocks: CLK,
     pins: EthPins<REFCLK, CRS, TXEN, TXD0, TXD1, RXD0, RXD1>,
-) -> Result<Parts<'rx, 'tx, EthernetMAC>, WrongClock>
+) -> Result<Parts<'rx, 'tx, EthernetMAC, RX_BUF>, InitError>
 where
+    CLK: Hclk,
     REFCLK: RmiiRefClk + AlternateVeryHighSpeed,
     CRS: RmiiCrsDv + AlternateVeryHighSpeed,
     TXEN: RmiiTxEN + AlternateVeryHighSpeed,
@@ -133,17 +295,25 @@ where
     // Set up the clocks and reset the MAC periperhal
     setup::setup();
 
+    let hclk = clocks.hclk_hz();
+
     let eth_mac = parts.mac.into();
 
     // Congfigure and start up the ethernet DMA.
-    let dma = EthernetDMA::new(parts.dma.into(), rx_buffer, tx_buffer);
+    let dma = EthernetDMA::new(
+        parts.dma.into(),
+        #[cfg(not(feature = "tx-only"))]
+        rx_buffer,
+        #[cfg(not(feature = "rx-only"))]
+        tx_buffer,
+    )?;
 
     // Configure the ethernet PTP
     #[cfg(feature = "ptp")]
-    let ptp = EthernetPTP::new(parts.ptp.into(), clocks, &dma);
+    let ptp = EthernetPTP::new(parts.ptp.into(), hclk, &dma);
 
     // Configure the ethernet MAC
-    let mac = EthernetMAC::new(eth_mac, parts.mmc, clocks, Speed::FullDuplexBase100Tx, &dma)?;
+    let mac = EthernetMAC::new(eth_mac, parts.mmc, hclk, Speed::FullDuplexBase100Tx, &dma)?;
 
     let parts = Parts {
         mac,
@@ -174,18 +344,40 @@ where
 /// - Make sure that the buffers reside in a memory region that is
 /// accessible by the peripheral. Core-Coupled Memory (CCM) is
 /// usually not accessible.
-/// - HCLK must be at least 25 MHz.
+/// - HCLK must be at least 25 MHz. `clocks` may be the `Clocks` of the HAL,
+/// or an [`HclkHz`] if the clocks were configured without the HAL.
+/// - The reference clock of the PHY must be running, or
+/// [`InitError::NoRefClk`] is returned.
+/// - Both rings must contain between [`dma::MIN_RING_LEN`] and
+/// [`dma::MAX_RING_LEN`] entries.
+/// - If the `rx-only` or `tx-only` feature is enabled, the unused ring
+/// is not passed to this function.
 #[cfg(feature = "device-selected")]
-pub fn new_with_mii<'rx, 'tx, REFCLK, CRS, TXEN, TXD0, TXD1, RXD0, RXD1, MDIO, MDC>(
+pub fn new_with_mii<
+    'rx,
+    'tx,
+    #[cfg(not(feature = "tx-only"))] const RX_BUF: usize,
+    CLK,
+    REFCLK,
+    CRS,
+    TXEN,
+    TXD0,
+    TXD1,
+    RXD0,
+    RXD1,
+    MDIO,
+    MDC,
+>(
     parts: PartsIn,
-    rx_buffer: &'rx mut [RxRingEntry],
-    tx_buffer: &'tx mut [TxRingEntry],
-    clocks: Clocks,
+    #[cfg(not(feature = "tx-only"))] rx_buffer: &'rx mut [RxRingEntry<RX_BUF>],
+    #[cfg(not(feature = "rx-only"))] tx_buffer: &'tx mut [TxRingEntry],
+    clocks: CLK,
     pins: EthPins<REFCLK, CRS, TXEN, TXD0, TXD1, RXD0, RXD1>,
     mdio: MDIO,
     mdc: MDC,
-) -> Result<Parts<'rx, 'tx, EthernetMACWithMii<MDIO, MDC>>, WrongClock>
+) -> Result<Parts<'rx, 'tx, EthernetMACWithMii<MDIO, MDC>, RX_BUF>, InitError>
 where
+    CLK: Hclk,
     REFCLK: RmiiRefClk + AlternateVeryHighSpeed,
     CRS: RmiiCrsDv + AlternateVeryHighSpeed,
     TXEN: RmiiTxEN + AlternateVeryHighSpeed,
@@ -202,17 +394,25 @@ where
     // Set up the clocks and reset the MAC periperhal
     setup::setup();
 
+    let hclk = clocks.hclk_hz();
+
     let eth_mac = parts.mac.into();
 
     // Congfigure and start up the ethernet DMA.
-    let dma = EthernetDMA::new(parts.dma.into(), rx_buffer, tx_buffer);
+    let dma = EthernetDMA::new(
+        parts.dma.into(),
+        #[cfg(not(feature = "tx-only"))]
+        rx_buffer,
+        #[cfg(not(feature = "rx-only"))]
+        tx_buffer,
+    )?;
 
     // Configure the ethernet PTP
     #[cfg(feature = "ptp")]
-    let ptp = EthernetPTP::new(parts.ptp.into(), clocks, &dma);
+    let ptp = EthernetPTP::new(parts.ptp.into(), hclk, &dma);
 
     // Configure the ethernet MAC
-    let mac = EthernetMAC::new(eth_mac, parts.mmc, clocks, Speed::FullDuplexBase100Tx, &dma)?
+    let mac = EthernetMAC::new(eth_mac, parts.mmc, hclk, Speed::FullDuplexBase100Tx, &dma)?
         .with_mii(mdio, mdc);
 
     let parts = Parts {
@@ -0,0 +1,248 @@
+//! Periodic transmission of LLDP announcements.
+//!
+//! Managed switches use the Link Layer Discovery Protocol (IEEE 802.1AB) to
+//! discover the devices that are connected to their ports. An [`LldpAgent`]
+//! builds a minimal LLDP announcement, consisting of the chassis ID (the MAC
+//! address of this station), a port ID, a time to live, and optionally a
+//! system name, and transmits it every [`LldpConfig::interval_ms`].
+//!
+//! ```rust,ignore
+//! let mut lldp = LldpAgent::new(LldpConfig::new(mac, b"eth0")).unwrap();
+//!
+//! loop {
+//!     lldp.poll(&mut dma, now_ms()).ok();
+//! }
+//! ```
+
+use crate::{
+    dma::{EthernetDMA, TxError, ETH_HEADER_LEN, MIN_FRAME_LEN},
+    mac::Mac,
+};
+
+/// The ethertype of LLDP frames.
+const ETHERTYPE_LLDP: [u8; 2] = [0x88, 0xCC];
+
+const TLV_END: u8 = 0;
+const TLV_CHASSIS_ID: u8 = 1;
+const TLV_PORT_ID: u8 = 2;
+const TLV_TTL: u8 = 3;
+const TLV_SYSTEM_NAME: u8 = 5;
+
+/// Chassis ID subtype: MAC address.
+const CHASSIS_ID_MAC: u8 = 4;
+/// Port ID subtype: locally assigned.
+const PORT_ID_LOCAL: u8 = 7;
+
+/// The length of the header of a TLV.
+const TLV_HEADER_LEN: usize = 2;
+/// The maximum length of the value of a port ID or system name.
+const MAX_STRING_LEN: usize = 255;
+
+/// An error that occurs when creating an [`LldpAgent`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LldpError {
+    /// The port ID is empty, or longer than 255 bytes.
+    InvalidPortId,
+    /// The system name is longer than 255 bytes.
+    InvalidSystemName,
+}
+
+/// The contents and schedule of LLDP announcements.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LldpConfig<'a> {
+    /// The MAC address of this station, which is used as the
+    /// source address and as the chassis ID.
+    pub address: Mac,
+    /// The (locally assigned) ID of the port, such as `b"eth0"`.
+    pub port_id: &'a [u8],
+    /// The time for which switches keep the information, in seconds.
+    pub ttl_s: u16,
+    /// The system name, if any.
+    pub system_name: Option<&'a str>,
+    /// The interval between announcements, in milliseconds.
+    pub interval_ms: u64,
+}
+
+impl<'a> LldpConfig<'a> {
+    /// A configuration with the default TTL of 120 seconds, no system
+    /// name, and the default interval of 30 seconds.
+    pub const fn new(address: Mac, port_id: &'a [u8]) -> Self {
+        Self {
+            address,
+            port_id,
+            ttl_s: 120,
+            system_name: None,
+            interval_ms: 30_000,
+        }
+    }
+}
+
+/// Transmits LLDP announcements periodically.
+#[derive(Debug, Clone)]
+pub struct LldpAgent<'a> {
+    config: LldpConfig<'a>,
+    next_ms: Option<u64>,
+}
+
+impl<'a> LldpAgent<'a> {
+    /// Create a new agent, which sends its first announcement
+    /// on the first call to [`LldpAgent::poll`].
+    pub fn new(config: LldpConfig<'a>) -> Result<Self, LldpError> {
+        if config.port_id.is_empty() || config.port_id.len() > MAX_STRING_LEN - 1 {
+            return Err(LldpError::InvalidPortId);
+        }
+
+        if let Some(name) = config.system_name {
+            if name.len() > MAX_STRING_LEN {
+                return Err(LldpError::InvalidSystemName);
+            }
+        }
+
+        Ok(Self {
+            config,
+            next_ms: None,
+        })
+    }
+
+    /// The configuration of this agent.
+    pub fn config(&self) -> &LldpConfig<'a> {
+        &self.config
+    }
+
+    /// The length of an announcement, excluding padding.
+    pub fn frame_len(&self) -> usize {
+        let system_name = self
+            .config
+            .system_name
+            .map_or(0, |name| TLV_HEADER_LEN + name.len());
+
+        ETH_HEADER_LEN
+            + (TLV_HEADER_LEN + 1 + 6)
+            + (TLV_HEADER_LEN + 1 + self.config.port_id.len())
+            + (TLV_HEADER_LEN + 2)
+            + system_name
+            + TLV_HEADER_LEN
+    }
+
+    /// Write an announcement into `buffer`, zero-padded to
+    /// [`MIN_FRAME_LEN`], and return its length.
+    ///
+    /// # Panics
+    /// This function panics if `buffer` is too short.
+    pub fn write_frame(&self, buffer: &mut [u8]) -> usize {
+        let length = self.frame_len().max(MIN_FRAME_LEN);
+        let buffer = &mut buffer[..length];
+        buffer.fill(0);
+
+        buffer[..6].copy_from_slice(Mac::LLDP_NEAREST_BRIDGE.raw());
+        buffer[6..12].copy_from_slice(self.config.address.raw());
+        buffer[12..14].copy_from_slice(&ETHERTYPE_LLDP);
+
+        let mut offset = ETH_HEADER_LEN;
+        let mut tlv = |tlv_type: u8, parts: &[&[u8]]| {
+            let len: usize = parts.iter().map(|part| part.len()).sum();
+            let header = ((tlv_type as u16) << 9) | len as u16;
+            buffer[offset..offset + TLV_HEADER_LEN].copy_from_slice(&header.to_be_bytes());
+            offset += TLV_HEADER_LEN;
+            for part in parts {
+                buffer[offset..offset + part.len()].copy_from_slice(part);
+                offset += part.len();
+            }
+        };
+
+        tlv(
+            TLV_CHASSIS_ID,
+            &[&[CHASSIS_ID_MAC], self.config.address.raw()],
+        );
+        tlv(TLV_PORT_ID, &[&[PORT_ID_LOCAL], self.config.port_id]);
+        tlv(TLV_TTL, &[&self.config.ttl_s.to_be_bytes()]);
+        if let Some(name) = self.config.system_name {
+            tlv(TLV_SYSTEM_NAME, &[name.as_bytes()]);
+        }
+        tlv(TLV_END, &[]);
+
+        length
+    }
+
+    /// Send an announcement if one is due at time `now_ms`.
+    ///
+    /// Returns `Ok(true)` if an announcement was sent. If no TX slot was
+    /// available, [`Err(TxError::WouldBlock)`](TxError::WouldBlock) is
+    /// returned, and the announcement is sent on a later call.
+    pub fn poll<const RX_BUF: usize>(
+        &mut self,
+        dma: &mut EthernetDMA<'_, '_, RX_BUF>,
+        now_ms: u64,
+    ) -> Result<bool, TxError> {
+        if let Some(next) = self.next_ms {
+            if now_ms < next {
+                return Ok(false);
+            }
+        }
+
+        let length = self.frame_len().max(MIN_FRAME_LEN);
+        dma.send(length, None, |buffer| {
+            self.write_frame(buffer);
+        })?;
+
+        self.next_ms = Some(now_ms + self.config.interval_ms);
+        Ok(true)
+    }
+
+    /// Send the next announcement on the next call to [`LldpAgent::poll`],
+    /// for instance because the link came up.
+    pub fn announce_now(&mut self) {
+        self.next_ms = None;
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn announcement() {
+        let address = Mac::new([0x02, 0x00, 0x00, 0x12, 0x34, 0x56]);
+        let mut config = LldpConfig::new(address, b"p1");
+        config.system_name = Some("dev");
+        let agent = LldpAgent::new(config).unwrap();
+
+        let mut buffer = [0xAA; 128];
+        let length = agent.write_frame(&mut buffer);
+        assert_eq!(length, MIN_FRAME_LEN);
+        assert_eq!(agent.frame_len(), 14 + 9 + 5 + 4 + 5 + 2);
+
+        #[rustfmt::skip]
+        let expected: &[u8] = &[
+            0x01, 0x80, 0xC2, 0x00, 0x00, 0x0E,
+            0x02, 0x00, 0x00, 0x12, 0x34, 0x56,
+            0x88, 0xCC,
+            0x02, 0x07, 0x04, 0x02, 0x00, 0x00, 0x12, 0x34, 0x56,
+            0x04, 0x03, 0x07, b'p', b'1',
+            0x06, 0x02, 0x00, 120,
+            0x0A, 0x03, b'd', b'e', b'v',
+            0x00, 0x00,
+        ];
+        assert_eq!(&buffer[..expected.len()], expected);
+        assert!(buffer[expected.len()..length].iter().all(|b| *b == 0));
+        assert_eq!(buffer[length], 0xAA);
+    }
+
+    #[test]
+    fn invalid_config() {
+        let address = Mac::new([0x02, 0, 0, 0, 0, 1]);
+        assert_eq!(
+            LldpAgent::new(LldpConfig::new(address, b"")).err(),
+            Some(LldpError::InvalidPortId)
+        );
+
+        let long = [b'a'; 256];
+        let mut config = LldpConfig::new(address, b"p1");
+        config.system_name = core::str::from_utf8(&long).ok();
+        assert_eq!(
+            LldpAgent::new(config).err(),
+            Some(LldpError::InvalidSystemName)
+        );
+    }
+}
@@ -0,0 +1,221 @@
+/// A MAC address.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Mac([u8; 6]);
+
+impl Mac {
+    /// The broadcast address, `FF:FF:FF:FF:FF:FF`.
+    pub const BROADCAST: Self = Self([0xFF; 6]);
+
+    /// The bridge group address used by the Spanning Tree Protocol,
+    /// `01:80:C2:00:00:00`.
+    pub const STP: Self = Self([0x01, 0x80, 0xC2, 0x00, 0x00, 0x00]);
+
+    /// The address of MAC control frames, such as pause frames,
+    /// `01:80:C2:00:00:01`.
+    pub const MAC_CONTROL: Self = Self([0x01, 0x80, 0xC2, 0x00, 0x00, 0x01]);
+
+    /// The LLDP nearest non-TPMR bridge address, `01:80:C2:00:00:03`.
+    pub const LLDP_NEAREST_NON_TPMR_BRIDGE: Self = Self([0x01, 0x80, 0xC2, 0x00, 0x00, 0x03]);
+
+    /// The LLDP nearest bridge address, `01:80:C2:00:00:0E`.
+    ///
+    /// This is the most commonly used LLDP destination address.
+    pub const LLDP_NEAREST_BRIDGE: Self = Self([0x01, 0x80, 0xC2, 0x00, 0x00, 0x0E]);
+
+    /// The address of PTP (IEEE 1588) messages over Ethernet that
+    /// may be forwarded, `01:1B:19:00:00:00`.
+    pub const PTP_PRIMARY: Self = Self([0x01, 0x1B, 0x19, 0x00, 0x00, 0x00]);
+
+    /// The address of PTP (IEEE 1588) peer delay messages over
+    /// Ethernet, `01:80:C2:00:00:0E`.
+    pub const PTP_PEER_DELAY: Self = Self::LLDP_NEAREST_BRIDGE;
+
+    /// The address of the IPv4 all-hosts group (`224.0.0.1`),
+    /// `01:00:5E:00:00:01`.
+    pub const IPV4_ALL_HOSTS: Self = Self::from_ipv4_multicast([224, 0, 0, 1]);
+
+    /// The address of the IPv6 all-nodes group (`ff02::1`),
+    /// `33:33:00:00:00:01`.
+    pub const IPV6_ALL_NODES: Self =
+        Self::from_ipv6_multicast([0xFF, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]);
+
+    /// Create a new MAC address.
+    pub const fn new(address: [u8; 6]) -> Self {
+        Self(address)
+    }
+
+    /// The multicast address that frames for the IPv4 multicast
+    /// group `address` are sent to.
+    ///
+    /// This is `01:00:5E` followed by the lower 23 bits of `address`
+    /// (RFC 1112). `address` is not checked to be a multicast address.
+    pub const fn from_ipv4_multicast(address: [u8; 4]) -> Self {
+        Self([0x01, 0x00, 0x5E, address[1] & 0x7F, address[2], address[3]])
+    }
+
+    /// The multicast address that frames for the IPv6 multicast
+    /// group `address` are sent to.
+    ///
+    /// This is `33:33` followed by the lower 32 bits of `address`
+    /// (RFC 2464). `address` is not checked to be a multicast address.
+    pub const fn from_ipv6_multicast(address: [u8; 16]) -> Self {
+        let a = address;
+        Self([0x33, 0x33, a[12], a[13], a[14], a[15]])
+    }
+
+    /// The raw bytes of this MAC address.
+    pub const fn raw(&self) -> &[u8; 6] {
+        &self.0
+    }
+
+    /// Check whether this is a multicast (group) address.
+    ///
+    /// The broadcast address is a multicast address as well.
+    pub const fn is_multicast(&self) -> bool {
+        self.0[0] & 0x01 == 0x01
+    }
+
+    /// Check whether this is the broadcast address.
+    pub fn is_broadcast(&self) -> bool {
+        *self == Self::BROADCAST
+    }
+
+    /// Check whether this is a unicast (individual) address.
+    pub const fn is_unicast(&self) -> bool {
+        !self.is_multicast()
+    }
+
+    /// The Organizationally Unique Identifier (OUI) of this address,
+    /// which consists of its first three bytes.
+    pub const fn oui(&self) -> [u8; 3] {
+        [self.0[0], self.0[1], self.0[2]]
+    }
+
+    /// Check whether this is a universally administered address,
+    /// i.e. an address that was assigned by its manufacturer.
+    pub const fn is_universally_administered(&self) -> bool {
+        self.0[0] & 0x02 == 0
+    }
+
+    /// Check whether this is a locally administered address.
+    pub const fn is_locally_administered(&self) -> bool {
+        !self.is_universally_administered()
+    }
+
+    /// The modified EUI-64 interface identifier derived from this
+    /// address, as specified by RFC 4291.
+    ///
+    /// `FF:FE` is inserted in the middle of the address, and the
+    /// universal/local bit is inverted.
+    pub const fn to_eui64(&self) -> [u8; 8] {
+        let a = self.0;
+        [a[0] ^ 0x02, a[1], a[2], 0xFF, 0xFE, a[3], a[4], a[5]]
+    }
+
+    /// The IPv6 link-local address (`fe80::/64`) that is derived
+    /// from this address using its modified EUI-64 interface identifier.
+    pub const fn ipv6_link_local(&self) -> [u8; 16] {
+        let eui64 = self.to_eui64();
+        [
+            0xFE, 0x80, 0, 0, 0, 0, 0, 0, eui64[0], eui64[1], eui64[2], eui64[3], eui64[4],
+            eui64[5], eui64[6], eui64[7],
+        ]
+    }
+
+    /// The values of the address fields of the `MACAxHR` and `MACAxLR`
+    /// registers for this address, as `(high, low)`.
+    ///
+    /// The first byte of the address is the least significant byte of the
+    /// low register, and the last byte is the most significant byte of the
+    /// 16 bit field in the high register. Other address registers, such as
+    /// those of PHYs that support Wake-on-LAN, often use the same layout.
+    pub const fn to_register_pair(&self) -> (u16, u32) {
+        let a = self.0;
+        (
+            u16::from_le_bytes([a[4], a[5]]),
+            u32::from_le_bytes([a[0], a[1], a[2], a[3]]),
+        )
+    }
+
+    /// Create an address from the values of the address fields of the
+    /// `MACAxHR` and `MACAxLR` registers.
+    ///
+    /// This is the inverse of [`Mac::to_register_pair`].
+    pub const fn from_register_pair(high: u16, low: u32) -> Self {
+        let [a, b, c, d] = low.to_le_bytes();
+        let [e, f] = high.to_le_bytes();
+        Self([a, b, c, d, e, f])
+    }
+}
+
+impl From<[u8; 6]> for Mac {
+    fn from(value: [u8; 6]) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Mac> for [u8; 6] {
+    fn from(value: Mac) -> Self {
+        value.0
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn eui64_and_link_local() {
+        let mac = Mac::new([0x00, 0x80, 0xE1, 0x12, 0x34, 0x56]);
+        assert_eq!(mac.oui(), [0x00, 0x80, 0xE1]);
+        assert!(mac.is_universally_administered());
+        assert_eq!(
+            mac.to_eui64(),
+            [0x02, 0x80, 0xE1, 0xFF, 0xFE, 0x12, 0x34, 0x56]
+        );
+        assert_eq!(
+            mac.ipv6_link_local(),
+            [0xFE, 0x80, 0, 0, 0, 0, 0, 0, 0x02, 0x80, 0xE1, 0xFF, 0xFE, 0x12, 0x34, 0x56]
+        );
+
+        let local = Mac::new([0x02, 0x00, 0x00, 0x00, 0x00, 0x01]);
+        assert!(local.is_locally_administered());
+        assert_eq!(local.to_eui64()[0], 0x00);
+    }
+
+    #[test]
+    fn multicast_mapping() {
+        assert_eq!(
+            Mac::from_ipv4_multicast([239, 255, 1, 2]),
+            Mac::new([0x01, 0x00, 0x5E, 0x7F, 0x01, 0x02])
+        );
+        assert_eq!(
+            Mac::IPV4_ALL_HOSTS,
+            Mac::new([0x01, 0x00, 0x5E, 0x00, 0x00, 0x01])
+        );
+        assert_eq!(
+            Mac::from_ipv6_multicast([
+                0xFF, 0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0xFF, 0x12, 0x34, 0x56
+            ]),
+            Mac::new([0x33, 0x33, 0xFF, 0x12, 0x34, 0x56])
+        );
+        assert_eq!(
+            Mac::IPV6_ALL_NODES,
+            Mac::new([0x33, 0x33, 0x00, 0x00, 0x00, 0x01])
+        );
+        assert!(Mac::PTP_PEER_DELAY.is_multicast());
+        assert!(Mac::STP.is_multicast());
+    }
+
+    #[test]
+    fn register_pair() {
+        let mac = Mac::new([0x00, 0x80, 0xE1, 0x12, 0x34, 0x56]);
+        assert_eq!(mac.to_register_pair(), (0x5634, 0x12E1_8000));
+        assert_eq!(Mac::from_register_pair(0x5634, 0x12E1_8000), mac);
+
+        assert_eq!(Mac::BROADCAST.to_register_pair(), (0xFFFF, 0xFFFF_FFFF));
+        let (high, low) = Mac::LLDP_NEAREST_BRIDGE.to_register_pair();
+        assert_eq!(Mac::from_register_pair(high, low), Mac::LLDP_NEAREST_BRIDGE);
+    }
+}
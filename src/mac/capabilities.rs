@@ -0,0 +1,201 @@
+//! Runtime detection of the capabilities of the MAC.
+//!
+//! The MACs of the STM32F1, F4 and F7 do not expose a documented version or
+//! hardware feature register. Instead, the family, and therefore the
+//! capabilities of the MAC, are derived from the device ID in the
+//! `DBGMCU_IDCODE` register.
+//!
+//! According to the errata of the STM32F1 and STM32F4, `DBGMCU_IDCODE` reads
+//! as zero unless a debugger is connected. In that case, the family is derived
+//! from the part number of the Cortex-M core in the `CPUID` register of the
+//! SCB instead, which is always readable: the F1 has a Cortex-M3, the F4 a
+//! Cortex-M4, and the F7 a Cortex-M7 core.
+//!
+//! Application code that is shared between builds for several families can
+//! use [`Capabilities`] to adapt at runtime, instead of relying on the
+//! cargo features of this crate alone.
+
+use super::EthernetMAC;
+
+/// A family of devices with the ethernet MAC.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Family {
+    /// STM32F105/STM32F107 (connectivity line).
+    F1,
+    /// STM32F4 devices with the ethernet MAC.
+    F4,
+    /// STM32F7 devices with the ethernet MAC.
+    F7,
+}
+
+/// The capabilities of the MAC.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities {
+    /// The device ID from `DBGMCU_IDCODE`, or `None` if the capabilities
+    /// were not detected from the device ID.
+    pub device_id: Option<u16>,
+    /// The family of the device.
+    pub family: Family,
+    /// The DMA supports the enhanced (8 word) descriptor format.
+    pub enhanced_descriptors: bool,
+    /// The MAC supports PTP timestamping.
+    pub ptp: bool,
+    /// The MAC supports PTPv2 (IEEE 1588-2008) timestamping and message
+    /// filtering, instead of only PTPv1 (IEEE 1588-2002).
+    pub ptp_v2: bool,
+    /// The MAC supports IP header and payload checksum offloading.
+    pub checksum_offload: bool,
+    /// The MAC reports the frame type and checksum results of received
+    /// frames in the (enhanced) RX descriptors.
+    pub rx_checksum_status: bool,
+    /// The MAC can strip the FCS from type (non-length) frames.
+    pub type_frame_crc_stripping: bool,
+}
+
+impl Capabilities {
+    /// The capabilities of `family`.
+    pub const fn of_family(family: Family) -> Self {
+        let v2 = !matches!(family, Family::F1);
+        Self {
+            device_id: None,
+            family,
+            enhanced_descriptors: v2,
+            ptp: true,
+            ptp_v2: v2,
+            checksum_offload: true,
+            rx_checksum_status: v2,
+            type_frame_crc_stripping: v2,
+        }
+    }
+
+    /// The capabilities of the family that this crate was built for.
+    pub const fn compiled() -> Self {
+        #[cfg(feature = "stm32f1xx-hal")]
+        let family = Family::F1;
+        #[cfg(feature = "stm32f4xx-hal")]
+        let family = Family::F4;
+        #[cfg(feature = "stm32f7xx-hal")]
+        let family = Family::F7;
+
+        Self::of_family(family)
+    }
+
+    /// The capabilities of the device with the device ID `device_id`, or
+    /// `None` if it is not a known device with the ethernet MAC.
+    pub const fn from_device_id(device_id: u16) -> Option<Self> {
+        let family = match device_id {
+            // STM32F105/STM32F107
+            0x418 => Family::F1,
+            // STM32F405/407/415/417, STM32F42x/43x, STM32F469/479
+            0x413 | 0x419 | 0x434 => Family::F4,
+            // STM32F74x/75x, STM32F76x/77x
+            0x449 | 0x451 => Family::F7,
+            _ => return None,
+        };
+
+        let mut capabilities = Self::of_family(family);
+        capabilities.device_id = Some(device_id);
+        Some(capabilities)
+    }
+
+    /// The capabilities of the family of the device whose Cortex-M core
+    /// reports `cpuid` in its `CPUID` register, or `None` if the core is
+    /// not used by a family with the ethernet MAC.
+    pub const fn from_cpuid(cpuid: u32) -> Option<Self> {
+        let family = match (cpuid >> 4) & 0xFFF {
+            // Cortex-M3
+            0xC23 => Family::F1,
+            // Cortex-M4
+            0xC24 => Family::F4,
+            // Cortex-M7
+            0xC27 => Family::F7,
+            _ => return None,
+        };
+
+        Some(Self::of_family(family))
+    }
+
+    /// Detect the capabilities of the MAC of the device that this code
+    /// is running on.
+    ///
+    /// The capabilities are derived from the device ID in `DBGMCU_IDCODE`.
+    /// On the STM32F1 and STM32F4, this register reads as zero unless a
+    /// debugger is connected, so the capabilities are usually derived from
+    /// the core in the `CPUID` register instead, and
+    /// [`Capabilities::device_id`] is `None`. Falls back to
+    /// [`Capabilities::compiled`] if neither is known.
+    pub fn detect() -> Self {
+        // SAFETY: we only read the read-only IDCODE register.
+        let idcode = unsafe { &*crate::stm32::DBGMCU::ptr() }
+            .idcode
+            .read()
+            .bits();
+        let device_id = (idcode & 0xFFF) as u16;
+
+        if let Some(capabilities) = Self::from_device_id(device_id) {
+            return capabilities;
+        }
+
+        // SAFETY: we only read the read-only CPUID register.
+        let cpuid = unsafe { &*cortex_m::peripheral::CPUID::PTR }.base.read();
+
+        match Self::from_cpuid(cpuid) {
+            Some(capabilities) => capabilities,
+            None => Self::compiled(),
+        }
+    }
+}
+
+impl EthernetMAC {
+    /// Detect the capabilities of this MAC.
+    ///
+    /// See [`Capabilities::detect`].
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities::detect()
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn device_ids() {
+        let f1 = Capabilities::from_device_id(0x418).unwrap();
+        assert_eq!(f1.family, Family::F1);
+        assert!(!f1.enhanced_descriptors);
+        assert!(!f1.ptp_v2);
+
+        let f4 = Capabilities::from_device_id(0x419).unwrap();
+        assert_eq!(f4.family, Family::F4);
+        assert_eq!(f4.device_id, Some(0x419));
+        assert!(f4.enhanced_descriptors && f4.type_frame_crc_stripping);
+
+        assert_eq!(
+            Capabilities::from_device_id(0x451).map(|c| c.family),
+            Some(Family::F7)
+        );
+        assert_eq!(Capabilities::from_device_id(0x421), None);
+    }
+
+    #[test]
+    fn cpuids() {
+        // Cortex-M4 r0p1
+        let f4 = Capabilities::from_cpuid(0x410F_C241).unwrap();
+        assert_eq!(f4.family, Family::F4);
+        assert_eq!(f4.device_id, None);
+
+        assert_eq!(
+            Capabilities::from_cpuid(0x411F_C231).map(|c| c.family),
+            Some(Family::F1)
+        );
+        assert_eq!(
+            Capabilities::from_cpuid(0x411F_C272).map(|c| c.family),
+            Some(Family::F7)
+        );
+        // Cortex-M0+
+        assert_eq!(Capabilities::from_cpuid(0x410C_C601), None);
+    }
+}
@@ -0,0 +1,386 @@
+//! IEEE 802.3x flow control: transmission and reception of pause frames.
+//!
+//! A pause frame asks the link partner to stop transmitting for a number of
+//! slot times (quanta of 512 bit times). The MAC transmits a pause frame
+//! when one is requested using [`EthernetMAC::send_pause_frame`], and
+//! retransmits it automatically according to the
+//! [`PauseLowThreshold`] for as long as the request is active.
+//!
+//! The MACs of the STM32F1, F4 and F7 do not implement the RX FIFO
+//! activation and deactivation thresholds of the DMA operation mode register,
+//! so pause frames are never requested by the hardware itself. Instead, the
+//! driver can request a pause frame whenever the DMA reports that it ran out
+//! of RX descriptors, or that the RX FIFO overflowed (see
+//! [`FlowControlConfig::pause_on_rx_exhaustion`]).
+//!
+//! Pause frames may only be used if both link partners advertised support
+//! for them during auto-negotiation. Use [`PhyPause::resolve_pause`] to
+//! determine in which directions pause frames may be used, and apply the
+//! result with [`FlowControlConfig::with_resolution`]:
+//!
+//! ```rust,ignore
+//! let resolution = phy.resolve_pause();
+//! mac.configure_flow_control(&FlowControlConfig::symmetric(0x100).with_resolution(resolution));
+//! ```
+//!
+//! [`PhyPause::resolve_pause`]: super::phy::PhyPause::resolve_pause
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use super::EthernetMAC;
+use crate::peripherals::ETHERNET_MAC;
+
+/// Flow control busy/back pressure activate
+const MACFCR_FCB: u32 = 1 << 0;
+/// Transmit flow control enable
+const MACFCR_TFCE: u32 = 1 << 1;
+/// Receive flow control enable
+const MACFCR_RFCE: u32 = 1 << 2;
+/// Unicast pause frame detect
+const MACFCR_UPFD: u32 = 1 << 3;
+/// Pause low threshold
+const MACFCR_PLT_SHIFT: u32 = 4;
+/// Zero-quanta pause disable
+const MACFCR_ZQPD: u32 = 1 << 7;
+/// Pause time
+const MACFCR_PT_SHIFT: u32 = 16;
+
+static PAUSE_ON_RX_EXHAUSTION: AtomicBool = AtomicBool::new(false);
+
+/// The remaining pause time at which a pause frame is retransmitted,
+/// if the pause frame request is still active.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseLowThreshold {
+    /// The pause time minus 4 slot times.
+    Minus4,
+    /// The pause time minus 28 slot times.
+    Minus28,
+    /// The pause time minus 144 slot times.
+    Minus144,
+    /// The pause time minus 256 slot times.
+    Minus256,
+}
+
+impl PauseLowThreshold {
+    fn bits(&self) -> u32 {
+        match self {
+            PauseLowThreshold::Minus4 => 0b00,
+            PauseLowThreshold::Minus28 => 0b01,
+            PauseLowThreshold::Minus144 => 0b10,
+            PauseLowThreshold::Minus256 => 0b11,
+        }
+    }
+}
+
+/// The flow control configuration of the MAC.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlowControlConfig {
+    /// Transmit pause frames when they are requested. In half-duplex mode,
+    /// requests activate back pressure instead.
+    pub transmit_pause: bool,
+    /// Stop transmitting when a pause frame is received.
+    pub receive_pause: bool,
+    /// Also accept pause frames that are sent to the unicast address of
+    /// this station, instead of only to [`Mac::MAC_CONTROL`](super::Mac::MAC_CONTROL).
+    pub unicast_pause_detect: bool,
+    /// The pause time of transmitted pause frames, in slot times.
+    pub pause_time: u16,
+    /// When to retransmit a pause frame while a request is active.
+    pub pause_low_threshold: PauseLowThreshold,
+    /// Transmit a pause frame with a pause time of zero once a request
+    /// ends, so that the link partner resumes transmission immediately.
+    pub zero_quanta_pause: bool,
+    /// Request a pause frame from the `ETH` interrupt whenever the DMA
+    /// reports that no RX descriptor was available, or that the RX FIFO
+    /// overflowed.
+    pub pause_on_rx_exhaustion: bool,
+}
+
+impl FlowControlConfig {
+    /// Flow control is disabled.
+    pub const DISABLED: Self = Self {
+        transmit_pause: false,
+        receive_pause: false,
+        unicast_pause_detect: false,
+        pause_time: 0x100,
+        pause_low_threshold: PauseLowThreshold::Minus4,
+        zero_quanta_pause: true,
+        pause_on_rx_exhaustion: false,
+    };
+
+    /// Transmit and receive pause frames with a pause time of
+    /// `pause_time` slot times, and request pause frames when the
+    /// RX ring is exhausted.
+    pub const fn symmetric(pause_time: u16) -> Self {
+        Self {
+            transmit_pause: true,
+            receive_pause: true,
+            pause_time,
+            pause_on_rx_exhaustion: true,
+            ..Self::DISABLED
+        }
+    }
+
+    fn bits(&self) -> u32 {
+        let mut bits = (self.pause_time as u32) << MACFCR_PT_SHIFT;
+        bits |= self.pause_low_threshold.bits() << MACFCR_PLT_SHIFT;
+        if self.transmit_pause {
+            bits |= MACFCR_TFCE;
+        }
+        if self.receive_pause {
+            bits |= MACFCR_RFCE;
+        }
+        if self.unicast_pause_detect {
+            bits |= MACFCR_UPFD;
+        }
+        if !self.zero_quanta_pause {
+            bits |= MACFCR_ZQPD;
+        }
+        bits
+    }
+}
+
+/// The pause capabilities that a device advertises during auto-negotiation.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PauseAbility {
+    /// The device supports symmetric pause (the `PAUSE` bit).
+    pub symmetric: bool,
+    /// The device supports asymmetric pause (the `ASM_DIR` bit).
+    pub asymmetric: bool,
+}
+
+/// The directions in which pause frames are used, as resolved from
+/// the [`PauseAbility`]s of both link partners.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PauseResolution {
+    /// This station may transmit pause frames.
+    pub transmit: bool,
+    /// This station must honour received pause frames.
+    pub receive: bool,
+}
+
+impl PauseResolution {
+    /// Resolve the use of pause frames from the `local` and `partner`
+    /// abilities, as specified in IEEE 802.3 Annex 28B.
+    pub const fn resolve(local: PauseAbility, partner: PauseAbility) -> Self {
+        let (transmit, receive) = match (
+            local.symmetric,
+            local.asymmetric,
+            partner.symmetric,
+            partner.asymmetric,
+        ) {
+            (true, _, true, _) => (true, true),
+            (false, true, true, true) => (true, false),
+            (true, true, false, true) => (false, true),
+            _ => (false, false),
+        };
+
+        Self { transmit, receive }
+    }
+}
+
+impl FlowControlConfig {
+    /// Return this configuration with pause frame transmission and
+    /// reception set according to `resolution`.
+    ///
+    /// In half-duplex mode, no pause frames are used, so the resolution
+    /// should only be applied to full-duplex links.
+    pub const fn with_resolution(self, resolution: PauseResolution) -> Self {
+        Self {
+            transmit_pause: resolution.transmit,
+            receive_pause: resolution.receive,
+            ..self
+        }
+    }
+}
+
+impl Default for FlowControlConfig {
+    fn default() -> Self {
+        Self::DISABLED
+    }
+}
+
+impl EthernetMAC {
+    /// Configure flow control.
+    ///
+    /// Pause frames should only be enabled if the link partner advertised
+    /// support for them during auto-negotiation.
+    pub fn configure_flow_control(&mut self, config: &FlowControlConfig) {
+        let bits = config.bits();
+        // SAFETY: MACFCR is only modified within a critical section.
+        cortex_m::interrupt::free(|_| {
+            self.eth_mac.macfcr.write(|w| unsafe { w.bits(bits) });
+        });
+        PAUSE_ON_RX_EXHAUSTION.store(
+            config.transmit_pause && config.pause_on_rx_exhaustion,
+            Ordering::Relaxed,
+        );
+    }
+
+    /// Request the transmission of a pause frame, or activate back
+    /// pressure in half-duplex mode.
+    ///
+    /// Returns `false` if a pause frame is still being transmitted, in which
+    /// case the request is ignored.
+    pub fn send_pause_frame(&mut self) -> bool {
+        Self::request_pause()
+    }
+
+    pub(crate) fn request_pause() -> bool {
+        // SAFETY: MACFCR is only modified within a critical section.
+        let macfcr = &unsafe { &*ETHERNET_MAC::ptr() }.macfcr;
+        let requested = cortex_m::interrupt::free(|_| {
+            let bits = macfcr.read().bits();
+            if bits & MACFCR_FCB != 0 {
+                false
+            } else {
+                macfcr.write(|w| unsafe { w.bits(bits | MACFCR_FCB) });
+                true
+            }
+        });
+
+        if requested {
+            crate::stats::add(&crate::stats::counters().tx_pause_requests, 1);
+        }
+        requested
+    }
+}
+
+/// Whether the transmission of pause frames is enabled.
+#[cfg(not(feature = "tx-only"))]
+pub(crate) fn transmit_pause_enabled() -> bool {
+    // SAFETY: we only read MACFCR.
+    let macfcr = &unsafe { &*ETHERNET_MAC::ptr() }.macfcr;
+    macfcr.read().bits() & MACFCR_TFCE != 0
+}
+
+/// Wait until the pause frame that is being transmitted, if any, has been
+/// transmitted. Returns `false` if it is still being transmitted after
+/// polling `MACFCR` for several milliseconds.
+#[cfg(not(feature = "tx-only"))]
+fn wait_for_pause_frame() -> bool {
+    /// A pause frame takes about 50 us to transmit at 10 Mbit/s, so this
+    /// leaves a margin of several milliseconds even at the highest HCLK.
+    /// The frame is never transmitted if the link is down.
+    const FCB_POLLS: u32 = 100_000;
+
+    // SAFETY: we only read MACFCR.
+    let macfcr = &unsafe { &*ETHERNET_MAC::ptr() }.macfcr;
+    (0..FCB_POLLS).any(|_| macfcr.read().bits() & MACFCR_FCB == 0)
+}
+
+/// Transmit a pause frame with a pause time of zero, so that the link
+/// partner resumes transmission immediately, if transmission of pause
+/// frames is enabled.
+///
+/// Returns `false` if transmission of pause frames is disabled, or if a
+/// pause frame could not be transmitted in time, for instance because the
+/// link is down. The link partner then resumes transmission once the pause
+/// time of the previous pause frame expires.
+#[cfg(not(feature = "tx-only"))]
+pub(crate) fn send_zero_quanta_pause() -> bool {
+    if !transmit_pause_enabled() {
+        return false;
+    }
+
+    // SAFETY: MACFCR is only modified within a critical section.
+    let macfcr = &unsafe { &*ETHERNET_MAC::ptr() }.macfcr;
+
+    // A pause frame that is still being transmitted must complete
+    // before the pause time can be changed.
+    if !wait_for_pause_frame() {
+        return false;
+    }
+
+    let bits = cortex_m::interrupt::free(|_| {
+        let bits = macfcr.read().bits();
+        let zero_quanta = bits & !(0xFFFF << MACFCR_PT_SHIFT);
+        macfcr.write(|w| unsafe { w.bits(zero_quanta | MACFCR_FCB) });
+        bits
+    });
+
+    let sent = wait_for_pause_frame();
+
+    // Restore the configured pause time. If the zero-quanta pause frame is
+    // still pending, it may be transmitted with the configured pause time
+    // instead, which only delays the resumption of the link partner.
+    cortex_m::interrupt::free(|_| {
+        macfcr.write(|w| unsafe { w.bits(bits) });
+    });
+
+    sent
+}
+
+/// Request a pause frame if the RX ring was exhausted and
+/// [`FlowControlConfig::pause_on_rx_exhaustion`] is enabled.
+#[cfg(not(feature = "tx-only"))]
+pub(crate) fn on_rx_exhaustion() {
+    if PAUSE_ON_RX_EXHAUSTION.load(Ordering::Relaxed) {
+        EthernetMAC::request_pause();
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pause_resolution() {
+        const fn ability(symmetric: bool, asymmetric: bool) -> PauseAbility {
+            PauseAbility {
+                symmetric,
+                asymmetric,
+            }
+        }
+        let resolve = |local, partner| {
+            let PauseResolution { transmit, receive } = PauseResolution::resolve(local, partner);
+            (transmit, receive)
+        };
+
+        assert_eq!(
+            resolve(ability(true, false), ability(true, false)),
+            (true, true)
+        );
+        assert_eq!(
+            resolve(ability(true, true), ability(true, false)),
+            (true, true)
+        );
+        assert_eq!(
+            resolve(ability(false, true), ability(true, true)),
+            (true, false)
+        );
+        assert_eq!(
+            resolve(ability(false, true), ability(true, false)),
+            (false, false)
+        );
+        assert_eq!(
+            resolve(ability(true, true), ability(false, true)),
+            (false, true)
+        );
+        assert_eq!(
+            resolve(ability(true, false), ability(false, true)),
+            (false, false)
+        );
+        assert_eq!(
+            resolve(ability(false, false), ability(true, true)),
+            (false, false)
+        );
+    }
+
+    #[test]
+    fn config_bits() {
+        assert_eq!(FlowControlConfig::DISABLED.bits(), 0x0100_0000);
+
+        let config = FlowControlConfig {
+            pause_low_threshold: PauseLowThreshold::Minus144,
+            zero_quanta_pause: false,
+            unicast_pause_detect: true,
+            ..FlowControlConfig::symmetric(0xFFFF)
+        };
+        assert_eq!(config.bits(), 0xFFFF_00AE);
+    }
+}
@@ -0,0 +1,655 @@
+//! Filtering of received frames by the MAC.
+//!
+//! By default, the MAC is configured to be promiscuous and passes all
+//! received frames to the DMA. Use [`EthernetMAC::configure_frame_filtering`]
+//! to only receive frames that are addressed to this station.
+
+use super::{EthernetMAC, Mac};
+use crate::{crc::crc32, peripherals::ETHERNET_MAC};
+
+#[cfg(not(feature = "tx-only"))]
+use crate::dma::RxPacket;
+
+/// The amount of additional perfect address filters supported by the MAC.
+pub const PERFECT_FILTER_COUNT: usize = 3;
+
+/// The frame filtering mode of the MAC.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FrameFilteringMode {
+    /// Filter received frames according to the given configuration.
+    Filter(FrameFiltering),
+    /// Pass all received frames, regardless of their destination.
+    Promiscuous,
+}
+
+/// A perfect filter for a destination address.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressFilter {
+    /// The address to compare the destination address of received frames with.
+    pub address: Mac,
+    /// A mask of the bytes of `address` that are ignored in the comparison.
+    ///
+    /// Bit 0 corresponds to the first byte of the address (`address.raw()[0]`),
+    /// bit 5 corresponds to the last byte.
+    pub ignored_bytes: u8,
+}
+
+impl AddressFilter {
+    /// A filter that matches `address` exactly.
+    pub const fn new(address: Mac) -> Self {
+        Self {
+            address,
+            ignored_bytes: 0,
+        }
+    }
+}
+
+/// The filtering of multicast frames.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MulticastFiltering {
+    /// Pass all multicast frames.
+    PassAll,
+    /// Only pass multicast frames that match one of the perfect
+    /// address filters.
+    Perfect,
+    /// Only pass multicast frames that match the hash table.
+    Hash,
+    /// Only pass multicast frames that match one of the perfect address
+    /// filters, or the hash table.
+    PerfectOrHash,
+}
+
+/// The filtering of control (pause) frames.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlFrameFiltering {
+    /// Do not pass any control frames.
+    BlockAll,
+    /// Pass all control frames, except for pause frames.
+    NoPause,
+    /// Pass all control frames.
+    PassAll,
+    /// Pass the control frames that pass the address filters.
+    AddressFilter,
+}
+
+/// The 64-bit hash table used for hash filtering of destination
+/// addresses.
+///
+/// The MAC computes the CRC32 of the destination address of a received
+/// frame, and uses its upper 6 bits (after bit reversal) as an index
+/// into this table.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HashTable {
+    /// The value of the `MACHTHR` register.
+    pub high: u32,
+    /// The value of the `MACHTLR` register.
+    pub low: u32,
+}
+
+impl HashTable {
+    /// An empty hash table, which matches no addresses.
+    pub const fn new() -> Self {
+        Self { high: 0, low: 0 }
+    }
+
+    /// Compute the index of `address` in the hash table.
+    pub const fn index(address: &Mac) -> u8 {
+        (crc32(address.raw()).reverse_bits() >> 26) as u8
+    }
+
+    /// Return this hash table with `address` added to it.
+    ///
+    /// This can be used to build a hash table for statically known
+    /// addresses at compile time:
+    ///
+    /// ```ignore
+    /// const TABLE: HashTable = HashTable::new()
+    ///     .with(&Mac::new([0x01, 0x00, 0x5E, 0x00, 0x00, 0xFB]))
+    ///     .with(&Mac::new([0x33, 0x33, 0x00, 0x00, 0x00, 0xFB]));
+    /// ```
+    pub const fn with(self, address: &Mac) -> Self {
+        let index = Self::index(address);
+        if index >= 32 {
+            Self {
+                high: self.high | 1 << (index - 32),
+                low: self.low,
+            }
+        } else {
+            Self {
+                high: self.high,
+                low: self.low | 1 << index,
+            }
+        }
+    }
+
+    /// Add `address` to this hash table.
+    pub fn insert(&mut self, address: &Mac) {
+        *self = self.with(address);
+    }
+
+    /// Check whether `address` matches this hash table.
+    pub const fn contains(&self, address: &Mac) -> bool {
+        let index = Self::index(address);
+        if index >= 32 {
+            self.high & (1 << (index - 32)) != 0
+        } else {
+            self.low & (1 << index) != 0
+        }
+    }
+
+    /// Remove all addresses from this hash table.
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+}
+
+/// A frame filtering configuration.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameFiltering {
+    /// The address of this station.
+    ///
+    /// Unicast frames are only passed if their destination address
+    /// is this address, or if they match one of the `perfect_filters`.
+    pub address: Mac,
+    /// Additional perfect filters for destination addresses.
+    pub perfect_filters: [Option<AddressFilter>; PERFECT_FILTER_COUNT],
+    /// The hash table used if `multicast` uses hash filtering.
+    pub hash_table: HashTable,
+    /// The filtering of multicast frames.
+    pub multicast: MulticastFiltering,
+    /// The filtering of control frames.
+    pub control_frames: ControlFrameFiltering,
+    /// Drop all broadcast frames.
+    pub filter_broadcast: bool,
+}
+
+impl FrameFiltering {
+    /// A configuration that only passes frames addressed to `address`,
+    /// broadcast frames, and multicast frames.
+    pub const fn new(address: Mac) -> Self {
+        Self {
+            address,
+            perfect_filters: [None; PERFECT_FILTER_COUNT],
+            hash_table: HashTable::new(),
+            multicast: MulticastFiltering::PassAll,
+            control_frames: ControlFrameFiltering::BlockAll,
+            filter_broadcast: false,
+        }
+    }
+
+    fn configure(&self, eth_mac: &ETHERNET_MAC) {
+        let (high, low) = self.address.to_register_pair();
+        eth_mac.maca0hr.modify(|_, w| w.maca0h().bits(high));
+        eth_mac.maca0lr.write(|w| w.maca0l().bits(low));
+
+        let [filter1, filter2, filter3] = self.perfect_filters;
+
+        let (address, mask, enabled) = perfect_filter_bits(filter1);
+        let (high, low) = address.to_register_pair();
+        eth_mac
+            .maca1hr
+            .write(|w| w.maca1h().bits(high).mbc().bits(mask).ae().bit(enabled));
+        eth_mac.maca1lr.write(|w| w.maca1l().bits(low));
+
+        let (address, mask, enabled) = perfect_filter_bits(filter2);
+        let (high, low) = address.to_register_pair();
+        eth_mac
+            .maca2hr
+            .write(|w| w.maca2h().bits(high).mbc().bits(mask).ae().bit(enabled));
+        crate::quirks::write_maca2l(eth_mac, low);
+
+        let (address, mask, enabled) = perfect_filter_bits(filter3);
+        let (high, low) = address.to_register_pair();
+        eth_mac
+            .maca3hr
+            .write(|w| w.maca3h().bits(high).mbc().bits(mask).ae().bit(enabled));
+        eth_mac.maca3lr.write(|w| w.maca3l().bits(low));
+
+        eth_mac
+            .machthr
+            .write(|w| w.hth().bits(self.hash_table.high));
+        eth_mac.machtlr.write(|w| w.htl().bits(self.hash_table.low));
+
+        let (pam, hm, hpf) = match self.multicast {
+            MulticastFiltering::PassAll => (true, false, false),
+            MulticastFiltering::Perfect => (false, false, false),
+            MulticastFiltering::Hash => (false, true, false),
+            MulticastFiltering::PerfectOrHash => (false, true, true),
+        };
+
+        let pcf = match self.control_frames {
+            ControlFrameFiltering::BlockAll => 0b00,
+            ControlFrameFiltering::NoPause => 0b01,
+            ControlFrameFiltering::PassAll => 0b10,
+            ControlFrameFiltering::AddressFilter => 0b11,
+        };
+
+        eth_mac.macffr.write(|w| {
+            w.ra()
+                .clear_bit()
+                .pm()
+                .clear_bit()
+                .hu()
+                .clear_bit()
+                .daif()
+                .clear_bit()
+                .saf()
+                .clear_bit()
+                .pam()
+                .bit(pam)
+                .hm()
+                .bit(hm)
+                .hpf()
+                .bit(hpf)
+                .bfd()
+                .bit(self.filter_broadcast)
+                .pcf()
+                .bits(pcf)
+        });
+    }
+}
+
+impl AddressFilter {
+    /// Check whether `address` matches this filter.
+    pub fn matches(&self, address: &Mac) -> bool {
+        self.address
+            .raw()
+            .iter()
+            .zip(address.raw())
+            .enumerate()
+            .all(|(i, (a, b))| self.ignored_bytes & (1 << i) != 0 || a == b)
+    }
+}
+
+/// The filtering category in which a received frame was passed.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterCategory {
+    /// The destination address matched the address of this station,
+    /// or one of the perfect filters.
+    PerfectMatch,
+    /// The destination address matched the hash table.
+    HashMatch,
+    /// The frame did not match any filter, and was passed because the
+    /// MAC is promiscuous or passes all multicast frames.
+    Promiscuous,
+    /// The frame was a broadcast frame.
+    Broadcast,
+    /// The frame was a MAC control frame.
+    ControlFrame,
+}
+
+impl FrameFilteringMode {
+    /// Classify a received `frame` according to this filtering mode.
+    ///
+    /// Returns `None` if `frame` is too short to contain an Ethernet header.
+    pub fn classify(&self, frame: &[u8]) -> Option<FilterCategory> {
+        /// The EtherType of MAC control frames.
+        const MAC_CONTROL: [u8; 2] = [0x88, 0x08];
+
+        if frame.len() < 14 {
+            return None;
+        }
+
+        let mut destination = [0; 6];
+        destination.copy_from_slice(&frame[..6]);
+        let destination = Mac::new(destination);
+
+        let filtering = match self {
+            FrameFilteringMode::Filter(filtering) => filtering,
+            FrameFilteringMode::Promiscuous => return Some(FilterCategory::Promiscuous),
+        };
+
+        let category = if frame[12..14] == MAC_CONTROL {
+            FilterCategory::ControlFrame
+        } else if destination.is_broadcast() {
+            FilterCategory::Broadcast
+        } else if destination == filtering.address
+            || filtering
+                .perfect_filters
+                .iter()
+                .flatten()
+                .any(|f| f.matches(&destination))
+        {
+            FilterCategory::PerfectMatch
+        } else if destination.is_multicast()
+            && matches!(
+                filtering.multicast,
+                MulticastFiltering::Hash | MulticastFiltering::PerfectOrHash
+            )
+            && filtering.hash_table.contains(&destination)
+        {
+            FilterCategory::HashMatch
+        } else {
+            FilterCategory::Promiscuous
+        };
+
+        Some(category)
+    }
+}
+
+/// The amount of received frames per [`FilterCategory`].
+///
+/// This can be used to validate a filter configuration in the field.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FilterStatistics {
+    /// See [`FilterCategory::PerfectMatch`].
+    pub perfect_match: u32,
+    /// See [`FilterCategory::HashMatch`].
+    pub hash_match: u32,
+    /// See [`FilterCategory::Promiscuous`].
+    pub promiscuous: u32,
+    /// See [`FilterCategory::Broadcast`].
+    pub broadcast: u32,
+    /// See [`FilterCategory::ControlFrame`].
+    pub control_frames: u32,
+}
+
+impl FilterStatistics {
+    /// Create a new set of statistics with all counters set to zero.
+    pub const fn new() -> Self {
+        Self {
+            perfect_match: 0,
+            hash_match: 0,
+            promiscuous: 0,
+            broadcast: 0,
+            control_frames: 0,
+        }
+    }
+
+    /// Count a frame that was passed in `category`.
+    pub fn record(&mut self, category: FilterCategory) {
+        let counter = match category {
+            FilterCategory::PerfectMatch => &mut self.perfect_match,
+            FilterCategory::HashMatch => &mut self.hash_match,
+            FilterCategory::Promiscuous => &mut self.promiscuous,
+            FilterCategory::Broadcast => &mut self.broadcast,
+            FilterCategory::ControlFrame => &mut self.control_frames,
+        };
+        *counter = counter.wrapping_add(1);
+    }
+
+    /// Classify `packet` according to `mode`, and count it.
+    ///
+    /// Packets that the MAC reports as having failed the address
+    /// filters are always counted as [`FilterCategory::Promiscuous`].
+    #[cfg(not(feature = "tx-only"))]
+    pub fn record_packet<const N: usize>(
+        &mut self,
+        mode: &FrameFilteringMode,
+        packet: &RxPacket<'_, N>,
+    ) -> Option<FilterCategory> {
+        let category = if packet.failed_address_filter() {
+            Some(FilterCategory::Promiscuous)
+        } else {
+            mode.classify(packet)
+        };
+
+        if let Some(category) = category {
+            self.record(category);
+        }
+
+        category
+    }
+}
+
+/// The address, mask byte control, and address enable bits of a perfect filter.
+fn perfect_filter_bits(filter: Option<AddressFilter>) -> (Mac, u8, bool) {
+    match filter {
+        Some(filter) => (filter.address, filter.ignored_bytes & 0x3F, true),
+        None => (Mac::new([0xFF; 6]), 0, false),
+    }
+}
+
+impl EthernetMAC {
+    /// Configure the filtering of received frames.
+    pub fn configure_frame_filtering(&mut self, mode: &FrameFilteringMode) {
+        match mode {
+            FrameFilteringMode::Filter(filtering) => filtering.configure(&self.eth_mac),
+            FrameFilteringMode::Promiscuous => {
+                self.eth_mac
+                    .macffr
+                    .write(|w| w.ra().set_bit().pm().set_bit());
+            }
+        }
+    }
+}
+
+/// Address enable
+const MACAHR_AE: u32 = 1 << 31;
+/// Mask byte control
+const MACAHR_MBC_SHIFT: u32 = 24;
+
+/// One of the address filter slots of the MAC.
+///
+/// Slots can be updated individually while the MAC is receiving, for
+/// instance to take over a virtual MAC address during failover.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MacAddressFilterSlot {
+    /// The address of this station (`MACA0`), which is always enabled,
+    /// and whose [`AddressFilter::ignored_bytes`] are ignored.
+    Station,
+    /// The first perfect filter (`MACA1`).
+    Perfect1,
+    /// The second perfect filter (`MACA2`).
+    Perfect2,
+    /// The third perfect filter (`MACA3`).
+    Perfect3,
+}
+
+impl MacAddressFilterSlot {
+    /// The slots of the perfect filters, in the order of
+    /// [`FrameFiltering::perfect_filters`].
+    pub const PERFECT: [Self; PERFECT_FILTER_COUNT] =
+        [Self::Perfect1, Self::Perfect2, Self::Perfect3];
+
+    /// Replace the filter in this slot with `filter`, or disable the
+    /// slot if `filter` is `None`.
+    ///
+    /// The MAC only transfers the high register of an address filter to
+    /// its receive clock domain once the low register is written. The high
+    /// register (with the mask and the address enable bit) is therefore
+    /// written first, and the low register last, so that the filter
+    /// switches from the old to the new configuration in one step. At no
+    /// point does the slot match a mix of both addresses, or is it
+    /// temporarily disabled.
+    ///
+    /// To take over an address without dropping frames for either the
+    /// old or the new address, first program the new address into a
+    /// spare slot, and only then remove the old address.
+    ///
+    /// # Panics
+    /// This function panics if `filter` is `None` for [`MacAddressFilterSlot::Station`],
+    /// which can not be disabled.
+    // NOTE(allow): writing the raw bits is not unsafe for all PACs.
+    #[allow(unused_unsafe)]
+    pub fn update(self, mac: &mut EthernetMAC, filter: Option<AddressFilter>) {
+        let (high, low) = self.register_bits(filter);
+
+        // Make sure that the high and low registers are written
+        // back-to-back, without another update in between.
+        cortex_m::interrupt::free(|_| {
+            let eth_mac = &mac.eth_mac;
+            // SAFETY: `register_bits` only sets defined bits.
+            match self {
+                Self::Station => {
+                    eth_mac.maca0hr.write(|w| unsafe { w.bits(high) });
+                    eth_mac.maca0lr.write(|w| unsafe { w.bits(low) });
+                }
+                Self::Perfect1 => {
+                    eth_mac.maca1hr.write(|w| unsafe { w.bits(high) });
+                    eth_mac.maca1lr.write(|w| unsafe { w.bits(low) });
+                }
+                Self::Perfect2 => {
+                    eth_mac.maca2hr.write(|w| unsafe { w.bits(high) });
+                    crate::quirks::write_maca2l(eth_mac, low);
+                }
+                Self::Perfect3 => {
+                    eth_mac.maca3hr.write(|w| unsafe { w.bits(high) });
+                    eth_mac.maca3lr.write(|w| unsafe { w.bits(low) });
+                }
+            }
+        });
+    }
+
+    /// Read the filter that is currently programmed into this slot, or
+    /// `None` if the slot is disabled.
+    pub fn read(self, mac: &EthernetMAC) -> Option<AddressFilter> {
+        let eth_mac = &mac.eth_mac;
+        let (high, low) = match self {
+            Self::Station => (eth_mac.maca0hr.read().bits(), eth_mac.maca0lr.read().bits()),
+            Self::Perfect1 => (eth_mac.maca1hr.read().bits(), eth_mac.maca1lr.read().bits()),
+            Self::Perfect2 => (eth_mac.maca2hr.read().bits(), eth_mac.maca2lr.read().bits()),
+            Self::Perfect3 => (eth_mac.maca3hr.read().bits(), eth_mac.maca3lr.read().bits()),
+        };
+        self.decode(high, low)
+    }
+
+    /// The values of the high and low registers of this slot for `filter`.
+    fn register_bits(self, filter: Option<AddressFilter>) -> (u32, u32) {
+        if self == Self::Station {
+            let filter = filter.expect("The station address filter can not be disabled");
+            let (high, low) = filter.address.to_register_pair();
+            return (high as u32, low);
+        }
+
+        let (address, mask, enabled) = perfect_filter_bits(filter);
+        let (high, low) = address.to_register_pair();
+        let mut high = high as u32 | ((mask as u32) << MACAHR_MBC_SHIFT);
+        if enabled {
+            high |= MACAHR_AE;
+        }
+        (high, low)
+    }
+
+    /// The filter described by the high and low registers of this slot.
+    fn decode(self, high: u32, low: u32) -> Option<AddressFilter> {
+        if self != Self::Station && high & MACAHR_AE == 0 {
+            return None;
+        }
+
+        let ignored_bytes = if self == Self::Station {
+            0
+        } else {
+            ((high >> MACAHR_MBC_SHIFT) & 0x3F) as u8
+        };
+
+        Some(AddressFilter {
+            address: Mac::from_register_pair(high as u16, low),
+            ignored_bytes,
+        })
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hash_table_insert() {
+        let address = Mac::new([0x01, 0x00, 0x5E, 0x00, 0x00, 0x01]);
+        let mut table = HashTable::new();
+        assert!(!table.contains(&address));
+
+        table.insert(&address);
+        assert!(table.contains(&address));
+        assert_eq!((table.high | table.low).count_ones(), 1);
+
+        table.clear();
+        assert_eq!(table, HashTable::new());
+    }
+
+    fn frame(destination: [u8; 6], ethertype: [u8; 2]) -> [u8; 14] {
+        let mut frame = [0; 14];
+        frame[..6].copy_from_slice(&destination);
+        frame[12..].copy_from_slice(&ethertype);
+        frame
+    }
+
+    #[test]
+    fn classify_frames() {
+        const IPV4: [u8; 2] = [0x08, 0x00];
+        let station = [0x02, 0x00, 0x00, 0x00, 0x00, 0x01];
+        let group = [0x01, 0x00, 0x5E, 0x00, 0x00, 0x01];
+
+        let mut filtering = FrameFiltering::new(Mac::new(station));
+        filtering.multicast = MulticastFiltering::Hash;
+        filtering.hash_table.insert(&Mac::new(group));
+        filtering.perfect_filters[0] = Some(AddressFilter {
+            address: Mac::new([0x02, 0x00, 0x00, 0x00, 0x01, 0x00]),
+            ignored_bytes: 1 << 5,
+        });
+        let mode = FrameFilteringMode::Filter(filtering);
+
+        let classify = |destination, ethertype| mode.classify(&frame(destination, ethertype));
+
+        assert_eq!(classify(station, IPV4), Some(FilterCategory::PerfectMatch));
+        assert_eq!(
+            classify([0x02, 0x00, 0x00, 0x00, 0x01, 0x42], IPV4),
+            Some(FilterCategory::PerfectMatch)
+        );
+        assert_eq!(classify(group, IPV4), Some(FilterCategory::HashMatch));
+        assert_eq!(classify([0xFF; 6], IPV4), Some(FilterCategory::Broadcast));
+        assert_eq!(
+            classify(station, [0x88, 0x08]),
+            Some(FilterCategory::ControlFrame)
+        );
+        assert_eq!(
+            classify([0x01, 0x00, 0x5E, 0x00, 0x00, 0x02], IPV4),
+            Some(FilterCategory::Promiscuous)
+        );
+        assert_eq!(mode.classify(&[0; 13]), None);
+
+        let mut statistics = FilterStatistics::new();
+        statistics.record(FilterCategory::HashMatch);
+        assert_eq!(statistics.hash_match, 1);
+    }
+
+    #[test]
+    fn address_filter_slot_bits() {
+        let filter = AddressFilter {
+            address: Mac::new([0x02, 0x00, 0x00, 0x12, 0x34, 0x56]),
+            ignored_bytes: 0xFF,
+        };
+
+        let slot = MacAddressFilterSlot::Perfect2;
+        let (high, low) = slot.register_bits(Some(filter));
+        assert_eq!((high, low), (0xBF00_5634, 0x1200_0002));
+        assert_eq!(
+            slot.decode(high, low),
+            Some(AddressFilter {
+                ignored_bytes: 0x3F,
+                ..filter
+            })
+        );
+
+        let (high, low) = slot.register_bits(None);
+        assert_eq!(slot.decode(high, low), None);
+
+        let station = MacAddressFilterSlot::Station;
+        let (high, low) = station.register_bits(Some(filter));
+        assert_eq!(high, 0x5634);
+        assert_eq!(
+            station.decode(high | MACAHR_AE, low),
+            Some(AddressFilter::new(filter.address))
+        );
+    }
+
+    #[test]
+    fn hash_table_const() {
+        const ADDRESS: Mac = Mac::new([0x01, 0x00, 0x5E, 0x00, 0x00, 0x01]);
+        const TABLE: HashTable = HashTable::new().with(&ADDRESS);
+
+        let mut table = HashTable::new();
+        table.insert(&ADDRESS);
+        assert_eq!(TABLE, table);
+    }
+}
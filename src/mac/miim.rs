@@ -2,6 +2,13 @@ pub use ieee802_3_miim::Miim;
 
 pub use ieee802_3_miim::*;
 
+use core::{
+    cell::RefCell,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use cortex_m::interrupt::Mutex;
+
 use crate::{peripherals::ETHERNET_MAC, stm32::ethernet_mac::MACMIIAR};
 
 use super::EthernetMAC;
@@ -25,45 +32,231 @@ fn miim_wait_ready(iar: &MACMIIAR) {
     while iar.read().mb().bit_is_set() {}
 }
 
+/// Perform an SMI transaction.
+///
+/// A transaction consists of several register accesses, so it is
+/// executed in a critical section. Otherwise, a transaction that is
+/// started from a higher priority (for instance from the handler of the
+/// interrupt of the PHY) could interleave with a transaction that is in
+/// progress, and corrupt both. An SMI transaction takes 64 MDC cycles,
+/// which is about 26 µs at the maximum MDC frequency of 2.5 MHz.
+#[inline(always)]
+fn miim_transaction<T>(f: impl FnOnce() -> T) -> T {
+    cortex_m::interrupt::free(|_| f())
+}
+
 #[inline(always)]
 fn miim_write(eth_mac: &mut ETHERNET_MAC, phy: u8, reg: u8, data: u16) {
-    miim_wait_ready(&eth_mac.macmiiar);
-    eth_mac.macmiidr.write(|w| w.md().bits(data));
+    miim_transaction(|| {
+        miim_wait_ready(&eth_mac.macmiiar);
+        eth_mac.macmiidr.write(|w| w.md().bits(data));
 
-    miim_wait_ready(&eth_mac.macmiiar);
+        miim_wait_ready(&eth_mac.macmiiar);
 
-    eth_mac.macmiiar.modify(|_, w| {
-        w.pa()
-            .bits(phy)
-            .mr()
-            .bits(reg)
-            /* Write operation MW=1*/
-            .mw()
-            .set_bit()
-            .mb()
-            .set_bit()
-    });
-    miim_wait_ready(&eth_mac.macmiiar);
+        eth_mac.macmiiar.modify(|_, w| {
+            w.pa()
+                .bits(phy)
+                .mr()
+                .bits(reg)
+                /* Write operation MW=1*/
+                .mw()
+                .set_bit()
+                .mb()
+                .set_bit()
+        });
+        miim_wait_ready(&eth_mac.macmiiar);
+    })
 }
 
 #[inline(always)]
 fn miim_read(eth_mac: &mut ETHERNET_MAC, phy: u8, reg: u8) -> u16 {
-    miim_wait_ready(&eth_mac.macmiiar);
-    eth_mac.macmiiar.modify(|_, w| {
-        w.pa()
-            .bits(phy)
-            .mr()
-            .bits(reg)
-            /* Read operation MW=0 */
-            .mw()
-            .clear_bit()
-            .mb()
-            .set_bit()
-    });
-    miim_wait_ready(&eth_mac.macmiiar);
-
-    // Return value:
-    eth_mac.macmiidr.read().md().bits()
+    miim_transaction(|| {
+        miim_wait_ready(&eth_mac.macmiiar);
+        eth_mac.macmiiar.modify(|_, w| {
+            w.pa()
+                .bits(phy)
+                .mr()
+                .bits(reg)
+                /* Read operation MW=0 */
+                .mw()
+                .clear_bit()
+                .mb()
+                .set_bit()
+        });
+        miim_wait_ready(&eth_mac.macmiiar);
+
+        // Return value:
+        eth_mac.macmiidr.read().md().bits()
+    })
+}
+
+/// MMD Access Control Register
+const MMDACR: u8 = 13;
+/// MMD Access Address/Data Register
+const MAADR: u8 = 14;
+/// Access the address register of the MMD
+const MMDACR_FUNCTION_ADDRESS: u16 = 0;
+/// Access the data register of the MMD, without incrementing the address
+const MMDACR_FUNCTION_DATA: u16 = 0b01 << 14;
+/// The device address field
+const MMDACR_DEVAD: u16 = 0x1F;
+
+/// The PMA/PMD MMD.
+pub const MMD_PMA_PMD: u8 = 1;
+/// The PCS MMD.
+pub const MMD_PCS: u8 = 3;
+/// The auto-negotiation MMD.
+pub const MMD_AUTONEGOTIATION: u8 = 7;
+/// The first vendor specific MMD.
+pub const MMD_VENDOR_SPECIFIC_1: u8 = 30;
+/// The second vendor specific MMD.
+pub const MMD_VENDOR_SPECIFIC_2: u8 = 31;
+
+/// Access to Clause 45 registers, in the MDIO Manageable Devices (MMDs)
+/// of a PHY.
+///
+/// The MAC of the STM32F1, STM32F4 and STM32F7 only generates Clause 22
+/// frames, so the registers are accessed indirectly, through the MMD
+/// access control (13) and address/data (14) registers that are defined
+/// in Clause 22. Every access takes four SMI transactions.
+///
+/// Interrupts are only disabled for the duration of a single SMI
+/// transaction, like for any other [`Miim`] access. If an MMD access from
+/// an interrupt handler completes while the register of an interrupted
+/// access is being selected, the interrupted access selects its register
+/// again, so accesses from interrupt handlers take effect first, and
+/// accesses from lower priorities may be retried.
+pub trait MiimClause45: Miim {
+    /// Read register `reg` of MMD `device` of the PHY at address `phy`.
+    fn mmd_read(&mut self, phy: u8, device: u8, reg: u16) -> u16 {
+        mmd_access(self, phy, device, reg, |miim| miim.read(phy, MAADR))
+    }
+
+    /// Write `data` to register `reg` of MMD `device` of the PHY at
+    /// address `phy`.
+    fn mmd_write(&mut self, phy: u8, device: u8, reg: u16, data: u16) {
+        mmd_access(self, phy, device, reg, |miim| miim.write(phy, MAADR, data))
+    }
+}
+
+impl<M: Miim> MiimClause45 for M {}
+
+/// The amount of MMD accesses that have completed.
+static MMD_ACCESSES: AtomicU32 = AtomicU32::new(0);
+
+/// Select register `reg` of MMD `device`, and perform `access` on
+/// its data register.
+///
+/// The register is selected again until no other MMD access completed
+/// while it was being selected. The check and `access` are performed in
+/// a critical section, so no other MMD access can select another register
+/// in between.
+fn mmd_access<M, T>(
+    miim: &mut M,
+    phy: u8,
+    device: u8,
+    reg: u16,
+    mut access: impl FnMut(&mut M) -> T,
+) -> T
+where
+    M: Miim + ?Sized,
+{
+    loop {
+        let accesses = MMD_ACCESSES.load(Ordering::Relaxed);
+
+        select_mmd_register(miim, phy, device, reg);
+
+        let result = cortex_m::interrupt::free(|_| {
+            if MMD_ACCESSES.load(Ordering::Relaxed) != accesses {
+                return None;
+            }
+
+            MMD_ACCESSES.store(accesses.wrapping_add(1), Ordering::Relaxed);
+            Some(access(miim))
+        });
+
+        if let Some(result) = result {
+            return result;
+        }
+    }
+}
+
+/// Set the address of MMD `device` to `reg`, and select its data
+/// register for the next access to `MAADR`.
+fn select_mmd_register<M: Miim + ?Sized>(miim: &mut M, phy: u8, device: u8, reg: u16) {
+    let device = device as u16 & MMDACR_DEVAD;
+    miim.write(phy, MMDACR, MMDACR_FUNCTION_ADDRESS | device);
+    miim.write(phy, MAADR, reg);
+    miim.write(phy, MMDACR, MMDACR_FUNCTION_DATA | device);
+}
+
+/// An SMI bus that is shared by several PHYs, such as an Ethernet
+/// switch and a separate PHY that are connected to the same MDC and
+/// MDIO pins.
+///
+/// Every PHY driver takes a [`SharedMiim`] handle to the bus, which
+/// executes each access in a critical section, so the handles can also
+/// be used from interrupt handlers.
+///
+/// ```rust,ignore
+/// let bus = MiimBus::new(mac.mii(&mut mdio, &mut mdc));
+///
+/// let mut switch = BarePhy::new(bus.share(), 0x10, Pause::NoPause);
+/// let mut phy = LAN8742A::new(bus.share(), 0);
+/// ```
+pub struct MiimBus<M> {
+    // Only `None` once the bus has been released, which consumes the `MiimBus`.
+    miim: Mutex<RefCell<Option<M>>>,
+}
+
+impl<M: Miim> MiimBus<M> {
+    /// Create a bus that is shared through `miim`.
+    pub const fn new(miim: M) -> Self {
+        Self {
+            miim: Mutex::new(RefCell::new(Some(miim))),
+        }
+    }
+
+    /// Get a handle to this bus.
+    pub fn share(&self) -> SharedMiim<'_, M> {
+        SharedMiim { bus: self }
+    }
+
+    /// Release the underlying [`Miim`].
+    pub fn release(self) -> M {
+        cortex_m::interrupt::free(|cs| self.miim.borrow(cs).borrow_mut().take())
+            .expect("The bus is only released once")
+    }
+
+    fn with<T>(&self, f: impl FnOnce(&mut M) -> T) -> T {
+        cortex_m::interrupt::free(|cs| {
+            let mut miim = self.miim.borrow(cs).borrow_mut();
+            f(miim.as_mut().expect("The bus is not released yet"))
+        })
+    }
+}
+
+/// A handle to a [`MiimBus`].
+pub struct SharedMiim<'bus, M> {
+    bus: &'bus MiimBus<M>,
+}
+
+impl<M> Clone for SharedMiim<'_, M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M> Copy for SharedMiim<'_, M> {}
+
+impl<M: Miim> Miim for SharedMiim<'_, M> {
+    fn read(&mut self, phy: u8, reg: u8) -> u16 {
+        self.bus.with(|miim| miim.read(phy, reg))
+    }
+
+    fn write(&mut self, phy: u8, reg: u8, data: u16) {
+        self.bus.with(|miim| miim.write(phy, reg, data))
+    }
 }
 
 /// Serial Management Interface
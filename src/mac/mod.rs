@@ -2,11 +2,30 @@
 
 use core::ops::{Deref, DerefMut};
 
-use crate::{dma::EthernetDMA, hal::rcc::Clocks, peripherals::ETHERNET_MAC, stm32::ETHERNET_MMC};
+use crate::{dma::EthernetDMA, peripherals::ETHERNET_MAC, stm32::ETHERNET_MMC};
+
+use self::{flow_control::FlowControlConfig, phy::LinkConfiguration};
 
 mod miim;
 pub use miim::*;
 
+mod address;
+pub use address::Mac;
+
+pub mod capabilities;
+
+pub mod flow_control;
+
+pub mod frame_filtering;
+
+pub mod multicast;
+
+pub mod phy;
+
+pub mod pmt;
+
+pub mod vlan;
+
 /// Speeds at which this MAC can be configured
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -21,6 +40,45 @@ pub enum Speed {
     FullDuplexBase100Tx,
 }
 
+/// The duplex mode of a link.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Duplex {
+    /// Half duplex
+    Half,
+    /// Full duplex
+    Full,
+}
+
+impl Speed {
+    /// The duplex mode of this speed.
+    pub const fn duplex(&self) -> Duplex {
+        match self {
+            Speed::HalfDuplexBase10T | Speed::HalfDuplexBase100Tx => Duplex::Half,
+            Speed::FullDuplexBase10T | Speed::FullDuplexBase100Tx => Duplex::Full,
+        }
+    }
+
+    /// The bit rate of this speed, in Mbit/s.
+    pub const fn mbps(&self) -> u32 {
+        match self {
+            Speed::HalfDuplexBase10T | Speed::FullDuplexBase10T => 10,
+            Speed::HalfDuplexBase100Tx | Speed::FullDuplexBase100Tx => 100,
+        }
+    }
+}
+
+/// The speed programmed into the MAC does not match the speed
+/// that the PHY resolved.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpeedMismatch {
+    /// The speed that is programmed into the MAC.
+    pub mac: Speed,
+    /// The speed that the PHY resolved.
+    pub phy: Speed,
+}
+
 mod consts {
     /* For HCLK 60-100 MHz */
     pub const ETH_MACMIIAR_CR_HCLK_DIV_42: u8 = 0;
@@ -41,10 +99,40 @@ use self::consts::*;
 #[derive(Debug)]
 pub struct WrongClock;
 
+/// The interrupt sources of the MAC that can be masked in the
+/// MAC interrupt mask register (MACIMR).
+///
+/// A source that is set in a [`MacInterrupts`] is masked, so it does not
+/// cause an `ETH` interrupt.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct MacInterrupts {
+    /// The power management (PMT) interrupt, which is raised when
+    /// a magic packet or wakeup frame is received.
+    pub pmt: bool,
+    /// The timestamp trigger interrupt, which is raised when the PTP
+    /// clock reaches the configured target time.
+    pub timestamp_trigger: bool,
+}
+
+impl MacInterrupts {
+    /// No interrupt sources.
+    pub const NONE: Self = Self {
+        pmt: false,
+        timestamp_trigger: false,
+    };
+
+    /// All interrupt sources.
+    pub const ALL: Self = Self {
+        pmt: true,
+        timestamp_trigger: true,
+    };
+}
+
 /// Ethernet media access control (MAC).
 ///
 // impl note: access to the MACIMR register should _only_ be performed
-// atomically.
+// within a critical section, as it is also modified from the `ETH` interrupt.
 pub struct EthernetMAC {
     eth_mac: ETHERNET_MAC,
 }
@@ -59,19 +147,17 @@ impl EthernetMAC {
     ///
     /// Additionally, an `impl` of the [`ieee802_3_miim::Miim`] trait is available
     /// for PHY communication.
-    pub(crate) fn new(
+    pub(crate) fn new<const RX_BUF: usize>(
         eth_mac: ETHERNET_MAC,
         eth_mmc: ETHERNET_MMC,
-        clocks: Clocks,
+        hclk_hz: u32,
         initial_speed: Speed,
         // Note(_dma): this field exists to ensure that the MAC is not
         // initialized before the DMA. If MAC is started before the DMA,
         // it doesn't work.
-        _dma: &EthernetDMA,
+        _dma: &EthernetDMA<'_, '_, RX_BUF>,
     ) -> Result<Self, WrongClock> {
-        let clock_frequency = clocks.hclk().to_Hz();
-
-        let clock_range = match clock_frequency {
+        let clock_range = match hclk_hz {
             0..=24_999_999 => return Err(WrongClock),
             25_000_000..=34_999_999 => ETH_MACMIIAR_CR_HCLK_DIV_16,
             35_000_000..=59_999_999 => ETH_MACMIIAR_CR_HCLK_DIV_26,
@@ -108,10 +194,10 @@ impl EthernetMAC {
                 .set_bit()
                 // Receiver enable
                 .re()
-                .set_bit()
+                .bit(cfg!(not(feature = "tx-only")))
                 // Transmitter enable
                 .te()
-                .set_bit()
+                .bit(cfg!(not(feature = "rx-only")))
         });
 
         // Frame filter register
@@ -199,9 +285,33 @@ impl EthernetMAC {
         });
     }
 
+    /// Reconfigure the MAC for `link`, the configuration that the PHY
+    /// resolved during auto-negotiation.
+    ///
+    /// This sets the speed and duplex mode of the MAC, and configures flow
+    /// control according to `flow_control`, with the use of pause frames
+    /// restricted to the directions in [`LinkConfiguration::pause`].
+    ///
+    /// ```rust,ignore
+    /// if let Some(link) = phy.link_configuration() {
+    ///     mac.apply_link(&link, &FlowControlConfig::symmetric(0x100));
+    /// }
+    /// ```
+    pub fn apply_link(&mut self, link: &LinkConfiguration, flow_control: &FlowControlConfig) {
+        self.set_speed(link.speed);
+        self.configure_flow_control(&flow_control.with_resolution(link.pause));
+    }
+
     /// Get the Ethernet Speed at which the MAC communicates
     pub fn get_speed(&self) -> Speed {
-        let cr = self.eth_mac.maccr.read();
+        Self::configured_speed()
+    }
+
+    /// The speed that is programmed into the MAC, which can be read
+    /// without a reference to the [`EthernetMAC`].
+    pub(crate) fn configured_speed() -> Speed {
+        // SAFETY: we only read MACCR.
+        let cr = unsafe { &*ETHERNET_MAC::ptr() }.maccr.read();
         match (cr.fes().bit_is_set(), cr.dm().bit_is_set()) {
             (false, false) => Speed::HalfDuplexBase10T,
             (false, true) => Speed::FullDuplexBase10T,
@@ -210,19 +320,133 @@ impl EthernetMAC {
         }
     }
 
+    /// Get the bit rate at which the MAC communicates, in Mbit/s.
+    pub fn current_speed(&self) -> u32 {
+        self.get_speed().mbps()
+    }
+
+    /// Get the duplex mode in which the MAC communicates.
+    pub fn current_duplex(&self) -> Duplex {
+        self.get_speed().duplex()
+    }
+
+    /// Enable or disable the internal loopback mode of the MAC.
+    ///
+    /// In loopback mode, transmitted frames are received by the MAC
+    /// itself instead of being passed to the PHY, and frames that are
+    /// received by the PHY are ignored. The MAC still requires the
+    /// receive clock of the PHY.
+    pub fn set_loopback(&mut self, enabled: bool) {
+        self.eth_mac.maccr.modify(|_, w| w.lm().bit(enabled));
+    }
+
+    /// Check whether the internal loopback mode of the MAC is enabled.
+    pub fn loopback(&self) -> bool {
+        self.eth_mac.maccr.read().lm().bit_is_set()
+    }
+
+    /// Check that the MAC is configured for `phy_speed`, the speed
+    /// that the PHY resolved for the link.
+    ///
+    /// A MAC that is not updated after the PHY renegotiates the link
+    /// (for instance with [`Self::set_speed`]) still appears to work,
+    /// but suffers from late collisions and CRC errors.
+    pub fn verify_speed(&self, phy_speed: Speed) -> Result<(), SpeedMismatch> {
+        let mac = self.get_speed();
+        if mac == phy_speed {
+            Ok(())
+        } else {
+            Err(SpeedMismatch {
+                mac,
+                phy: phy_speed,
+            })
+        }
+    }
+
+    /// Mask the given MAC interrupt sources, so that they no longer
+    /// cause an `ETH` interrupt. Sources that are not set in `interrupts`
+    /// are left unchanged.
+    pub fn mask_interrupts(&mut self, interrupts: MacInterrupts) {
+        Self::modify_interrupt_mask(interrupts, true);
+    }
+
+    /// Unmask the given MAC interrupt sources. Sources that are not set
+    /// in `interrupts` are left unchanged.
+    ///
+    /// The timestamp trigger interrupt is handled by [`eth_interrupt_handler`],
+    /// and is usually unmasked by `EthernetPTP::configure_target_time_interrupt`.
+    /// The PMT interrupt is not handled by the driver, and must be cleared
+    /// by calling [`EthernetMAC::pmt_status`].
+    ///
+    /// [`eth_interrupt_handler`]: crate::eth_interrupt_handler
+    pub fn unmask_interrupts(&mut self, interrupts: MacInterrupts) {
+        Self::modify_interrupt_mask(interrupts, false);
+    }
+
+    /// The MAC interrupt sources that are currently masked.
+    pub fn masked_interrupts(&self) -> MacInterrupts {
+        let macimr = self.eth_mac.macimr.read();
+        MacInterrupts {
+            pmt: macimr.pmtim().bit_is_set(),
+            timestamp_trigger: macimr.tstim().bit_is_set(),
+        }
+    }
+
+    /// Enable or disable stripping of the FCS (CRC) from received frames.
+    #[cfg(not(feature = "tx-only"))]
+    pub(crate) fn set_crc_stripping(strip: bool) {
+        // SAFETY: MACCR is only modified within a critical section.
+        let maccr = &unsafe { &*ETHERNET_MAC::ptr() }.maccr;
+        cortex_m::interrupt::free(|_| {
+            maccr.modify(|_, w| {
+                // STM32F1xx do not have the bit for type frames.
+                #[cfg(any(feature = "stm32f4xx-hal", feature = "stm32f7xx-hal"))]
+                let w = w.cstf().bit(strip);
+                w.apcs().bit(strip)
+            })
+        });
+    }
+
+    pub(crate) fn modify_interrupt_mask(interrupts: MacInterrupts, masked: bool) {
+        // SAFETY: MACIMR is only modified within a critical section.
+        let macimr = &unsafe { &*ETHERNET_MAC::ptr() }.macimr;
+        cortex_m::interrupt::free(|_| {
+            macimr.modify(|_, w| {
+                let w = if interrupts.pmt {
+                    w.pmtim().bit(masked)
+                } else {
+                    w
+                };
+                if interrupts.timestamp_trigger {
+                    w.tstim().bit(masked)
+                } else {
+                    w
+                }
+            })
+        });
+    }
+
     #[cfg(feature = "ptp")]
     pub(crate) fn mask_timestamp_trigger_interrupt() {
-        // SAFETY: MACIMR only receives atomic writes.
-        let mac = &unsafe { &*ETHERNET_MAC::ptr() };
-        mac.macimr.write(|w| w.tstim().set_bit());
+        Self::modify_interrupt_mask(
+            MacInterrupts {
+                timestamp_trigger: true,
+                ..MacInterrupts::NONE
+            },
+            true,
+        );
     }
 
     // NOTE(allow): only used on F4 and F7
     #[allow(dead_code)]
     pub(crate) fn unmask_timestamp_trigger_interrupt() {
-        // SAFETY: MACIMR only receives atomic writes.
-        let macimr = &unsafe { &*ETHERNET_MAC::ptr() }.macimr;
-        macimr.write(|w| w.tstim().clear_bit());
+        Self::modify_interrupt_mask(
+            MacInterrupts {
+                timestamp_trigger: true,
+                ..MacInterrupts::NONE
+            },
+            false,
+        );
     }
 }
 
@@ -0,0 +1,252 @@
+//! Management of multicast group memberships.
+//!
+//! [`Groups`] keeps track of the IPv4 and IPv6 multicast groups that the
+//! application is a member of, and computes the frame filtering that is
+//! required to receive the traffic of those groups. An IGMP or MLD
+//! implementation can use [`Groups::iter`] to report the memberships.
+
+use super::{
+    frame_filtering::{AddressFilter, FrameFiltering, MulticastFiltering, PERFECT_FILTER_COUNT},
+    Mac,
+};
+
+/// An IP multicast group.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Group {
+    /// An IPv4 multicast group (`224.0.0.0/4`).
+    Ipv4([u8; 4]),
+    /// An IPv6 multicast group (`ff00::/8`).
+    Ipv6([u8; 16]),
+}
+
+impl Group {
+    /// Check whether this is a valid multicast address.
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Group::Ipv4(address) => address[0] & 0xF0 == 0xE0,
+            Group::Ipv6(address) => address[0] == 0xFF,
+        }
+    }
+
+    /// The multicast MAC address that frames for this group are sent to.
+    ///
+    /// See [`Mac::from_ipv4_multicast`] and [`Mac::from_ipv6_multicast`].
+    pub fn mac(&self) -> Mac {
+        match self {
+            Group::Ipv4(a) => Mac::from_ipv4_multicast(*a),
+            Group::Ipv6(a) => Mac::from_ipv6_multicast(*a),
+        }
+    }
+}
+
+/// An error that occurs when joining a [`Group`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinError {
+    /// The address of the group is not a multicast address.
+    InvalidGroup,
+    /// The maximum amount of groups has been joined already.
+    Full,
+}
+
+/// The multicast groups that this station is a member of.
+///
+/// At most `N` groups can be joined at the same time.
+#[derive(Debug, Clone)]
+pub struct Groups<const N: usize> {
+    groups: [Option<Group>; N],
+}
+
+impl<const N: usize> Default for Groups<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Groups<N> {
+    /// Create a new set of groups, without any members.
+    pub const fn new() -> Self {
+        Self { groups: [None; N] }
+    }
+
+    /// Join `group`.
+    ///
+    /// Returns `Ok(true)` if the group was joined, and `Ok(false)`
+    /// if this station was already a member of the group. An IGMP or
+    /// MLD implementation should send an unsolicited report for the group
+    /// if `Ok(true)` is returned.
+    ///
+    /// The frame filtering must be reconfigured using [`Groups::apply`]
+    /// for the change to take effect.
+    pub fn join(&mut self, group: Group) -> Result<bool, JoinError> {
+        if !group.is_valid() {
+            return Err(JoinError::InvalidGroup);
+        }
+
+        if self.contains(&group) {
+            return Ok(false);
+        }
+
+        let slot = self
+            .groups
+            .iter_mut()
+            .find(|g| g.is_none())
+            .ok_or(JoinError::Full)?;
+        *slot = Some(group);
+        Ok(true)
+    }
+
+    /// Leave `group`.
+    ///
+    /// Returns `true` if this station was a member of the group.
+    ///
+    /// The frame filtering must be reconfigured using [`Groups::apply`]
+    /// for the change to take effect.
+    pub fn leave(&mut self, group: &Group) -> bool {
+        match self.groups.iter_mut().find(|g| g.as_ref() == Some(group)) {
+            Some(slot) => {
+                *slot = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Check whether this station is a member of `group`.
+    pub fn contains(&self, group: &Group) -> bool {
+        self.iter().any(|g| g == group)
+    }
+
+    /// The amount of groups that this station is a member of.
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Check whether this station is not a member of any group.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterate over the groups that this station is a member of.
+    pub fn iter(&self) -> impl Iterator<Item = &Group> {
+        self.groups.iter().flatten()
+    }
+
+    /// Update `filtering` so that it passes the frames of all
+    /// joined groups, and no other multicast frames.
+    ///
+    /// If the multicast MAC addresses of all groups fit in the perfect
+    /// filters that are not used for other addresses, perfect filtering is
+    /// used. Otherwise, the groups are added to the hash table, which may
+    /// also pass frames of some other groups.
+    ///
+    /// Any multicast addresses that were previously added to the hash table
+    /// of `filtering` are removed.
+    pub fn apply(&self, filtering: &mut FrameFiltering) {
+        let mut free = [false; PERFECT_FILTER_COUNT];
+        for (free, filter) in free.iter_mut().zip(filtering.perfect_filters.iter_mut()) {
+            if filter.map(|f| f.address.is_multicast()).unwrap_or(true) {
+                *filter = None;
+                *free = true;
+            }
+        }
+
+        let mut macs = [None; N];
+        let mut distinct = 0;
+        for mac in self.iter().map(Group::mac) {
+            if !macs[..distinct].contains(&Some(mac)) {
+                macs[distinct] = Some(mac);
+                distinct += 1;
+            }
+        }
+
+        filtering.hash_table.clear();
+
+        if distinct <= free.iter().filter(|f| **f).count() {
+            let free_filters = filtering
+                .perfect_filters
+                .iter_mut()
+                .zip(free.iter())
+                .filter_map(|(filter, free)| free.then_some(filter));
+
+            for (filter, mac) in free_filters.zip(macs.iter().flatten()) {
+                *filter = Some(AddressFilter::new(*mac));
+            }
+            filtering.multicast = MulticastFiltering::Perfect;
+        } else {
+            for mac in macs.iter().flatten() {
+                filtering.hash_table.insert(mac);
+            }
+            filtering.multicast = MulticastFiltering::Hash;
+        }
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    const STATION: Mac = Mac::new([0x00, 0x00, 0xDE, 0xAD, 0xBE, 0xEF]);
+
+    #[test]
+    fn group_macs() {
+        assert_eq!(
+            Group::Ipv4([239, 129, 2, 3]).mac(),
+            Mac::new([0x01, 0x00, 0x5E, 0x01, 0x02, 0x03])
+        );
+
+        let mut ipv6 = [0u8; 16];
+        ipv6[0] = 0xFF;
+        ipv6[1] = 0x02;
+        ipv6[12..].copy_from_slice(&[0x00, 0x00, 0x00, 0xFB]);
+        assert_eq!(
+            Group::Ipv6(ipv6).mac(),
+            Mac::new([0x33, 0x33, 0x00, 0x00, 0x00, 0xFB])
+        );
+    }
+
+    #[test]
+    fn join_leave_and_apply() {
+        let mut groups = Groups::<4>::new();
+        assert_eq!(
+            groups.join(Group::Ipv4([10, 0, 0, 1])),
+            Err(JoinError::InvalidGroup)
+        );
+        assert_eq!(groups.join(Group::Ipv4([224, 0, 0, 251])), Ok(true));
+        assert_eq!(groups.join(Group::Ipv4([224, 0, 0, 251])), Ok(false));
+        // Maps to the same MAC address as 224.0.0.251
+        assert_eq!(groups.join(Group::Ipv4([225, 128, 0, 251])), Ok(true));
+
+        let mut filtering = FrameFiltering::new(STATION);
+        let unicast = AddressFilter::new(Mac::new([0x02, 0, 0, 0, 0, 1]));
+        filtering.perfect_filters[0] = Some(unicast);
+
+        groups.apply(&mut filtering);
+        assert_eq!(filtering.multicast, MulticastFiltering::Perfect);
+        assert_eq!(filtering.perfect_filters[0], Some(unicast));
+        assert_eq!(
+            filtering.perfect_filters[1],
+            Some(AddressFilter::new(Group::Ipv4([224, 0, 0, 251]).mac()))
+        );
+        assert_eq!(filtering.perfect_filters[2], None);
+
+        groups.join(Group::Ipv4([224, 0, 0, 1])).unwrap();
+        groups.join(Group::Ipv4([224, 0, 0, 2])).unwrap();
+        assert_eq!(
+            groups.join(Group::Ipv4([224, 0, 0, 3])),
+            Err(JoinError::Full)
+        );
+
+        groups.apply(&mut filtering);
+        assert_eq!(filtering.multicast, MulticastFiltering::Hash);
+        assert_eq!(filtering.perfect_filters, [Some(unicast), None, None]);
+        assert!(groups
+            .iter()
+            .all(|g| filtering.hash_table.contains(&g.mac())));
+
+        assert!(groups.leave(&Group::Ipv4([224, 0, 0, 1])));
+        assert!(!groups.leave(&Group::Ipv4([224, 0, 0, 1])));
+        assert_eq!(groups.len(), 3);
+    }
+}
@@ -0,0 +1,255 @@
+use ieee802_3_miim::{Miim, Phy};
+
+use super::{BarePhy, DetectedPhy, Dp83848, PhyPause, KSZ8081R, LAN8720A, LAN8742A};
+use crate::mac::{flow_control::PauseResolution, Duplex, Speed};
+
+/// Basic Mode Control Register
+const BMCR: u8 = 0;
+/// Restart auto-negotiation
+const BMCR_RESTART_AN: u16 = 1 << 9;
+/// Auto-negotiation enable
+const BMCR_AN_ENABLE: u16 = 1 << 12;
+
+/// Basic Mode Status Register
+const BMSR: u8 = 1;
+/// Auto-negotiation complete
+const BMSR_AN_COMPLETE: u16 = 1 << 5;
+
+/// Auto-Negotiation Advertisement Register
+const ANAR: u8 = 4;
+/// Auto-Negotiation Link Partner Ability Register
+const ANLPAR: u8 = 5;
+/// IEEE 802.3 selector
+const ANAR_SELECTOR_802_3: u16 = 0x01;
+const ANAR_SELECTOR_MASK: u16 = 0x1F;
+const ANAR_10BASE_T_HD: u16 = 1 << 5;
+const ANAR_10BASE_T_FD: u16 = 1 << 6;
+const ANAR_100BASE_TX_HD: u16 = 1 << 7;
+const ANAR_100BASE_TX_FD: u16 = 1 << 8;
+const ANAR_TECHNOLOGIES: u16 =
+    ANAR_10BASE_T_HD | ANAR_10BASE_T_FD | ANAR_100BASE_TX_HD | ANAR_100BASE_TX_FD;
+
+/// The link modes that are advertised during auto-negotiation.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Advertisement {
+    /// 10BASE-T half duplex.
+    pub base10t_half_duplex: bool,
+    /// 10BASE-T full duplex.
+    pub base10t_full_duplex: bool,
+    /// 100BASE-TX half duplex.
+    pub base100tx_half_duplex: bool,
+    /// 100BASE-TX full duplex.
+    pub base100tx_full_duplex: bool,
+}
+
+impl Advertisement {
+    /// All link modes that are supported by the MAC.
+    pub const ALL: Self = Self {
+        base10t_half_duplex: true,
+        base10t_full_duplex: true,
+        base100tx_half_duplex: true,
+        base100tx_full_duplex: true,
+    };
+
+    /// Only full duplex link modes.
+    pub const FULL_DUPLEX: Self = Self {
+        base10t_half_duplex: false,
+        base100tx_half_duplex: false,
+        ..Self::ALL
+    };
+
+    /// Only 10BASE-T full duplex, which is more robust on long or
+    /// noisy cables.
+    pub const BASE10T_FULL_DUPLEX: Self = Self {
+        base10t_half_duplex: false,
+        base10t_full_duplex: true,
+        base100tx_half_duplex: false,
+        base100tx_full_duplex: false,
+    };
+
+    /// Whether any link mode is advertised.
+    pub fn any(&self) -> bool {
+        self.bits() != 0
+    }
+
+    /// Resolve the link mode that is used when `local` and `partner`
+    /// are advertised, which is the highest priority mode that both
+    /// advertise, as specified in IEEE 802.3 Annex 28B.3.
+    pub fn resolve(local: Advertisement, partner: Advertisement) -> Option<Speed> {
+        let common = Self::from_bits(local.bits() & partner.bits());
+
+        if common.base100tx_full_duplex {
+            Some(Speed::FullDuplexBase100Tx)
+        } else if common.base100tx_half_duplex {
+            Some(Speed::HalfDuplexBase100Tx)
+        } else if common.base10t_full_duplex {
+            Some(Speed::FullDuplexBase10T)
+        } else if common.base10t_half_duplex {
+            Some(Speed::HalfDuplexBase10T)
+        } else {
+            None
+        }
+    }
+
+    fn bits(&self) -> u16 {
+        let mut bits = 0;
+        if self.base10t_half_duplex {
+            bits |= ANAR_10BASE_T_HD;
+        }
+        if self.base10t_full_duplex {
+            bits |= ANAR_10BASE_T_FD;
+        }
+        if self.base100tx_half_duplex {
+            bits |= ANAR_100BASE_TX_HD;
+        }
+        if self.base100tx_full_duplex {
+            bits |= ANAR_100BASE_TX_FD;
+        }
+        bits
+    }
+
+    fn from_bits(bits: u16) -> Self {
+        Self {
+            base10t_half_duplex: bits & ANAR_10BASE_T_HD != 0,
+            base10t_full_duplex: bits & ANAR_10BASE_T_FD != 0,
+            base100tx_half_duplex: bits & ANAR_100BASE_TX_HD != 0,
+            base100tx_full_duplex: bits & ANAR_100BASE_TX_FD != 0,
+        }
+    }
+}
+
+/// The configuration of a link, as resolved by auto-negotiation.
+///
+/// See [`PhyAdvertisement::link_configuration`] and
+/// [`EthernetMAC::apply_link`](crate::mac::EthernetMAC::apply_link).
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkConfiguration {
+    /// The speed and duplex mode of the link.
+    pub speed: Speed,
+    /// The directions in which pause frames are used. Pause frames
+    /// are never used on half duplex links.
+    pub pause: PauseResolution,
+}
+
+/// No link mode is advertised.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmptyAdvertisement;
+
+/// A PHY whose advertised link modes can be restricted.
+pub trait PhyAdvertisement<M: Miim>: Phy<M> {
+    /// Advertise only the link modes in `advertisement`, and restart
+    /// auto-negotiation.
+    ///
+    /// The advertised pause abilities are kept (see
+    /// [`PhyPause`](super::PhyPause)).
+    fn set_advertisement(
+        &mut self,
+        advertisement: Advertisement,
+    ) -> Result<(), EmptyAdvertisement> {
+        if !advertisement.any() {
+            return Err(EmptyAdvertisement);
+        }
+
+        let anar = self.read(ANAR) & !(ANAR_TECHNOLOGIES | ANAR_SELECTOR_MASK);
+        self.write(ANAR, anar | advertisement.bits() | ANAR_SELECTOR_802_3);
+
+        let bmcr = self.read(BMCR);
+        self.write(BMCR, bmcr | BMCR_AN_ENABLE | BMCR_RESTART_AN);
+        Ok(())
+    }
+
+    /// The link modes that are advertised by this PHY.
+    fn advertisement(&mut self) -> Advertisement {
+        Advertisement::from_bits(self.read(ANAR))
+    }
+
+    /// The link modes that were advertised by the link partner.
+    ///
+    /// This is only valid once auto-negotiation has completed.
+    fn partner_advertisement(&mut self) -> Advertisement {
+        Advertisement::from_bits(self.read(ANLPAR))
+    }
+
+    /// Resolve the configuration of the link from the abilities that were
+    /// advertised by this PHY and its link partner.
+    ///
+    /// Returns `None` if auto-negotiation has not completed, or if there
+    /// is no link mode that both advertised.
+    fn link_configuration(&mut self) -> Option<LinkConfiguration>
+    where
+        Self: PhyPause<M>,
+    {
+        if self.read(BMSR) & BMSR_AN_COMPLETE == 0 {
+            return None;
+        }
+
+        let speed = Advertisement::resolve(self.advertisement(), self.partner_advertisement())?;
+        let pause = match speed.duplex() {
+            Duplex::Full => self.resolve_pause(),
+            Duplex::Half => PauseResolution::default(),
+        };
+
+        Some(LinkConfiguration { speed, pause })
+    }
+}
+
+impl<M: Miim> PhyAdvertisement<M> for BarePhy<M> {}
+
+impl<M: Miim> PhyAdvertisement<M> for LAN8720A<M> {}
+
+impl<M: Miim> PhyAdvertisement<M> for LAN8742A<M> {}
+
+impl<M: Miim> PhyAdvertisement<M> for KSZ8081R<M> {}
+
+impl<M: Miim> PhyAdvertisement<M> for Dp83848<M> {}
+
+impl<M: Miim> PhyAdvertisement<M> for DetectedPhy<M> {}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn advertisement_bits() {
+        assert_eq!(Advertisement::ALL.bits(), 0x01E0);
+        assert_eq!(Advertisement::BASE10T_FULL_DUPLEX.bits(), 0x0040);
+        assert_eq!(
+            Advertisement::from_bits(0x0DE1),
+            Advertisement::ALL,
+            "pause and selector bits are ignored"
+        );
+        assert_eq!(
+            Advertisement::from_bits(Advertisement::FULL_DUPLEX.bits()),
+            Advertisement::FULL_DUPLEX
+        );
+    }
+
+    #[test]
+    fn resolution() {
+        let half_duplex = Advertisement {
+            base10t_full_duplex: false,
+            base100tx_full_duplex: false,
+            ..Advertisement::ALL
+        };
+
+        assert_eq!(
+            Advertisement::resolve(Advertisement::ALL, Advertisement::ALL),
+            Some(Speed::FullDuplexBase100Tx)
+        );
+        assert_eq!(
+            Advertisement::resolve(Advertisement::ALL, half_duplex),
+            Some(Speed::HalfDuplexBase100Tx)
+        );
+        assert_eq!(
+            Advertisement::resolve(Advertisement::ALL, Advertisement::BASE10T_FULL_DUPLEX),
+            Some(Speed::FullDuplexBase10T)
+        );
+        assert_eq!(
+            Advertisement::resolve(Advertisement::BASE10T_FULL_DUPLEX, half_duplex),
+            None
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use ieee802_3_miim::{Miim, Phy};
+
+use super::{KSZ8081R, LAN8720A, LAN8742A};
+
+/// The raw value of a PHY error counter.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCounter {
+    /// A counter that wraps around on overflow, and is not
+    /// cleared when it is read.
+    Wrapping(u16),
+    /// A counter that is cleared when it is read.
+    ClearOnRead(u16),
+}
+
+impl ErrorCounter {
+    /// The amount of errors that occured since `previous` was read.
+    ///
+    /// `previous` is updated to the current value of a [`ErrorCounter::Wrapping`]
+    /// counter. If `previous` is `None`, the current value is only recorded.
+    pub(crate) fn delta(self, previous: &mut Option<u16>) -> u32 {
+        match self {
+            ErrorCounter::Wrapping(value) => {
+                let delta = previous.map(|p| value.wrapping_sub(p)).unwrap_or(0);
+                *previous = Some(value);
+                delta as u32
+            }
+            ErrorCounter::ClearOnRead(value) => value as u32,
+        }
+    }
+}
+
+/// A PHY that exposes receive error counters.
+pub trait PhyErrorCounters<M: Miim>: Phy<M> {
+    /// Read the receive symbol error counter of this PHY.
+    fn symbol_error_counter(&mut self) -> ErrorCounter;
+}
+
+/// Symbol Error Counter Register
+const LAN87XXA_SECR: u8 = 26;
+
+impl<M: Miim> PhyErrorCounters<M> for LAN8720A<M> {
+    fn symbol_error_counter(&mut self) -> ErrorCounter {
+        ErrorCounter::Wrapping(self.read(LAN87XXA_SECR))
+    }
+}
+
+impl<M: Miim> PhyErrorCounters<M> for LAN8742A<M> {
+    fn symbol_error_counter(&mut self) -> ErrorCounter {
+        ErrorCounter::Wrapping(self.read(LAN87XXA_SECR))
+    }
+}
+
+impl<M: Miim> PhyErrorCounters<M> for KSZ8081R<M> {
+    fn symbol_error_counter(&mut self) -> ErrorCounter {
+        /// RXER Counter
+        const RXER_COUNTER: u8 = 0x15;
+
+        ErrorCounter::ClearOnRead(self.read(RXER_COUNTER))
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wrapping_counter_delta() {
+        let mut previous = None;
+
+        assert_eq!(ErrorCounter::Wrapping(10).delta(&mut previous), 0);
+        assert_eq!(ErrorCounter::Wrapping(15).delta(&mut previous), 5);
+        assert_eq!(ErrorCounter::Wrapping(2).delta(&mut previous), 65523);
+        assert_eq!(previous, Some(2));
+    }
+}
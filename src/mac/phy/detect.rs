@@ -0,0 +1,194 @@
+//! Detection of the PHYs on the MDIO bus.
+//!
+//! Boards strap the address of their PHY differently, so instead of assuming
+//! a fixed address, [`scan`] probes all 32 addresses for a PHY, and [`detect`]
+//! creates a driver for the first PHY that is found.
+
+use ieee802_3_miim::{AutoNegotiationAdvertisement, Miim, Pause, Phy};
+
+use super::{BarePhy, Dp83848, DP83848_ID, KSZ8081R, LAN8720A, LAN8742A};
+
+/// PHY Identifier 1
+const PHYIDR1: u8 = 2;
+/// PHY Identifier 2
+const PHYIDR2: u8 = 3;
+
+/// The amount of PHY addresses on an MDIO bus.
+pub const PHY_ADDRESS_COUNT: u8 = 32;
+
+/// The identifier of a PHY, read from its `PHYIDR1` and `PHYIDR2` registers.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhyIdentifier(u32);
+
+impl PhyIdentifier {
+    /// Create an identifier from the values of the identifier registers.
+    pub const fn new(id1: u16, id2: u16) -> Self {
+        Self(((id1 as u32) << 16) | id2 as u32)
+    }
+
+    /// The raw identifier, with `PHYIDR1` in the upper 16 bits.
+    pub const fn raw(&self) -> u32 {
+        self.0
+    }
+
+    /// Bits 3 to 24 of the Organizationally Unique Identifier
+    /// of the vendor.
+    pub const fn oui(&self) -> u32 {
+        self.0 >> 10
+    }
+
+    /// The model number of the PHY.
+    pub const fn model(&self) -> u8 {
+        ((self.0 >> 4) & 0x3F) as u8
+    }
+
+    /// The revision number of the PHY.
+    pub const fn revision(&self) -> u8 {
+        (self.0 & 0xF) as u8
+    }
+
+    /// The identifier without the revision number.
+    pub const fn without_revision(&self) -> u32 {
+        self.0 & !0xF
+    }
+
+    /// Whether this identifier was read from an address at which a PHY
+    /// responded. The data line of the MDIO bus is pulled up, so an
+    /// address without a PHY reads as all ones.
+    fn is_present(&self) -> bool {
+        self.0 != 0xFFFF_FFFF && self.0 != 0
+    }
+}
+
+/// Probe all addresses on the MDIO bus, and return the address and the
+/// identifier of every PHY that responds, in order of address.
+pub fn scan<M: Miim>(miim: &mut M) -> impl Iterator<Item = (u8, PhyIdentifier)> + '_ {
+    (0..PHY_ADDRESS_COUNT).filter_map(move |address| {
+        let id1 = miim.read(address, PHYIDR1);
+        let id2 = miim.read(address, PHYIDR2);
+        let identifier = PhyIdentifier::new(id1, id2);
+        identifier.is_present().then_some((address, identifier))
+    })
+}
+
+/// A PHY that was found by [`detect`].
+pub enum DetectedPhy<M: Miim> {
+    /// A LAN8720A.
+    LAN8720A(LAN8720A<M>),
+    /// A LAN8742A.
+    LAN8742A(LAN8742A<M>),
+    /// A KSZ8081 or KSZ8091.
+    KSZ8081R(KSZ8081R<M>),
+    /// A DP83848.
+    DP83848(Dp83848<M>),
+    /// A PHY without a dedicated driver, which is driven through
+    /// its standard registers only.
+    Unknown(BarePhy<M>, PhyIdentifier),
+}
+
+/// Create a driver for the PHY at the lowest address on the MDIO bus.
+///
+/// Returns `miim` if no PHY responds.
+pub fn detect<M: Miim>(mut miim: M) -> Result<DetectedPhy<M>, M> {
+    let found = scan(&mut miim).next();
+    let (address, identifier) = match found {
+        Some(phy) => phy,
+        None => return Err(miim),
+    };
+
+    Ok(DetectedPhy::new(miim, address, identifier))
+}
+
+impl<M: Miim> DetectedPhy<M> {
+    /// Create a driver for the PHY at `address`, whose identifier
+    /// is `identifier`.
+    pub fn new(miim: M, address: u8, identifier: PhyIdentifier) -> Self {
+        match identifier.without_revision() {
+            0x0007_C0F0 => Self::LAN8720A(LAN8720A::new(miim, address)),
+            0x0007_C130 => Self::LAN8742A(LAN8742A::new(miim, address)),
+            0x0022_1560 => Self::KSZ8081R(KSZ8081R::new(miim, address)),
+            DP83848_ID => Self::DP83848(Dp83848::new(miim, address)),
+            _ => Self::Unknown(BarePhy::new(miim, address, Pause::NoPause), identifier),
+        }
+    }
+
+    /// The name of the detected PHY.
+    pub const fn name(&self) -> &'static str {
+        match self {
+            DetectedPhy::LAN8720A(_) => "LAN8720A",
+            DetectedPhy::LAN8742A(_) => "LAN8742A",
+            DetectedPhy::KSZ8081R(_) => "KSZ8081R",
+            DetectedPhy::DP83848(_) => "DP83848",
+            DetectedPhy::Unknown(..) => "unknown",
+        }
+    }
+
+    /// Initialize the PHY, and advertise all of its capabilities.
+    ///
+    /// The LAN87xxA and DP83848 are reset first.
+    pub fn phy_init(&mut self) {
+        match self {
+            DetectedPhy::LAN8720A(phy) => phy.phy_init(),
+            DetectedPhy::LAN8742A(phy) => phy.phy_init(),
+            DetectedPhy::KSZ8081R(phy) => {
+                phy.set_autonegotiation_advertisement(phy.best_supported_advertisement());
+            }
+            DetectedPhy::DP83848(phy) => phy.phy_init(),
+            DetectedPhy::Unknown(phy, _) => {
+                phy.set_autonegotiation_advertisement(phy.best_supported_advertisement());
+            }
+        }
+    }
+}
+
+impl<M: Miim> Phy<M> for DetectedPhy<M> {
+    fn best_supported_advertisement(&self) -> AutoNegotiationAdvertisement {
+        match self {
+            DetectedPhy::LAN8720A(phy) => phy.best_supported_advertisement(),
+            DetectedPhy::LAN8742A(phy) => phy.best_supported_advertisement(),
+            DetectedPhy::KSZ8081R(phy) => phy.best_supported_advertisement(),
+            DetectedPhy::DP83848(phy) => phy.best_supported_advertisement(),
+            DetectedPhy::Unknown(phy, _) => phy.best_supported_advertisement(),
+        }
+    }
+
+    fn get_miim(&mut self) -> &mut M {
+        match self {
+            DetectedPhy::LAN8720A(phy) => phy.get_miim(),
+            DetectedPhy::LAN8742A(phy) => phy.get_miim(),
+            DetectedPhy::KSZ8081R(phy) => phy.get_miim(),
+            DetectedPhy::DP83848(phy) => phy.get_miim(),
+            DetectedPhy::Unknown(phy, _) => phy.get_miim(),
+        }
+    }
+
+    fn get_phy_addr(&self) -> u8 {
+        match self {
+            DetectedPhy::LAN8720A(phy) => phy.get_phy_addr(),
+            DetectedPhy::LAN8742A(phy) => phy.get_phy_addr(),
+            DetectedPhy::KSZ8081R(phy) => phy.get_phy_addr(),
+            DetectedPhy::DP83848(phy) => phy.get_phy_addr(),
+            DetectedPhy::Unknown(phy, _) => phy.get_phy_addr(),
+        }
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn identifier_fields() {
+        // LAN8742A, revision 1
+        let identifier = PhyIdentifier::new(0x0007, 0xC131);
+        assert_eq!(identifier.oui(), 0x1F0);
+        assert_eq!(identifier.model(), 0x13);
+        assert_eq!(identifier.revision(), 1);
+        assert_eq!(identifier.without_revision(), 0x0007_C130);
+
+        assert!(identifier.is_present());
+        assert!(!PhyIdentifier::new(0xFFFF, 0xFFFF).is_present());
+        assert!(!PhyIdentifier::new(0, 0).is_present());
+    }
+}
@@ -0,0 +1,311 @@
+//! A driver for the Texas Instruments DP83848 PHY.
+//!
+//! The DP83848 is not supported by [`ieee802_3_miim`], so it is built on
+//! top of a [`BarePhy`], and adds the vendor-specific PHY status (`PHYSTS`),
+//! interrupt (`MICR`/`MISR`) and RMII configuration (`RBR`) registers.
+
+use ieee802_3_miim::{phy::PhySpeed, AutoNegotiationAdvertisement, Miim, Pause, Phy};
+
+use crate::mac::Speed;
+
+use super::{
+    BarePhy, ErrorCounter, PhyErrorCounters, PhyInterruptControl, PhyInterrupts, PhyPause,
+    PhyTestMode,
+};
+
+/// The OUI and model number of the DP83848, as reported in `PHYIDR1`
+/// and `PHYIDR2`, without the revision number.
+pub const DP83848_ID: u32 = 0x2000_5C90;
+
+/// Basic Mode Control Register
+const BMCR: u8 = 0x00;
+/// Reset
+const BMCR_RESET: u16 = 1 << 15;
+
+/// PHY Status Register
+const PHYSTS: u8 = 0x10;
+const PHYSTS_LINK: u16 = 1 << 0;
+const PHYSTS_SPEED_10: u16 = 1 << 1;
+const PHYSTS_DUPLEX: u16 = 1 << 2;
+const PHYSTS_LOOPBACK: u16 = 1 << 3;
+const PHYSTS_AUTONEG_COMPLETE: u16 = 1 << 4;
+const PHYSTS_JABBER: u16 = 1 << 5;
+const PHYSTS_REMOTE_FAULT: u16 = 1 << 6;
+const PHYSTS_DESCRAMBLER_LOCK: u16 = 1 << 9;
+const PHYSTS_SIGNAL_DETECT: u16 = 1 << 10;
+const PHYSTS_POLARITY_INVERTED: u16 = 1 << 12;
+const PHYSTS_MDIX: u16 = 1 << 14;
+
+/// MII Interrupt Control Register
+const MICR: u8 = 0x11;
+/// Interrupt enable
+const MICR_INTEN: u16 = 1 << 0;
+/// Interrupt output enable
+const MICR_INT_OE: u16 = 1 << 1;
+
+/// MII Interrupt Status and Misc. Control Register
+const MISR: u8 = 0x12;
+/// Auto-negotiation complete
+const MISR_ANC: u16 = 1 << 2;
+/// Link status changed
+const MISR_LINK: u16 = 1 << 5;
+/// Energy detect
+const MISR_ED: u16 = 1 << 6;
+/// The offset of the status bits from their enable bits
+const MISR_STATUS_SHIFT: u16 = 8;
+
+/// Receive Error Counter Register
+const RECR: u8 = 0x15;
+
+/// RMII and Bypass Register
+const RBR: u8 = 0x17;
+/// Reduced MII mode
+const RBR_RMII_MODE: u16 = 1 << 5;
+/// RMII revision 1.0 (instead of 1.2) mode
+const RBR_RMII_REV1_0: u16 = 1 << 4;
+/// RX elastic buffer overflow
+const RBR_RX_OVF_STS: u16 = 1 << 3;
+/// RX elastic buffer underflow
+const RBR_RX_UNF_STS: u16 = 1 << 2;
+
+/// The vendor-specific status of a [`Dp83848`], read from its
+/// PHY status register.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dp83848Status {
+    /// A valid link is established.
+    pub link_up: bool,
+    /// The speed and duplex mode of the link, if it is up.
+    pub speed: Option<Speed>,
+    /// Loopback is enabled.
+    pub loopback: bool,
+    /// Auto-negotiation has completed.
+    pub autonegotiation_complete: bool,
+    /// Jabber was detected (10 Mbit/s only).
+    pub jabber: bool,
+    /// The link partner signalled a remote fault.
+    pub remote_fault: bool,
+    /// The 100BASE-TX descrambler is locked.
+    pub descrambler_lock: bool,
+    /// A 100BASE-TX signal is detected.
+    pub signal_detect: bool,
+    /// Inverted polarity was detected (10 Mbit/s only).
+    pub polarity_inverted: bool,
+    /// The MDI pairs are swapped (MDI-X).
+    pub mdix: bool,
+}
+
+impl Dp83848Status {
+    fn from_bits(bits: u16) -> Self {
+        let link_up = bits & PHYSTS_LINK != 0;
+        let speed = if link_up {
+            Some(
+                match (bits & PHYSTS_SPEED_10 != 0, bits & PHYSTS_DUPLEX != 0) {
+                    (true, false) => Speed::HalfDuplexBase10T,
+                    (true, true) => Speed::FullDuplexBase10T,
+                    (false, false) => Speed::HalfDuplexBase100Tx,
+                    (false, true) => Speed::FullDuplexBase100Tx,
+                },
+            )
+        } else {
+            None
+        };
+
+        Self {
+            link_up,
+            speed,
+            loopback: bits & PHYSTS_LOOPBACK != 0,
+            autonegotiation_complete: bits & PHYSTS_AUTONEG_COMPLETE != 0,
+            jabber: bits & PHYSTS_JABBER != 0,
+            remote_fault: bits & PHYSTS_REMOTE_FAULT != 0,
+            descrambler_lock: bits & PHYSTS_DESCRAMBLER_LOCK != 0,
+            signal_detect: bits & PHYSTS_SIGNAL_DETECT != 0,
+            polarity_inverted: bits & PHYSTS_POLARITY_INVERTED != 0,
+            mdix: bits & PHYSTS_MDIX != 0,
+        }
+    }
+}
+
+/// The RMII configuration of a [`Dp83848`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dp83848Interface {
+    /// MII.
+    Mii,
+    /// RMII, revision 1.2.
+    Rmii,
+    /// RMII, revision 1.0, in which `CRS_DV` does not toggle at
+    /// the end of a frame.
+    RmiiRev1_0,
+}
+
+/// A Texas Instruments DP83848 PHY.
+pub struct Dp83848<M: Miim> {
+    phy: BarePhy<M>,
+}
+
+impl<M: Miim> Dp83848<M> {
+    /// Create a DP83848 PHY at `phy_addr`.
+    pub fn new(miim: M, phy_addr: u8) -> Self {
+        Self {
+            phy: BarePhy::new(miim, phy_addr, Pause::NoPause),
+        }
+    }
+
+    /// Release the underlying MIIM.
+    pub fn release(self) -> M {
+        self.phy.release()
+    }
+
+    /// Reset the PHY, and advertise all of its capabilities.
+    pub fn phy_init(&mut self) {
+        let bmcr = self.read(BMCR);
+        self.write(BMCR, bmcr | BMCR_RESET);
+        while self.read(BMCR) & BMCR_RESET != 0 {}
+
+        self.set_autonegotiation_advertisement(self.best_supported_advertisement());
+    }
+
+    /// Read the vendor-specific PHY status.
+    pub fn status(&mut self) -> Dp83848Status {
+        Dp83848Status::from_bits(self.read(PHYSTS))
+    }
+
+    /// The speed and duplex mode of the link, or `None` if the link is down.
+    pub fn link_speed(&mut self) -> Option<PhySpeed> {
+        self.status().speed.map(|speed| match speed {
+            Speed::HalfDuplexBase10T => PhySpeed::HalfDuplexBase10T,
+            Speed::FullDuplexBase10T => PhySpeed::FullDuplexBase10T,
+            Speed::HalfDuplexBase100Tx => PhySpeed::HalfDuplexBase100Tx,
+            Speed::FullDuplexBase100Tx => PhySpeed::FullDuplexBase100Tx,
+        })
+    }
+
+    /// The MAC interface that is used, which is selected by the
+    /// `RMII_MODE` strap at reset.
+    pub fn interface(&mut self) -> Dp83848Interface {
+        let rbr = self.read(RBR);
+        match (rbr & RBR_RMII_MODE != 0, rbr & RBR_RMII_REV1_0 != 0) {
+            (false, _) => Dp83848Interface::Mii,
+            (true, false) => Dp83848Interface::Rmii,
+            (true, true) => Dp83848Interface::RmiiRev1_0,
+        }
+    }
+
+    /// Select the MAC interface, overriding the `RMII_MODE` strap.
+    pub fn set_interface(&mut self, interface: Dp83848Interface) {
+        let mut rbr = self.read(RBR) & !(RBR_RMII_MODE | RBR_RMII_REV1_0);
+        match interface {
+            Dp83848Interface::Mii => {}
+            Dp83848Interface::Rmii => rbr |= RBR_RMII_MODE,
+            Dp83848Interface::RmiiRev1_0 => rbr |= RBR_RMII_MODE | RBR_RMII_REV1_0,
+        }
+        self.write(RBR, rbr);
+    }
+
+    /// Whether the RMII elastic buffer overflowed or underflowed, which
+    /// indicates that `REF_CLK` deviates too far from the recovered clock.
+    pub fn elastic_buffer_error(&mut self) -> bool {
+        self.read(RBR) & (RBR_RX_OVF_STS | RBR_RX_UNF_STS) != 0
+    }
+}
+
+impl<M: Miim> Phy<M> for Dp83848<M> {
+    fn best_supported_advertisement(&self) -> AutoNegotiationAdvertisement {
+        self.phy.best_supported_advertisement()
+    }
+
+    fn get_miim(&mut self) -> &mut M {
+        self.phy.get_miim()
+    }
+
+    fn get_phy_addr(&self) -> u8 {
+        self.phy.get_phy_addr()
+    }
+}
+
+impl PhyInterrupts {
+    fn to_dp83848(self) -> u16 {
+        let mut bits = 0;
+        if self.link_down {
+            bits |= MISR_LINK;
+        }
+        if self.autonegotiation_complete {
+            bits |= MISR_ANC;
+        }
+        if self.energy_on {
+            bits |= MISR_ED;
+        }
+        bits
+    }
+
+    fn from_dp83848(bits: u16) -> Self {
+        let status = bits >> MISR_STATUS_SHIFT;
+        Self {
+            link_down: status & MISR_LINK != 0,
+            autonegotiation_complete: status & MISR_ANC != 0,
+            energy_on: status & MISR_ED != 0,
+            ..Self::NONE
+        }
+    }
+}
+
+/// The DP83848 only has a single interrupt source for link status
+/// changes, which is reported as [`PhyInterrupts::link_down`] for both
+/// directions. Remote faults and auto-negotiation pages are not reported.
+impl<M: Miim> PhyInterruptControl<M> for Dp83848<M> {
+    fn enable_phy_interrupts(&mut self, interrupts: PhyInterrupts) {
+        let misr = interrupts.to_dp83848();
+        self.write(MISR, misr);
+
+        let micr = if misr != 0 {
+            MICR_INTEN | MICR_INT_OE
+        } else {
+            0
+        };
+        self.write(MICR, micr);
+    }
+
+    fn take_phy_interrupts(&mut self) -> PhyInterrupts {
+        PhyInterrupts::from_dp83848(self.read(MISR))
+    }
+}
+
+impl<M: Miim> PhyErrorCounters<M> for Dp83848<M> {
+    fn symbol_error_counter(&mut self) -> ErrorCounter {
+        ErrorCounter::ClearOnRead(self.read(RECR) & 0xFF)
+    }
+}
+
+impl<M: Miim> PhyPause<M> for Dp83848<M> {}
+
+impl<M: Miim> PhyTestMode<M> for Dp83848<M> {}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn status_bits() {
+        let status = Dp83848Status::from_bits(0x0615);
+        assert!(status.link_up && status.autonegotiation_complete);
+        assert_eq!(status.speed, Some(Speed::FullDuplexBase100Tx));
+        assert!(status.descrambler_lock && status.signal_detect);
+
+        let status = Dp83848Status::from_bits(PHYSTS_SPEED_10);
+        assert_eq!(status.speed, None);
+    }
+
+    #[test]
+    fn interrupt_bits() {
+        assert_eq!(PhyInterrupts::LINK.to_dp83848(), MISR_LINK | MISR_ANC);
+        assert_eq!(
+            PhyInterrupts::from_dp83848((MISR_LINK | MISR_ED) << MISR_STATUS_SHIFT | MISR_LINK),
+            PhyInterrupts {
+                link_down: true,
+                energy_on: true,
+                ..PhyInterrupts::NONE
+            }
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use ieee802_3_miim::{Miim, Phy};
+
+use super::{KSZ8081R, LAN8720A, LAN8742A};
+
+/// Interrupt Source Flag Register
+const LAN87XXA_ISFR: u8 = 29;
+/// Interrupt Mask Register
+const LAN87XXA_IMR: u8 = 30;
+
+/// Auto-negotiation page received
+const LAN87XXA_INT1: u16 = 1 << 1;
+/// Parallel detection fault
+const LAN87XXA_INT2: u16 = 1 << 2;
+/// Auto-negotiation LP acknowledge
+const LAN87XXA_INT3: u16 = 1 << 3;
+/// Link down
+const LAN87XXA_INT4: u16 = 1 << 4;
+/// Remote fault detected
+const LAN87XXA_INT5: u16 = 1 << 5;
+/// Auto-negotiation complete
+const LAN87XXA_INT6: u16 = 1 << 6;
+/// ENERGYON generated
+const LAN87XXA_INT7: u16 = 1 << 7;
+
+/// Interrupt Control/Status Register
+const KSZ80XX_ICSR: u8 = 0x1B;
+/// The offset of the enable bits from their status bits
+const KSZ80XX_ICSR_ENABLE_SHIFT: u16 = 8;
+
+/// Link up
+const KSZ80XX_LINK_UP: u16 = 1 << 0;
+/// Remote fault
+const KSZ80XX_REMOTE_FAULT: u16 = 1 << 1;
+/// Link down
+const KSZ80XX_LINK_DOWN: u16 = 1 << 2;
+/// Link partner acknowledge
+const KSZ80XX_LP_ACKNOWLEDGE: u16 = 1 << 3;
+/// Parallel detect fault
+const KSZ80XX_PARALLEL_DETECT_FAULT: u16 = 1 << 4;
+/// Page received
+const KSZ80XX_PAGE_RECEIVED: u16 = 1 << 5;
+
+/// The interrupt sources of a PHY.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhyInterrupts {
+    /// The link went down.
+    pub link_down: bool,
+    /// Auto-negotiation completed, which usually means that the link
+    /// is about to go up.
+    pub autonegotiation_complete: bool,
+    /// The link partner signalled a remote fault.
+    pub remote_fault: bool,
+    /// The PHY detected energy on the line, for instance because a cable
+    /// was plugged in.
+    pub energy_on: bool,
+    /// A parallel detection fault occured during auto-negotiation.
+    pub parallel_detection_fault: bool,
+    /// An auto-negotiation page was received.
+    pub autonegotiation_page_received: bool,
+    /// The link partner acknowledged an auto-negotiation page.
+    pub autonegotiation_lp_acknowledge: bool,
+}
+
+impl PhyInterrupts {
+    /// No interrupt sources.
+    pub const NONE: Self = Self {
+        link_down: false,
+        autonegotiation_complete: false,
+        remote_fault: false,
+        energy_on: false,
+        parallel_detection_fault: false,
+        autonegotiation_page_received: false,
+        autonegotiation_lp_acknowledge: false,
+    };
+
+    /// The interrupt sources that signal a change of the link state.
+    pub const LINK: Self = Self {
+        link_down: true,
+        autonegotiation_complete: true,
+        remote_fault: true,
+        ..Self::NONE
+    };
+
+    /// Whether any interrupt source is set.
+    pub fn any(&self) -> bool {
+        *self != Self::NONE
+    }
+
+    fn to_lan87xxa(self) -> u16 {
+        let mut bits = 0;
+        let mut set = |is_set: bool, bit: u16| {
+            if is_set {
+                bits |= bit;
+            }
+        };
+        set(self.autonegotiation_page_received, LAN87XXA_INT1);
+        set(self.parallel_detection_fault, LAN87XXA_INT2);
+        set(self.autonegotiation_lp_acknowledge, LAN87XXA_INT3);
+        set(self.link_down, LAN87XXA_INT4);
+        set(self.remote_fault, LAN87XXA_INT5);
+        set(self.autonegotiation_complete, LAN87XXA_INT6);
+        set(self.energy_on, LAN87XXA_INT7);
+        bits
+    }
+
+    fn to_ksz80xx(self) -> u16 {
+        let mut bits = 0;
+        let mut set = |is_set: bool, bit: u16| {
+            if is_set {
+                bits |= bit;
+            }
+        };
+        set(self.autonegotiation_complete, KSZ80XX_LINK_UP);
+        set(self.remote_fault, KSZ80XX_REMOTE_FAULT);
+        set(self.link_down, KSZ80XX_LINK_DOWN);
+        set(self.autonegotiation_lp_acknowledge, KSZ80XX_LP_ACKNOWLEDGE);
+        set(self.parallel_detection_fault, KSZ80XX_PARALLEL_DETECT_FAULT);
+        set(self.autonegotiation_page_received, KSZ80XX_PAGE_RECEIVED);
+        bits << KSZ80XX_ICSR_ENABLE_SHIFT
+    }
+
+    fn from_ksz80xx(bits: u16) -> Self {
+        Self {
+            autonegotiation_complete: bits & KSZ80XX_LINK_UP != 0,
+            remote_fault: bits & KSZ80XX_REMOTE_FAULT != 0,
+            link_down: bits & KSZ80XX_LINK_DOWN != 0,
+            autonegotiation_lp_acknowledge: bits & KSZ80XX_LP_ACKNOWLEDGE != 0,
+            parallel_detection_fault: bits & KSZ80XX_PARALLEL_DETECT_FAULT != 0,
+            autonegotiation_page_received: bits & KSZ80XX_PAGE_RECEIVED != 0,
+            energy_on: false,
+        }
+    }
+
+    fn from_lan87xxa(bits: u16) -> Self {
+        Self {
+            autonegotiation_page_received: bits & LAN87XXA_INT1 != 0,
+            parallel_detection_fault: bits & LAN87XXA_INT2 != 0,
+            autonegotiation_lp_acknowledge: bits & LAN87XXA_INT3 != 0,
+            link_down: bits & LAN87XXA_INT4 != 0,
+            remote_fault: bits & LAN87XXA_INT5 != 0,
+            autonegotiation_complete: bits & LAN87XXA_INT6 != 0,
+            energy_on: bits & LAN87XXA_INT7 != 0,
+        }
+    }
+}
+
+/// A PHY with an interrupt output (`nINT`), whose sources are configured
+/// through vendor-specific registers.
+///
+/// Typically, the interrupt pin of the PHY is connected to an EXTI line,
+/// whose handler reads (and thereby clears) the interrupt sources with
+/// [`PhyInterruptControl::take_phy_interrupts`], or calls
+/// [`on_phy_interrupt`](super::on_phy_interrupt) so that the PHY is polled
+/// by [`LinkMonitor::poll_interrupt`](super::LinkMonitor::poll_interrupt).
+pub trait PhyInterruptControl<M: Miim>: Phy<M> {
+    /// Enable the interrupt sources that are set in `interrupts`, and
+    /// disable all others.
+    fn enable_phy_interrupts(&mut self, interrupts: PhyInterrupts);
+
+    /// Read and clear the interrupt sources that are pending.
+    fn take_phy_interrupts(&mut self) -> PhyInterrupts;
+
+    /// Enable the interrupt sources that signal a change of the link
+    /// state ([`PhyInterrupts::LINK`]), and disable all others, and clear
+    /// the interrupt sources that are already pending.
+    fn enable_link_interrupts(&mut self) {
+        self.enable_phy_interrupts(PhyInterrupts::LINK);
+        self.take_phy_interrupts();
+    }
+}
+
+impl<M: Miim> PhyInterruptControl<M> for LAN8742A<M> {
+    fn enable_phy_interrupts(&mut self, interrupts: PhyInterrupts) {
+        self.write(LAN87XXA_IMR, interrupts.to_lan87xxa());
+    }
+
+    fn take_phy_interrupts(&mut self) -> PhyInterrupts {
+        PhyInterrupts::from_lan87xxa(self.read(LAN87XXA_ISFR))
+    }
+}
+
+/// The `nINT` output of the LAN8720A shares its pin with `REFCLKO`, so it
+/// is only available if the PHY is clocked by an external 50 MHz clock.
+impl<M: Miim> PhyInterruptControl<M> for LAN8720A<M> {
+    fn enable_phy_interrupts(&mut self, interrupts: PhyInterrupts) {
+        self.write(LAN87XXA_IMR, interrupts.to_lan87xxa());
+    }
+
+    fn take_phy_interrupts(&mut self) -> PhyInterrupts {
+        PhyInterrupts::from_lan87xxa(self.read(LAN87XXA_ISFR))
+    }
+}
+
+/// The KSZ8081 and KSZ8091 have no auto-negotiation complete interrupt,
+/// so [`PhyInterrupts::autonegotiation_complete`] selects their link up
+/// interrupt instead. [`PhyInterrupts::energy_on`] is not supported.
+impl<M: Miim> PhyInterruptControl<M> for KSZ8081R<M> {
+    fn enable_phy_interrupts(&mut self, interrupts: PhyInterrupts) {
+        self.write(KSZ80XX_ICSR, interrupts.to_ksz80xx());
+    }
+
+    fn take_phy_interrupts(&mut self) -> PhyInterrupts {
+        PhyInterrupts::from_ksz80xx(self.read(KSZ80XX_ICSR))
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lan87xxa_bits() {
+        assert_eq!(PhyInterrupts::LINK.to_lan87xxa(), 0b0111_0000);
+        assert_eq!(
+            PhyInterrupts::from_lan87xxa(0b0111_0000 | 1 << 8),
+            PhyInterrupts::LINK
+        );
+        assert!(!PhyInterrupts::from_lan87xxa(1 << 8).any());
+    }
+
+    #[test]
+    fn ksz80xx_bits() {
+        assert_eq!(PhyInterrupts::LINK.to_ksz80xx(), 0b0111_0000_0000);
+        assert_eq!(
+            PhyInterrupts::from_ksz80xx(0b0111_0000_0000 | 0b0111),
+            PhyInterrupts::LINK
+        );
+    }
+}
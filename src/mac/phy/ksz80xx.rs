@@ -0,0 +1,127 @@
+//! Configuration of the Microchip (Micrel) KSZ8081 and KSZ8091 PHYs.
+//!
+//! Both PHYs report the same PHY identifier, and share their vendor-specific
+//! registers, so they are both driven by [`KSZ8081R`].
+//!
+//! In RMII mode, the PHY is either clocked by a 25 MHz crystal, from which it
+//! generates the 50 MHz `REF_CLK` for the MAC, or by an external 50 MHz clock
+//! that also drives the `REF_CLK` input of the MAC. Bit 7 of the PHY Control 2
+//! register selects the clock mode, but its meaning is inverted between the
+//! variants: it selects 50 MHz on the `RNA` variants, and 25 MHz on the `RND`
+//! variants. As the bit resets to 0 on both, an `RNA` defaults to a 25 MHz
+//! crystal, and an `RND` to an external 50 MHz clock. The variant can not be
+//! read from the PHY, so it must be passed to [`PhyKsz80xx::set_rmii_clock`].
+
+use ieee802_3_miim::{Miim, Phy};
+
+use super::KSZ8081R;
+
+/// PHY Control 2
+const PHY_CONTROL_2: u8 = 0x1F;
+/// RMII reference clock select, whose meaning depends on the variant
+const PHY_CONTROL_2_RMII_CLOCK: u16 = 1 << 7;
+/// Interrupt level (active high)
+const PHY_CONTROL_2_INT_ACTIVE_HIGH: u16 = 1 << 9;
+
+/// The clock that is connected to the `XI` input of the PHY in RMII mode.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RmiiClock {
+    /// A 25 MHz crystal or clock, from which the PHY generates
+    /// the 50 MHz reference clock on its `REF_CLK` output.
+    Crystal25MHz,
+    /// An external 50 MHz reference clock, which is shared with the MAC.
+    External50MHz,
+}
+
+/// The variant of a KSZ8081 or KSZ8091, which determines the meaning
+/// of the RMII clock select bit.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ksz80xxVariant {
+    /// The `RNA` variants, such as the KSZ8081RNA.
+    Rna,
+    /// The `RND` variants, such as the KSZ8081RND.
+    Rnd,
+}
+
+impl Ksz80xxVariant {
+    /// Whether the RMII clock select bit is set for `clock`.
+    fn clock_select_bit(&self, clock: RmiiClock) -> bool {
+        match self {
+            Ksz80xxVariant::Rna => clock == RmiiClock::External50MHz,
+            Ksz80xxVariant::Rnd => clock == RmiiClock::Crystal25MHz,
+        }
+    }
+
+    /// The clock that is selected by the RMII clock select `bit`.
+    fn clock(&self, bit: bool) -> RmiiClock {
+        if self.clock_select_bit(RmiiClock::External50MHz) == bit {
+            RmiiClock::External50MHz
+        } else {
+            RmiiClock::Crystal25MHz
+        }
+    }
+}
+
+/// Vendor-specific configuration of the KSZ8081 and KSZ8091.
+pub trait PhyKsz80xx<M: Miim>: Phy<M> {
+    /// The RMII clock mode that the PHY, which is of the given
+    /// `variant`, is configured for.
+    fn rmii_clock(&mut self, variant: Ksz80xxVariant) -> RmiiClock {
+        variant.clock(self.read(PHY_CONTROL_2) & PHY_CONTROL_2_RMII_CLOCK != 0)
+    }
+
+    /// Select the RMII clock mode of the PHY, which is of the given `variant`.
+    ///
+    /// The mode must match the clock that is connected to the PHY, otherwise
+    /// the PHY (and the MAC, if it uses the `REF_CLK` output) is clocked at the
+    /// wrong frequency.
+    fn set_rmii_clock(&mut self, variant: Ksz80xxVariant, clock: RmiiClock) {
+        let value = self.read(PHY_CONTROL_2) & !PHY_CONTROL_2_RMII_CLOCK;
+        let value = if variant.clock_select_bit(clock) {
+            value | PHY_CONTROL_2_RMII_CLOCK
+        } else {
+            value
+        };
+        self.write(PHY_CONTROL_2, value);
+    }
+
+    /// Select whether the interrupt output is active high,
+    /// instead of active low (the default).
+    fn set_interrupt_active_high(&mut self, active_high: bool) {
+        let value = self.read(PHY_CONTROL_2) & !PHY_CONTROL_2_INT_ACTIVE_HIGH;
+        let value = if active_high {
+            value | PHY_CONTROL_2_INT_ACTIVE_HIGH
+        } else {
+            value
+        };
+        self.write(PHY_CONTROL_2, value);
+    }
+}
+
+impl<M: Miim> PhyKsz80xx<M> for KSZ8081R<M> {}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn clock_select_bit_depends_on_variant() {
+        let rna = Ksz80xxVariant::Rna;
+        let rnd = Ksz80xxVariant::Rnd;
+
+        // Both variants reset to 0, which selects a different clock.
+        assert_eq!(rna.clock(false), RmiiClock::Crystal25MHz);
+        assert_eq!(rnd.clock(false), RmiiClock::External50MHz);
+
+        assert!(rna.clock_select_bit(RmiiClock::External50MHz));
+        assert!(!rnd.clock_select_bit(RmiiClock::External50MHz));
+
+        for variant in [rna, rnd] {
+            for clock in [RmiiClock::Crystal25MHz, RmiiClock::External50MHz] {
+                assert_eq!(variant.clock(variant.clock_select_bit(clock)), clock);
+            }
+        }
+    }
+}
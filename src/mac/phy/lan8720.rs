@@ -0,0 +1,178 @@
+//! Configuration of the LAN8720A, the PHY on most of the cheap RMII
+//! breakout boards.
+//!
+//! The LAN8720A selects the source of its 50 MHz RMII reference clock with
+//! the `nINTSEL` strap, which is sampled at reset:
+//!
+//! * With `nINTSEL` pulled high, `REF_CLK` is an input, and the `nINT/REFCLKO`
+//!   pin is the interrupt output.
+//! * With `nINTSEL` pulled low, the PHY generates `REF_CLK` from a 25 MHz
+//!   crystal, and outputs it on the `nINT/REFCLKO` pin, so the interrupt
+//!   output is unavailable.
+//!
+//! Many breakout boards use the first option, but connect the enable input of
+//! their 50 MHz oscillator to `nINT/REFCLKO`. On those boards, an interrupt
+//! that is raised by the PHY stops `REF_CLK`, which stalls the MAC and the
+//! PHY until the next power cycle. [`RefClk`] describes how the board is wired,
+//! so that [`PhyLan8720::configure_lan8720`] can keep the interrupts masked
+//! whenever the pin is not a usable interrupt output.
+
+use ieee802_3_miim::{Miim, Phy};
+
+use super::{PhyInterruptControl, PhyInterrupts, LAN8720A};
+
+/// Basic Control Register
+const BCR: u8 = 0;
+/// Soft reset
+const BCR_RESET: u16 = 1 << 15;
+
+/// Special Modes Register
+const LAN8720A_SMR: u8 = 18;
+const LAN8720A_SMR_MODE_SHIFT: u16 = 5;
+const LAN8720A_SMR_MODE_MASK: u16 = 0b111 << LAN8720A_SMR_MODE_SHIFT;
+const LAN8720A_SMR_PHYAD_MASK: u16 = 0b1_1111;
+
+/// The transceiver mode of the LAN8720A, which is set by the `MODE[2:0]`
+/// straps, or through the special modes register.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lan8720Mode {
+    /// 10BASE-T half duplex, auto-negotiation disabled.
+    Base10THalfDuplex,
+    /// 10BASE-T full duplex, auto-negotiation disabled.
+    Base10TFullDuplex,
+    /// 100BASE-TX half duplex, auto-negotiation disabled.
+    Base100TxHalfDuplex,
+    /// 100BASE-TX full duplex, auto-negotiation disabled.
+    Base100TxFullDuplex,
+    /// 100BASE-TX half duplex is advertised, auto-negotiation enabled.
+    Base100TxHalfDuplexAdvertised,
+    /// Repeater mode: 100BASE-TX half duplex is advertised,
+    /// auto-negotiation enabled, and CRS is only asserted on receive.
+    Repeater,
+    /// Power-down mode.
+    PowerDown,
+    /// All capabilities are advertised, auto-negotiation enabled.
+    AllCapable,
+}
+
+impl Lan8720Mode {
+    fn bits(&self) -> u16 {
+        match self {
+            Lan8720Mode::Base10THalfDuplex => 0b000,
+            Lan8720Mode::Base10TFullDuplex => 0b001,
+            Lan8720Mode::Base100TxHalfDuplex => 0b010,
+            Lan8720Mode::Base100TxFullDuplex => 0b011,
+            Lan8720Mode::Base100TxHalfDuplexAdvertised => 0b100,
+            Lan8720Mode::Repeater => 0b101,
+            Lan8720Mode::PowerDown => 0b110,
+            Lan8720Mode::AllCapable => 0b111,
+        }
+    }
+
+    fn from_bits(bits: u16) -> Self {
+        match bits & 0b111 {
+            0b000 => Lan8720Mode::Base10THalfDuplex,
+            0b001 => Lan8720Mode::Base10TFullDuplex,
+            0b010 => Lan8720Mode::Base100TxHalfDuplex,
+            0b011 => Lan8720Mode::Base100TxFullDuplex,
+            0b100 => Lan8720Mode::Base100TxHalfDuplexAdvertised,
+            0b101 => Lan8720Mode::Repeater,
+            0b110 => Lan8720Mode::PowerDown,
+            _ => Lan8720Mode::AllCapable,
+        }
+    }
+}
+
+/// How the RMII reference clock of the LAN8720A is wired.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefClk {
+    /// `REF_CLK` is supplied by an oscillator (or the MCU), and
+    /// `nINT/REFCLKO` is a usable interrupt output.
+    Input,
+    /// `REF_CLK` is supplied by an oscillator whose enable input is
+    /// connected to `nINT/REFCLKO`, as on many breakout boards.
+    OscillatorEnabledByNint,
+    /// The PHY outputs `REF_CLK` on `nINT/REFCLKO` (`nINTSEL` strapped low).
+    Output,
+}
+
+impl RefClk {
+    /// Whether `nINT/REFCLKO` can be used as an interrupt output.
+    pub fn interrupts_available(&self) -> bool {
+        matches!(self, RefClk::Input)
+    }
+}
+
+/// The configuration of a LAN8720A.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lan8720Config {
+    /// The transceiver mode.
+    pub mode: Lan8720Mode,
+    /// How the reference clock is wired.
+    pub ref_clk: RefClk,
+}
+
+impl Default for Lan8720Config {
+    fn default() -> Self {
+        Self {
+            mode: Lan8720Mode::AllCapable,
+            ref_clk: RefClk::OscillatorEnabledByNint,
+        }
+    }
+}
+
+/// Vendor-specific configuration of the LAN8720A.
+pub trait PhyLan8720<M: Miim>: Phy<M> + PhyInterruptControl<M> {
+    /// The transceiver mode in the special modes register.
+    fn lan8720_mode(&mut self) -> Lan8720Mode {
+        Lan8720Mode::from_bits(self.read(LAN8720A_SMR) >> LAN8720A_SMR_MODE_SHIFT)
+    }
+
+    /// The PHY address in the special modes register, which is
+    /// latched from the `PHYAD0` strap at power-on.
+    fn strapped_address(&mut self) -> u8 {
+        (self.read(LAN8720A_SMR) & LAN8720A_SMR_PHYAD_MASK) as u8
+    }
+
+    /// Apply `config`, and soft reset the PHY.
+    ///
+    /// The transceiver mode is only applied by a reset, which is why this
+    /// function blocks until the soft reset has completed. Afterwards, the
+    /// PHY interrupts are masked, unless [`RefClk::interrupts_available`].
+    fn configure_lan8720(&mut self, config: &Lan8720Config) {
+        let smr = self.read(LAN8720A_SMR) & !LAN8720A_SMR_MODE_MASK;
+        self.write(
+            LAN8720A_SMR,
+            smr | config.mode.bits() << LAN8720A_SMR_MODE_SHIFT,
+        );
+
+        let bcr = self.read(BCR);
+        self.write(BCR, bcr | BCR_RESET);
+        while self.read(BCR) & BCR_RESET != 0 {}
+
+        if !config.ref_clk.interrupts_available() {
+            self.enable_phy_interrupts(PhyInterrupts::NONE);
+        }
+    }
+}
+
+impl<M: Miim> PhyLan8720<M> for LAN8720A<M> {}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn mode_bits() {
+        for bits in 0..8 {
+            assert_eq!(Lan8720Mode::from_bits(bits).bits(), bits);
+        }
+        assert_eq!(
+            Lan8720Mode::from_bits(0x00E1 >> LAN8720A_SMR_MODE_SHIFT),
+            Lan8720Mode::AllCapable
+        );
+    }
+}
@@ -0,0 +1,360 @@
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use ieee802_3_miim::{registers::Bsr, Miim, Phy};
+
+use super::{PhyErrorCounters, PhyInterruptControl};
+
+#[cfg(feature = "async-await")]
+use {
+    core::{future::Future, task::Poll},
+    futures::task::AtomicWaker,
+};
+
+static PHY_INTERRUPT: AtomicBool = AtomicBool::new(false);
+
+#[cfg(feature = "async-await")]
+fn waker() -> &'static AtomicWaker {
+    static WAKER: AtomicWaker = AtomicWaker::new();
+    &WAKER
+}
+
+/// Record that the PHY signalled an interrupt, so that the next call to
+/// [`LinkMonitor::poll_interrupt`] polls the PHY, and wake tasks waiting in
+/// [`LinkMonitor::wait_for_link`] so that they poll the PHY immediately.
+///
+/// Call this from the interrupt handler of the interrupt pin of the PHY,
+/// which is usually an EXTI line that triggers on the falling edge of `nINT`.
+pub fn on_phy_interrupt() {
+    PHY_INTERRUPT.store(true, Ordering::Release);
+
+    #[cfg(feature = "async-await")]
+    waker().wake();
+}
+
+/// Wait until [`on_phy_interrupt`] is called, without consuming
+/// the interrupt.
+#[cfg(feature = "async-await")]
+async fn phy_interrupt() {
+    core::future::poll_fn(|ctx| {
+        waker().register(ctx.waker());
+        if PHY_INTERRUPT.load(Ordering::Acquire) {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+/// The state of the link of a PHY.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkState {
+    /// The link is down.
+    Down,
+    /// The link is up.
+    Up,
+    /// The link is up, but the link partner is signalling a
+    /// remote fault.
+    UpWithRemoteFault,
+}
+
+impl LinkState {
+    /// Check whether the link is up, regardless of whether
+    /// a remote fault is being signalled.
+    pub fn is_up(&self) -> bool {
+        *self != LinkState::Down
+    }
+}
+
+/// An event that occured on the link of a PHY.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkEvent {
+    /// The link went up.
+    Up,
+    /// The link went down.
+    Down,
+    /// The link partner started signalling a remote fault.
+    RemoteFault,
+    /// The link partner stopped signalling a remote fault.
+    RemoteFaultCleared,
+    /// The PHY detected a jabber condition.
+    Jabber,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Status {
+    link_up: bool,
+    remote_fault: bool,
+}
+
+/// Monitors the Basic Mode Status Register (BMSR) of a PHY and
+/// turns changes into [`LinkEvent`]s.
+///
+/// The link status, remote fault, and jabber detect bits of the BMSR
+/// are latching, so a transient condition that occurs between two reads
+/// is still reported by the next read. To make sure that no events are
+/// lost, [`LinkMonitor::poll`] reads the BMSR at most once per batch of
+/// events, and should be called until it returns [`None`].
+#[derive(Debug, Clone)]
+pub struct LinkMonitor {
+    reported: Status,
+    observed: Status,
+    jabber: bool,
+    autonegotiation_complete: bool,
+    symbol_errors: Option<u16>,
+    interrupted: bool,
+}
+
+impl Default for LinkMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LinkMonitor {
+    /// Create a new [`LinkMonitor`] that assumes that the link is
+    /// initially down.
+    pub const fn new() -> Self {
+        let down = Status {
+            link_up: false,
+            remote_fault: false,
+        };
+
+        Self {
+            reported: down,
+            observed: down,
+            jabber: false,
+            autonegotiation_complete: false,
+            symbol_errors: None,
+            interrupted: false,
+        }
+    }
+
+    /// The state of the link, as of the last event returned by [`LinkMonitor::poll`].
+    pub fn state(&self) -> LinkState {
+        match (self.reported.link_up, self.reported.remote_fault) {
+            (false, _) => LinkState::Down,
+            (true, false) => LinkState::Up,
+            (true, true) => LinkState::UpWithRemoteFault,
+        }
+    }
+
+    /// Whether the PHY reported that auto-negotiation has completed,
+    /// as of the last time that [`LinkMonitor::poll`] read the BMSR.
+    ///
+    /// This is never `true` if auto-negotiation is disabled.
+    pub fn autonegotiation_complete(&self) -> bool {
+        self.autonegotiation_complete
+    }
+
+    /// Poll `phy` for the next [`LinkEvent`].
+    ///
+    /// Returns `None` if the state of the link has not changed since
+    /// the previous call.
+    pub fn poll<M, P>(&mut self, phy: &mut P) -> Option<LinkEvent>
+    where
+        M: Miim,
+        P: Phy<M>,
+    {
+        if self.observed == self.reported && !self.jabber {
+            self.update(phy.bsr());
+        }
+
+        let event = self.next_event();
+
+        match event {
+            Some(LinkEvent::Up) => crate::stats::add(&crate::stats::counters().link_up, 1),
+            Some(LinkEvent::Down) => crate::stats::add(&crate::stats::counters().link_down, 1),
+            _ => {}
+        }
+
+        #[cfg(feature = "event-log")]
+        if let Some(event) = event {
+            crate::event_log::record(crate::event_log::DriverEvent::Link(event));
+        }
+
+        event
+    }
+
+    /// Poll `phy` for the next [`LinkEvent`], but only if the PHY signalled
+    /// an interrupt (see [`on_phy_interrupt`]) since the previous batch of
+    /// events was returned.
+    ///
+    /// This avoids reading the BMSR in every iteration of the main loop. The
+    /// pending interrupt sources of the PHY are cleared, so that it can signal
+    /// the next interrupt, which requires that the link interrupts are enabled
+    /// with [`PhyInterruptControl::enable_link_interrupts`]. Like
+    /// [`LinkMonitor::poll`], this should be called until it returns [`None`].
+    ///
+    /// ```rust,ignore
+    /// phy.enable_link_interrupts();
+    ///
+    /// loop {
+    ///     while let Some(event) = link.poll_interrupt(&mut phy) {
+    ///         defmt::info!("Link event: {}", event);
+    ///     }
+    ///     // Process frames.
+    /// }
+    /// ```
+    pub fn poll_interrupt<M, P>(&mut self, phy: &mut P) -> Option<LinkEvent>
+    where
+        M: Miim,
+        P: PhyInterruptControl<M>,
+    {
+        if !self.interrupted && !self.take_interrupt(phy) {
+            return None;
+        }
+
+        let event = self.poll(phy);
+        self.interrupted = event.is_some();
+        event
+    }
+
+    /// Consume the interrupt that was recorded by [`on_phy_interrupt`],
+    /// if any, and clear the pending interrupt sources of `phy`.
+    ///
+    /// This is the only place where the interrupt is consumed, so that
+    /// [`LinkMonitor::poll_interrupt`] and [`LinkMonitor::wait_for_link`]
+    /// do not steal each other's interrupts.
+    fn take_interrupt<M, P>(&mut self, phy: &mut P) -> bool
+    where
+        M: Miim,
+        P: PhyInterruptControl<M>,
+    {
+        if PHY_INTERRUPT.swap(false, Ordering::AcqRel) {
+            phy.take_phy_interrupts();
+            self.interrupted = true;
+        }
+        self.interrupted
+    }
+
+    /// Poll the error counters of `phy`, and add any new errors
+    /// to the [`Statistics`](crate::stats::Statistics).
+    pub fn poll_error_counters<M, P>(&mut self, phy: &mut P)
+    where
+        M: Miim,
+        P: PhyErrorCounters<M>,
+    {
+        let symbol_errors = phy.symbol_error_counter().delta(&mut self.symbol_errors);
+
+        if symbol_errors != 0 {
+            crate::stats::add(&crate::stats::counters().phy_symbol_errors, symbol_errors);
+        }
+    }
+
+    /// Wait until the link of `phy` is up.
+    ///
+    /// The PHY is polled whenever the future returned by `delay` completes,
+    /// or when [`on_phy_interrupt`] is called, whichever comes first. With a
+    /// PHY interrupt, `delay` may return a future that never completes (such
+    /// as [`core::future::pending`]). Without one, it should return a timer,
+    /// for instance one that completes after 100 ms.
+    ///
+    /// Like [`LinkMonitor::poll_interrupt`], this clears the pending interrupt
+    /// sources of the PHY after it signalled an interrupt, so that it can
+    /// signal the next one.
+    ///
+    /// Completes immediately if the link is already up. The [`LinkEvent`]s
+    /// that occur while waiting are not returned, but are still counted in
+    /// the [`Statistics`](crate::stats::Statistics).
+    ///
+    /// ```rust,ignore
+    /// link.wait_for_link(&mut phy, || Timer::after_millis(100)).await;
+    /// // Start DHCP.
+    /// ```
+    #[cfg(feature = "async-await")]
+    pub async fn wait_for_link<M, P, D, F>(&mut self, phy: &mut P, mut delay: D)
+    where
+        M: Miim,
+        P: PhyInterruptControl<M>,
+        D: FnMut() -> F,
+        F: Future<Output = ()>,
+    {
+        loop {
+            self.take_interrupt(phy);
+            while self.poll(phy).is_some() {}
+            self.interrupted = false;
+
+            if self.state().is_up() {
+                return;
+            }
+
+            let delay = delay();
+            let interrupt = phy_interrupt();
+            futures::pin_mut!(delay, interrupt);
+            futures::future::select(delay, interrupt).await;
+        }
+    }
+
+    fn update(&mut self, bsr: Bsr) {
+        self.observed = Status {
+            link_up: bsr.contains(Bsr::LINK_STATUS),
+            remote_fault: bsr.contains(Bsr::REMOTE_FAULT),
+        };
+        self.jabber |= bsr.contains(Bsr::JABBER_DETECT);
+        self.autonegotiation_complete = bsr.contains(Bsr::AUTONEG_COMPLETE);
+    }
+
+    fn next_event(&mut self) -> Option<LinkEvent> {
+        if self.jabber {
+            self.jabber = false;
+            return Some(LinkEvent::Jabber);
+        }
+
+        if self.observed.link_up != self.reported.link_up {
+            self.reported.link_up = self.observed.link_up;
+            return Some(if self.observed.link_up {
+                LinkEvent::Up
+            } else {
+                LinkEvent::Down
+            });
+        }
+
+        if self.observed.remote_fault != self.reported.remote_fault {
+            self.reported.remote_fault = self.observed.remote_fault;
+            return Some(if self.observed.remote_fault {
+                LinkEvent::RemoteFault
+            } else {
+                LinkEvent::RemoteFaultCleared
+            });
+        }
+
+        None
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn remote_fault_is_distinct_from_link_up() {
+        let mut monitor = LinkMonitor::new();
+
+        monitor.update(Bsr::LINK_STATUS | Bsr::REMOTE_FAULT);
+        assert_eq!(monitor.next_event(), Some(LinkEvent::Up));
+        assert_eq!(monitor.next_event(), Some(LinkEvent::RemoteFault));
+        assert_eq!(monitor.next_event(), None);
+        assert_eq!(monitor.state(), LinkState::UpWithRemoteFault);
+
+        monitor.update(Bsr::LINK_STATUS);
+        assert_eq!(monitor.next_event(), Some(LinkEvent::RemoteFaultCleared));
+        assert_eq!(monitor.state(), LinkState::Up);
+    }
+
+    #[test]
+    fn jabber_is_reported_once() {
+        let mut monitor = LinkMonitor::new();
+
+        monitor.update(Bsr::LINK_STATUS | Bsr::JABBER_DETECT);
+        assert_eq!(monitor.next_event(), Some(LinkEvent::Jabber));
+        assert_eq!(monitor.next_event(), Some(LinkEvent::Up));
+        assert_eq!(monitor.next_event(), None);
+
+        monitor.update(Bsr::LINK_STATUS);
+        assert_eq!(monitor.next_event(), None);
+        assert_eq!(monitor.state(), LinkState::Up);
+    }
+}
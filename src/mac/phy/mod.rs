@@ -0,0 +1,37 @@
+//! PHY access and monitoring.
+//!
+//! This module re-exports all PHY implementations provided by
+//! [`ieee802_3_miim::phy`], and adds functionality for monitoring
+//! the state of a PHY's link and for compliance testing on top of them.
+
+pub use ieee802_3_miim::phy::*;
+
+mod advertisement;
+pub use advertisement::{Advertisement, EmptyAdvertisement, LinkConfiguration, PhyAdvertisement};
+
+mod counters;
+pub use counters::{ErrorCounter, PhyErrorCounters};
+
+mod detect;
+pub use detect::{detect, scan, DetectedPhy, PhyIdentifier, PHY_ADDRESS_COUNT};
+
+mod dp83848;
+pub use dp83848::{Dp83848, Dp83848Interface, Dp83848Status, DP83848_ID};
+
+mod interrupts;
+pub use interrupts::{PhyInterruptControl, PhyInterrupts};
+
+mod ksz80xx;
+pub use ksz80xx::{Ksz80xxVariant, PhyKsz80xx, RmiiClock};
+
+mod lan8720;
+pub use lan8720::{Lan8720Config, Lan8720Mode, PhyLan8720, RefClk};
+
+mod link;
+pub use link::{on_phy_interrupt, LinkEvent, LinkMonitor, LinkState};
+
+mod pause;
+pub use pause::PhyPause;
+
+mod test_mode;
+pub use test_mode::{PhyTestMode, TestMode, UnsupportedTestMode};
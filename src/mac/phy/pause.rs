@@ -0,0 +1,70 @@
+use ieee802_3_miim::{Miim, Phy};
+
+use super::{BarePhy, DetectedPhy, KSZ8081R, LAN8720A, LAN8742A};
+use crate::mac::flow_control::{PauseAbility, PauseResolution};
+
+/// Auto-Negotiation Advertisement Register
+const ANAR: u8 = 4;
+/// Auto-Negotiation Link Partner Ability Register
+const ANLPAR: u8 = 5;
+/// Symmetric pause
+const PAUSE: u16 = 1 << 10;
+/// Asymmetric pause direction
+const ASM_DIR: u16 = 1 << 11;
+
+fn ability(register: u16) -> PauseAbility {
+    PauseAbility {
+        symmetric: register & PAUSE != 0,
+        asymmetric: register & ASM_DIR != 0,
+    }
+}
+
+/// A PHY that can negotiate the use of pause frames with its link partner.
+///
+/// The resolved [`PauseResolution`] can be applied to the MAC with
+/// [`FlowControlConfig::with_resolution`](crate::mac::flow_control::FlowControlConfig::with_resolution).
+pub trait PhyPause<M: Miim>: Phy<M> {
+    /// Set the pause abilities that are advertised during auto-negotiation.
+    ///
+    /// Auto-negotiation must be restarted for the change to take effect.
+    fn advertise_pause(&mut self, ability: PauseAbility) {
+        let mut anar = self.read(ANAR) & !(PAUSE | ASM_DIR);
+        if ability.symmetric {
+            anar |= PAUSE;
+        }
+        if ability.asymmetric {
+            anar |= ASM_DIR;
+        }
+        self.write(ANAR, anar);
+    }
+
+    /// The pause abilities that are advertised by this PHY.
+    fn local_pause(&mut self) -> PauseAbility {
+        ability(self.read(ANAR))
+    }
+
+    /// The pause abilities that were advertised by the link partner.
+    ///
+    /// This is only valid once auto-negotiation has completed.
+    fn partner_pause(&mut self) -> PauseAbility {
+        ability(self.read(ANLPAR))
+    }
+
+    /// Resolve the use of pause frames from the advertised abilities
+    /// of this PHY and its link partner.
+    ///
+    /// This is only valid once auto-negotiation has completed.
+    fn resolve_pause(&mut self) -> PauseResolution {
+        PauseResolution::resolve(self.local_pause(), self.partner_pause())
+    }
+}
+
+impl<M: Miim> PhyPause<M> for BarePhy<M> {}
+
+impl<M: Miim> PhyPause<M> for LAN8720A<M> {}
+
+impl<M: Miim> PhyPause<M> for LAN8742A<M> {}
+
+impl<M: Miim> PhyPause<M> for KSZ8081R<M> {}
+
+impl<M: Miim> PhyPause<M> for DetectedPhy<M> {}
@@ -0,0 +1,105 @@
+use ieee802_3_miim::{registers::Bcr, Miim, Phy};
+
+use super::{BarePhy, KSZ8081R, LAN8720A, LAN8742A};
+
+/// Test modes that can be used for signal integrity and EMC
+/// compliance testing.
+///
+/// All test modes force the link configuration through the Basic
+/// Mode Control Register (BMCR), disabling auto-negotiation. The
+/// PHY keeps transmitting while the MAC is idle, so the transmitted
+/// signal can be measured without a link partner.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestMode {
+    /// Force 100BASE-TX full duplex. The PHY continuously transmits
+    /// (scrambled) idle symbols.
+    Base100TxIdle,
+    /// Force 100BASE-TX full duplex with the scrambler disabled, so the
+    /// transmitted idle symbols produce a plain MLT-3 pattern.
+    ///
+    /// This requires vendor-specific register support.
+    Base100TxMlt3,
+    /// Force 10BASE-T full duplex. Frames consisting of all-ones
+    /// data, transmitted by the MAC, produce the 10BASE-T harmonic
+    /// content test pattern.
+    Base10THarmonic,
+}
+
+/// The requested [`TestMode`] is not supported by the PHY.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsupportedTestMode;
+
+/// A PHY that can be put into a [`TestMode`].
+pub trait PhyTestMode<M: Miim>: Phy<M> {
+    /// Enable or disable the 100BASE-TX scrambler.
+    ///
+    /// Returns `false` if the PHY does not support controlling
+    /// the scrambler.
+    fn set_scrambler(&mut self, enabled: bool) -> bool {
+        let _ = enabled;
+        false
+    }
+
+    /// Put the PHY into the given test mode.
+    ///
+    /// Use [`PhyTestMode::exit_test_mode`] to return to normal operation.
+    fn enter_test_mode(&mut self, mode: TestMode) -> Result<(), UnsupportedTestMode> {
+        let base100 = match mode {
+            TestMode::Base100TxIdle => true,
+            TestMode::Base100TxMlt3 => {
+                if !self.set_scrambler(false) {
+                    return Err(UnsupportedTestMode);
+                }
+                true
+            }
+            TestMode::Base10THarmonic => false,
+        };
+
+        self.modify_bcr(|bcr| {
+            bcr.set_autonegotiation(false)
+                .set_loopback(false)
+                .set_isolated(false)
+                .set_power_down(false)
+                .set_full_duplex(true);
+            bcr.remove(Bcr::SPEED_SEL_MSB);
+            bcr.set(Bcr::SPEED_SEL_LSB, base100);
+        });
+
+        Ok(())
+    }
+
+    /// Leave any test mode, and restart auto-negotiation.
+    fn exit_test_mode(&mut self) {
+        self.set_scrambler(true);
+
+        self.modify_bcr(|bcr| {
+            bcr.set_autonegotiation(true).restart_autonegotiation();
+        });
+    }
+}
+
+impl<M: Miim> PhyTestMode<M> for BarePhy<M> {}
+
+impl<M: Miim> PhyTestMode<M> for LAN8720A<M> {}
+
+impl<M: Miim> PhyTestMode<M> for LAN8742A<M> {}
+
+impl<M: Miim> PhyTestMode<M> for KSZ8081R<M> {
+    fn set_scrambler(&mut self, enabled: bool) -> bool {
+        /// PHY Control 2
+        const PHY_CONTROL_2: u8 = 0x1F;
+        /// Disable data scrambling
+        const DISABLE_SCRAMBLER: u16 = 1 << 0;
+
+        let value = self.read(PHY_CONTROL_2);
+        let value = if enabled {
+            value & !DISABLE_SCRAMBLER
+        } else {
+            value | DISABLE_SCRAMBLER
+        };
+        self.write(PHY_CONTROL_2, value);
+        true
+    }
+}
@@ -0,0 +1,270 @@
+//! Power management (PMT): wake-on-LAN.
+//!
+//! The MAC can be put into a power-down state in which it drops all
+//! received frames, except for frames that wake the system up: magic
+//! packets (see [`WakeupConfig::magic_packet`]) and frames that match one
+//! of the [`WakeupFilter`]s. When such a frame is received, the MAC leaves
+//! the power-down state, and raises the PMT interrupt if it is unmasked (see
+//! [`MacInterrupts::pmt`](super::MacInterrupts::pmt)). The PMT interrupt is
+//! connected to the `ETH_WKUP` EXTI line, so it can wake the MCU from Stop mode.
+//!
+//! ```rust,ignore
+//! mac.unmask_interrupts(MacInterrupts { pmt: true, ..MacInterrupts::NONE });
+//! mac.enter_power_down(WakeupConfig { magic_packet: true, ..WakeupConfig::NONE });
+//! // Enter Stop mode, and wait for the `ETH_WKUP` interrupt.
+//! let status = mac.pmt_status();
+//! ```
+
+use super::EthernetMAC;
+
+/// Power down
+const PMTCSR_PD: u32 = 1 << 0;
+/// Magic packet enable
+const PMTCSR_MPE: u32 = 1 << 1;
+/// Wakeup frame enable
+const PMTCSR_WFE: u32 = 1 << 2;
+/// Magic packet received
+const PMTCSR_MPR: u32 = 1 << 5;
+/// Wakeup frame received
+const PMTCSR_WFR: u32 = 1 << 6;
+/// Global unicast
+const PMTCSR_GU: u32 = 1 << 9;
+/// Wakeup frame filter register pointer reset
+const PMTCSR_WFFRPR: u32 = 1 << 31;
+
+/// The amount of wake-up frame filters supported by the MAC.
+pub const WAKEUP_FILTER_COUNT: usize = 4;
+
+/// The amount of bytes that a [`WakeupFilter`] can match.
+pub const WAKEUP_FILTER_LEN: usize = 31;
+
+/// The frames that wake the MAC up from the power-down state.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WakeupConfig {
+    /// Wake up when a magic packet is received, which contains 6 bytes
+    /// of `FF` followed by 16 repetitions of the address of this station.
+    pub magic_packet: bool,
+    /// Wake up when a frame that matches one of the wake-up frame filters
+    /// is received, see [`EthernetMAC::set_wakeup_filters`].
+    pub wakeup_frames: bool,
+    /// Wake up when any unicast frame that passes the address
+    /// filters is received.
+    pub global_unicast: bool,
+}
+
+impl WakeupConfig {
+    /// No frames wake the MAC up.
+    pub const NONE: Self = Self {
+        magic_packet: false,
+        wakeup_frames: false,
+        global_unicast: false,
+    };
+
+    fn bits(&self) -> u32 {
+        let mut bits = 0;
+        if self.magic_packet {
+            bits |= PMTCSR_MPE;
+        }
+        if self.wakeup_frames {
+            bits |= PMTCSR_WFE;
+        }
+        if self.global_unicast {
+            bits |= PMTCSR_GU;
+        }
+        bits
+    }
+}
+
+/// The reason why the MAC woke up.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PmtStatus {
+    /// A magic packet was received.
+    pub magic_packet_received: bool,
+    /// A frame that matched a wake-up frame filter was received.
+    pub wakeup_frame_received: bool,
+}
+
+/// A remote wake-up frame filter.
+///
+/// A filter matches a received frame if the CRC-16 of the bytes of the
+/// frame that are selected by [`WakeupFilter::byte_mask`] matches
+/// [`WakeupFilter::crc`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WakeupFilter {
+    /// The offset of the first byte that the filter examines, counted
+    /// from the start of the frame. Must be at least 12.
+    pub offset: u8,
+    /// Bit `i` selects byte `offset + i` of the frame. Bit 31 must be zero.
+    pub byte_mask: u32,
+    /// The CRC-16 of the selected bytes.
+    pub crc: u16,
+    /// Only match multicast frames instead of unicast frames.
+    pub multicast: bool,
+}
+
+impl WakeupFilter {
+    /// A filter that matches frames that contain `pattern` at `offset`,
+    /// for the bytes selected by `byte_mask`.
+    ///
+    /// Bit `i` of `byte_mask` selects `pattern[i]`.
+    ///
+    /// # Panics
+    /// This function panics if `pattern` is longer than [`WAKEUP_FILTER_LEN`].
+    pub fn new(offset: u8, pattern: &[u8], byte_mask: u32, multicast: bool) -> Self {
+        assert!(
+            pattern.len() <= WAKEUP_FILTER_LEN,
+            "A wake-up filter pattern is at most {} bytes long",
+            WAKEUP_FILTER_LEN
+        );
+
+        let byte_mask = byte_mask & ((1u32 << pattern.len()) - 1);
+
+        let mut crc = 0xFFFF;
+        for (i, byte) in pattern.iter().enumerate() {
+            if byte_mask & (1 << i) != 0 {
+                crc = crc16_update(crc, *byte);
+            }
+        }
+
+        Self {
+            offset,
+            byte_mask,
+            crc,
+            multicast,
+        }
+    }
+
+    /// The nibble of this filter in the filter command register.
+    fn command(&self) -> u32 {
+        let enable = 1;
+        let multicast = if self.multicast { 1 << 3 } else { 0 };
+        enable | multicast
+    }
+}
+
+/// Feed `byte` into a (reflected) CRC-16 with the polynomial
+/// `x^16 + x^15 + x^2 + 1`.
+fn crc16_update(mut crc: u16, byte: u8) -> u16 {
+    crc ^= byte as u16;
+    for _ in 0..8 {
+        let mask = (crc & 1).wrapping_neg();
+        crc = (crc >> 1) ^ (0xA001 & mask);
+    }
+    crc
+}
+
+/// The values of the 8 consecutive writes to the wake-up
+/// frame filter register for `filters`.
+fn filter_register_values(filters: &[Option<WakeupFilter>; WAKEUP_FILTER_COUNT]) -> [u32; 8] {
+    let mut values = [0; 8];
+
+    for (i, filter) in filters.iter().enumerate() {
+        if let Some(filter) = filter {
+            values[i] = filter.byte_mask & 0x7FFF_FFFF;
+            values[4] |= filter.command() << (8 * i);
+            values[5] |= (filter.offset as u32) << (8 * i);
+            values[6 + i / 2] |= (filter.crc as u32) << (16 * (i % 2));
+        }
+    }
+
+    values
+}
+
+impl EthernetMAC {
+    /// Program the remote wake-up frame filters. Filters that are
+    /// `None` are disabled.
+    ///
+    /// The filters are only used in the power-down state, if
+    /// [`WakeupConfig::wakeup_frames`] is set.
+    pub fn set_wakeup_filters(&mut self, filters: &[Option<WakeupFilter>; WAKEUP_FILTER_COUNT]) {
+        // Reset the pointer of the filter register, so that the
+        // following writes start at the first filter register.
+        self.eth_mac
+            .macpmtcsr
+            .modify(|r, w| unsafe { w.bits(r.bits() | PMTCSR_WFFRPR) });
+
+        for value in filter_register_values(filters) {
+            crate::quirks::write_macrwuffr(&self.eth_mac, value);
+        }
+    }
+
+    /// Put the MAC into the power-down state, in which it drops all
+    /// received frames until it is woken up by one of the frames
+    /// selected by `config`.
+    ///
+    /// The MAC leaves the power-down state by itself once it is woken up.
+    /// The transmit path should be idle before calling this function.
+    pub fn enter_power_down(&mut self, config: WakeupConfig) {
+        // The wake-up sources must be configured before power-down
+        // is enabled.
+        let bits = config.bits();
+        self.eth_mac.macpmtcsr.write(|w| unsafe { w.bits(bits) });
+        self.eth_mac
+            .macpmtcsr
+            .write(|w| unsafe { w.bits(bits | PMTCSR_PD) });
+    }
+
+    /// Check whether the MAC is in the power-down state.
+    pub fn is_powered_down(&self) -> bool {
+        self.eth_mac.macpmtcsr.read().bits() & PMTCSR_PD != 0
+    }
+
+    /// Read why the MAC woke up.
+    ///
+    /// Reading the status clears it, and clears the PMT interrupt.
+    pub fn pmt_status(&mut self) -> PmtStatus {
+        let bits = self.eth_mac.macpmtcsr.read().bits();
+        PmtStatus {
+            magic_packet_received: bits & PMTCSR_MPR != 0,
+            wakeup_frame_received: bits & PMTCSR_WFR != 0,
+        }
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn crc16_check_value() {
+        let crc = b"123456789"
+            .iter()
+            .fold(0xFFFF, |crc, byte| crc16_update(crc, *byte));
+        assert_eq!(crc, 0x4B37);
+    }
+
+    #[test]
+    fn filter_registers() {
+        let filter = WakeupFilter {
+            offset: 12,
+            byte_mask: 0x3,
+            crc: 0x1234,
+            multicast: true,
+        };
+        let values = filter_register_values(&[None, Some(filter), None, Some(filter)]);
+        assert_eq!(
+            values,
+            [
+                0,
+                0x3,
+                0,
+                0x3,
+                0x0900_0900,
+                0x0C00_0C00,
+                0x1234_0000,
+                0x1234_0000
+            ]
+        );
+    }
+
+    #[test]
+    fn filter_mask_is_limited_to_pattern() {
+        let a = WakeupFilter::new(12, &[0x08, 0x06], u32::MAX, false);
+        let b = WakeupFilter::new(12, &[0x08, 0x06, 0xFF], 0x3, false);
+        assert_eq!(a.byte_mask, 0x3);
+        assert_eq!(a.crc, b.crc);
+    }
+}
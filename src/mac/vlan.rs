@@ -0,0 +1,63 @@
+//! Detection of IEEE 802.1Q VLAN frames.
+//!
+//! The MAC compares the tag of received frames with the EtherType `0x8100`
+//! against the VLAN tag register. Matching frames are marked as VLAN frames,
+//! so that their tag is returned by [`RxPacket::vlan_tag`], and may be up to
+//! 1522 bytes long. Frames that do not match are received as usual, but
+//! are not marked.
+//!
+//! [`RxPacket::vlan_tag`]: crate::dma::RxPacket::vlan_tag
+
+use super::EthernetMAC;
+
+/// VLAN tag comparison (12 bit)
+const MACVLANTR_VLANTC: u32 = 1 << 16;
+
+/// The comparison of the tags of received VLAN frames.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VlanTagComparison {
+    /// All frames with a VLAN tag are VLAN frames.
+    Any,
+    /// Only frames whose VLAN ID (the lower 12 bits of the TCI)
+    /// is this ID are VLAN frames.
+    ///
+    /// A VLAN ID of zero matches all frames with a VLAN tag.
+    Id(u16),
+    /// Only frames whose complete TCI is this TCI are VLAN frames.
+    ///
+    /// A TCI of zero matches all frames with a VLAN tag.
+    Tci(u16),
+}
+
+impl VlanTagComparison {
+    fn bits(&self) -> u32 {
+        match self {
+            VlanTagComparison::Any => 0,
+            VlanTagComparison::Id(id) => MACVLANTR_VLANTC | (*id as u32 & 0x0FFF),
+            VlanTagComparison::Tci(tci) => *tci as u32,
+        }
+    }
+}
+
+impl EthernetMAC {
+    /// Configure which received frames are marked as VLAN frames.
+    ///
+    /// By default, all frames with a VLAN tag are VLAN frames.
+    pub fn set_vlan_tag_comparison(&mut self, comparison: VlanTagComparison) {
+        let bits = comparison.bits();
+        self.eth_mac.macvlantr.write(|w| unsafe { w.bits(bits) });
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn comparison_bits() {
+        assert_eq!(VlanTagComparison::Any.bits(), 0);
+        assert_eq!(VlanTagComparison::Id(0xF123).bits(), 0x0001_0123);
+        assert_eq!(VlanTagComparison::Tci(0xF123).bits(), 0xF123);
+    }
+}
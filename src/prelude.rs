@@ -0,0 +1,27 @@
+//! The types and traits that are commonly needed to set up and use the driver.
+//!
+//! ```ignore
+//! use stm32_eth::prelude::*;
+//! ```
+
+pub use crate::{
+    dma::{EthernetDMA, PacketId},
+    mac::{EthernetMAC, EthernetMACWithMii, Mac, MdcPin, MdioPin, Speed},
+    setup::{
+        AlternateVeryHighSpeed, RmiiCrsDv, RmiiRefClk, RmiiRxD0, RmiiRxD1, RmiiTxD0, RmiiTxD1,
+        RmiiTxEN,
+    },
+    EthPins, Hclk, HclkHz, Parts, PartsIn,
+};
+
+#[cfg(not(feature = "tx-only"))]
+pub use crate::dma::{RxPacket, RxRingEntry};
+
+#[cfg(not(feature = "rx-only"))]
+pub use crate::dma::{TxPacket, TxRingEntry};
+
+#[cfg(feature = "ptp")]
+pub use crate::ptp::{EthernetPTP, Timestamp};
+
+pub use crate::mac::MiimClause45 as _;
+pub use ieee802_3_miim::{Miim as _, Phy as _};
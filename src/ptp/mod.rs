@@ -1,8 +1,33 @@
 //! PTP access and configuration.
 //!
 //! See [`EthernetPTP`] for a more details.
+//!
+//! # Availability
+//!
+//! The PTP and timestamping API only exists if the `ptp` feature is enabled,
+//! so using it in a build without that feature is a compile error. All
+//! supported families have a PTP block, so the API is not gated any further
+//! by family, except that the STM32F107 only implements IEEE 1588-2002
+//! (PTPv1). The parts of the API that depend on PTPv2 hardware do not exist
+//! when building for the STM32F107, so using them is a compile error rather
+//! than a silent no-op:
+//!
+//! | API                                                   | F1 | F4 | F7 |
+//! |-------------------------------------------------------|----|----|----|
+//! | Time keeping and frequency adjustment                 | ✓  | ✓  | ✓  |
+//! | `enable_pps` (with a fixed frequency of 1 Hz on F1)   | ✓  | ✓  | ✓  |
+//! | TX and RX timestamps of frames                        | ✓¹ | ✓  | ✓  |
+//! | `configure_target_time_interrupt`, `interrupt_handler`|    | ✓  | ✓  |
+//! | `wait_until` (with `async-await`)                     |    | ✓  | ✓  |
+//! | `set_pps_freq`, `set_pps_frequency`                   |    | ✓  | ✓  |
+//!
+//! ¹ The STM32F107 can not take snapshots for all received frames, so only
+//! received PTP event messages have a timestamp.
+//!
+//! To check the family at runtime, use
+//! [`Capabilities::ptp_v2`](crate::mac::capabilities::Capabilities::ptp_v2).
 
-use crate::{dma::EthernetDMA, hal::rcc::Clocks, mac::EthernetMAC, peripherals::ETHERNET_PTP};
+use crate::{dma::EthernetDMA, mac::EthernetMAC, peripherals::ETHERNET_PTP};
 
 mod timestamp;
 pub use timestamp::Timestamp;
@@ -33,7 +58,8 @@ pub use pps_pin::PPSPin;
 /// Using HCLK, values for `subsecond_increment` and `addend` are calculated so that `global_time` represents
 /// real-time.
 ///
-/// Subsequently, `addend` can be adjusted to compensate for possible errors in HCLK, using [`EthernetPTP::addend`] and [`EthernetPTP::set_addend`]
+/// Subsequently, `addend` can be adjusted to compensate for possible errors in HCLK, using [`EthernetPTP::addend`] and [`EthernetPTP::set_addend`],
+/// or relative to the calculated value using [`EthernetPTP::adjust_frequency`].
 ///
 /// To assess the correctness of the current speed at which `global_time` is running, one can use the
 /// following equation:
@@ -48,6 +74,7 @@ pub use pps_pin::PPSPin;
 /// [`NonZeroU8`]: core::num::NonZeroU8
 pub struct EthernetPTP {
     eth_ptp: ETHERNET_PTP,
+    nominal_addend: u32,
 }
 
 impl EthernetPTP {
@@ -68,19 +95,17 @@ impl EthernetPTP {
         (stssi, tsa)
     }
 
-    pub(crate) fn new(
+    pub(crate) fn new<const RX_BUF: usize>(
         eth_ptp: ETHERNET_PTP,
-        clocks: Clocks,
+        hclk: u32,
         // Note(_dma): this field exists to ensure that the PTP is not
         // initialized before the DMA. If PTP is started before the DMA,
         // it doesn't work.
-        _dma: &EthernetDMA,
+        _dma: &EthernetDMA<'_, '_, RX_BUF>,
     ) -> Self {
         // Mask timestamp interrupt register
         EthernetMAC::mask_timestamp_trigger_interrupt();
 
-        let hclk = clocks.hclk().to_Hz();
-
         let (stssi, tsa) = Self::calculate_regs(hclk);
 
         // Setup PTP timestamping in fine mode.
@@ -97,7 +122,10 @@ impl EthernetPTP {
             .ptpssir
             .write(|w| unsafe { w.stssi().bits(stssi.raw() as u8) });
 
-        let mut me = Self { eth_ptp };
+        let mut me = Self {
+            eth_ptp,
+            nominal_addend: tsa,
+        };
 
         me.set_addend(tsa);
         me.set_time(Timestamp::new_unchecked(false, 0, 0));
@@ -121,19 +149,28 @@ impl EthernetPTP {
         let ptp = &self.eth_ptp;
         ptp.ptptsar.write(|w| unsafe { w.bits(rate) });
 
-        #[cfg(feature = "stm32f1xx-hal")]
-        {
-            while ptp.ptptscr.read().tsaru().bit_is_set() {}
-            ptp.ptptscr.modify(|_, w| w.tsaru().set_bit());
-            while ptp.ptptscr.read().tsaru().bit_is_set() {}
-        }
+        crate::quirks::update_ptp_addend(ptp);
+    }
 
-        #[cfg(not(feature = "stm32f1xx-hal"))]
-        {
-            while ptp.ptptscr.read().ttsaru().bit_is_set() {}
-            ptp.ptptscr.modify(|_, w| w.ttsaru().set_bit());
-            while ptp.ptptscr.read().ttsaru().bit_is_set() {}
-        }
+    /// Get the PTP clock addend that was calculated from HCLK.
+    pub fn nominal_addend(&self) -> u32 {
+        self.nominal_addend
+    }
+
+    /// Run the PTP clock `ppb` parts per billion faster (or slower, if
+    /// `ppb` is negative) than the rate calculated from HCLK.
+    ///
+    /// The adjustment is absolute: it replaces any previous adjustment.
+    /// This is the frequency correction that a PTP servo applies.
+    pub fn adjust_frequency(&mut self, ppb: i32) {
+        self.set_addend(Self::adjusted_addend(self.nominal_addend, ppb));
+    }
+
+    /// Scale `nominal` by `ppb` parts per billion, saturating at the
+    /// limits of the addend register.
+    fn adjusted_addend(nominal: u32, ppb: i32) -> u32 {
+        let delta = nominal as i64 * ppb as i64 / 1_000_000_000;
+        (nominal as i64 + delta).clamp(0, u32::MAX as i64) as u32
     }
 
     /// Set the current time.
@@ -156,6 +193,7 @@ impl EthernetPTP {
     ///
     /// If `time` is negative, it will instead be subtracted from the
     /// system time.
+    #[doc(alias = "add_offset")]
     pub fn update_time(&mut self, time: Timestamp) {
         let ptp = &self.eth_ptp;
 
@@ -300,9 +338,9 @@ impl EthernetPTP {
     /// Configure the PPS output frequency.
     ///
     /// The PPS output frequency becomes `2 ^ pps_freq`. `pps_freq` is
-    /// clamped to `[0..31]`.
+    /// clamped to `[0..15]`.
     pub fn set_pps_freq(&mut self, pps_freq: u8) {
-        let pps_freq = pps_freq.max(31);
+        let pps_freq = pps_freq.min(15);
 
         // SAFETY: we atomically write to the PTPPPSCR register, which is
         // not read or written to anywhere else. The SVD files are incorrectly
@@ -312,6 +350,23 @@ impl EthernetPTP {
             core::ptr::write_volatile(ptpppscr, pps_freq as u32);
         }
     }
+
+    /// Configure the PPS output frequency in Hz.
+    ///
+    /// The hardware only supports powers of two, so `hz` is rounded
+    /// down to the nearest power of two between 1 Hz and 32768 Hz.
+    /// Frequencies above 1 Hz only have a 50% duty cycle if the PTP
+    /// clock uses binary subsecond rollover.
+    pub fn set_pps_frequency(&mut self, hz: u32) {
+        self.set_pps_freq(pps_freq(hz));
+    }
+}
+
+/// The `PPSFREQ` value for the highest supported frequency
+/// that does not exceed `hz`.
+#[cfg(not(feature = "stm32f1xx-hal"))]
+fn pps_freq(hz: u32) -> u8 {
+    (31 - hz.max(1).leading_zeros()).min(15) as u8
 }
 
 #[cfg(all(test, not(target_os = "none")))]
@@ -338,4 +393,31 @@ mod test {
             assert!(ppm <= 0.06, "{} at {}", ppm, hclk_hz);
         }
     }
+
+    #[test]
+    fn addend_adjustment() {
+        assert_eq!(
+            EthernetPTP::adjusted_addend(2_000_000_000, 0),
+            2_000_000_000
+        );
+        assert_eq!(
+            EthernetPTP::adjusted_addend(2_000_000_000, 500),
+            2_000_001_000
+        );
+        assert_eq!(
+            EthernetPTP::adjusted_addend(2_000_000_000, -500),
+            1_999_999_000
+        );
+        assert_eq!(EthernetPTP::adjusted_addend(u32::MAX, i32::MAX), u32::MAX);
+    }
+
+    #[test]
+    #[cfg(not(feature = "stm32f1xx-hal"))]
+    fn pps_frequency_rounds_down() {
+        assert_eq!(pps_freq(0), 0);
+        assert_eq!(pps_freq(1), 0);
+        assert_eq!(pps_freq(1000), 9);
+        assert_eq!(pps_freq(1024), 10);
+        assert_eq!(pps_freq(u32::MAX), 15);
+    }
 }
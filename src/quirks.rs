@@ -0,0 +1,99 @@
+//! Accessors for the registers whose fields differ between PACs.
+//!
+//! The SVD files that the PACs are generated from do not agree on the names,
+//! widths and safety of a handful of fields. All of those differences are
+//! contained in this module, so that updating a PAC only requires changes here.
+
+use crate::peripherals::{DmaRegisterBlock, ETHERNET_DMA, ETHERNET_MAC};
+
+#[cfg(feature = "ptp")]
+use crate::peripherals::ETHERNET_PTP;
+
+/// Write the low 32 bits of MAC address 2.
+pub(crate) fn write_maca2l(eth_mac: &ETHERNET_MAC, low: u32) {
+    // NOTE: the `maca2l` field is (incorrectly) only 31 bits wide
+    // for `stm32f107`, so we write the full register instead.
+    #[cfg(feature = "stm32f1xx-hal")]
+    eth_mac.maca2lr.write(|w| w.bits(low));
+    #[cfg(not(feature = "stm32f1xx-hal"))]
+    eth_mac.maca2lr.write(|w| w.maca2l().bits(low));
+}
+
+/// Write `value` to the remote wake-up frame filter register.
+pub(crate) fn write_macrwuffr(eth_mac: &ETHERNET_MAC, value: u32) {
+    #[cfg(feature = "stm32f1xx-hal")]
+    eth_mac.macrwuffr.write(|w| unsafe { w.bits(value) });
+
+    // NOTE: the register is missing from the `stm32f4` and `stm32f7` PACs,
+    // so we write it through its offset in the MAC register block.
+    #[cfg(not(feature = "stm32f1xx-hal"))]
+    {
+        let macrwuffr = (&**eth_mac as *const _ as usize + 0x28) as *mut u32;
+
+        // NOTE: successive writes to the same MAC register need a delay on
+        // the F4 parts, see `DelayedReg`.
+        #[cfg(feature = "stm32f4xx-hal")]
+        cortex_m::asm::delay(29);
+
+        // SAFETY: 0x28 is the offset of the remote wake-up frame filter
+        // register, which accepts any value.
+        unsafe { core::ptr::write_volatile(macrwuffr, value) };
+    }
+}
+
+/// Write the start address of the TX descriptor list.
+#[cfg(not(feature = "rx-only"))]
+pub(crate) fn write_tx_descriptor_list(eth_dma: &ETHERNET_DMA, address: u32) {
+    // NOTE: the `stl` field is only marked as unsafe for `stm32f107`.
+    #[allow(unused_unsafe)]
+    eth_dma.dmatdlar.write(|w| unsafe { w.stl().bits(address) });
+}
+
+/// Write the start address of the RX descriptor list.
+#[cfg(not(feature = "tx-only"))]
+pub(crate) fn write_rx_descriptor_list(eth_dma: &ETHERNET_DMA, address: u32) {
+    eth_dma.dmardlar.write(|w| unsafe { w.srl().bits(address) });
+}
+
+/// Demand that the TX DMA polls the current descriptor.
+#[cfg(not(feature = "rx-only"))]
+pub(crate) fn tx_demand_poll(eth_dma: &DmaRegisterBlock) {
+    eth_dma.dmatpdr.write(|w| {
+        #[cfg(any(feature = "stm32f4xx-hal", feature = "stm32f7xx-hal"))]
+        {
+            w.tpd().poll()
+        }
+        // NOTE: there is no `poll` method for `stm32f107`.
+        #[cfg(feature = "stm32f1xx-hal")]
+        unsafe {
+            w.tpd().bits(0)
+        }
+    });
+}
+
+/// Demand that the RX DMA polls the current descriptor.
+#[cfg(not(feature = "tx-only"))]
+pub(crate) fn rx_demand_poll(eth_dma: &DmaRegisterBlock) {
+    eth_dma.dmarpdr.write(|w| unsafe { w.rpd().bits(1) });
+}
+
+/// Request that the PTP clock addend is updated, and wait for
+/// the update to complete.
+#[cfg(feature = "ptp")]
+pub(crate) fn update_ptp_addend(eth_ptp: &ETHERNET_PTP) {
+    // NOTE: the addend update bit is called `tsaru` for `stm32f107`,
+    // and `ttsaru` for all other parts.
+    #[cfg(feature = "stm32f1xx-hal")]
+    {
+        while eth_ptp.ptptscr.read().tsaru().bit_is_set() {}
+        eth_ptp.ptptscr.modify(|_, w| w.tsaru().set_bit());
+        while eth_ptp.ptptscr.read().tsaru().bit_is_set() {}
+    }
+
+    #[cfg(not(feature = "stm32f1xx-hal"))]
+    {
+        while eth_ptp.ptptscr.read().ttsaru().bit_is_set() {}
+        eth_ptp.ptptscr.modify(|_, w| w.ttsaru().set_bit());
+        while eth_ptp.ptptscr.read().ttsaru().bit_is_set() {}
+    }
+}
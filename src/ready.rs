@@ -0,0 +1,182 @@
+//! A notifier for when the interface is ready to carry traffic.
+//!
+//! Network stacks usually start address configuration (such as DHCP) as soon
+//! as they are started. If the link is still training at that point, the first
+//! discovery messages are lost and the client only retries after a long
+//! timeout. [`ReadyMonitor`] combines the state of the link, the result of
+//! auto-negotiation and the state of the DMA into a single condition that can
+//! be checked with [`is_ready`], or awaited with [`wait_ready`] if the
+//! `async-await` feature is enabled.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{dma::EthernetDMA, mac::phy::LinkMonitor};
+
+#[cfg(feature = "async-await")]
+use {core::task::Poll, futures::task::AtomicWaker};
+
+static READY: AtomicBool = AtomicBool::new(false);
+
+#[cfg(feature = "async-await")]
+fn waker() -> &'static AtomicWaker {
+    static WAKER: AtomicWaker = AtomicWaker::new();
+    &WAKER
+}
+
+/// Register `waker` to be woken when the interface becomes ready.
+#[cfg(feature = "embassy-net")]
+pub(crate) fn register_waker(waker: &core::task::Waker) {
+    self::waker().register(waker);
+}
+
+/// A change of the readiness of the interface.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadyEvent {
+    /// The interface became ready to carry traffic.
+    Ready,
+    /// The interface is no longer ready to carry traffic.
+    NotReady,
+}
+
+/// Tracks whether the interface is ready to carry traffic.
+///
+/// The interface is ready when the link is up, auto-negotiation has
+/// completed, and the DMA has been started. [`ReadyMonitor::update`] should
+/// be called after every call to [`LinkMonitor::poll`].
+#[derive(Debug, Clone)]
+pub struct ReadyMonitor {
+    autonegotiation: bool,
+    ready: bool,
+}
+
+impl Default for ReadyMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReadyMonitor {
+    /// Create a new [`ReadyMonitor`] for a PHY that uses auto-negotiation.
+    pub const fn new() -> Self {
+        Self {
+            autonegotiation: true,
+            ready: false,
+        }
+    }
+
+    /// Create a new [`ReadyMonitor`] for a PHY with a fixed speed and duplex
+    /// mode, that does not wait for auto-negotiation to complete.
+    pub const fn without_autonegotiation() -> Self {
+        Self {
+            autonegotiation: false,
+            ready: false,
+        }
+    }
+
+    /// Whether the interface was ready as of the last call to
+    /// [`ReadyMonitor::update`].
+    pub fn is_ready(&self) -> bool {
+        self.ready
+    }
+
+    /// Update the readiness of the interface from the state of `link` and `dma`.
+    ///
+    /// Returns the change in readiness, if any. A change is also published to
+    /// [`is_ready`], and wakes any task waiting in [`wait_ready`].
+    pub fn update<const RX_BUF: usize>(
+        &mut self,
+        link: &LinkMonitor,
+        dma: &EthernetDMA<'_, '_, RX_BUF>,
+    ) -> Option<ReadyEvent> {
+        self.update_with(
+            link.state().is_up(),
+            link.autonegotiation_complete(),
+            dma.is_started(),
+        )
+    }
+
+    fn update_with(
+        &mut self,
+        link_up: bool,
+        autonegotiation_complete: bool,
+        dma_started: bool,
+    ) -> Option<ReadyEvent> {
+        let ready = link_up && (autonegotiation_complete || !self.autonegotiation) && dma_started;
+
+        if ready == self.ready {
+            return None;
+        }
+
+        self.ready = ready;
+        READY.store(ready, Ordering::Release);
+
+        #[cfg(feature = "async-await")]
+        waker().wake();
+
+        if ready {
+            Some(ReadyEvent::Ready)
+        } else {
+            Some(ReadyEvent::NotReady)
+        }
+    }
+}
+
+/// Check whether the interface is ready to carry traffic, as of the last
+/// call to [`ReadyMonitor::update`].
+///
+/// This function may be called from any context.
+pub fn is_ready() -> bool {
+    READY.load(Ordering::Acquire)
+}
+
+/// Wait until the interface is ready to carry traffic.
+///
+/// Completes immediately if the interface is already ready.
+#[cfg(feature = "async-await")]
+pub async fn wait_ready() {
+    core::future::poll_fn(|ctx| {
+        waker().register(ctx.waker());
+        if is_ready() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ready_requires_autonegotiation() {
+        let mut monitor = ReadyMonitor::new();
+
+        assert_eq!(monitor.update_with(true, false, true), None);
+        assert_eq!(monitor.update_with(true, true, false), None);
+        assert_eq!(
+            monitor.update_with(true, true, true),
+            Some(ReadyEvent::Ready)
+        );
+        assert!(monitor.is_ready());
+
+        assert_eq!(
+            monitor.update_with(false, false, true),
+            Some(ReadyEvent::NotReady)
+        );
+        assert!(!monitor.is_ready());
+    }
+
+    #[test]
+    fn fixed_speed_ignores_autonegotiation() {
+        let mut monitor = ReadyMonitor::without_autonegotiation();
+
+        assert_eq!(
+            monitor.update_with(true, false, true),
+            Some(ReadyEvent::Ready)
+        );
+        assert_eq!(monitor.update_with(true, false, true), None);
+    }
+}
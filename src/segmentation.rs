@@ -0,0 +1,224 @@
+//! Software segmentation of large TCP payloads.
+//!
+//! A [`Segmenter`] splits a large TCP payload into segments of at most the
+//! maximum segment size (MSS), and builds the headers of every segment from
+//! a single header template, so that the IP stack does not have to build
+//! them for every segment of a bulk upload.
+//!
+//! The IP and TCP checksums of the segments are left at zero: they are
+//! inserted by the checksum offload engine of the MAC.
+
+use crate::dma::{EthernetDMA, TxError, ETH_HEADER_LEN, MTU};
+
+const ETHERTYPE_IPV4: [u8; 2] = [0x08, 0x00];
+const IP_PROTOCOL_TCP: u8 = 6;
+
+const TCP_FLAG_FIN: u8 = 1 << 0;
+const TCP_FLAG_PSH: u8 = 1 << 3;
+
+/// An error that occurs when creating a [`Segmenter`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentationError {
+    /// The header template does not consist of exactly an ethernet
+    /// header, an IPv4 header, and a TCP header.
+    InvalidHeaders,
+    /// The MSS is zero, or a segment of the MSS would not fit in a frame.
+    InvalidMss,
+}
+
+/// Splits a TCP payload into segments of at most the MSS.
+///
+/// The header template contains the ethernet, IPv4, and TCP headers of the
+/// first segment. For every following segment, the sequence number is
+/// advanced by the amount of payload that was sent before it, and the IP
+/// identification is incremented. The IP total length is set to the length
+/// of each segment. The `PSH` and `FIN` flags of the template are only set
+/// on the last segment.
+#[derive(Debug, Clone)]
+pub struct Segmenter<'a> {
+    headers: &'a [u8],
+    ip_header_len: usize,
+    payload: &'a [u8],
+    mss: usize,
+    sent: usize,
+    segments: u16,
+}
+
+impl<'a> Segmenter<'a> {
+    /// Create a new segmenter that sends `payload` in segments of at most
+    /// `mss` bytes, using the header template `headers`.
+    pub fn new(
+        headers: &'a [u8],
+        payload: &'a [u8],
+        mss: usize,
+    ) -> Result<Self, SegmentationError> {
+        if headers.len() < ETH_HEADER_LEN + 20 || headers[12..14] != ETHERTYPE_IPV4 {
+            return Err(SegmentationError::InvalidHeaders);
+        }
+
+        let ip = &headers[ETH_HEADER_LEN..];
+        let ip_header_len = ((ip[0] & 0x0F) as usize) * 4;
+        if ip[0] >> 4 != 4 || ip_header_len < 20 || ip[9] != IP_PROTOCOL_TCP {
+            return Err(SegmentationError::InvalidHeaders);
+        }
+
+        let tcp_offset = ETH_HEADER_LEN + ip_header_len;
+        if headers.len() < tcp_offset + 20 {
+            return Err(SegmentationError::InvalidHeaders);
+        }
+
+        let tcp_header_len = ((headers[tcp_offset + 12] >> 4) as usize) * 4;
+        if tcp_header_len < 20 || headers.len() != tcp_offset + tcp_header_len {
+            return Err(SegmentationError::InvalidHeaders);
+        }
+
+        if mss == 0 || headers.len() + mss > MTU {
+            return Err(SegmentationError::InvalidMss);
+        }
+
+        Ok(Self {
+            headers,
+            ip_header_len,
+            payload,
+            mss,
+            sent: 0,
+            segments: 0,
+        })
+    }
+
+    /// Check whether all of the payload has been sent.
+    pub fn is_done(&self) -> bool {
+        self.sent == self.payload.len()
+    }
+
+    /// The amount of payload that has not been sent yet.
+    pub fn remaining(&self) -> usize {
+        self.payload.len() - self.sent
+    }
+
+    /// Send as many segments as possible.
+    ///
+    /// Returns [`Err(TxError::WouldBlock)`](TxError::WouldBlock) if no
+    /// TX slot was available before all segments were sent. In that case,
+    /// this function should be called again once a TX slot frees up.
+    pub fn send<const RX_BUF: usize>(
+        &mut self,
+        dma: &mut EthernetDMA<'_, '_, RX_BUF>,
+    ) -> Result<(), TxError> {
+        while let Some(len) = self.next_len() {
+            let this = &*self;
+            dma.send(len, None, |buf| this.write_next(buf))?;
+            self.advance();
+        }
+        Ok(())
+    }
+
+    /// The length of the next frame, if any segments remain.
+    fn next_len(&self) -> Option<usize> {
+        if self.is_done() {
+            None
+        } else {
+            Some(self.headers.len() + self.remaining().min(self.mss))
+        }
+    }
+
+    /// Write the next frame into `buf`, which must be exactly
+    /// as long as [`Segmenter::next_len`] returned.
+    fn write_next(&self, buf: &mut [u8]) {
+        let (headers, payload) = buf.split_at_mut(self.headers.len());
+        let payload_len = payload.len();
+        let is_last = self.sent + payload_len == self.payload.len();
+
+        headers.copy_from_slice(self.headers);
+        payload.copy_from_slice(&self.payload[self.sent..self.sent + payload_len]);
+
+        let ip = &mut headers[ETH_HEADER_LEN..];
+        let total_len = (ip.len() + payload_len) as u16;
+        ip[2..4].copy_from_slice(&total_len.to_be_bytes());
+        let id = u16::from_be_bytes([ip[4], ip[5]]).wrapping_add(self.segments);
+        ip[4..6].copy_from_slice(&id.to_be_bytes());
+        ip[10..12].copy_from_slice(&[0, 0]);
+
+        let tcp = &mut ip[self.ip_header_len..];
+        let seq =
+            u32::from_be_bytes([tcp[4], tcp[5], tcp[6], tcp[7]]).wrapping_add(self.sent as u32);
+        tcp[4..8].copy_from_slice(&seq.to_be_bytes());
+        if !is_last {
+            tcp[13] &= !(TCP_FLAG_PSH | TCP_FLAG_FIN);
+        }
+        tcp[16..18].copy_from_slice(&[0, 0]);
+    }
+
+    /// Advance past the segment that was written last.
+    fn advance(&mut self) {
+        self.sent += self.remaining().min(self.mss);
+        self.segments = self.segments.wrapping_add(1);
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    const ACK: u8 = 1 << 4;
+
+    fn headers() -> [u8; 54] {
+        let mut headers = [0u8; 54];
+        headers[12..14].copy_from_slice(&ETHERTYPE_IPV4);
+        let ip = &mut headers[ETH_HEADER_LEN..];
+        ip[0] = 0x45;
+        ip[4..6].copy_from_slice(&0xFFFFu16.to_be_bytes());
+        ip[9] = IP_PROTOCOL_TCP;
+        ip[10..12].copy_from_slice(&[0xAB, 0xCD]);
+        let tcp = &mut ip[20..];
+        tcp[4..8].copy_from_slice(&100u32.to_be_bytes());
+        tcp[12] = 5 << 4;
+        tcp[13] = ACK | TCP_FLAG_PSH | TCP_FLAG_FIN;
+        headers
+    }
+
+    #[test]
+    fn segments_payload() {
+        let headers = headers();
+        let payload: [u8; 25] = core::array::from_fn(|i| i as u8);
+
+        assert_eq!(
+            Segmenter::new(&headers[..40], &payload, 10).err(),
+            Some(SegmentationError::InvalidHeaders)
+        );
+        assert_eq!(
+            Segmenter::new(&headers, &payload, MTU).err(),
+            Some(SegmentationError::InvalidMss)
+        );
+
+        let mut segmenter = Segmenter::new(&headers, &payload, 10).unwrap();
+        let mut frame = [0u8; 64];
+        let expected = [
+            (100u32, 0xFFFFu16, 10usize, ACK),
+            (110, 0, 10, ACK),
+            (120, 1, 5, ACK | TCP_FLAG_PSH | TCP_FLAG_FIN),
+        ];
+
+        for (seq, id, payload_len, flags) in expected {
+            let len = segmenter.next_len().unwrap();
+            assert_eq!(len, 54 + payload_len);
+            segmenter.write_next(&mut frame[..len]);
+            segmenter.advance();
+
+            let ip = &frame[ETH_HEADER_LEN..len];
+            assert_eq!(
+                u16::from_be_bytes([ip[2], ip[3]]) as usize,
+                40 + payload_len
+            );
+            assert_eq!(u16::from_be_bytes([ip[4], ip[5]]), id);
+            assert_eq!(&ip[10..12], &[0, 0]);
+            assert_eq!(u32::from_be_bytes([ip[24], ip[25], ip[26], ip[27]]), seq);
+            assert_eq!(ip[33], flags);
+            assert_eq!(ip[40], (seq - 100) as u8);
+        }
+
+        assert!(segmenter.is_done());
+        assert_eq!(segmenter.next_len(), None);
+    }
+}
@@ -12,7 +12,7 @@ use stm32f4xx_hal::{
         gpiob::{PB11, PB12, PB13},
         gpioc::{PC4, PC5},
         gpiog::{PG11, PG13, PG14},
-        Input,
+        Alternate, Input,
         Speed::VeryHigh,
     },
     pac::{RCC, SYSCFG},
@@ -28,22 +28,80 @@ use stm32f7xx_hal::{
         gpiob::{PB11, PB12, PB13},
         gpioc::{PC4, PC5},
         gpiog::{PG11, PG13, PG14},
-        Input,
+        Alternate, Input,
         Speed::VeryHigh,
     },
     pac::{RCC, SYSCFG},
 };
 
 use crate::{
-    dma::EthernetDMA,
+    dma::{EthernetDMA, MTU},
     stm32::{ETHERNET_DMA, ETHERNET_MAC, ETHERNET_MMC},
 };
 
 #[cfg(feature = "ptp")]
 use crate::{ptp::EthernetPTP, stm32::ETHERNET_PTP};
 
+use core::sync::atomic::{AtomicBool, Ordering};
+
+/// The interface between the MAC and the PHY.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaInterface {
+    /// The Media Independent Interface.
+    Mii,
+    /// The Reduced Media Independent Interface.
+    Rmii,
+}
+
+/// The media interface can not be changed while the driver is in use.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DriverInUse;
+
+static MII_SELECTED: AtomicBool = AtomicBool::new(false);
+static DRIVER_IN_USE: AtomicBool = AtomicBool::new(false);
+
+/// Select the media interface that the MAC is configured for when the
+/// driver is created, for hardware that can route either interface to
+/// the PHY. The default is [`MediaInterface::Rmii`].
+///
+/// The selection can only be changed while the MAC is disabled or held
+/// in reset, which [`new`](crate::new) and [`new_with_mii`](crate::new_with_mii)
+/// take care of. To switch the interface at runtime, drop the
+/// [`EthernetDMA`] (which stops the DMA), select the new interface, and
+/// create the driver again. If the [`EthernetDMA`] still exists, this
+/// function returns [`Err(DriverInUse)`](DriverInUse).
+///
+/// The pins are only configured for RMII by the driver, so the MII pins
+/// must be configured by the application.
+pub fn select_media_interface(interface: MediaInterface) -> Result<(), DriverInUse> {
+    if DRIVER_IN_USE.load(Ordering::Acquire) {
+        return Err(DriverInUse);
+    }
+
+    MII_SELECTED.store(interface == MediaInterface::Mii, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Get the media interface that the MAC is configured for.
+pub fn media_interface() -> MediaInterface {
+    if MII_SELECTED.load(Ordering::Relaxed) {
+        MediaInterface::Mii
+    } else {
+        MediaInterface::Rmii
+    }
+}
+
+/// Record whether an [`EthernetDMA`] exists.
+pub(crate) fn set_driver_in_use(in_use: bool) {
+    DRIVER_IN_USE.store(in_use, Ordering::Release);
+}
+
 // Enable syscfg and ethernet clocks. Reset the Ethernet MAC.
 pub(crate) fn setup() {
+    let rmii = media_interface() == MediaInterface::Rmii;
+
     #[cfg(feature = "stm32f4xx-hal")]
     unsafe {
         const SYSCFG_BIT: u8 = 14;
@@ -65,7 +123,11 @@ pub(crate) fn setup() {
         }
         // select MII or RMII mode
         // 0 = MII, 1 = RMII
-        bb::set(&syscfg.pmc, MII_RMII_BIT);
+        if rmii {
+            bb::set(&syscfg.pmc, MII_RMII_BIT);
+        } else {
+            bb::clear(&syscfg.pmc, MII_RMII_BIT);
+        }
 
         // enable ethernet clocks
         bb::set(&rcc.ahb1enr, ETH_MAC_BIT);
@@ -92,7 +154,7 @@ pub(crate) fn setup() {
 
         // select MII or RMII mode
         // 0 = MII, 1 = RMII
-        syscfg.pmc.modify(|_, w| w.mii_rmii_sel().set_bit());
+        syscfg.pmc.modify(|_, w| w.mii_rmii_sel().bit(rmii));
 
         // enable ethernet clocks
         rcc.ahb1enr.modify(|_, w| {
@@ -124,7 +186,7 @@ pub(crate) fn setup() {
 
         // select MII or RMII mode
         // 0 = MII, 1 = RMII
-        afio.mapr.modify(|_, w| w.mii_rmii_sel().set_bit());
+        afio.mapr.modify(|_, w| w.mii_rmii_sel().bit(rmii));
 
         // enable ethernet clocks
         rcc.ahbenr.modify(|_, w| {
@@ -179,11 +241,43 @@ pin_trait!(
 );
 
 /// Trait needed to setup the pins for the Ethernet peripheral.
+///
+/// This trait is only implemented for pins in their reset mode, and (on
+/// the STM32F4 and STM32F7) for pins that are already in alternate function
+/// 11. Passing a pin in any other mode is a compile error.
 pub trait AlternateVeryHighSpeed {
     /// Puts the pin in the Alternate Function 11 with Very High Speed.
     fn into_af11_very_high_speed(self);
 }
 
+/// A source for the frequency of HCLK, which is required to configure
+/// the MII clock and the PTP clock.
+///
+/// This trait is implemented for the `Clocks` of the HAL, and for
+/// [`HclkHz`], so that the driver can be constructed without the HAL's
+/// clock configuration.
+pub trait Hclk {
+    /// The frequency of HCLK, in Hz.
+    fn hclk_hz(&self) -> u32;
+}
+
+impl Hclk for crate::hal::rcc::Clocks {
+    fn hclk_hz(&self) -> u32 {
+        self.hclk().to_Hz()
+    }
+}
+
+/// The frequency of HCLK, in Hz.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HclkHz(pub u32);
+
+impl Hclk for HclkHz {
+    fn hclk_hz(&self) -> u32 {
+        self.0
+    }
+}
+
 /// A struct that contains all peripheral parts required to configure
 /// the ethernet peripheral.
 #[allow(missing_docs)]
@@ -219,28 +313,28 @@ impl From<(ETHERNET_MAC, ETHERNET_MMC, ETHERNET_DMA)> for PartsIn {
 }
 
 /// Access to all configured parts of the ethernet peripheral.
-pub struct Parts<'rx, 'tx, T> {
+pub struct Parts<'rx, 'tx, T, const RX_BUF: usize = MTU> {
     /// Access to and control over the ethernet MAC.
     pub mac: T,
     /// Access to and control over the ethernet DMA.
-    pub dma: EthernetDMA<'rx, 'tx>,
+    pub dma: EthernetDMA<'rx, 'tx, RX_BUF>,
     /// Access to and control over the ethernet PTP module.
     #[cfg(feature = "ptp")]
     pub ptp: EthernetPTP,
 }
 
 #[cfg(feature = "ptp")]
-impl<'rx, 'tx, T> Parts<'rx, 'tx, T> {
+impl<'rx, 'tx, T, const RX_BUF: usize> Parts<'rx, 'tx, T, RX_BUF> {
     /// Split this [`Parts`] into its components.
-    pub fn split(self) -> (T, EthernetDMA<'rx, 'tx>, EthernetPTP) {
+    pub fn split(self) -> (T, EthernetDMA<'rx, 'tx, RX_BUF>, EthernetPTP) {
         (self.mac, self.dma, self.ptp)
     }
 }
 
 #[cfg(not(feature = "ptp"))]
-impl<'rx, 'tx, T> Parts<'rx, 'tx, T> {
+impl<'rx, 'tx, T, const RX_BUF: usize> Parts<'rx, 'tx, T, RX_BUF> {
     /// Split this [`Parts`] into its components.
-    pub fn split(self) -> (T, EthernetDMA<'rx, 'tx>) {
+    pub fn split(self) -> (T, EthernetDMA<'rx, 'tx, RX_BUF>) {
         (self.mac, self.dma)
     }
 }
@@ -290,46 +384,50 @@ where
 
 #[allow(unused_macros)]
 macro_rules! impl_pins {
-    ( $($traity:ident: [$($pin:ty,)+],)+ ) => {
+    ( $($traity:ident: [$($pin:ident,)+],)+ ) => {
         $(
             $(
-                unsafe impl $traity for $pin {}
-
-                impl AlternateVeryHighSpeed for $pin {
-                    fn into_af11_very_high_speed(self) {
-                        self.into_alternate::<11>().set_speed(VeryHigh);
-                    }
-                }
+                impl_pins!(@mode $traity, $pin<Input>);
+                impl_pins!(@mode $traity, $pin<Alternate<11>>);
             )+
         )+
     };
+    (@mode $traity:ident, $pin:ty) => {
+        unsafe impl $traity for $pin {}
+
+        impl AlternateVeryHighSpeed for $pin {
+            fn into_af11_very_high_speed(self) {
+                self.into_alternate::<11>().set_speed(VeryHigh);
+            }
+        }
+    };
 }
 
 #[cfg(any(feature = "stm32f4xx-hal", feature = "stm32f7xx-hal"))]
 impl_pins!(
     RmiiRefClk: [
-        PA1<Input>,
+        PA1,
     ],
     RmiiCrsDv: [
-        PA7<Input>,
+        PA7,
     ],
     RmiiTxEN: [
-        PB11<Input>,
-        PG11<Input>,
+        PB11,
+        PG11,
     ],
     RmiiTxD0: [
-        PB12<Input>,
-        PG13<Input>,
+        PB12,
+        PG13,
     ],
     RmiiTxD1: [
-        PB13<Input>,
-        PG14<Input>,
+        PB13,
+        PG14,
     ],
     RmiiRxD0: [
-        PC4<Input>,
+        PC4,
     ],
     RmiiRxD1: [
-        PC5<Input>,
+        PC5,
     ],
 );
 
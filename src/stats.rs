@@ -0,0 +1,214 @@
+//! Statistics gathered by the driver.
+//!
+//! The driver aggregates counters from several sources (such as the PHY
+//! and the causes of DMA interrupts) into a single set of [`Statistics`],
+//! which can be retrieved using [`statistics`].
+//!
+//! Every counter is stored in its own atomic, so the counters can be updated
+//! from the `ETH` interrupt while being read from thread context without
+//! taking any locks. Each individual counter is never torn, but a snapshot
+//! is not guaranteed to reflect all counters at a single instant in time.
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+macro_rules! statistics {
+    ($($(#[$meta:meta])* $name:ident,)*) => {
+        /// A snapshot of the statistics gathered by the driver.
+        ///
+        /// All counters wrap around on overflow.
+        #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub struct Statistics {
+            $(
+                $(#[$meta])*
+                pub $name: u32,
+            )*
+        }
+
+        impl Statistics {
+            /// Create a new set of statistics with all counters set to zero.
+            pub const fn new() -> Self {
+                Self {
+                    $($name: 0,)*
+                }
+            }
+        }
+
+        pub(crate) struct Counters {
+            $(pub(crate) $name: AtomicU32,)*
+        }
+
+        impl Counters {
+            const fn new() -> Self {
+                Self {
+                    $($name: AtomicU32::new(0),)*
+                }
+            }
+
+            fn snapshot(&self) -> Statistics {
+                Statistics {
+                    $($name: self.$name.load(Ordering::Relaxed),)*
+                }
+            }
+
+            fn reset(&self) {
+                $(self.$name.store(0, Ordering::Relaxed);)*
+            }
+        }
+    };
+}
+
+statistics!(
+    /// The amount of receive symbol errors reported by the PHY.
+    ///
+    /// This counter is only updated for PHYs that implement
+    /// [`PhyErrorCounters`](crate::mac::phy::PhyErrorCounters), when
+    /// polled using [`LinkMonitor::poll_error_counters`](crate::mac::phy::LinkMonitor::poll_error_counters).
+    phy_symbol_errors,
+    /// The amount of times that the link went up, as reported by
+    /// [`LinkMonitor::poll`](crate::mac::phy::LinkMonitor::poll).
+    link_up,
+    /// The amount of times that the link went down, as reported by
+    /// [`LinkMonitor::poll`](crate::mac::phy::LinkMonitor::poll).
+    ///
+    /// The time of every link change is recorded in the event log
+    /// if the `event-log` feature is enabled.
+    link_down,
+    /// The amount of received frames that were discarded because
+    /// the RX gate was closed.
+    ///
+    /// See [`RxRing::close_gate`](crate::dma::RxRing::close_gate).
+    rx_frames_suppressed,
+    /// The amount of received frames whose FCS was verified in software.
+    ///
+    /// See [`RxRing::start_crc_check`](crate::dma::RxRing::start_crc_check).
+    rx_crc_checked,
+    /// The amount of received frames that the MAC passed as valid,
+    /// but whose FCS did not match the CRC calculated in software.
+    rx_crc_mismatches,
+    /// The amount of received frames that the DMA dropped because no
+    /// RX descriptor was available.
+    ///
+    /// This counter is only updated when the DMA counters are read using
+    /// [`EthernetDMA::rx_drop_counters`](crate::dma::EthernetDMA::rx_drop_counters).
+    rx_missed_frames,
+    /// The amount of received frames that the DMA dropped because the
+    /// RX FIFO overflowed.
+    ///
+    /// This counter is only updated when the DMA counters are read using
+    /// [`EthernetDMA::rx_drop_counters`](crate::dma::EthernetDMA::rx_drop_counters).
+    rx_fifo_overflows,
+    /// The amount of `ETH` interrupts during which the DMA reported
+    /// that a frame was received.
+    dma_rx_interrupts,
+    /// The amount of `ETH` interrupts during which the DMA reported
+    /// that a frame was transmitted.
+    dma_tx_interrupts,
+    /// The amount of `ETH` interrupts during which the DMA reported that
+    /// the next RX descriptor was owned by the CPU.
+    ///
+    /// A high count means that the RX ring is too small, or that
+    /// received frames are not processed quickly enough.
+    dma_rx_buffer_unavailable,
+    /// The amount of `ETH` interrupts during which the DMA reported that
+    /// the next TX descriptor was owned by the CPU.
+    ///
+    /// This is expected whenever the TX ring runs empty.
+    dma_tx_buffer_unavailable,
+    /// The amount of `ETH` interrupts during which the DMA reported that
+    /// the RX process was stopped.
+    dma_rx_process_stopped,
+    /// The amount of `ETH` interrupts during which the DMA reported that
+    /// the TX process was stopped.
+    dma_tx_process_stopped,
+    /// The amount of `ETH` interrupts during which the DMA reported
+    /// a receive FIFO overflow.
+    dma_rx_overflow,
+    /// The amount of `ETH` interrupts during which the DMA reported
+    /// a transmit FIFO underflow.
+    dma_tx_underflow,
+    /// The amount of `ETH` interrupts during which the DMA reported
+    /// a receive watchdog timeout.
+    dma_rx_watchdog_timeout,
+    /// The amount of `ETH` interrupts during which the DMA reported
+    /// a transmit jabber timeout.
+    dma_tx_jabber_timeout,
+    /// The amount of `ETH` interrupts during which the DMA reported
+    /// a fatal bus error.
+    dma_fatal_bus_error,
+    /// The amount of `ETH` interrupts for which no cause was found.
+    ///
+    /// See [`storm`](crate::storm).
+    unhandled_interrupts,
+    /// The amount of interrupt storms after which the unhandled interrupt
+    /// sources were masked.
+    ///
+    /// See [`storm::set_auto_mask_threshold`](crate::storm::set_auto_mask_threshold).
+    interrupt_storms,
+    /// The amount of collisions that occured while transmitting frames.
+    ///
+    /// Collisions only occur in half-duplex mode. This counter, and the
+    /// other TX descriptor counters, are updated when the completion of a
+    /// frame is retrieved using [`TxRing::poll_completion`](crate::dma::TxRing::poll_completion),
+    /// or when its descriptor is reused.
+    tx_collisions,
+    /// The amount of transmitted frames whose transmission was deferred
+    /// because the medium was busy.
+    tx_deferred,
+    /// The amount of frames that were aborted because their transmission
+    /// was deferred for too long.
+    tx_excessive_deferral,
+    /// The amount of pause frames that were requested, either explicitly or
+    /// because the RX ring was exhausted.
+    ///
+    /// See [`flow_control`](crate::mac::flow_control).
+    tx_pause_requests,
+);
+
+impl Default for Statistics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub(crate) fn counters() -> &'static Counters {
+    static COUNTERS: Counters = Counters::new();
+    &COUNTERS
+}
+
+/// Add `value` to `counter`.
+#[inline(always)]
+pub(crate) fn add(counter: &AtomicU32, value: u32) {
+    counter.fetch_add(value, Ordering::Relaxed);
+}
+
+/// Get a snapshot of the current statistics.
+///
+/// This function may be called from any context.
+pub fn statistics() -> Statistics {
+    counters().snapshot()
+}
+
+/// Reset all statistics to zero.
+pub fn reset_statistics() {
+    counters().reset();
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn counters_snapshot_and_reset() {
+        // Use local counters, as the global counters are shared with
+        // other tests that run in parallel.
+        let counters = Counters::new();
+
+        add(&counters.phy_symbol_errors, 3);
+        add(&counters.phy_symbol_errors, u32::MAX);
+        assert_eq!(counters.snapshot().phy_symbol_errors, 2);
+
+        counters.reset();
+        assert_eq!(counters.snapshot(), Statistics::default());
+    }
+}
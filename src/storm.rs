@@ -0,0 +1,89 @@
+//! Detection of `ETH` interrupt storms.
+//!
+//! If an interrupt source is enabled whose status bit is never cleared (for
+//! instance the timestamp trigger interrupt, when it is unmasked without the
+//! PTP support of this crate), the `ETH` interrupt fires continuously and
+//! consumes all CPU time without any visible cause.
+//!
+//! [`eth_interrupt_handler`](crate::eth_interrupt_handler) counts every
+//! interrupt that it did not find a cause for in
+//! [`Statistics::unhandled_interrupts`](crate::stats::Statistics::unhandled_interrupts).
+//! If an auto-mask threshold is configured using [`set_auto_mask_threshold`],
+//! all interrupt sources that the driver does not handle are masked once that
+//! many consecutive interrupts were unhandled, and the storm is reported in
+//! [`InterruptReason::interrupt_storm`](crate::InterruptReason::interrupt_storm).
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate::{
+    mac::{EthernetMAC, MacInterrupts},
+    peripherals::ETHERNET_DMA,
+};
+
+/// The MAC interrupt sources that the driver does not handle.
+///
+/// The timestamp trigger interrupt is handled by
+/// [`eth_interrupt_handler`](crate::eth_interrupt_handler) if PTP support
+/// is enabled, except on the STM32F1.
+const UNHANDLED_MAC_INTERRUPTS: MacInterrupts = MacInterrupts {
+    pmt: true,
+    timestamp_trigger: !cfg!(all(feature = "ptp", not(feature = "stm32f1xx-hal"))),
+};
+
+static CONSECUTIVE_UNHANDLED: AtomicU32 = AtomicU32::new(0);
+static THRESHOLD: AtomicU32 = AtomicU32::new(0);
+
+/// Mask all interrupt sources that the driver does not handle once
+/// `threshold` consecutive `ETH` interrupts were unhandled.
+///
+/// `None` disables masking, which is the default. Unhandled interrupts
+/// are counted regardless.
+pub fn set_auto_mask_threshold(threshold: Option<u32>) {
+    THRESHOLD.store(threshold.unwrap_or(0), Ordering::Relaxed);
+}
+
+/// Update the storm detection with whether the current `ETH` interrupt
+/// had a cause that was handled.
+///
+/// Returns `true` if an interrupt storm was detected, and the unhandled
+/// interrupt sources were masked.
+pub(crate) fn check(handled: bool) -> bool {
+    if handled {
+        CONSECUTIVE_UNHANDLED.store(0, Ordering::Relaxed);
+        return false;
+    }
+
+    crate::stats::add(&crate::stats::counters().unhandled_interrupts, 1);
+
+    let consecutive = CONSECUTIVE_UNHANDLED.fetch_add(1, Ordering::Relaxed) + 1;
+    let threshold = THRESHOLD.load(Ordering::Relaxed);
+    if threshold == 0 || consecutive < threshold {
+        return false;
+    }
+
+    CONSECUTIVE_UNHANDLED.store(0, Ordering::Relaxed);
+    mask_unhandled_sources();
+
+    crate::stats::add(&crate::stats::counters().interrupt_storms, 1);
+
+    #[cfg(feature = "event-log")]
+    crate::event_log::record(crate::event_log::DriverEvent::InterruptStorm);
+
+    true
+}
+
+fn mask_unhandled_sources() {
+    EthernetMAC::modify_interrupt_mask(UNHANDLED_MAC_INTERRUPTS, true);
+
+    // SAFETY: we only clear interrupt enable bits that the driver never sets.
+    let eth_dma = unsafe { &*ETHERNET_DMA::ptr() };
+    eth_dma.dmaier.modify(|_, w| {
+        w
+            // Early receive interrupt enable
+            .erie()
+            .clear_bit()
+            // Early transmit interrupt enable
+            .etie()
+            .clear_bit()
+    });
+}
@@ -0,0 +1,97 @@
+//! Hooks for forcing error paths of the driver.
+//!
+//! When the `test-hooks` feature is enabled, [`inject`] makes the driver
+//! behave as if the hardware reported a fault, so that the recovery logic
+//! of an application can be exercised on the bench. Injected faults go
+//! through the same code paths as real ones: they are counted in the
+//! [`Statistics`](crate::stats::Statistics), recorded in the event log, and
+//! reported by [`eth_interrupt_handler`](crate::eth_interrupt_handler).
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use cortex_m::peripheral::NVIC;
+
+use crate::{dma::AbnormalInterrupts, stm32::Interrupt};
+
+/// A fault that can be injected into the driver.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// The DMA reports that the next RX descriptor was owned by the CPU.
+    RxBufferUnavailable,
+    /// The DMA reports a fatal bus error.
+    ///
+    /// Only the report is simulated: the DMA keeps running.
+    FatalBusError,
+    /// The DMA reports a transmit FIFO underflow.
+    TxUnderflow,
+    /// The next received frame has its error summary bit set, and is
+    /// reported as [`RxError::DmaError`](crate::dma::RxError::DmaError).
+    RxDescriptorCorruption,
+}
+
+impl Fault {
+    const fn bit(&self) -> u32 {
+        1 << *self as u32
+    }
+}
+
+static PENDING: AtomicU32 = AtomicU32::new(0);
+
+/// Inject `fault` into the driver.
+///
+/// DMA faults are reported by the next call to
+/// [`eth_interrupt_handler`](crate::eth_interrupt_handler), which is
+/// triggered by pending the `ETH` interrupt.
+pub fn inject(fault: Fault) {
+    PENDING.fetch_or(fault.bit(), Ordering::Relaxed);
+
+    if fault != Fault::RxDescriptorCorruption {
+        NVIC::pend(Interrupt::ETH);
+    }
+}
+
+fn take(fault: Fault) -> bool {
+    PENDING.fetch_and(!fault.bit(), Ordering::Relaxed) & fault.bit() != 0
+}
+
+/// Take the injected DMA faults, which are reported as if the
+/// DMA had reported them.
+pub(crate) fn take_dma_faults() -> AbnormalInterrupts {
+    AbnormalInterrupts {
+        rx_buffer_unavailable: take(Fault::RxBufferUnavailable),
+        fatal_bus_error: take(Fault::FatalBusError),
+        tx_underflow: take(Fault::TxUnderflow),
+        ..AbnormalInterrupts::NONE
+    }
+}
+
+/// Take an injected RX descriptor corruption.
+#[cfg(not(feature = "tx-only"))]
+pub(crate) fn take_rx_corruption() -> bool {
+    take(Fault::RxDescriptorCorruption)
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn faults_are_taken_once() {
+        PENDING.fetch_or(Fault::FatalBusError.bit(), Ordering::Relaxed);
+
+        let faults = take_dma_faults();
+        assert!(faults.fatal_bus_error);
+        assert!(!faults.rx_buffer_unavailable);
+        assert!(!take_dma_faults().any());
+    }
+
+    #[test]
+    #[cfg(not(feature = "tx-only"))]
+    fn rx_corruption_is_taken_once() {
+        PENDING.fetch_or(Fault::RxDescriptorCorruption.bit(), Ordering::Relaxed);
+
+        assert!(take_rx_corruption());
+        assert!(!take_rx_corruption());
+    }
+}
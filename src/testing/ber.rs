@@ -0,0 +1,164 @@
+use crate::dma::EthernetDMA;
+
+use super::{
+    generator::PAYLOAD_OFFSET, PacketGenerator, PacketVerifier, Payload, Verdict, VerifierStats,
+    MAX_FRAME_LEN,
+};
+
+/// The amount of payload bits in a frame sent by a [`BerTest`].
+const FRAME_PAYLOAD_BITS: u64 = ((MAX_FRAME_LEN - PAYLOAD_OFFSET) * 8) as u64;
+
+/// The results of a [`BerTest`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BerReport {
+    /// The amount of frames that were received.
+    pub frames: u32,
+    /// The amount of received frames that contained bit errors.
+    pub errored_frames: u32,
+    /// The amount of frames that were never received.
+    ///
+    /// Frames with an FCS error are dropped by the MAC, so bit errors
+    /// usually show up as lost frames.
+    pub lost_frames: u32,
+    /// The amount of payload bits that were verified.
+    pub bits: u64,
+    /// The amount of payload bits that were received in error.
+    pub bit_errors: u64,
+}
+
+impl BerReport {
+    fn from_stats(stats: VerifierStats) -> Self {
+        Self {
+            frames: stats.received + stats.corrupted,
+            errored_frames: stats.corrupted,
+            lost_frames: stats.lost,
+            bits: stats.payload_bits,
+            bit_errors: stats.bit_errors,
+        }
+    }
+
+    /// The estimated bit error rate.
+    ///
+    /// Every lost frame is counted as a frame with a single bit error, as
+    /// that is the most likely reason for an FCS error. Returns `None` if
+    /// no frames were received or lost.
+    pub fn ber(&self) -> Option<f32> {
+        let bits = self.bits + self.lost_frames as u64 * FRAME_PAYLOAD_BITS;
+        let errors = self.bit_errors + self.lost_frames as u64;
+
+        if bits == 0 {
+            None
+        } else {
+            Some(errors as f32 / bits as f32)
+        }
+    }
+
+    /// The upper bound of the bit error rate with a confidence level of 95%,
+    /// if no errors were observed at all.
+    ///
+    /// Returns `None` if any errors were observed, or if no bits were verified.
+    pub fn ber_upper_bound(&self) -> Option<f32> {
+        if self.bit_errors != 0 || self.lost_frames != 0 || self.bits == 0 {
+            None
+        } else {
+            // ln(1 / (1 - 0.95)) ~= 3
+            Some(3.0 / self.bits as f32)
+        }
+    }
+}
+
+/// A bit error rate test.
+///
+/// One node streams [`MAX_FRAME_LEN`]-sized frames with a PRBS-15 payload
+/// using [`BerTest::transmit`], while the receiving node verifies them
+/// using [`BerTest::receive`], and reports the results in a [`BerReport`].
+///
+/// This is useful for validating marginal magnetics and cabling in production.
+#[derive(Debug, Clone)]
+pub struct BerTest {
+    generator: PacketGenerator,
+    verifier: PacketVerifier,
+}
+
+impl BerTest {
+    /// Create a new bit error rate test, which sends frames from `src`
+    /// to `dst` with the given `ethertype`.
+    pub fn new(src: [u8; 6], dst: [u8; 6], ethertype: u16) -> Self {
+        Self {
+            generator: PacketGenerator::new(src, dst, ethertype).payload(Payload::Prbs15),
+            verifier: PacketVerifier::new(ethertype, Payload::Prbs15),
+        }
+    }
+
+    /// Send test frames until the TX ring is full.
+    ///
+    /// Returns the amount of frames that were sent.
+    pub fn transmit<const RX_BUF: usize>(
+        &mut self,
+        dma: &mut EthernetDMA<'_, '_, RX_BUF>,
+    ) -> usize {
+        let mut sent = 0;
+
+        loop {
+            let generator = &mut self.generator;
+            match dma.send(generator.next_len(), None, |buf| generator.fill(buf)) {
+                Ok(()) => sent += 1,
+                Err(_) => break sent,
+            }
+        }
+    }
+
+    /// Verify all received frames.
+    ///
+    /// Returns the amount of test frames that were received.
+    pub fn receive<const RX_BUF: usize>(&mut self, dma: &mut EthernetDMA<'_, '_, RX_BUF>) -> usize {
+        let mut received = 0;
+
+        while let Ok(packet) = dma.recv_next(None) {
+            if self.verifier.check(&packet) != Verdict::Ignored {
+                received += 1;
+            }
+        }
+
+        received
+    }
+
+    /// The results of this test so far.
+    pub fn report(&self) -> BerReport {
+        BerReport::from_stats(self.verifier.stats())
+    }
+
+    /// Reset the results of this test.
+    pub fn reset(&mut self) {
+        self.verifier.reset();
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ber_estimate() {
+        let report = BerReport {
+            frames: 999,
+            errored_frames: 1,
+            lost_frames: 1,
+            bits: 999 * FRAME_PAYLOAD_BITS,
+            bit_errors: 3,
+        };
+
+        let expected = 4.0 / (1000 * FRAME_PAYLOAD_BITS) as f32;
+        assert!((report.ber().unwrap() - expected).abs() < expected * 1e-3);
+        assert_eq!(report.ber_upper_bound(), None);
+
+        let report = BerReport {
+            frames: 1,
+            bits: 3_000_000,
+            ..Default::default()
+        };
+        assert_eq!(report.ber(), Some(0.0));
+        assert_eq!(report.ber_upper_bound(), Some(1e-6));
+    }
+}
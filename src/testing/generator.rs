@@ -0,0 +1,486 @@
+use super::{MAX_FRAME_LEN, MIN_FRAME_LEN};
+
+const SEQUENCE_OFFSET: usize = 14;
+pub(super) const PAYLOAD_OFFSET: usize = SEQUENCE_OFFSET + 4;
+
+/// The pattern with which the payload of a generated frame is filled.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Payload {
+    /// All payload bytes are zero.
+    Zeros,
+    /// Every payload byte is one larger than the previous byte, starting
+    /// at the lowest byte of the sequence number of the frame.
+    Incrementing,
+    /// A PRBS-15 (x^15 + x^14 + 1) sequence, seeded with the sequence
+    /// number of the frame.
+    Prbs15,
+}
+
+impl Payload {
+    fn bytes(self, sequence: u32) -> impl Iterator<Item = u8> {
+        let mut counter = sequence as u8;
+        let mut lfsr = prbs15_seed(sequence);
+
+        core::iter::repeat(()).map(move |_| match self {
+            Payload::Zeros => 0,
+            Payload::Incrementing => {
+                let value = counter;
+                counter = counter.wrapping_add(1);
+                value
+            }
+            Payload::Prbs15 => {
+                let mut value = 0;
+                for _ in 0..8 {
+                    let bit = ((lfsr >> 14) ^ (lfsr >> 13)) & 1;
+                    lfsr = ((lfsr << 1) | bit) & 0x7FFF;
+                    value = (value << 1) | bit as u8;
+                }
+                value
+            }
+        })
+    }
+}
+
+fn prbs15_seed(sequence: u32) -> u16 {
+    let seed = (sequence ^ (sequence >> 15)) as u16 & 0x7FFF;
+    if seed == 0 {
+        1
+    } else {
+        seed
+    }
+}
+
+/// The distribution of the lengths of generated frames.
+///
+/// All lengths are clamped to the range
+/// [`MIN_FRAME_LEN`]..=[`MAX_FRAME_LEN`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeDistribution {
+    /// All frames have the same length.
+    Fixed(usize),
+    /// Frame lengths are uniformly distributed between `min` and `max` (inclusive).
+    Uniform {
+        /// The minimum frame length.
+        min: usize,
+        /// The maximum frame length.
+        max: usize,
+    },
+    /// Frame lengths increase from `min` to `max` (inclusive) in increments
+    /// of `step`, and then start at `min` again.
+    Sweep {
+        /// The minimum frame length.
+        min: usize,
+        /// The maximum frame length.
+        max: usize,
+        /// The increment between consecutive frame lengths.
+        step: usize,
+    },
+}
+
+impl SizeDistribution {
+    fn bounds(&self) -> (usize, usize) {
+        let (min, max) = match *self {
+            SizeDistribution::Fixed(len) => (len, len),
+            SizeDistribution::Uniform { min, max } | SizeDistribution::Sweep { min, max, .. } => {
+                (min, max)
+            }
+        };
+
+        let min = min.clamp(MIN_FRAME_LEN, MAX_FRAME_LEN);
+        let max = max.clamp(min, MAX_FRAME_LEN);
+        (min, max)
+    }
+}
+
+/// The rate at which a [`PacketGenerator`] produces frames.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rate {
+    /// Produce frames as fast as they can be transmitted.
+    Unlimited,
+    /// Produce at most the given amount of frames per second.
+    FramesPerSecond(u32),
+}
+
+/// A generator of Ethernet frames for load testing.
+///
+/// Every frame carries a sequence number, which a [`PacketVerifier`] on
+/// the receiving side uses to detect lost, reordered, and corrupted frames.
+///
+/// The layout of a generated frame is:
+///
+/// | Offset | Length | Contents                              |
+/// |--------|--------|---------------------------------------|
+/// | 0      | 6      | Destination MAC address               |
+/// | 6      | 6      | Source MAC address                    |
+/// | 12     | 2      | EtherType                             |
+/// | 14     | 4      | Sequence number (big endian)          |
+/// | 18     | ...    | Payload, as determined by [`Payload`] |
+///
+/// # Example
+/// ```ignore
+/// let mut generator = PacketGenerator::new(SRC_MAC, DST_MAC, ETH_TYPE)
+///     .size_distribution(SizeDistribution::Uniform { min: 64, max: 1514 })
+///     .payload(Payload::Prbs15);
+///
+/// while generator.ready(now_ms) {
+///     let len = generator.next_len();
+///     if dma.send(len, None, |buf| generator.fill(buf)).is_err() {
+///         break;
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PacketGenerator {
+    dst: [u8; 6],
+    src: [u8; 6],
+    ethertype: u16,
+    sizes: SizeDistribution,
+    payload: Payload,
+    rate: Rate,
+    sequence: u32,
+    next_len: usize,
+    rng: u32,
+    last_refill_ms: Option<u64>,
+    credit_millis: u64,
+}
+
+impl PacketGenerator {
+    /// Create a new generator that produces [`MAX_FRAME_LEN`]-sized frames
+    /// from `src` to `dst` with the given `ethertype`, filled with zeros, as
+    /// fast as possible.
+    pub fn new(src: [u8; 6], dst: [u8; 6], ethertype: u16) -> Self {
+        let mut me = Self {
+            dst,
+            src,
+            ethertype,
+            sizes: SizeDistribution::Fixed(MAX_FRAME_LEN),
+            payload: Payload::Zeros,
+            rate: Rate::Unlimited,
+            sequence: 0,
+            next_len: MAX_FRAME_LEN,
+            rng: 0x2545_F491,
+            last_refill_ms: None,
+            credit_millis: 0,
+        };
+        me.advance_len();
+        me
+    }
+
+    /// Set the size distribution of generated frames.
+    pub fn size_distribution(mut self, sizes: SizeDistribution) -> Self {
+        self.sizes = sizes;
+        self.next_len = 0;
+        self.advance_len();
+        self
+    }
+
+    /// Set the payload pattern of generated frames.
+    pub fn payload(mut self, payload: Payload) -> Self {
+        self.payload = payload;
+        self
+    }
+
+    /// Set the rate at which frames are generated.
+    pub fn rate(mut self, rate: Rate) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    /// The sequence number of the next frame.
+    pub fn sequence(&self) -> u32 {
+        self.sequence
+    }
+
+    /// The length of the next frame.
+    pub fn next_len(&self) -> usize {
+        self.next_len
+    }
+
+    /// Check whether the next frame may be sent at time `now_ms`, according
+    /// to the configured [`Rate`].
+    ///
+    /// If this function returns `true`, the caller should send the next frame
+    /// using [`PacketGenerator::fill`].
+    pub fn ready(&mut self, now_ms: u64) -> bool {
+        let fps = match self.rate {
+            Rate::Unlimited => return true,
+            Rate::FramesPerSecond(fps) => fps as u64,
+        };
+
+        // Allow a burst of at most one second worth of frames.
+        let max_credit = fps * 1000;
+
+        let elapsed = self
+            .last_refill_ms
+            .map(|last| now_ms.saturating_sub(last))
+            .unwrap_or(1000);
+        self.last_refill_ms = Some(now_ms);
+        self.credit_millis = (self.credit_millis + elapsed * fps).min(max_credit);
+
+        self.credit_millis >= 1000
+    }
+
+    /// Write the next frame into `buffer`, and advance to the next frame.
+    ///
+    /// `buffer` should be [`PacketGenerator::next_len`] bytes long.
+    pub fn fill(&mut self, buffer: &mut [u8]) {
+        buffer[0..6].copy_from_slice(&self.dst);
+        buffer[6..12].copy_from_slice(&self.src);
+        buffer[12..14].copy_from_slice(&self.ethertype.to_be_bytes());
+        buffer[SEQUENCE_OFFSET..PAYLOAD_OFFSET].copy_from_slice(&self.sequence.to_be_bytes());
+
+        buffer[PAYLOAD_OFFSET..]
+            .iter_mut()
+            .zip(self.payload.bytes(self.sequence))
+            .for_each(|(b, v)| *b = v);
+
+        self.sequence = self.sequence.wrapping_add(1);
+        self.credit_millis = self.credit_millis.saturating_sub(1000);
+        self.advance_len();
+    }
+
+    fn advance_len(&mut self) {
+        let (min, max) = self.sizes.bounds();
+
+        self.next_len = match self.sizes {
+            SizeDistribution::Fixed(_) => min,
+            SizeDistribution::Uniform { .. } => {
+                // xorshift32
+                self.rng ^= self.rng << 13;
+                self.rng ^= self.rng >> 17;
+                self.rng ^= self.rng << 5;
+                min + (self.rng as usize % (max - min + 1))
+            }
+            SizeDistribution::Sweep { step, .. } => {
+                if self.next_len < min {
+                    min
+                } else {
+                    let next = self.next_len + step.max(1);
+                    if next > max {
+                        min
+                    } else {
+                        next
+                    }
+                }
+            }
+        };
+    }
+}
+
+/// The result of checking a received frame with a [`PacketVerifier`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// The frame is the expected next frame.
+    Ok,
+    /// The frame is intact, but one or more frames before it were lost.
+    Lost(u32),
+    /// The frame is intact, but arrived after a frame with a higher
+    /// sequence number.
+    Reordered,
+    /// The payload of the frame does not match its sequence number. Contains
+    /// the amount of erroneous payload bits.
+    Corrupted(u32),
+    /// The frame was not produced by a matching [`PacketGenerator`].
+    Ignored,
+}
+
+/// Counters kept by a [`PacketVerifier`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct VerifierStats {
+    /// The amount of intact frames that were received.
+    pub received: u32,
+    /// The amount of bytes in intact frames that were received.
+    pub received_bytes: u64,
+    /// The amount of frames that were skipped in the sequence.
+    ///
+    /// Frames that arrive late are subtracted from this counter again.
+    pub lost: u32,
+    /// The amount of frames that arrived out of order.
+    pub reordered: u32,
+    /// The amount of frames with a corrupted payload.
+    pub corrupted: u32,
+    /// The amount of payload bits that were compared.
+    pub payload_bits: u64,
+    /// The amount of payload bits that did not match the expected payload.
+    pub bit_errors: u64,
+}
+
+/// Verifies frames produced by a [`PacketGenerator`].
+#[derive(Debug, Clone)]
+pub struct PacketVerifier {
+    ethertype: u16,
+    payload: Payload,
+    expected: Option<u32>,
+    stats: VerifierStats,
+}
+
+impl PacketVerifier {
+    /// Create a new verifier for frames with the given `ethertype` and `payload`.
+    pub fn new(ethertype: u16, payload: Payload) -> Self {
+        Self {
+            ethertype,
+            payload,
+            expected: None,
+            stats: VerifierStats::default(),
+        }
+    }
+
+    /// The counters gathered by this verifier.
+    pub fn stats(&self) -> VerifierStats {
+        self.stats
+    }
+
+    /// Reset the counters and the expected sequence number of this verifier.
+    pub fn reset(&mut self) {
+        self.expected = None;
+        self.stats = VerifierStats::default();
+    }
+
+    /// Check a received `frame`, and update the counters of this verifier.
+    pub fn check(&mut self, frame: &[u8]) -> Verdict {
+        if frame.len() < PAYLOAD_OFFSET || frame[12..14] != self.ethertype.to_be_bytes() {
+            return Verdict::Ignored;
+        }
+
+        let mut sequence = [0u8; 4];
+        sequence.copy_from_slice(&frame[SEQUENCE_OFFSET..PAYLOAD_OFFSET]);
+        let sequence = u32::from_be_bytes(sequence);
+
+        let payload = &frame[PAYLOAD_OFFSET..];
+        let bit_errors: u32 = payload
+            .iter()
+            .zip(self.payload.bytes(sequence))
+            .map(|(b, v)| (b ^ v).count_ones())
+            .sum();
+
+        self.stats.payload_bits += payload.len() as u64 * 8;
+        self.stats.bit_errors += bit_errors as u64;
+
+        if bit_errors != 0 {
+            self.stats.corrupted += 1;
+            return Verdict::Corrupted(bit_errors);
+        }
+
+        self.stats.received += 1;
+        self.stats.received_bytes += frame.len() as u64;
+
+        let expected = self.expected.unwrap_or(sequence);
+        // Interpret the difference as signed, so that wrapping sequence
+        // numbers are handled correctly.
+        let ahead = sequence.wrapping_sub(expected) as i32;
+
+        if ahead < 0 {
+            self.stats.reordered += 1;
+            self.stats.lost = self.stats.lost.saturating_sub(1);
+            return Verdict::Reordered;
+        }
+
+        self.expected = Some(sequence.wrapping_add(1));
+
+        if ahead == 0 {
+            Verdict::Ok
+        } else {
+            self.stats.lost += ahead as u32;
+            Verdict::Lost(ahead as u32)
+        }
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    const ETHERTYPE: u16 = 0x88B5;
+
+    fn generate(generator: &mut PacketGenerator) -> ([u8; MAX_FRAME_LEN], usize) {
+        let mut buffer = [0u8; MAX_FRAME_LEN];
+        let len = generator.next_len();
+        generator.fill(&mut buffer[..len]);
+        (buffer, len)
+    }
+
+    #[test]
+    fn sizes_stay_in_bounds() {
+        let mut generator = PacketGenerator::new([0; 6], [0xFF; 6], ETHERTYPE)
+            .size_distribution(SizeDistribution::Uniform { min: 0, max: 9000 });
+
+        for _ in 0..1000 {
+            let len = generator.next_len();
+            assert!((MIN_FRAME_LEN..=MAX_FRAME_LEN).contains(&len));
+            generate(&mut generator);
+        }
+
+        let mut generator = PacketGenerator::new([0; 6], [0xFF; 6], ETHERTYPE).size_distribution(
+            SizeDistribution::Sweep {
+                min: 100,
+                max: 300,
+                step: 100,
+            },
+        );
+
+        let lens: [usize; 4] = core::array::from_fn(|_| {
+            let len = generator.next_len();
+            generate(&mut generator);
+            len
+        });
+        assert_eq!(lens, [100, 200, 300, 100]);
+    }
+
+    #[test]
+    fn rate_limits_frames() {
+        let mut generator =
+            PacketGenerator::new([0; 6], [0xFF; 6], ETHERTYPE).rate(Rate::FramesPerSecond(10));
+
+        let mut sent = 0;
+        for now_ms in 0..2000 {
+            while generator.ready(now_ms) {
+                generate(&mut generator);
+                sent += 1;
+            }
+        }
+
+        // One second worth of initial burst, plus two seconds of frames.
+        assert!((29..=30).contains(&sent), "sent {}", sent);
+    }
+
+    #[test]
+    fn verifier_detects_loss_reordering_and_corruption() {
+        for payload in [Payload::Zeros, Payload::Incrementing, Payload::Prbs15] {
+            let mut generator = PacketGenerator::new([0; 6], [0xFF; 6], ETHERTYPE).payload(payload);
+            let mut verifier = PacketVerifier::new(ETHERTYPE, payload);
+
+            let frames: [_; 4] = core::array::from_fn(|_| generate(&mut generator));
+            let frame = |i: usize| &frames[i].0[..frames[i].1];
+
+            assert_eq!(verifier.check(frame(0)), Verdict::Ok);
+            assert_eq!(verifier.check(frame(2)), Verdict::Lost(1));
+            assert_eq!(verifier.check(frame(1)), Verdict::Reordered);
+            assert_eq!(verifier.check(frame(3)), Verdict::Ok);
+
+            let mut corrupted = frames[0];
+            corrupted.0[PAYLOAD_OFFSET + 10] ^= 0x11;
+            assert_eq!(
+                verifier.check(&corrupted.0[..corrupted.1]),
+                Verdict::Corrupted(2)
+            );
+
+            assert_eq!(
+                verifier.stats(),
+                VerifierStats {
+                    received: 4,
+                    received_bytes: 4 * MAX_FRAME_LEN as u64,
+                    lost: 0,
+                    reordered: 1,
+                    corrupted: 1,
+                    payload_bits: 5 * (MAX_FRAME_LEN - PAYLOAD_OFFSET) as u64 * 8,
+                    bit_errors: 2,
+                }
+            );
+        }
+    }
+}
@@ -0,0 +1,44 @@
+//! Utilities for load testing and link qualification.
+//!
+//! A [`PacketGenerator`] produces a stream of Ethernet frames with a
+//! configurable size distribution, rate, and payload pattern, which a
+//! [`PacketVerifier`] checks for loss, reordering, and corruption.
+//!
+//! [`Ping`] measures the round-trip time of frames that are looped
+//! back by a link partner, and [`BerTest`] estimates the bit error rate
+//! of a link.
+//!
+//! [`ThroughputResponder`] implements the device side of a simple, iperf-like,
+//! raw Ethernet throughput test.
+//!
+//! [`ring_stress`] pushes traffic through both descriptor rings in the
+//! loopback mode of the MAC, as a self-test of the driver on new chips.
+
+/// The minimum length of a generated frame, excluding the FCS.
+pub const MIN_FRAME_LEN: usize = 60;
+
+/// The maximum length of a generated frame, excluding the FCS.
+pub const MAX_FRAME_LEN: usize = 1514;
+
+mod generator;
+pub use generator::{
+    PacketGenerator, PacketVerifier, Payload, Rate, SizeDistribution, Verdict, VerifierStats,
+};
+
+mod ping;
+pub use ping::{Ping, PingStats};
+
+#[cfg(not(any(feature = "rx-only", feature = "tx-only")))]
+mod ber;
+#[cfg(not(any(feature = "rx-only", feature = "tx-only")))]
+pub use ber::{BerReport, BerTest};
+
+#[cfg(not(any(feature = "rx-only", feature = "tx-only")))]
+mod throughput;
+#[cfg(not(any(feature = "rx-only", feature = "tx-only")))]
+pub use throughput::{ThroughputReport, ThroughputResponder, THROUGHPUT_ETHERTYPE};
+
+#[cfg(not(any(feature = "rx-only", feature = "tx-only")))]
+mod stress;
+#[cfg(not(any(feature = "rx-only", feature = "tx-only")))]
+pub use stress::{ring_stress, RingStressReport, RING_STRESS_ETHERTYPE};
@@ -0,0 +1,224 @@
+use super::{MAX_FRAME_LEN, MIN_FRAME_LEN};
+
+const SEQUENCE_OFFSET: usize = 14;
+const TIMESTAMP_OFFSET: usize = SEQUENCE_OFFSET + 4;
+const PAYLOAD_OFFSET: usize = TIMESTAMP_OFFSET + 8;
+
+/// Round-trip time statistics gathered by a [`Ping`].
+///
+/// All times are expressed in the unit of the timestamps passed
+/// to [`Ping`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PingStats {
+    /// The amount of frames that were sent.
+    pub sent: u32,
+    /// The amount of frames that were received back.
+    pub received: u32,
+    /// The minimum round-trip time.
+    pub min: u64,
+    /// The maximum round-trip time.
+    pub max: u64,
+    /// The sum of all round-trip times.
+    pub total: u64,
+    /// The interarrival jitter, as defined in RFC 3550: the smoothed
+    /// mean deviation of the difference in round-trip time of consecutive
+    /// frames.
+    pub jitter: u64,
+}
+
+impl PingStats {
+    /// The average round-trip time, or `None` if no frames
+    /// were received back.
+    pub fn avg(&self) -> Option<u64> {
+        if self.received == 0 {
+            None
+        } else {
+            Some(self.total / self.received as u64)
+        }
+    }
+
+    /// The amount of frames that were sent, but not (yet) received back.
+    pub fn lost(&self) -> u32 {
+        self.sent.saturating_sub(self.received)
+    }
+}
+
+/// Measures the round-trip time of frames that are looped back by a
+/// link partner.
+///
+/// Every frame carries a sequence number and the time at which it was
+/// generated. The link partner is expected to send the frame back unmodified,
+/// apart from (optionally) swapping the MAC addresses.
+///
+/// Timestamps can be taken from any monotonic clock, such as the total
+/// nanoseconds of the PTP clock or a timer of the MCU, as long as the same
+/// clock is used for sending and receiving.
+///
+/// The layout of a generated frame is:
+///
+/// | Offset | Length | Contents                     |
+/// |--------|--------|------------------------------|
+/// | 0      | 6      | Destination MAC address      |
+/// | 6      | 6      | Source MAC address           |
+/// | 12     | 2      | EtherType                    |
+/// | 14     | 4      | Sequence number (big endian) |
+/// | 18     | 8      | Timestamp (big endian)       |
+/// | 26     | ...    | Zero padding                 |
+///
+/// # Example
+/// ```ignore
+/// let mut ping = Ping::new(SRC_MAC, DST_MAC, ETH_TYPE);
+///
+/// let len = ping.frame_len();
+/// dma.send(len, None, |buf| ping.fill(buf, now())).ok();
+///
+/// while let Ok(packet) = dma.recv_next(None) {
+///     if let Some(rtt) = ping.receive(&packet, now()) {
+///         defmt::info!("RTT: {}", rtt);
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Ping {
+    dst: [u8; 6],
+    src: [u8; 6],
+    ethertype: u16,
+    frame_len: usize,
+    sequence: u32,
+    last_rtt: Option<u64>,
+    stats: PingStats,
+}
+
+impl Ping {
+    /// Create a new [`Ping`] that sends [`MIN_FRAME_LEN`]-sized frames from
+    /// `src` to `dst` with the given `ethertype`.
+    pub fn new(src: [u8; 6], dst: [u8; 6], ethertype: u16) -> Self {
+        Self {
+            dst,
+            src,
+            ethertype,
+            frame_len: MIN_FRAME_LEN,
+            sequence: 0,
+            last_rtt: None,
+            stats: PingStats::default(),
+        }
+    }
+
+    /// Set the length of the generated frames.
+    ///
+    /// The length is clamped to the range
+    /// [`MIN_FRAME_LEN`]..=[`MAX_FRAME_LEN`].
+    pub fn with_frame_len(mut self, len: usize) -> Self {
+        self.frame_len = len.clamp(MIN_FRAME_LEN, MAX_FRAME_LEN);
+        self
+    }
+
+    /// The length of the generated frames.
+    pub fn frame_len(&self) -> usize {
+        self.frame_len
+    }
+
+    /// The statistics gathered so far.
+    pub fn stats(&self) -> PingStats {
+        self.stats
+    }
+
+    /// Reset the gathered statistics.
+    ///
+    /// Frames that were sent before the reset are ignored when they
+    /// are received.
+    pub fn reset(&mut self) {
+        self.last_rtt = None;
+        self.stats = PingStats::default();
+    }
+
+    /// Write the next frame, sent at time `now`, into `buffer`.
+    ///
+    /// `buffer` should be [`Ping::frame_len`] bytes long.
+    pub fn fill(&mut self, buffer: &mut [u8], now: u64) {
+        buffer[0..6].copy_from_slice(&self.dst);
+        buffer[6..12].copy_from_slice(&self.src);
+        buffer[12..14].copy_from_slice(&self.ethertype.to_be_bytes());
+        buffer[SEQUENCE_OFFSET..TIMESTAMP_OFFSET].copy_from_slice(&self.sequence.to_be_bytes());
+        buffer[TIMESTAMP_OFFSET..PAYLOAD_OFFSET].copy_from_slice(&now.to_be_bytes());
+        buffer[PAYLOAD_OFFSET..].fill(0);
+
+        self.sequence = self.sequence.wrapping_add(1);
+        self.stats.sent += 1;
+    }
+
+    /// Process a `frame` that was received at time `now`.
+    ///
+    /// Returns the round-trip time of the frame, or `None` if the frame
+    /// was not sent by this [`Ping`] since it was last reset.
+    pub fn receive(&mut self, frame: &[u8], now: u64) -> Option<u64> {
+        if frame.len() < PAYLOAD_OFFSET || frame[12..14] != self.ethertype.to_be_bytes() {
+            return None;
+        }
+
+        let mut sequence = [0u8; 4];
+        sequence.copy_from_slice(&frame[SEQUENCE_OFFSET..TIMESTAMP_OFFSET]);
+        let sequence = u32::from_be_bytes(sequence);
+
+        // Only accept frames that were sent since the last reset.
+        let age = self.sequence.wrapping_sub(sequence);
+        if age == 0 || age > self.stats.sent {
+            return None;
+        }
+
+        let mut sent_at = [0u8; 8];
+        sent_at.copy_from_slice(&frame[TIMESTAMP_OFFSET..PAYLOAD_OFFSET]);
+        let rtt = now.wrapping_sub(u64::from_be_bytes(sent_at));
+
+        let stats = &mut self.stats;
+        if stats.received == 0 {
+            stats.min = rtt;
+            stats.max = rtt;
+        } else {
+            stats.min = stats.min.min(rtt);
+            stats.max = stats.max.max(rtt);
+        }
+        stats.received += 1;
+        stats.total = stats.total.wrapping_add(rtt);
+
+        if let Some(last_rtt) = self.last_rtt {
+            let deviation = rtt.abs_diff(last_rtt);
+            // J = J + (|D| - J) / 16
+            stats.jitter = (stats.jitter * 15 + deviation) / 16;
+        }
+        self.last_rtt = Some(rtt);
+
+        Some(rtt)
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    const ETHERTYPE: u16 = 0x88B5;
+
+    #[test]
+    fn round_trip_statistics() {
+        let mut ping = Ping::new([0; 6], [0xFF; 6], ETHERTYPE);
+        let mut frames = [[0u8; MIN_FRAME_LEN]; 3];
+
+        for (i, frame) in frames.iter_mut().enumerate() {
+            ping.fill(frame, i as u64 * 1000);
+        }
+
+        assert_eq!(ping.receive(&frames[0], 100), Some(100));
+        assert_eq!(ping.receive(&frames[2], 2300), Some(300));
+        assert_eq!(ping.stats().lost(), 1);
+
+        let stats = ping.stats();
+        assert_eq!(stats.min, 100);
+        assert_eq!(stats.max, 300);
+        assert_eq!(stats.avg(), Some(200));
+        assert_eq!(stats.jitter, 200 / 16);
+
+        ping.reset();
+        assert_eq!(ping.receive(&frames[1], 1200), None);
+    }
+}
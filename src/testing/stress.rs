@@ -0,0 +1,182 @@
+use crate::{
+    dma::{EthernetDMA, RxError},
+    mac::{frame_filtering::MacAddressFilterSlot, EthernetMAC},
+};
+
+use super::{
+    PacketGenerator, PacketVerifier, Payload, SizeDistribution, VerifierStats, MAX_FRAME_LEN,
+    MIN_FRAME_LEN,
+};
+
+/// The EtherType of the frames sent by [`ring_stress`].
+pub const RING_STRESS_ETHERTYPE: u16 = 0x88B7;
+
+/// The amount of milliseconds that [`ring_stress`] waits for frames
+/// that are still in flight after the test duration has passed.
+const DRAIN_TIMEOUT_MS: u64 = 100;
+
+/// The results of [`ring_stress`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RingStressReport {
+    /// The amount of frames that were sent.
+    pub sent: u32,
+    /// The results of checking the received frames.
+    pub received: VerifierStats,
+    /// The amount of frames for which [`EthernetDMA::recv_next`] returned
+    /// an error.
+    pub rx_errors: u32,
+    /// The amount of times that a TX descriptor was owned by the DMA
+    /// while it was not in flight.
+    pub tx_ownership_violations: u32,
+    /// Whether the RX descriptors were not all owned by the DMA after
+    /// all frames were received.
+    pub rx_ownership_violation: bool,
+}
+
+impl RingStressReport {
+    /// The amount of frames that were sent, but not received.
+    pub fn missing(&self) -> u32 {
+        self.sent
+            .saturating_sub(self.received.received + self.received.corrupted)
+    }
+
+    /// Whether every frame was received intact and in order, and no
+    /// descriptor ownership violations were detected.
+    pub fn passed(&self) -> bool {
+        let received = &self.received;
+
+        self.missing() == 0
+            && received.lost == 0
+            && received.reordered == 0
+            && received.corrupted == 0
+            && self.rx_errors == 0
+            && self.tx_ownership_violations == 0
+            && !self.rx_ownership_violation
+    }
+}
+
+/// Push traffic through both descriptor rings in the internal loopback
+/// mode of the MAC, for `duration_ms` milliseconds as measured by `now_ms`.
+///
+/// Frames of every length from [`MIN_FRAME_LEN`] up to the RX buffer size
+/// are sent as fast as the TX ring accepts them, and are verified as they
+/// are received. While frames are in flight, no TX descriptor outside of
+/// the frames in flight may be owned by the DMA, and once all frames are
+/// received, all RX descriptors must be owned by the DMA again.
+///
+/// This is meant to catch cache, memory barrier, and alignment issues on
+/// new chips or configurations before they show up in the field. The MAC is
+/// taken out of loopback mode again afterwards, but all frames that are
+/// received during the test are consumed, so it should not run while the
+/// interface is in use.
+///
+/// ```rust,ignore
+/// let report = ring_stress(&mut mac, &mut dma, 1_000, || monotonic_ms());
+/// assert!(report.passed(), "{:?}", report);
+/// ```
+pub fn ring_stress<const RX_BUF: usize, F>(
+    mac: &mut EthernetMAC,
+    dma: &mut EthernetDMA<'_, '_, RX_BUF>,
+    duration_ms: u64,
+    mut now_ms: F,
+) -> RingStressReport
+where
+    F: FnMut() -> u64,
+{
+    // Address the frames to the station address, so that they pass
+    // any address filter.
+    let address = MacAddressFilterSlot::Station
+        .read(mac)
+        .map_or([0; 6], |filter| *filter.address.raw());
+
+    // The FCS is not stripped from frames while software CRC checks
+    // are active, so leave room for it in the RX buffer.
+    let max_len = RX_BUF.saturating_sub(4).min(MAX_FRAME_LEN);
+    let mut generator = PacketGenerator::new(address, address, RING_STRESS_ETHERTYPE)
+        .size_distribution(SizeDistribution::Sweep {
+            min: MIN_FRAME_LEN,
+            max: max_len,
+            step: 1,
+        })
+        .payload(Payload::Prbs15);
+    let mut verifier = PacketVerifier::new(RING_STRESS_ETHERTYPE, Payload::Prbs15);
+    let mut report = RingStressReport::default();
+
+    let loopback = mac.loopback();
+    mac.set_loopback(true);
+
+    let start = now_ms();
+    while now_ms().wrapping_sub(start) < duration_ms {
+        loop {
+            let generator = &mut generator;
+            match dma.send(generator.next_len(), None, |buf| generator.fill(buf)) {
+                Ok(()) => report.sent += 1,
+                Err(_) => break,
+            }
+        }
+
+        if !dma.tx_ring.ownership_consistent() {
+            report.tx_ownership_violations += 1;
+        }
+
+        receive(dma, &mut verifier, &mut report);
+    }
+
+    let drain_start = now_ms();
+    while report.missing() != 0 && now_ms().wrapping_sub(drain_start) < DRAIN_TIMEOUT_MS {
+        receive(dma, &mut verifier, &mut report);
+    }
+
+    report.rx_ownership_violation = !dma.rx_ring.ownership_consistent();
+
+    mac.set_loopback(loopback);
+
+    report
+}
+
+/// Verify all received frames.
+fn receive<const RX_BUF: usize>(
+    dma: &mut EthernetDMA<'_, '_, RX_BUF>,
+    verifier: &mut PacketVerifier,
+    report: &mut RingStressReport,
+) {
+    loop {
+        match dma.recv_next(None) {
+            Ok(packet) => {
+                verifier.check(&packet);
+            }
+            Err(RxError::WouldBlock) => break,
+            Err(_) => report.rx_errors += 1,
+        }
+    }
+
+    report.received = verifier.stats();
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn missing_frames_fail_the_test() {
+        let mut report = RingStressReport {
+            sent: 10,
+            received: VerifierStats {
+                received: 10,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(report.missing(), 0);
+        assert!(report.passed());
+
+        report.sent = 12;
+        assert_eq!(report.missing(), 2);
+        assert!(!report.passed());
+
+        report.sent = 10;
+        report.rx_ownership_violation = true;
+        assert!(!report.passed());
+    }
+}
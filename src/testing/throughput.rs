@@ -0,0 +1,250 @@
+use crate::dma::EthernetDMA;
+
+use super::{MAX_FRAME_LEN, MIN_FRAME_LEN};
+
+/// The default EtherType used by the throughput test protocol.
+pub const THROUGHPUT_ETHERTYPE: u16 = 0x88B6;
+
+const OPCODE_OFFSET: usize = 14;
+const SEQUENCE_OFFSET: usize = OPCODE_OFFSET + 1;
+const ARGS_OFFSET: usize = SEQUENCE_OFFSET + 4;
+
+/// A data frame. Counted by the receiver.
+const OP_DATA: u8 = 0x01;
+/// Reset the receive counters.
+const OP_RESET: u8 = 0x02;
+/// Request a [`ThroughputReport`].
+const OP_REPORT: u8 = 0x03;
+/// Request the responder to send data frames. Arguments: the amount of
+/// frames (u32) and their length (u16).
+const OP_SEND: u8 = 0x04;
+/// Set on the opcode of a reply.
+const OP_REPLY: u8 = 0x80;
+
+/// Receive counters of a [`ThroughputResponder`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ThroughputReport {
+    /// The amount of data frames that were received.
+    pub frames: u32,
+    /// The amount of bytes in data frames that were received.
+    pub bytes: u64,
+    /// The amount of data frames that were skipped in the sequence.
+    pub lost: u32,
+}
+
+/// A responder for a simple, iperf-like, raw Ethernet throughput test.
+///
+/// The host side of the test is implemented by `tools/throughput.py` in
+/// the repository of this crate. All frames of the test protocol carry
+/// the following header after the Ethernet header:
+///
+/// | Offset | Length | Contents                     |
+/// |--------|--------|------------------------------|
+/// | 14     | 1      | Opcode                       |
+/// | 15     | 4      | Sequence number (big endian) |
+/// | 19     | ...    | Arguments                    |
+///
+/// The host sends data frames, which the responder counts, and requests a
+/// [`ThroughputReport`] at the end of a test to measure the receive
+/// throughput. To measure the transmit throughput, the host requests the
+/// responder to send a number of data frames, which the host counts.
+#[derive(Debug, Clone)]
+pub struct ThroughputResponder {
+    mac: [u8; 6],
+    ethertype: u16,
+    report: ThroughputReport,
+    expected: Option<u32>,
+    reply: Option<([u8; 6], u8)>,
+    peer: [u8; 6],
+    to_send: u32,
+    send_len: usize,
+    sequence: u32,
+}
+
+impl ThroughputResponder {
+    /// Create a new responder with the given `mac` address, which uses
+    /// [`THROUGHPUT_ETHERTYPE`].
+    pub fn new(mac: [u8; 6]) -> Self {
+        Self::with_ethertype(mac, THROUGHPUT_ETHERTYPE)
+    }
+
+    /// Create a new responder with the given `mac` address and `ethertype`.
+    pub fn with_ethertype(mac: [u8; 6], ethertype: u16) -> Self {
+        Self {
+            mac,
+            ethertype,
+            report: ThroughputReport::default(),
+            expected: None,
+            reply: None,
+            peer: [0; 6],
+            to_send: 0,
+            send_len: MIN_FRAME_LEN,
+            sequence: 0,
+        }
+    }
+
+    /// The receive counters of this responder.
+    pub fn report(&self) -> ThroughputReport {
+        self.report
+    }
+
+    /// Process all received frames, and send any pending replies
+    /// and data frames.
+    ///
+    /// This function should be called whenever a frame is received, or a
+    /// TX slot frees up.
+    pub fn poll<const RX_BUF: usize>(&mut self, dma: &mut EthernetDMA<'_, '_, RX_BUF>) {
+        loop {
+            // Don't receive any more requests until the previous reply is sent.
+            if self.reply.is_none() {
+                if let Ok(packet) = dma.recv_next(None) {
+                    self.process(&packet);
+                    continue;
+                }
+            }
+
+            if !self.transmit(dma) {
+                break;
+            }
+        }
+    }
+
+    /// Process a received `frame`.
+    fn process(&mut self, frame: &[u8]) {
+        if frame.len() < ARGS_OFFSET
+            || frame[12..14] != self.ethertype.to_be_bytes()
+            || frame[0..6] != self.mac
+        {
+            return;
+        }
+
+        let mut src = [0u8; 6];
+        src.copy_from_slice(&frame[6..12]);
+
+        let mut sequence = [0u8; 4];
+        sequence.copy_from_slice(&frame[SEQUENCE_OFFSET..ARGS_OFFSET]);
+        let sequence = u32::from_be_bytes(sequence);
+
+        let args = &frame[ARGS_OFFSET..];
+
+        match frame[OPCODE_OFFSET] {
+            OP_DATA => {
+                let expected = self.expected.unwrap_or(sequence);
+                let ahead = sequence.wrapping_sub(expected) as i32;
+                if ahead >= 0 {
+                    self.report.lost += ahead as u32;
+                    self.expected = Some(sequence.wrapping_add(1));
+                }
+
+                self.report.frames += 1;
+                self.report.bytes += frame.len() as u64;
+            }
+            OP_RESET => {
+                self.report = ThroughputReport::default();
+                self.expected = None;
+                self.to_send = 0;
+                self.reply = Some((src, OP_RESET));
+            }
+            OP_REPORT => self.reply = Some((src, OP_REPORT)),
+            OP_SEND if args.len() >= 6 => {
+                let mut count = [0u8; 4];
+                count.copy_from_slice(&args[0..4]);
+                let len = u16::from_be_bytes([args[4], args[5]]) as usize;
+
+                self.peer = src;
+                self.to_send = u32::from_be_bytes(count);
+                self.send_len = len.clamp(MIN_FRAME_LEN, MAX_FRAME_LEN);
+                self.sequence = 0;
+                self.reply = Some((src, OP_SEND));
+            }
+            _ => {}
+        }
+    }
+
+    /// Send the pending reply, or the next data frame.
+    ///
+    /// Returns `false` if there was nothing to send, or if the TX ring is full.
+    fn transmit<const RX_BUF: usize>(&mut self, dma: &mut EthernetDMA<'_, '_, RX_BUF>) -> bool {
+        let (dst, opcode, sequence, len) = match self.reply {
+            Some((dst, opcode)) => (dst, opcode | OP_REPLY, 0, MIN_FRAME_LEN),
+            None if self.to_send > 0 => (self.peer, OP_DATA, self.sequence, self.send_len),
+            None => return false,
+        };
+
+        let report = self.report;
+        let result = dma.send(len, None, |buf| {
+            buf[0..6].copy_from_slice(&dst);
+            buf[6..12].copy_from_slice(&self.mac);
+            buf[12..14].copy_from_slice(&self.ethertype.to_be_bytes());
+            buf[OPCODE_OFFSET] = opcode;
+            buf[SEQUENCE_OFFSET..ARGS_OFFSET].copy_from_slice(&sequence.to_be_bytes());
+
+            let args = &mut buf[ARGS_OFFSET..];
+            args.fill(0);
+            if opcode == OP_REPORT | OP_REPLY {
+                args[0..4].copy_from_slice(&report.frames.to_be_bytes());
+                args[4..12].copy_from_slice(&report.bytes.to_be_bytes());
+                args[12..16].copy_from_slice(&report.lost.to_be_bytes());
+            }
+        });
+
+        match result {
+            Ok(()) => {
+                if self.reply.take().is_none() {
+                    self.to_send -= 1;
+                    self.sequence = self.sequence.wrapping_add(1);
+                }
+                true
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    const MAC: [u8; 6] = [0x00, 0x00, 0xDE, 0xAD, 0xBE, 0xEF];
+    const HOST: [u8; 6] = [0x02, 0x00, 0x00, 0x00, 0x00, 0x01];
+
+    fn frame(opcode: u8, sequence: u32, args: &[u8]) -> [u8; MIN_FRAME_LEN] {
+        let mut frame = [0u8; MIN_FRAME_LEN];
+        frame[0..6].copy_from_slice(&MAC);
+        frame[6..12].copy_from_slice(&HOST);
+        frame[12..14].copy_from_slice(&THROUGHPUT_ETHERTYPE.to_be_bytes());
+        frame[OPCODE_OFFSET] = opcode;
+        frame[SEQUENCE_OFFSET..ARGS_OFFSET].copy_from_slice(&sequence.to_be_bytes());
+        frame[ARGS_OFFSET..ARGS_OFFSET + args.len()].copy_from_slice(args);
+        frame
+    }
+
+    #[test]
+    fn counts_data_and_handles_requests() {
+        let mut responder = ThroughputResponder::new(MAC);
+
+        for sequence in [0, 1, 3] {
+            responder.process(&frame(OP_DATA, sequence, &[]));
+        }
+
+        assert_eq!(
+            responder.report(),
+            ThroughputReport {
+                frames: 3,
+                bytes: 3 * MIN_FRAME_LEN as u64,
+                lost: 1,
+            }
+        );
+
+        responder.process(&frame(OP_SEND, 0, &[0, 0, 0, 10, 0x05, 0xEA]));
+        assert_eq!(responder.reply, Some((HOST, OP_SEND)));
+        assert_eq!(responder.to_send, 10);
+        assert_eq!(responder.send_len, MAX_FRAME_LEN);
+
+        responder.reply = None;
+        responder.process(&frame(OP_RESET, 0, &[]));
+        assert_eq!(responder.report(), ThroughputReport::default());
+        assert_eq!(responder.to_send, 0);
+    }
+}
@@ -0,0 +1,384 @@
+//! Tunneling of ethernet frames over a byte stream.
+//!
+//! Hardware-in-the-loop test rigs often only have a debug UART to talk to
+//! the device under test. A [`Tunnel`] encapsulates ethernet frames over
+//! any byte stream that implements the [`embedded_io`] traits, so that a
+//! test rig can inject frames into, and extract frames from, the device.
+//!
+//! Every frame is encoded using Consistent Overhead Byte Stuffing (COBS),
+//! and followed by a single `0x00` delimiter byte.
+//!
+//! [`Tunnel`] implements [`Interface`], so it can be used with a
+//! [`Bridge`](crate::bridge::Bridge) or [`Failover`](crate::failover::Failover).
+//! If the `rx-inject` feature is enabled, [`Tunnel::inject`] injects the
+//! frames received from the byte stream into the receive path of the
+//! driver, so that the application handles them like wire traffic.
+
+use embedded_io::{Read, ReadReady, Write};
+
+use crate::{bridge::Interface, dma::MTU};
+
+#[cfg(feature = "rx-inject")]
+use crate::dma::EthernetDMA;
+
+/// The maximum length of a COBS encoded frame, excluding the delimiter.
+const MAX_ENCODED_LEN: usize = MTU + MTU / 254 + 1;
+
+/// Counters of a [`Tunnel`].
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TunnelStats {
+    /// The amount of frames that were received from the byte stream.
+    pub received: u32,
+    /// The amount of frames that were transmitted over the byte stream.
+    pub transmitted: u32,
+    /// The amount of I/O errors, of frames that could not be decoded, and
+    /// of frames that were too long to be transmitted.
+    pub errors: u32,
+}
+
+/// A tunnel that carries ethernet frames over a byte stream.
+pub struct Tunnel<T> {
+    io: T,
+    rx: [u8; MAX_ENCODED_LEN],
+    rx_len: usize,
+    rx_discard: bool,
+    frame: [u8; MTU],
+    tx: [u8; MAX_ENCODED_LEN + 1],
+    stats: TunnelStats,
+}
+
+impl<T> Tunnel<T>
+where
+    T: Read + ReadReady + Write,
+{
+    /// Create a new tunnel over `io`.
+    pub fn new(io: T) -> Self {
+        Self {
+            io,
+            rx: [0; MAX_ENCODED_LEN],
+            rx_len: 0,
+            rx_discard: false,
+            frame: [0; MTU],
+            tx: [0; MAX_ENCODED_LEN + 1],
+            stats: TunnelStats::default(),
+        }
+    }
+
+    /// The counters of this tunnel.
+    pub fn stats(&self) -> TunnelStats {
+        self.stats
+    }
+
+    /// Release the byte stream.
+    pub fn free(self) -> T {
+        self.io
+    }
+
+    /// Inject the next frame received from the byte stream into the
+    /// receive path of `dma`.
+    ///
+    /// Returns `true` if a frame was injected.
+    ///
+    /// See [`EthernetDMA::inject_rx`].
+    #[cfg(feature = "rx-inject")]
+    pub fn inject<const RX_BUF: usize>(&mut self, dma: &mut EthernetDMA<'_, '_, RX_BUF>) -> bool {
+        if !dma.can_inject_rx() {
+            return false;
+        }
+
+        self.receive(|frame| dma.inject_rx(frame).is_ok())
+            .unwrap_or(false)
+    }
+
+    /// Read bytes from the stream until a complete frame has been received,
+    /// or no more bytes are available.
+    ///
+    /// Returns the length of the encoded frame, excluding the delimiter.
+    fn read_frame(&mut self) -> Option<usize> {
+        let mut scanned = 0;
+        loop {
+            if let Some(end) = self.rx[scanned..self.rx_len].iter().position(|b| *b == 0) {
+                let end = scanned + end;
+                if !self.rx_discard {
+                    return Some(end);
+                }
+
+                self.rx_discard = false;
+                self.consume(end);
+                scanned = 0;
+                continue;
+            }
+            scanned = self.rx_len;
+
+            if self.rx_len == self.rx.len() {
+                // Too long to be a valid frame: discard everything
+                // until the next delimiter.
+                self.stats.errors = self.stats.errors.wrapping_add(1);
+                self.rx_discard = true;
+                self.rx_len = 0;
+                scanned = 0;
+            }
+
+            match self.io.read_ready() {
+                Ok(true) => {}
+                Ok(false) => return None,
+                Err(_) => {
+                    self.stats.errors = self.stats.errors.wrapping_add(1);
+                    return None;
+                }
+            }
+
+            match self.io.read(&mut self.rx[self.rx_len..]) {
+                Ok(read) => self.rx_len += read,
+                Err(_) => {
+                    self.stats.errors = self.stats.errors.wrapping_add(1);
+                    return None;
+                }
+            }
+        }
+    }
+
+    /// Remove the encoded frame of length `len`, and its
+    /// delimiter, from the receive buffer.
+    fn consume(&mut self, len: usize) {
+        self.rx.copy_within(len + 1..self.rx_len, 0);
+        self.rx_len -= len + 1;
+    }
+}
+
+impl<T> Interface for Tunnel<T>
+where
+    T: Read + ReadReady + Write,
+{
+    fn max_frame_len(&self) -> usize {
+        MTU
+    }
+
+    fn can_transmit(&mut self) -> bool {
+        true
+    }
+
+    fn transmit<F>(&mut self, length: usize, f: F) -> bool
+    where
+        F: FnOnce(&mut [u8]),
+    {
+        let frame = match self.frame.get_mut(..length) {
+            Some(frame) => frame,
+            None => {
+                self.stats.errors = self.stats.errors.wrapping_add(1);
+                return false;
+            }
+        };
+        f(frame);
+
+        let len = cobs_encode(frame, &mut self.tx);
+        self.tx[len] = 0;
+
+        if self.io.write_all(&self.tx[..len + 1]).is_ok() {
+            self.stats.transmitted = self.stats.transmitted.wrapping_add(1);
+            true
+        } else {
+            self.stats.errors = self.stats.errors.wrapping_add(1);
+            false
+        }
+    }
+
+    fn receive<R, F>(&mut self, f: F) -> Option<R>
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        let (encoded_len, len) = loop {
+            let encoded_len = self.read_frame()?;
+
+            match cobs_decode_in_place(&mut self.rx[..encoded_len]) {
+                Some(len) if len > 0 => break (encoded_len, len),
+                // Empty frames can be used to resynchronize the stream.
+                Some(_) => {}
+                None => self.stats.errors = self.stats.errors.wrapping_add(1),
+            }
+
+            self.consume(encoded_len);
+        };
+
+        self.stats.received = self.stats.received.wrapping_add(1);
+        let result = f(&self.rx[..len]);
+        self.consume(encoded_len);
+        Some(result)
+    }
+}
+
+/// COBS encode `data` into `out`, which must be at least
+/// `data.len() + data.len() / 254 + 1` bytes long.
+///
+/// Returns the length of the encoded data.
+fn cobs_encode(data: &[u8], out: &mut [u8]) -> usize {
+    let mut code_idx = 0;
+    let mut write = 1;
+    let mut code = 1u8;
+
+    for byte in data {
+        if *byte == 0 {
+            out[code_idx] = code;
+            code_idx = write;
+            write += 1;
+            code = 1;
+        } else {
+            out[write] = *byte;
+            write += 1;
+            code += 1;
+            if code == 0xFF {
+                out[code_idx] = code;
+                code_idx = write;
+                write += 1;
+                code = 1;
+            }
+        }
+    }
+
+    out[code_idx] = code;
+    write
+}
+
+/// Decode the COBS encoded `data` in place.
+///
+/// Returns the length of the decoded data, or `None` if `data`
+/// is not validly encoded.
+fn cobs_decode_in_place(data: &mut [u8]) -> Option<usize> {
+    let mut read = 0;
+    let mut write = 0;
+
+    while read < data.len() {
+        let code = data[read] as usize;
+        if code == 0 || read + code > data.len() {
+            return None;
+        }
+        read += 1;
+
+        for _ in 1..code {
+            data[write] = data[read];
+            write += 1;
+            read += 1;
+        }
+
+        if code != 0xFF && read != data.len() {
+            data[write] = 0;
+            write += 1;
+        }
+    }
+
+    Some(write)
+}
+
+#[cfg(all(test, not(target_os = "none")))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cobs_round_trip() {
+        let mut out = [0u8; 600];
+
+        let cases: [&[u8]; 5] = [
+            &[],
+            &[0x00],
+            &[0x11, 0x22, 0x00, 0x33],
+            &[0x11, 0x00, 0x00, 0x00],
+            &[0x42; 300],
+        ];
+
+        for data in cases {
+            let len = cobs_encode(data, &mut out);
+            assert!(len <= data.len() + data.len() / 254 + 1);
+            assert!(!out[..len].contains(&0));
+
+            let decoded = cobs_decode_in_place(&mut out[..len]);
+            assert_eq!(decoded, Some(data.len()));
+            assert_eq!(&out[..data.len()], data);
+        }
+
+        let len = cobs_encode(&[0x11, 0x22, 0x00, 0x33], &mut out);
+        assert_eq!(&out[..len], &[0x03, 0x11, 0x22, 0x02, 0x33]);
+
+        assert_eq!(cobs_decode_in_place(&mut [0x05, 0x11]), None);
+    }
+
+    /// A byte stream that loops written bytes back.
+    struct Loopback {
+        buf: [u8; 256],
+        read: usize,
+        written: usize,
+    }
+
+    impl embedded_io::ErrorType for Loopback {
+        type Error = core::convert::Infallible;
+    }
+
+    impl Read for Loopback {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            // Return at most 7 bytes at a time, to exercise reassembly.
+            let len = buf.len().min(self.written - self.read).min(7);
+            buf[..len].copy_from_slice(&self.buf[self.read..self.read + len]);
+            self.read += len;
+            Ok(len)
+        }
+    }
+
+    impl ReadReady for Loopback {
+        fn read_ready(&mut self) -> Result<bool, Self::Error> {
+            Ok(self.read < self.written)
+        }
+    }
+
+    impl Write for Loopback {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            self.buf[self.written..self.written + buf.len()].copy_from_slice(buf);
+            self.written += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn tunnel_round_trip() {
+        let mut tunnel = Tunnel::new(Loopback {
+            buf: [0; 256],
+            read: 0,
+            written: 0,
+        });
+
+        let frames: [&[u8]; 2] = [&[0xFF, 0x00, 0x01, 0x00], &[0x12; 60]];
+        for frame in frames {
+            assert!(tunnel.transmit(frame.len(), |buf| buf.copy_from_slice(frame)));
+        }
+
+        for frame in frames {
+            assert_eq!(tunnel.receive(|rx| rx == frame), Some(true));
+        }
+        assert_eq!(tunnel.receive(|_| ()), None);
+
+        assert_eq!(
+            tunnel.stats(),
+            TunnelStats {
+                received: 2,
+                transmitted: 2,
+                errors: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn oversized_frames_are_rejected() {
+        let mut tunnel = Tunnel::new(Loopback {
+            buf: [0; 256],
+            read: 0,
+            written: 0,
+        });
+
+        assert!(!tunnel.transmit(MTU + 1, |_| panic!("Frame was written")));
+        assert_eq!(tunnel.stats().errors, 1);
+        assert_eq!(tunnel.stats().transmitted, 0);
+    }
+}
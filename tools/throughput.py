@@ -0,0 +1,133 @@
+#!/usr/bin/env python3
+"""Host side of the raw Ethernet throughput test.
+
+Runs against a device that uses `stm32_eth::testing::ThroughputResponder`,
+such as the `throughput` example. Requires Linux and raw socket
+privileges (e.g. run as root, or grant CAP_NET_RAW to the interpreter).
+
+Usage:
+    throughput.py <interface> <device MAC> [--mode rx|tx|both]
+                  [--count N] [--length L]
+
+In `rx` mode, the host sends data frames and the device reports how many
+it received. In `tx` mode, the device sends data frames to the host.
+"""
+
+import argparse
+import socket
+import struct
+import sys
+import time
+
+ETHERTYPE = 0x88B6
+
+OP_DATA = 0x01
+OP_RESET = 0x02
+OP_REPORT = 0x03
+OP_SEND = 0x04
+OP_REPLY = 0x80
+
+MIN_FRAME_LEN = 60
+MAX_FRAME_LEN = 1514
+
+
+def parse_mac(mac):
+    return bytes(int(b, 16) for b in mac.split(":"))
+
+
+class Link:
+    def __init__(self, interface, device):
+        self.sock = socket.socket(socket.AF_PACKET, socket.SOCK_RAW, socket.htons(ETHERTYPE))
+        self.sock.bind((interface, ETHERTYPE))
+        self.sock.setsockopt(socket.SOL_SOCKET, socket.SO_RCVBUF, 8 * 1024 * 1024)
+        self.mac = self.sock.getsockname()[4]
+        self.device = device
+
+    def send(self, opcode, sequence=0, args=b"", length=MIN_FRAME_LEN):
+        header = self.device + self.mac + struct.pack(">HBI", ETHERTYPE, opcode, sequence)
+        frame = header + args
+        self.sock.send(frame + bytes(max(length - len(frame), 0)))
+
+    def recv(self, timeout):
+        self.sock.settimeout(timeout)
+        try:
+            frame = self.sock.recv(2048)
+        except socket.timeout:
+            return None
+        if len(frame) < 19 or frame[6:12] != self.device:
+            return None
+        opcode, sequence = struct.unpack(">BI", frame[14:19])
+        return opcode, sequence, frame
+
+    def request(self, opcode, args=b"", retries=5):
+        for _ in range(retries):
+            self.send(opcode, args=args)
+            deadline = time.monotonic() + 0.5
+            while time.monotonic() < deadline:
+                reply = self.recv(0.5)
+                if reply is not None and reply[0] == opcode | OP_REPLY:
+                    return reply[2]
+        sys.exit("No reply from device")
+
+
+def report(label, frames, nbytes, lost, elapsed):
+    mbps = nbytes * 8 / elapsed / 1e6
+    print(f"{label}: {frames} frames, {nbytes} bytes, {lost} lost "
+          f"in {elapsed:.2f} s: {mbps:.2f} Mbit/s, {frames / elapsed:.0f} frames/s")
+
+
+def test_rx(link, count, length):
+    link.request(OP_RESET)
+
+    start = time.monotonic()
+    for sequence in range(count):
+        link.send(OP_DATA, sequence, length=length)
+    elapsed = time.monotonic() - start
+
+    # Give the device some time to process its RX ring.
+    time.sleep(0.1)
+    reply = link.request(OP_REPORT)
+    frames, nbytes, lost = struct.unpack(">IQI", reply[19:35])
+    report("Device RX", frames, nbytes, lost, elapsed)
+
+
+def test_tx(link, count, length):
+    link.request(OP_RESET)
+    link.request(OP_SEND, struct.pack(">IH", count, length))
+
+    frames = nbytes = 0
+    start = end = time.monotonic()
+    while frames < count:
+        reply = link.recv(1.0)
+        if reply is None:
+            break
+        opcode, _, frame = reply
+        if opcode != OP_DATA:
+            continue
+        frames += 1
+        nbytes += len(frame)
+        end = time.monotonic()
+
+    report("Device TX", frames, nbytes, count - frames, max(end - start, 1e-9))
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__, formatter_class=argparse.RawDescriptionHelpFormatter)
+    parser.add_argument("interface")
+    parser.add_argument("device", type=parse_mac)
+    parser.add_argument("--mode", choices=["rx", "tx", "both"], default="both")
+    parser.add_argument("--count", type=int, default=10000)
+    parser.add_argument("--length", type=int, default=MAX_FRAME_LEN)
+    args = parser.parse_args()
+
+    length = min(max(args.length, MIN_FRAME_LEN), MAX_FRAME_LEN)
+    link = Link(args.interface, args.device)
+
+    if args.mode in ("rx", "both"):
+        test_rx(link, args.count, length)
+    if args.mode in ("tx", "both"):
+        test_tx(link, args.count, length)
+
+
+if __name__ == "__main__":
+    main()